@@ -10,7 +10,7 @@ use itertools::Itertools;
 #[cfg(feature = "sgx")]
 use sgx_untrusted_alloc::UntrustedAllocator;
 
-use crate::file::AsyncFileRt;
+use crate::file::{AsyncFile, AsyncFileRt};
 use crate::page_cache::{Page, PageHandle, PageState};
 
 /// Flush dirty pages in a page cache.
@@ -130,16 +130,27 @@ impl<Rt: AsyncFileRt + ?Sized> Flusher<Rt> {
         offset: usize,
         mut consecutive_pages: Vec<PageHandle>,
     ) -> BoxFuture<'static, i32> {
+        // The last page of a file may be partial. Only the bytes within the
+        // file length are written so that the file is not extended to a
+        // multiple of the page size.
+        let file_len = {
+            let file = consecutive_pages[0]
+                .file()
+                .downcast_ref::<AsyncFile<Rt>>()
+                .unwrap();
+            *file.len.read()
+        };
         // TODO: I don't think this Box is necessary (at least for non-SGX build)
         let iovecs: Box<Vec<libc::iovec>> = Box::new(
             consecutive_pages
                 .iter()
                 .map(|page| libc::iovec {
                     iov_base: page.page().as_mut_ptr() as _,
-                    iov_len: Page::size(),
+                    iov_len: file_len.saturating_sub(page.offset()).min(Page::size()),
                 })
                 .collect(),
         );
+        let write_nbytes: usize = iovecs.iter().map(|iovec| iovec.iov_len).sum();
         #[cfg(not(feature = "sgx"))]
         let (iovecs_ptr, iovecs_len) = ((*iovecs).as_ptr(), (*iovecs).len());
         #[cfg(feature = "sgx")]
@@ -164,7 +175,7 @@ impl<Rt: AsyncFileRt + ?Sized> Flusher<Rt> {
             let flusher_pollee = self.pollee.clone();
             move |retval: i32| {
                 // TODO: handle partial writes or error
-                assert!(retval as usize == consecutive_pages.len() * Page::size());
+                assert!(retval as usize == write_nbytes);
 
                 for page in consecutive_pages {
                     let mut state = page.state();
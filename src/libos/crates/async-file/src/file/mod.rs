@@ -519,6 +519,14 @@ impl<Rt: AsyncFileRt + ?Sized> AsyncFile<Rt> {
                 dst_buf.copy_from_slice(src_buf);
 
                 write_nbytes += copy_size;
+
+                // Update file length if necessary. This must be done before
+                // the page state is unlocked, so that a flusher of the page
+                // always sees a file length that covers the written data.
+                let mut file_len = self.len.write();
+                if offset + write_nbytes > *file_len {
+                    *file_len = offset + write_nbytes;
+                }
             };
 
             let mut state = page_handle.state();
@@ -569,12 +577,6 @@ impl<Rt: AsyncFileRt + ?Sized> AsyncFile<Rt> {
         }
 
         if write_nbytes > 0 {
-            // Update file length if necessary
-            let mut file_len = self.len.write();
-            if offset + write_nbytes > *file_len {
-                *file_len = offset + write_nbytes;
-            }
-
             Ok(write_nbytes)
         } else {
             return_errno!(EAGAIN, "try again later");
@@ -115,6 +115,59 @@ mod tests {
         });
     }
 
+    #[test]
+    fn write_read_partial_last_page() {
+        async_rt::task::block_on(async {
+            let path = "tmp.data.partial_last_page";
+            let open_file = |flags| {
+                let mode = libc::S_IRUSR | libc::S_IWUSR;
+                AsyncFile::<Runtime>::open(path.to_string(), flags, mode).unwrap()
+            };
+
+            // The file size is not a multiple of the page size
+            let data_len = 2 * Page::size() + 100;
+            let data: Vec<u8> = (0..data_len).map(|i| (i % 128) as u8).collect();
+            let file = open_file(libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC);
+            file.write_exact_at(0, &data).await.unwrap();
+            file.flush().await.unwrap();
+            drop(file);
+            assert!(std::fs::metadata(path).unwrap().len() == data_len as u64);
+
+            // Read the exact size of the file, without trailing bytes
+            let file = open_file(libc::O_RDWR);
+            let mut output_buf = vec![0xff; data_len];
+            file.read_exact_at(0, &mut output_buf).await.unwrap();
+            assert!(output_buf == data);
+            let mut extra_buf = vec![0xff; Page::size()];
+            assert!(file.file().read_at(data_len, &mut extra_buf).unwrap() == 0);
+
+            // Overwrite within and append to the last partial page
+            let overwrite_offset = 2 * Page::size() + 10;
+            let overwrite_buf = vec![0xaa; 20];
+            file.write_exact_at(overwrite_offset, &overwrite_buf)
+                .await
+                .unwrap();
+            let append_buf = vec![0xbb; 50];
+            file.write_exact_at(data_len, &append_buf).await.unwrap();
+            file.flush().await.unwrap();
+            drop(file);
+
+            let new_data_len = data_len + append_buf.len();
+            assert!(std::fs::metadata(path).unwrap().len() == new_data_len as u64);
+            let expected_data = {
+                let mut expected_data = data.clone();
+                expected_data[overwrite_offset..overwrite_offset + overwrite_buf.len()]
+                    .copy_from_slice(&overwrite_buf);
+                expected_data.extend_from_slice(&append_buf);
+                expected_data
+            };
+            let file = open_file(libc::O_RDONLY);
+            let mut output_buf = vec![0xff; new_data_len];
+            file.read_exact_at(0, &mut output_buf).await.unwrap();
+            assert!(output_buf == expected_data);
+        });
+    }
+
     // #[test]
     // fn bench_random() {
     //     use std::time::{Duration, Instant};
@@ -9,4 +9,24 @@ use super::Events;
 pub trait Observer: Send + Sync + 'static {
     /// Notify the observer that some interesting events happen on the pollee.
     fn on_events(&self, pollee_id: u64, events: Events);
+
+    /// Returns whether the observer is an exclusive one.
+    ///
+    /// When some events happen on a pollee, all non-exclusive observers get
+    /// notified, while the exclusive ones get notified one after another until
+    /// one of them claims the events (see `on_exclusive_events`). The return
+    /// value is only checked once when the observer is being registered.
+    fn is_exclusive(&self) -> bool {
+        false
+    }
+
+    /// Notify an exclusive observer that some interesting events happen on the pollee.
+    ///
+    /// The return value indicates whether the observer has claimed the events,
+    /// e.g., by waking up some waiter. If it has not, the pollee will go on to
+    /// notify the next exclusive observer.
+    fn on_exclusive_events(&self, pollee_id: u64, events: Events) -> bool {
+        self.on_events(pollee_id, events);
+        true
+    }
 }
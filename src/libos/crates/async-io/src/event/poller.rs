@@ -21,6 +21,8 @@ struct PolleeInner {
     id: ObjectId,
     // A table that maintains all interesting pollers
     pollers: Mutex<HashMap<KeyableArc<dyn Observer>, Events>>,
+    // All exclusive observers, which are kept in the order of registration
+    exclusive_observers: Mutex<Vec<(KeyableArc<dyn Observer>, Events)>>,
     // For efficient manipulation, we use AtomicU32 instead of Atomic<Events>
     events: AtomicU32,
     // To reduce lock contentions, we maintain a counter for the size of the table
@@ -33,6 +35,7 @@ impl Pollee {
         let inner = PolleeInner {
             id: ObjectId::new(),
             pollers: Mutex::new(HashMap::new()),
+            exclusive_observers: Mutex::new(Vec::new()),
             events: AtomicU32::new(init_events.bits()),
             num_pollers: AtomicUsize::new(0),
         };
@@ -86,8 +89,10 @@ impl Pollee {
 
     /// Add some events to the pollee's state.
     ///
-    /// This method wakes up all registered pollers that are interested in
-    /// the added events.
+    /// This method wakes up all registered pollers and non-exclusive observers
+    /// that are interested in the added events. As for the exclusive observers,
+    /// they are notified in the order of registration until one of them claims
+    /// the events.
     pub fn add_events(&self, events: Events) {
         self.inner.events.fetch_or(events.bits(), Ordering::Release);
 
@@ -102,6 +107,15 @@ impl Pollee {
             .iter()
             .filter(|(_, mask)| mask.intersects(events))
             .for_each(|(poller, mask)| poller.on_events(self.inner.id.get(), events & *mask));
+        drop(pollers);
+
+        let exclusive_observers = self.inner.exclusive_observers.lock();
+        let _ = exclusive_observers
+            .iter()
+            .filter(|(_, mask)| mask.intersects(events))
+            .any(|(observer, mask)| {
+                observer.on_exclusive_events(self.inner.id.get(), events & *mask)
+            });
     }
 
     /// Remove some events from the pollee's state.
@@ -135,6 +149,10 @@ impl Pollee {
     /// Note that the observer will always get notified of the events in
     /// `Events::ALWAYS_POLL` regardless of the value of `masks`.
     ///
+    /// If the observer is an exclusive one (see `Observer::is_exclusive`), then
+    /// it shares the events with other exclusive observers: each time new events
+    /// happen, only one exclusive observer is supposed to claim them.
+    ///
     /// # Memory leakage
     ///
     /// Since an `Arc` for each observer is kept internally by a pollee,
@@ -142,6 +160,10 @@ impl Pollee {
     /// when the observer is no longer interested in the pollee. Otherwise,
     /// the observer will not be dropped.
     pub fn register_observer(&self, observer: Arc<dyn Observer>, mask: Events) {
+        if observer.is_exclusive() {
+            return self.register_exclusive_observer(observer.into(), mask);
+        }
+
         let observer: KeyableArc<dyn Observer> = observer.into();
 
         let mut pollers = self.inner.pollers.lock();
@@ -162,9 +184,34 @@ impl Pollee {
         let observer: &KeyableArc<dyn Observer> = unsafe { core::mem::transmute(observer) };
 
         let mut pollers = self.inner.pollers.lock();
-        pollers
+        let removed = pollers
             .remove_entry(observer)
-            .map(|(observer, _mask)| observer.into())
+            .map(|(observer, _mask)| observer)
+            .or_else(|| {
+                let mut exclusive_observers = self.inner.exclusive_observers.lock();
+                let pos = exclusive_observers
+                    .iter()
+                    .position(|(exclusive_observer, _)| exclusive_observer == observer)?;
+                Some(exclusive_observers.remove(pos).0)
+            });
+        if removed.is_some() {
+            self.inner.num_pollers.fetch_sub(1, Ordering::Relaxed);
+        }
+        removed.map(|observer| observer.into())
+    }
+
+    fn register_exclusive_observer(&self, observer: KeyableArc<dyn Observer>, mask: Events) {
+        let mut exclusive_observers = self.inner.exclusive_observers.lock();
+        match exclusive_observers
+            .iter_mut()
+            .find(|(exclusive_observer, _)| *exclusive_observer == observer)
+        {
+            Some((_, old_mask)) => *old_mask = mask,
+            None => {
+                exclusive_observers.push((observer, mask));
+                self.inner.num_pollers.fetch_add(1, Ordering::Release);
+            }
+        }
     }
 
     fn events(&self) -> Events {
@@ -308,4 +355,76 @@ mod tests {
         pollee.unregister_observer(&(counter.clone() as _));
         assert!(counter.count() == expected_count);
     }
+
+    #[test]
+    fn subscribe_exclusively() {
+        use std::sync::atomic::{AtomicBool, AtomicU64, Ordering::Relaxed};
+
+        struct ExclusiveCounter {
+            count: AtomicU64,
+            is_busy: AtomicBool,
+        }
+
+        impl ExclusiveCounter {
+            pub fn new() -> Self {
+                Self {
+                    count: AtomicU64::new(0),
+                    is_busy: AtomicBool::new(false),
+                }
+            }
+
+            pub fn count(&self) -> u64 {
+                self.count.load(Relaxed)
+            }
+        }
+
+        impl Observer for ExclusiveCounter {
+            fn on_events(&self, _pollee_id: u64, _events: Events) {
+                unreachable!("an exclusive observer is notified via on_exclusive_events");
+            }
+
+            fn is_exclusive(&self) -> bool {
+                true
+            }
+
+            fn on_exclusive_events(&self, _pollee_id: u64, _events: Events) -> bool {
+                if self.is_busy.load(Relaxed) {
+                    return false;
+                }
+                self.count.fetch_add(1, Relaxed);
+                true
+            }
+        }
+
+        let pollee = Pollee::new(Events::empty());
+        let counters: Vec<Arc<ExclusiveCounter>> =
+            (0..3).map(|_| Arc::new(ExclusiveCounter::new())).collect();
+        counters
+            .iter()
+            .for_each(|counter| pollee.register_observer(counter.clone(), Events::IN));
+
+        // Only the first observer that claims the events gets notified
+        (0..10).for_each(|_| pollee.add_events(Events::IN));
+        assert!(counters[0].count() == 10);
+        assert!(counters[1].count() == 0 && counters[2].count() == 0);
+
+        // A busy observer passes the events on to the next one
+        counters[0].is_busy.store(true, Relaxed);
+        (0..10).for_each(|_| pollee.add_events(Events::IN));
+        assert!(counters[0].count() == 10);
+        assert!(counters[1].count() == 10);
+        assert!(counters[2].count() == 0);
+
+        // An unregistered observer no longer gets notified
+        assert!(pollee
+            .unregister_observer(&(counters[1].clone() as _))
+            .is_some());
+        (0..10).for_each(|_| pollee.add_events(Events::IN));
+        assert!(counters[1].count() == 10);
+        assert!(counters[2].count() == 10);
+
+        counters.iter().for_each(|counter| {
+            pollee.unregister_observer(&(counter.clone() as _));
+        });
+    }
 }
@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::fmt::Debug;
 use std::mem::transmute;
 use std::ops::CoerceUnsized;
@@ -19,15 +20,26 @@ pub trait File: Debug + Sync + Send {
         return_errno!(EINVAL, "not support read");
     }
 
+    /// Read data into a set of buffers.
+    ///
+    /// Like Linux, the reading stops at the first buffer that cannot be
+    /// filled up and the number of bytes read so far is returned. An error is
+    /// returned only if no bytes have been read.
     fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
         let mut ret_len = 0;
         for buf in bufs {
-            if buf.len() > 0 {
-                let ret = self.read(buf)?;
-                if ret == 0 {
-                    return Ok(ret_len);
+            if buf.len() == 0 {
+                continue;
+            }
+            match self.read(buf) {
+                Ok(ret) => {
+                    ret_len += ret;
+                    if ret < buf.len() {
+                        break;
+                    }
                 }
-                ret_len += ret;
+                Err(_) if ret_len > 0 => break,
+                Err(e) => return Err(e),
             }
         }
         Ok(ret_len)
@@ -37,15 +49,26 @@ pub trait File: Debug + Sync + Send {
         return_errno!(EINVAL, "not support write");
     }
 
+    /// Write data from a set of buffers.
+    ///
+    /// Like Linux, the writing stops at the first buffer that cannot be
+    /// written entirely and the number of bytes written so far is returned.
+    /// An error is returned only if no bytes have been written.
     fn writev(&self, bufs: &[&[u8]]) -> Result<usize> {
         let mut ret_len = 0;
         for buf in bufs {
-            if buf.len() > 0 {
-                let ret = self.write(buf)?;
-                if ret == 0 {
-                    return Ok(ret_len);
+            if buf.len() == 0 {
+                continue;
+            }
+            match self.write(buf) {
+                Ok(ret) => {
+                    ret_len += ret;
+                    if ret < buf.len() {
+                        break;
+                    }
                 }
-                ret_len += ret;
+                Err(_) if ret_len > 0 => break,
+                Err(e) => return Err(e),
             }
         }
         Ok(ret_len)
@@ -82,6 +105,12 @@ pub trait File: Debug + Sync + Send {
     fn stat(&self) -> StatBuf {
         Default::default()
     }
+
+    /// Returns the file as `Any` so that its concrete type can be recovered,
+    /// if the file supports that.
+    fn as_any(&self) -> Option<&dyn Any> {
+        None
+    }
 }
 
 /// A wrapper type that makes a `T: File`'s I/O methods _async_.
@@ -80,9 +80,8 @@ impl CreationFlags {
     }
 
     pub fn must_be_directory(&self) -> bool {
+        // The path given with O_TMPFILE is the directory to create the file in
         if self.contains(CreationFlags::_O_TMPFILE) {
-            // TODO: add a warning
-            //warn!("O_TMPFILE is not supported, handle it as O_DIRECTORY");
             return true;
         }
         self.contains(CreationFlags::O_DIRECTORY)
@@ -91,6 +90,11 @@ impl CreationFlags {
     pub fn should_truncate(&self) -> bool {
         self.contains(CreationFlags::O_TRUNC)
     }
+
+    /// Returns whether O_TMPFILE is given, which may lack its O_DIRECTORY part.
+    pub fn is_tmpfile(&self) -> bool {
+        self.contains(CreationFlags::_O_TMPFILE)
+    }
 }
 
 bitflags! {
@@ -1,3 +1,4 @@
+use std::mem::MaybeUninit;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use atomic::Atomic;
@@ -150,6 +151,38 @@ impl<T> Common<T> {
     pub fn capacity(&self) -> usize {
         self.producer.ringbuf.lock().capacity()
     }
+
+    /// Replace the ring buffer with a new one of `new_capacity`, moving the
+    /// stored items into it.
+    pub fn resize(&self, new_capacity: usize) -> Result<()> {
+        if new_capacity == 0 {
+            return_errno!(EINVAL, "capacity cannot be zero");
+        }
+
+        // Both ends are locked so that no item could be pushed or popped
+        // during the resizing
+        let mut rb_producer = self.producer.ringbuf();
+        let mut rb_consumer = self.consumer.ringbuf();
+        if new_capacity < rb_consumer.len() {
+            return_errno!(EBUSY, "capacity is smaller than the stored items");
+        }
+
+        let (mut new_rb_producer, new_rb_consumer) = RingBuffer::new(new_capacity).split();
+        while let Some(item) = rb_consumer.pop() {
+            // The new ring buffer is large enough to hold all the items
+            let _ = new_rb_producer.push(item);
+        }
+        *rb_producer = new_rb_producer;
+        *rb_consumer = new_rb_consumer;
+
+        self.lock_event();
+        if rb_producer.is_full() {
+            self.producer.pollee().del_events(Events::OUT);
+        } else {
+            self.producer.pollee().add_events(Events::OUT);
+        }
+        Ok(())
+    }
 }
 
 impl<T> EndPoint<T> {
@@ -202,6 +235,23 @@ impl<T> Producer<T> {
         self.peer_end().is_shutdown()
     }
 
+    pub fn capacity(&self) -> usize {
+        self.common.capacity()
+    }
+
+    /// Resize the buffer of the channel, which must be able to hold the data
+    /// stored in it. Otherwise, EBUSY is returned.
+    pub fn resize(&self, new_capacity: usize) -> Result<()> {
+        self.common.resize(new_capacity)
+    }
+
+    // Get the length of free space in the buffer
+    pub fn free_len(&self) -> usize {
+        let this_end = self.this_end();
+        let rb = this_end.ringbuf();
+        rb.remaining()
+    }
+
     fn update_pollee(&self) {
         let this_end = self.this_end();
         let peer_end = self.peer_end();
@@ -304,6 +354,41 @@ impl Producer<u8> {
     }
 }
 
+impl Producer<u8> {
+    /// Hand the free space of the buffer to `f`, and produce the number of bytes
+    /// that `f` returns, which must have been filled by `f`.
+    ///
+    /// The free space is given in at most two slices. The buffer is locked while `f`
+    /// runs, so the space cannot be taken by anyone else in the meantime.
+    pub fn produce_with<F>(&self, f: F) -> Result<usize>
+    where
+        F: FnOnce(&mut [u8], &mut [u8]) -> Result<usize>,
+    {
+        if self.this_end().is_shutdown() || self.peer_end().is_shutdown() {
+            return_errno!(EPIPE, "");
+        }
+
+        let nbytes = {
+            let mut rb = self.this_end().ringbuf();
+            let mut res = Ok(0);
+            // Safety. Any bytes are valid u8 values. And only the bytes filled by `f`
+            // are pushed.
+            unsafe {
+                rb.push_access(|first, second| {
+                    let first = &mut *(first as *mut [MaybeUninit<u8>] as *mut [u8]);
+                    let second = &mut *(second as *mut [MaybeUninit<u8>] as *mut [u8]);
+                    let max_len = first.len() + second.len();
+                    res = f(first, second).map(|nbytes| nbytes.min(max_len));
+                    *res.as_ref().unwrap_or(&0)
+                });
+            }
+            res?
+        };
+        self.update_pollee();
+        Ok(nbytes)
+    }
+}
+
 impl File for Producer<u8> {
     fn write(&self, buf: &[u8]) -> Result<usize> {
         let this_end = self.this_end();
@@ -412,6 +497,20 @@ impl<T> Consumer<T> {
         }
     }
 
+    pub fn peer_is_shutdown(&self) -> bool {
+        self.peer_end().is_shutdown()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.common.capacity()
+    }
+
+    /// Resize the buffer of the channel, which must be able to hold the data
+    /// stored in it. Otherwise, EBUSY is returned.
+    pub fn resize(&self, new_capacity: usize) -> Result<()> {
+        self.common.resize(new_capacity)
+    }
+
     // Get the length of data stored in the buffer
     pub fn ready_len(&self) -> usize {
         let this_end = self.this_end();
@@ -506,6 +605,29 @@ impl Consumer<u8> {
     }
 }
 
+impl Consumer<u8> {
+    /// Hand the data stored in the buffer to `f`, and consume the number of bytes
+    /// that `f` returns.
+    ///
+    /// The data are given in at most two slices. The buffer is locked while `f` runs,
+    /// so the data cannot be taken by anyone else in the meantime.
+    pub fn consume_with<F>(&self, f: F) -> Result<usize>
+    where
+        F: FnOnce(&[u8], &[u8]) -> Result<usize>,
+    {
+        let nbytes = {
+            let mut rb = self.this_end().ringbuf();
+            let mut res = Ok(0);
+            rb.access(|first, second| {
+                res = f(first, second);
+            });
+            rb.discard(res?)
+        };
+        self.update_pollee();
+        Ok(nbytes)
+    }
+}
+
 impl File for Consumer<u8> {
     fn read(&self, buf: &mut [u8]) -> Result<usize> {
         let this_end = self.this_end();
@@ -697,6 +819,112 @@ mod tests {
         assert!(producer.poll(mask, None) == Events::empty());
         assert!(consumer.poll(mask, None) == Events::IN);
     }
+
+    #[test]
+    fn partial_writev_and_readv() {
+        const CHANNEL_CAPACITY: usize = 8;
+
+        let channel = Channel::with_capacity(CHANNEL_CAPACITY).unwrap();
+        let (producer, consumer) = channel.split();
+
+        // The channel fills up in the middle of the second buffer
+        let first = [1u8; 4];
+        let second = [2u8; 8];
+        let nbytes = producer.writev(&[&first, &second]).unwrap();
+        assert!(nbytes == CHANNEL_CAPACITY);
+        assert!(producer.writev(&[&second[4..]]).has_errno(EAGAIN));
+
+        // The data runs out in the middle of the second buffer
+        let mut first_buf = [0u8; 6];
+        let mut second_buf = [0u8; 6];
+        let nbytes = consumer
+            .readv(&mut [&mut first_buf, &mut second_buf])
+            .unwrap();
+        assert!(nbytes == CHANNEL_CAPACITY);
+        assert!(first_buf == [1, 1, 1, 1, 2, 2]);
+        assert!(second_buf == [2, 2, 0, 0, 0, 0]);
+
+        // Resume the writing from where it stopped
+        let nbytes = producer.writev(&[&second[4..]]).unwrap();
+        assert!(nbytes == 4);
+        let mut buf = [0u8; CHANNEL_CAPACITY];
+        let nbytes = consumer.readv(&mut [&mut buf]).unwrap();
+        assert!(nbytes == 4);
+        assert!(buf[..nbytes] == [2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn produce_and_consume_with() {
+        let channel = Channel::with_capacity(8).unwrap();
+        let (producer, consumer) = channel.split();
+        let mask = Events::all();
+
+        // Only the bytes reported by the closure are produced
+        let nbytes = producer
+            .produce_with(|first, second| {
+                assert!(first.len() + second.len() == 8);
+                first[..5].copy_from_slice(b"hello");
+                Ok(5)
+            })
+            .unwrap();
+        assert!(nbytes == 5);
+        assert!(consumer.ready_len() == 5);
+        assert!(consumer.poll(mask, None) == Events::IN);
+
+        // An error produces nothing
+        let err = producer
+            .produce_with(|_, _| Err(errno!(EIO, "")))
+            .unwrap_err();
+        assert!(err.errno() == EIO);
+        assert!(consumer.ready_len() == 5);
+
+        // Only the bytes reported by the closure are consumed
+        let nbytes = consumer
+            .consume_with(|first, second| {
+                assert!(first.len() + second.len() == 5);
+                assert!(&first[..3] == b"hel");
+                Ok(3)
+            })
+            .unwrap();
+        assert!(nbytes == 3);
+        let err = consumer
+            .consume_with(|_, _| Err(errno!(EIO, "")))
+            .unwrap_err();
+        assert!(err.errno() == EIO);
+        let mut buf = [0u8; 8];
+        assert!(consumer.read(&mut buf).unwrap() == 2);
+        assert!(&buf[..2] == b"lo");
+        assert!(consumer.poll(mask, None) == Events::empty());
+    }
+
+    #[test]
+    fn resize() {
+        let channel = Channel::with_capacity(4).unwrap();
+        let (producer, consumer) = channel.split();
+        let mask = Events::all();
+
+        assert!(producer.write(b"hello").unwrap() == 4);
+        assert!(producer.poll(mask, None) == Events::empty());
+
+        // Cannot be smaller than the stored data
+        assert!(consumer.resize(3).unwrap_err().errno() == EBUSY);
+
+        // Growing keeps the stored data
+        producer.resize(8).unwrap();
+        assert!(consumer.capacity() == 8);
+        assert!(producer.poll(mask, None) == Events::OUT);
+        assert!(producer.write(b"o, world").unwrap() == 4);
+
+        let mut buf = [0u8; 16];
+        assert!(consumer.read(&mut buf).unwrap() == 8);
+        assert!(&buf[..8] == b"hello, w");
+
+        // Shrinking an empty channel
+        consumer.resize(2).unwrap();
+        assert!(producer.capacity() == 2);
+        assert!(producer.write(b"or").unwrap() == 2);
+        assert!(producer.poll(mask, None) == Events::empty());
+    }
 }
 
 fn check_status_flags(flags: StatusFlags) -> Result<()> {
@@ -8,17 +8,75 @@ pub fn set_parallelism(parallelism: u32) {
     CONFIG.set_parallelism(parallelism);
 }
 
+/// Set the scheduling budget of new tasks.
+///
+/// The budget of a task is the number of times that the task can be polled
+/// before the scheduler picks a vCPU for it anew, instead of sticking to its last
+/// vCPU. A smaller budget balances the load more eagerly, while a larger budget
+/// favors the cache locality of long-running tasks. The default budget is 64.
+///
+/// This function must be called before spawning the tasks to take effect on them.
+/// A zero budget is not allowed.
+pub fn set_task_budget(budget: u8) {
+    CONFIG.set_task_budget(budget);
+}
+
+/// Set the base interval of the periodic load balancing of the priority scheduler.
+///
+/// The priority scheduler rebalances the workloads of the vCPUs once every
+/// `interval * parallelism` dequeues. A smaller interval suits the workloads of
+/// short-lived tasks, which otherwise leave the vCPUs imbalanced between two
+/// rebalancings. The default interval is 128.
+///
+/// This function must be called before using the executor to take effect.
+/// A zero interval is not allowed.
+pub fn set_rebalance_interval(interval: u64) {
+    CONFIG.set_rebalance_interval(interval);
+}
+
+/// Set the max number of the OS threads that run the closures of `task::spawn_blocking`.
+///
+/// The threads are spawned on demand and are separate from the threads that run
+/// the executor. The default number is 4.
+///
+/// This function must be called before calling `task::spawn_blocking` to take
+/// effect. A zero number is not allowed.
+pub fn set_blocking_threads(num: u32) {
+    CONFIG.set_blocking_threads(num);
+}
+
+/// Make the runtime use a deterministic test clock instead of the real clock.
+///
+/// With the test clock, `Instant::now()` and the firing of timers are driven
+/// by explicit calls to `crate::time::advance`, not by the passage of real time.
+/// This function must be called before using the executor to take effect.
+pub fn use_test_clock() {
+    crate::time::enable_test_clock();
+}
+
 pub(crate) struct Config {
     inner: Mutex<Inner>,
 }
 
 struct Inner {
     parallelism: u32,
+    task_budget: u8,
+    rebalance_interval: u64,
+    blocking_threads: u32,
 }
 
+const DEFAULT_TASK_BUDGET: u8 = 64;
+const DEFAULT_REBALANCE_INTERVAL: u64 = 128;
+const DEFAULT_BLOCKING_THREADS: u32 = 4;
+
 impl Config {
     pub fn new() -> Self {
-        let inner = Inner { parallelism: 1 };
+        let inner = Inner {
+            parallelism: 1,
+            task_budget: DEFAULT_TASK_BUDGET,
+            rebalance_interval: DEFAULT_REBALANCE_INTERVAL,
+            blocking_threads: DEFAULT_BLOCKING_THREADS,
+        };
         Self {
             inner: Mutex::new(inner),
         }
@@ -34,6 +92,39 @@ impl Config {
         let inner = self.inner.lock();
         inner.parallelism
     }
+
+    pub fn set_task_budget(&self, budget: u8) {
+        assert!(budget > 0);
+        let mut inner = self.inner.lock();
+        inner.task_budget = budget;
+    }
+
+    pub fn task_budget(&self) -> u8 {
+        let inner = self.inner.lock();
+        inner.task_budget
+    }
+
+    pub fn set_rebalance_interval(&self, interval: u64) {
+        assert!(interval > 0);
+        let mut inner = self.inner.lock();
+        inner.rebalance_interval = interval;
+    }
+
+    pub fn rebalance_interval(&self) -> u64 {
+        let inner = self.inner.lock();
+        inner.rebalance_interval
+    }
+
+    pub fn set_blocking_threads(&self, num: u32) {
+        assert!(num > 0);
+        let mut inner = self.inner.lock();
+        inner.blocking_threads = num;
+    }
+
+    pub fn blocking_threads(&self) -> u32 {
+        let inner = self.inner.lock();
+        inner.blocking_threads
+    }
 }
 
 lazy_static! {
@@ -1,3 +1,6 @@
+use core::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
 use futures::task::waker_ref;
 
 use crate::config::CONFIG;
@@ -5,12 +8,30 @@ use crate::parks::Parks;
 use crate::prelude::*;
 #[allow(unused_imports)]
 use crate::sched::{BasicScheduler, PriorityScheduler, Scheduler};
-use crate::task::Task;
+use crate::stats::{self, LatencyHistogram, SchedStats, VcpuStatsCounter};
+use crate::task::{Task, TaskId};
+#[cfg(feature = "sgx")]
+use std::thread::SgxThread as Thread;
+#[cfg(not(feature = "sgx"))]
+use std::thread::Thread;
 
 pub fn parallelism() -> u32 {
     EXECUTOR.parallelism()
 }
 
+/// Returns the number of runnable tasks queued on each vCPU, indexed by the vCPU IDs.
+///
+/// The numbers are cheap-to-get snapshots, which are suitable for being polled
+/// frequently, e.g., by a metrics exporter.
+pub fn load_per_vcpu() -> Vec<usize> {
+    EXECUTOR.load_per_vcpu()
+}
+
+/// Returns the total number of runnable tasks queued on all vCPUs.
+pub fn total_load() -> usize {
+    EXECUTOR.load_per_vcpu().iter().sum()
+}
+
 // Returning number of running vcpus
 pub fn run_tasks() -> u32 {
     EXECUTOR.run_tasks()
@@ -20,6 +41,58 @@ pub fn shutdown() {
     EXECUTOR.shutdown()
 }
 
+/// Shut down the executor after draining the runnable tasks.
+///
+/// New tasks are no longer accepted (see `task::try_spawn`), but the tasks that are already runnable keep
+/// being executed until all of them are pending or completed, or until `max_passes`
+/// passes are done. In each pass, the vCPUs execute the tasks that are runnable at
+/// the beginning of the pass. Then the executor is shut down as by `shutdown`.
+///
+/// Returns the number of the tasks that are still runnable after the drain.
+///
+/// This function must not be called by a vCPU, as it waits for the vCPUs to run
+/// the tasks.
+pub fn shutdown_gracefully(max_passes: u32) -> usize {
+    EXECUTOR.shutdown_gracefully(max_passes)
+}
+
+/// Shut down the executor after draining the runnable tasks, with a deadline.
+///
+/// This function works like `shutdown_gracefully`, except that the drain lasts
+/// until no task is runnable or the timeout expires. In the latter case, the vCPUs
+/// are forced to stop without executing the remaining runnable tasks. After the
+/// executor is shut down, this function waits for the vCPUs to return from
+/// `run_tasks` until the deadline.
+///
+/// Returns an error if the runnable tasks are not drained or the vCPUs have not
+/// all returned by the deadline.
+///
+/// This function must not be called by a vCPU, as it waits for the vCPUs to run
+/// the tasks.
+pub fn shutdown_timeout(timeout: Duration) -> core::result::Result<(), ShutdownTimedOut> {
+    EXECUTOR.shutdown_timeout(timeout)
+}
+
+/// The error of `shutdown_timeout` when the deadline is missed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownTimedOut {
+    /// The number of the tasks that were left runnable.
+    pub num_runnable_tasks: usize,
+    /// The number of the vCPUs that were still in `run_tasks`.
+    pub num_running_vcpus: u32,
+}
+
+/// Set the handler to be invoked when the future of a task panics.
+///
+/// A panicking task is aborted (see `Task::is_aborted`): its future is dropped and
+/// the vCPU goes on to run other tasks. The handler is given the ID of the task and
+/// the payload of the panic. Without a handler, the panic is only logged.
+pub fn set_panic_handler(handler: impl FnMut(TaskId, Box<dyn Any + Send>) + Send + 'static) {
+    EXECUTOR.set_panic_handler(Box::new(handler))
+}
+
+type PanicHandler = Box<dyn FnMut(TaskId, Box<dyn Any + Send>) + Send>;
+
 lazy_static! {
     pub(crate) static ref EXECUTOR: Executor = {
         let parallelism = CONFIG.parallelism();
@@ -32,8 +105,18 @@ pub(crate) struct Executor {
     running_vcpu_num: AtomicU32,
     next_thread_id: AtomicU32,
     is_shutdown: AtomicBool,
+    is_draining: AtomicBool,
+    should_stop: AtomicBool,
+    // The number of the tasks that are enqueued or being executed
+    num_runnable_tasks: AtomicU64,
+    num_executed_tasks: AtomicU64,
     parks: Arc<Parks>,
     scheduler: Box<dyn Scheduler>,
+    vcpu_stats: Vec<VcpuStatsCounter>,
+    sched_latency: LatencyHistogram,
+    panic_handler: Mutex<Option<PanicHandler>>,
+    // The thread that waits for the runnable tasks to drain
+    drain_waiter: Mutex<Option<Thread>>,
 }
 
 impl Executor {
@@ -48,14 +131,23 @@ impl Executor {
         let parks = Arc::new(Parks::new(parallelism));
         let scheduler = Box::new(BasicScheduler::new(parks.clone()));
         // let scheduler = Box::new(PriorityScheduler::new(parks.clone()));
+        let vcpu_stats = (0..parallelism).map(|_| VcpuStatsCounter::new()).collect();
 
         let new_self = Self {
             parallelism,
             running_vcpu_num,
             next_thread_id,
             is_shutdown,
+            is_draining: AtomicBool::new(false),
+            should_stop: AtomicBool::new(false),
+            num_runnable_tasks: AtomicU64::new(0),
+            num_executed_tasks: AtomicU64::new(0),
             parks,
             scheduler,
+            vcpu_stats,
+            sched_latency: LatencyHistogram::new(),
+            panic_handler: Mutex::new(None),
+            drain_waiter: Mutex::new(None),
         };
         Ok(new_self)
     }
@@ -64,6 +156,14 @@ impl Executor {
         self.parallelism
     }
 
+    pub fn load_per_vcpu(&self) -> Vec<usize> {
+        self.scheduler.load_per_vcpu()
+    }
+
+    pub fn sched_stats_per_vcpu(&self) -> Vec<SchedStats> {
+        self.scheduler.sched_stats_per_vcpu()
+    }
+
     pub fn run_tasks(&self) -> u32 {
         let thread_id = self.next_thread_id.fetch_add(1, Ordering::Relaxed) as usize;
         assert!(thread_id < self.parallelism as usize);
@@ -73,25 +173,49 @@ impl Executor {
         debug!("run tasks on vcpu {}", thread_id);
 
         self.parks.register(thread_id);
+        let vcpu_stats = &self.vcpu_stats[thread_id];
+        vcpu_stats.start();
 
         loop {
-            let task_option = self.scheduler.dequeue_task(thread_id);
+            // Leave the remaining tasks alone if forced to stop
+            let task_option = if self.should_stop.load(Ordering::Relaxed) {
+                None
+            } else {
+                self.scheduler.dequeue_task(thread_id)
+            };
 
             // Stop the executor iff all the ready tasks are executed
             if self.is_shutdown() && task_option.is_none() {
-                let num = self.running_vcpu_num.fetch_sub(1, Ordering::Relaxed) as u32;
+                let num = self.running_vcpu_num.fetch_sub(1, Ordering::SeqCst) as u32;
                 assert!(num >= 1);
                 self.parks.unregister(thread_id);
+                if self.is_draining.load(Ordering::SeqCst) {
+                    self.unpark_drain_waiter();
+                }
                 return num - 1;
             }
 
             match task_option {
                 Some(task) => {
                     task.reset_enqueued();
+                    if let Some(latency) = task.sched_info().on_dequeued(stats::now_nanos()) {
+                        self.sched_latency.record(latency);
+                    }
 
-                    self.execute_task(task)
+                    vcpu_stats.inc_switches();
+                    self.execute_task(task);
+
+                    self.num_executed_tasks.fetch_add(1, Ordering::SeqCst);
+                    self.num_runnable_tasks.fetch_sub(1, Ordering::SeqCst);
+                    if self.is_draining.load(Ordering::SeqCst) {
+                        self.unpark_drain_waiter();
+                    }
+                }
+                None => {
+                    let park_start = stats::now_nanos();
+                    self.parks.park();
+                    vcpu_stats.add_idle_time(stats::now_nanos().saturating_sub(park_start));
                 }
-                None => self.parks.park(),
             }
         }
     }
@@ -112,21 +236,50 @@ impl Executor {
 
         let waker = waker_ref(&task);
         let context = &mut Context::from_waker(&*waker);
-        if let Poll::Pending = future.as_mut().poll(context) {
-            *future_slot = Some(future);
+        // Catch the panic of the future so that the vCPU keeps running other tasks
+        match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(context))) {
+            Ok(Poll::Pending) => {
+                *future_slot = Some(future);
+            }
+            Ok(Poll::Ready(())) => {}
+            Err(payload) => {
+                task.set_aborted();
+                drop(future);
+                drop(future_slot);
+                self.handle_panic(task.tid(), payload);
+            }
         }
 
         crate::task::current::reset();
     }
 
+    pub fn set_panic_handler(&self, handler: PanicHandler) {
+        *self.panic_handler.lock() = Some(handler);
+    }
+
+    fn handle_panic(&self, tid: TaskId, payload: Box<dyn Any + Send>) {
+        let mut panic_handler = self.panic_handler.lock();
+        match panic_handler.as_mut() {
+            Some(handler) => handler(tid, payload),
+            None => error!("task {:?} is aborted due to a panic", tid),
+        }
+    }
+
     /// Accept a new task and schedule it.
-    pub fn accept_task(&self, task: Arc<Task>) {
-        if self.is_shutdown() {
-            panic!("a shut-down executor cannot spawn new tasks");
+    ///
+    /// A shut-down or draining executor rejects the task. The task is then aborted
+    /// and its future is dropped.
+    pub fn accept_task(&self, task: Arc<Task>) -> Result<()> {
+        if self.is_shutdown() || self.is_draining.load(Ordering::SeqCst) {
+            task.set_aborted();
+            drop(task.future().lock().take());
+            return_errno!(ESHUTDOWN, "a shut-down executor cannot spawn new tasks");
         }
 
         task.try_set_enqueued().unwrap();
-        self.scheduler.enqueue_task(task);
+        self.num_runnable_tasks.fetch_add(1, Ordering::SeqCst);
+        self.enqueue_task(task);
+        Ok(())
     }
 
     /// Wake up an old task and schedule it.
@@ -146,8 +299,23 @@ impl Executor {
         if let Err(_) = task.try_set_enqueued() {
             return;
         }
+        self.num_runnable_tasks.fetch_add(1, Ordering::Relaxed);
+
+        self.enqueue_task(task.clone());
+    }
+
+    fn enqueue_task(&self, task: Arc<Task>) {
+        if stats::is_sched_latency_enabled() {
+            task.sched_info().set_enqueue_nanos(stats::now_nanos());
+        }
+        self.scheduler.enqueue_task(task);
+    }
 
-        self.scheduler.enqueue_task(task.clone());
+    /// Run `f` with the unpark operations caused by waking up tasks batched.
+    ///
+    /// See `Parks::batch_unparks`.
+    pub fn batch_unparks<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.parks.batch_unparks(f)
     }
 
     pub fn shutdown(&self) {
@@ -157,7 +325,165 @@ impl Executor {
         crate::time::wake_timer_wheel(&Duration::default()); // wake the time wheel right now
     }
 
+    pub fn shutdown_gracefully(&self, max_passes: u32) -> usize {
+        self.start_draining();
+
+        for _ in 0..max_passes {
+            let num_runnable = self.num_runnable_tasks.load(Ordering::SeqCst);
+            if num_runnable == 0 || self.running_vcpu_num.load(Ordering::Relaxed) == 0 {
+                break;
+            }
+
+            // Wait for the vCPUs to execute as many tasks as those runnable now,
+            // or until no task is runnable
+            let target = self.num_executed_tasks.load(Ordering::SeqCst) + num_runnable;
+            while self.num_executed_tasks.load(Ordering::SeqCst) < target
+                && self.num_runnable_tasks.load(Ordering::SeqCst) > 0
+            {
+                std::thread::park();
+            }
+        }
+
+        let num_runnable = self.num_runnable_tasks.load(Ordering::SeqCst) as usize;
+        self.shutdown();
+        num_runnable
+    }
+
+    pub fn shutdown_timeout(
+        &self,
+        timeout: Duration,
+    ) -> core::result::Result<(), ShutdownTimedOut> {
+        let deadline = stats::now_nanos().saturating_add(timeout.as_nanos() as u64);
+        self.start_draining();
+
+        let num_runnable_tasks = loop {
+            let num_runnable = self.num_runnable_tasks.load(Ordering::SeqCst) as usize;
+            let now = stats::now_nanos();
+            if num_runnable == 0 || now >= deadline {
+                break num_runnable;
+            }
+            std::thread::park_timeout(Duration::from_nanos(deadline - now));
+        };
+        if num_runnable_tasks > 0 {
+            self.should_stop.store(true, Ordering::Relaxed);
+        }
+        self.shutdown();
+
+        // Wait for the vCPUs to return, which unpark the current thread when
+        // they do so
+        let num_running_vcpus = loop {
+            let num_running = self.running_vcpu_num.load(Ordering::SeqCst);
+            let now = stats::now_nanos();
+            if num_running == 0 || now >= deadline {
+                break num_running;
+            }
+            std::thread::park_timeout(Duration::from_nanos(deadline - now));
+        };
+
+        if num_runnable_tasks > 0 || num_running_vcpus > 0 {
+            return Err(ShutdownTimedOut {
+                num_runnable_tasks,
+                num_running_vcpus,
+            });
+        }
+        Ok(())
+    }
+
+    // Stop accepting new tasks and let the vCPUs unpark the current thread
+    // whenever a task is executed, until the executor is shut down
+    fn start_draining(&self) {
+        *self.drain_waiter.lock() = Some(std::thread::current());
+        self.is_draining.store(true, Ordering::SeqCst);
+    }
+
+    fn unpark_drain_waiter(&self) {
+        if let Some(waiter) = self.drain_waiter.lock().as_ref() {
+            waiter.unpark();
+        }
+    }
+
     pub fn is_shutdown(&self) -> bool {
         self.is_shutdown.load(Ordering::Relaxed)
     }
+
+    pub fn vcpu_stats(&self) -> &[VcpuStatsCounter] {
+        &self.vcpu_stats
+    }
+
+    pub fn sched_latency(&self) -> &LatencyHistogram {
+        &self.sched_latency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shutdown_timeout_expires() {
+        // An executor without any vCPU, so its tasks never complete
+        let executor = Executor::new(parallelism()).unwrap();
+        let task = crate::task::TaskBuilder::new(core::future::pending::<()>()).build();
+        executor.accept_task(task).unwrap();
+
+        let timeout = Duration::from_millis(10);
+        let start = stats::now_nanos();
+        let err = ShutdownTimedOut {
+            num_runnable_tasks: 1,
+            num_running_vcpus: 0,
+        };
+        assert!(executor.shutdown_timeout(timeout) == Err(err));
+        assert!(stats::now_nanos() - start >= timeout.as_nanos() as u64);
+        assert!(executor.is_shutdown());
+    }
+
+    #[test]
+    fn shutdown_timeout_waits_for_vcpus() {
+        // An executor with two vCPUs, which are run by new threads
+        let executor: &'static Executor = Box::leak(Box::new(Executor::new(2).unwrap()));
+        let vcpus: Vec<_> = (0..2)
+            .map(|_| std::thread::spawn(move || executor.run_tasks()))
+            .collect();
+        while executor.running_vcpu_num.load(Ordering::SeqCst) < 2 {
+            core::hint::spin_loop();
+        }
+        let task = crate::task::TaskBuilder::new(async {}).build();
+        executor.accept_task(task).unwrap();
+
+        // Both vCPUs have returned once the shutdown succeeds
+        assert!(executor.shutdown_timeout(Duration::from_secs(10)) == Ok(()));
+        assert!(executor.running_vcpu_num.load(Ordering::SeqCst) == 0);
+        for vcpu in vcpus {
+            vcpu.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn spawn_during_shutdown() {
+        // An executor with a single vCPU, which is run by a new thread
+        let executor: &'static Executor = Box::leak(Box::new(Executor::new(1).unwrap()));
+        std::thread::spawn(move || executor.run_tasks());
+
+        // A task that spawns a new task once the executor starts draining
+        let is_rejected = Arc::new(AtomicBool::new(false));
+        let spawner = {
+            let is_rejected = is_rejected.clone();
+            crate::task::TaskBuilder::new(async move {
+                while !executor.is_draining.load(Ordering::SeqCst) {
+                    core::hint::spin_loop();
+                }
+                let task = crate::task::TaskBuilder::new(async {}).build();
+                let e = executor.accept_task(task.clone()).unwrap_err();
+                assert!(e.errno() == ESHUTDOWN);
+                assert!(task.is_aborted());
+                is_rejected.store(true, Ordering::Release);
+            })
+            .build()
+        };
+        executor.accept_task(spawner.clone()).unwrap();
+
+        assert!(executor.shutdown_gracefully(u32::MAX) == 0);
+        assert!(is_rejected.load(Ordering::Acquire));
+        assert!(!spawner.is_aborted());
+    }
 }
@@ -49,9 +49,11 @@ mod macros;
 mod parks;
 pub mod prelude;
 pub mod sched;
+pub mod stats;
 pub mod sync;
 pub mod task;
 pub mod time;
+pub mod vcpu;
 pub mod wait;
 
 // All unit tests
@@ -98,6 +100,59 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_task_locals_across_vcpus() {
+        use crate::sched::Affinity;
+        use std::cell::Cell;
+
+        task_local! {
+            static VALUE: Cell<u32> = Cell::new(0);
+        }
+
+        crate::task::block_on(async {
+            let current = crate::task::current::get();
+            VALUE.with(|value| value.set(42));
+
+            // Move the task to each vCPU in turn
+            for vcpu_id in 0..crate::executor::parallelism() as usize {
+                let mut affinity = Affinity::new_empty();
+                affinity.set(vcpu_id, true);
+                current.sched_info().set_affinity(&affinity).unwrap();
+                crate::sched::yield_().await;
+
+                assert!(crate::task::current::get_vcpu_id() as usize == vcpu_id);
+                assert!(VALUE.with(|value| value.get()) == 42);
+            }
+        });
+    }
+
+    #[test]
+    fn test_try_get_vcpu_id() {
+        // The thread of the test is not a vCPU
+        assert!(crate::task::current::try_get_vcpu_id().is_none());
+        assert!(crate::task::current::get_vcpu_id() == 0);
+
+        crate::task::block_on(async {
+            let vcpu_id = crate::task::current::try_get_vcpu_id().unwrap();
+            assert!(vcpu_id < crate::executor::parallelism());
+            assert!(crate::task::current::get_vcpu_id() == vcpu_id);
+        });
+    }
+
+    #[test]
+    fn test_load_per_vcpu() {
+        crate::task::block_on(async {
+            use crate::sched::MAX_QUEUED_TASKS;
+
+            let load_per_vcpu = crate::executor::load_per_vcpu();
+            assert!(load_per_vcpu.len() == crate::executor::parallelism() as usize);
+            assert!(load_per_vcpu.iter().all(|load| *load <= MAX_QUEUED_TASKS));
+
+            let max_total_load = crate::executor::parallelism() as usize * MAX_QUEUED_TASKS;
+            assert!(crate::executor::total_load() <= max_total_load);
+        });
+    }
+
     #[test]
     fn test_spawn_and_join() {
         crate::task::block_on(async {
@@ -117,6 +172,92 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_join_typed_output() {
+        crate::task::block_on(async {
+            #[derive(Debug, PartialEq)]
+            struct Output {
+                id: u32,
+                name: String,
+            }
+
+            // Join a task that is still running
+            let join_handle = crate::task::spawn(async {
+                for _ in 0..10 {
+                    crate::sched::yield_().await;
+                }
+                Output {
+                    id: 1,
+                    name: "running".to_string(),
+                }
+            });
+            let output: Output = join_handle.await;
+            assert!(output.id == 1 && output.name == "running");
+
+            // Join a task that has already completed
+            let completed = Arc::new(AtomicBool::new(false));
+            let join_handle = {
+                let completed = completed.clone();
+                crate::task::spawn(async move {
+                    completed.store(true, Ordering::Release);
+                    Output {
+                        id: 2,
+                        name: "completed".to_string(),
+                    }
+                })
+            };
+            while !completed.load(Ordering::Acquire) {
+                crate::sched::yield_().await;
+            }
+            let output: Output = join_handle.await;
+            assert!(output.id == 2 && output.name == "completed");
+        });
+    }
+
+    #[test]
+    fn test_panicking_task() {
+        crate::task::block_on(async {
+            use crate::task::TaskId;
+
+            lazy_static! {
+                static ref ABORTED_TIDS: Mutex<Vec<TaskId>> = Mutex::new(Vec::new());
+            }
+            crate::executor::set_panic_handler(|tid, _payload| {
+                ABORTED_TIDS.lock().push(tid);
+            });
+
+            let panic_handle = crate::task::spawn(async {
+                crate::sched::yield_().await;
+                panic!("a panic in a task");
+            });
+            let normal_handle = crate::task::spawn(async {
+                for _ in 0..100 {
+                    crate::sched::yield_().await;
+                }
+                1
+            });
+
+            // The other tasks keep running
+            assert!(normal_handle.await == 1);
+
+            let panic_task = panic_handle.task().clone();
+            while !panic_task.is_aborted() {
+                crate::sched::yield_().await;
+            }
+            assert!(ABORTED_TIDS.lock().contains(&panic_task.tid()));
+
+            // The panic is re-raised to the joiner
+            let joiner_handle = crate::task::spawn(async move {
+                panic_handle.await;
+            });
+            let joiner_task = joiner_handle.task().clone();
+            while !joiner_task.is_aborted() {
+                crate::sched::yield_().await;
+            }
+            assert!(ABORTED_TIDS.lock().contains(&joiner_task.tid()));
+        });
+    }
+
     #[test]
     fn test_affinity() {
         crate::task::block_on(async {
@@ -142,6 +283,63 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_set_affinity() {
+        crate::task::block_on(async {
+            use crate::sched::Affinity;
+
+            let current = crate::task::current::get();
+            let vcpu_id = crate::executor::parallelism() as usize - 1;
+            let new_affinity = {
+                let mut new_affinity = Affinity::new_empty();
+                new_affinity.set(vcpu_id, true);
+                new_affinity
+            };
+
+            let err = current
+                .sched_info()
+                .set_affinity(&Affinity::new_empty())
+                .unwrap_err();
+            assert!(err.errno() == EINVAL);
+
+            current.sched_info().set_affinity(&new_affinity).unwrap();
+            // The task always runs on the only allowed vCPU after the next enqueue
+            for _ in 0..100 {
+                crate::sched::yield_().await;
+                assert!(crate::task::current::get_vcpu_id() as usize == vcpu_id);
+            }
+        });
+    }
+
+    #[test]
+    fn test_oversized_affinity() {
+        crate::task::block_on(async {
+            use crate::sched::Affinity;
+
+            // Bits beyond the number of vCPUs are ignored
+            let max_threads = Affinity::max_threads();
+            let mut affinity = Affinity::new_empty();
+            for idx in max_threads..max_threads * 2 + 64 {
+                affinity.set(idx, true);
+                assert!(!affinity.get(idx));
+            }
+            assert!(affinity.is_empty());
+
+            let current = crate::task::current::get();
+            let err = current.sched_info().set_affinity(&affinity).unwrap_err();
+            assert!(err.errno() == EINVAL);
+
+            // Only the valid bits take effect
+            affinity.set(0, true);
+            assert!(affinity.count() == 1);
+            current.sched_info().set_affinity(&affinity).unwrap();
+            for _ in 0..100 {
+                crate::sched::yield_().await;
+                assert!(crate::task::current::get_vcpu_id() == 0);
+            }
+        });
+    }
+
     #[test]
     fn test_scheduler() {
         crate::task::block_on(async {
@@ -163,6 +361,32 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_spread_across_vcpus() {
+        crate::task::block_on(async {
+            let task_num = TEST_PARALLELISM * 16;
+            let vcpu_mask = Arc::new(AtomicU32::new(0));
+            let join_handles: Vec<JoinHandle<()>> = (0..task_num)
+                .map(|_| {
+                    let vcpu_mask = vcpu_mask.clone();
+                    crate::task::spawn(async move {
+                        for _ in 0..10 {
+                            let vcpu_id = crate::task::current::get_vcpu_id();
+                            vcpu_mask.fetch_or(1 << vcpu_id, Ordering::Relaxed);
+                            crate::sched::yield_().await;
+                        }
+                    })
+                })
+                .collect();
+            for join_handle in join_handles {
+                join_handle.await;
+            }
+
+            // The tasks are not all run on a single vCPU
+            assert!(vcpu_mask.load(Ordering::Relaxed).count_ones() > 1);
+        });
+    }
+
     #[test]
     // FIXME: enable this test when async Mutex is ready
     #[ignore]
@@ -183,6 +407,80 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_preempt_by_priority() {
+        crate::task::block_on(async {
+            use crate::sched::Affinity;
+            use crate::wait::{Waiter, WaiterState};
+
+            const NUM_BUSY_TASKS: u32 = 16;
+
+            // All the tasks below run on the same vCPU, so they compete for it
+            let vcpu_id = crate::executor::parallelism() as usize - 1;
+            async fn move_to_vcpu(vcpu_id: usize) {
+                let mut affinity = Affinity::new_empty();
+                affinity.set(vcpu_id, true);
+                let current = crate::task::current::get();
+                current.sched_info().set_affinity(&affinity).unwrap();
+                crate::sched::yield_().await;
+            }
+
+            // A high-priority task that waits to be woken up
+            let num_busy_polls = Arc::new(AtomicU32::new(0));
+            let waiter = Waiter::new();
+            let waker = waiter.waker();
+            let high_handle = {
+                let num_busy_polls = num_busy_polls.clone();
+                SpawnOptions::new(async move {
+                    move_to_vcpu(vcpu_id).await;
+                    waiter.wait().await.unwrap();
+                    num_busy_polls.load(Ordering::Relaxed)
+                })
+                .priority(SchedPriority::High)
+                .spawn()
+            };
+            while waker.state() != WaiterState::Waiting {
+                crate::sched::yield_().await;
+            }
+
+            // Busy low-priority tasks that keep the run queue of the vCPU non-empty
+            let num_arrived = Arc::new(AtomicU32::new(0));
+            let should_stop = Arc::new(AtomicBool::new(false));
+            let busy_handles: Vec<JoinHandle<()>> = (0..NUM_BUSY_TASKS)
+                .map(|_| {
+                    let num_busy_polls = num_busy_polls.clone();
+                    let num_arrived = num_arrived.clone();
+                    let should_stop = should_stop.clone();
+                    SpawnOptions::new(async move {
+                        move_to_vcpu(vcpu_id).await;
+                        num_arrived.fetch_add(1, Ordering::Relaxed);
+                        while !should_stop.load(Ordering::Relaxed) {
+                            num_busy_polls.fetch_add(1, Ordering::Relaxed);
+                            crate::sched::yield_().await;
+                        }
+                    })
+                    .priority(SchedPriority::Low)
+                    .spawn()
+                })
+                .collect();
+            while num_arrived.load(Ordering::Relaxed) < NUM_BUSY_TASKS {
+                crate::sched::yield_().await;
+            }
+
+            // Without priority, the woken task would wait for all the queued busy
+            // tasks to be polled once. With priority, it is picked up at the next
+            // scheduling point of the vCPU.
+            let polls_at_wake = num_busy_polls.load(Ordering::Relaxed);
+            waker.wake();
+            let polls_at_run = high_handle.await;
+            should_stop.store(true, Ordering::Relaxed);
+            for busy_handle in busy_handles {
+                busy_handle.await;
+            }
+            assert!(polls_at_run - polls_at_wake < NUM_BUSY_TASKS / 2);
+        });
+    }
+
     fn spawn_priority_tasks(task_num: u32, priority: SchedPriority) -> JoinHandle<Duration> {
         SpawnOptions::new(async move {
             let start = std::time::Instant::now();
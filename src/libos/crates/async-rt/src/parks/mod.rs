@@ -1,4 +1,9 @@
+#[cfg(test)]
+use core::cell::Cell;
+use core::cell::RefCell;
+
 use crate::prelude::*;
+use crate::sched::Affinity;
 #[cfg(feature = "sgx")]
 use std::thread::SgxThread as Thread;
 #[cfg(not(feature = "sgx"))]
@@ -6,12 +11,31 @@ use std::thread::Thread;
 
 pub struct Parks {
     sleep_threads: Vec<Mutex<Option<Thread>>>,
+    // Whether a thread has been unparked since its last `park_timeout`
+    unpark_requested: Vec<AtomicBool>,
+    // Whether a thread is parked (or about to park)
+    parked: Vec<AtomicBool>,
+}
+
+/// The result of `Parks::park_timeout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParkResult {
+    /// The thread has been unparked.
+    Unparked,
+    /// The thread has woken up due to a timeout or spuriously.
+    TimedOut,
 }
 
 impl Parks {
     pub fn new(parallelism: u32) -> Self {
         let sleep_threads: Vec<_> = (0..parallelism).map(|_| Mutex::new(None)).collect();
-        Self { sleep_threads }
+        let unpark_requested: Vec<_> = (0..parallelism).map(|_| AtomicBool::new(false)).collect();
+        let parked: Vec<_> = (0..parallelism).map(|_| AtomicBool::new(false)).collect();
+        Self {
+            sleep_threads,
+            unpark_requested,
+            parked,
+        }
     }
 
     pub fn register(&self, thread_id: usize) {
@@ -29,16 +53,99 @@ impl Parks {
     }
 
     pub fn park(&self) {
+        let thread_id = crate::task::current::get_vcpu_id() as usize;
+        self.parked[thread_id].store(true, Ordering::Release);
         std::thread::park();
+        self.parked[thread_id].store(false, Ordering::Release);
     }
 
-    pub fn park_timeout(&self, duration: core::time::Duration) {
+    /// Park the current thread, whose ID is `thread_id`, for at most the given
+    /// duration.
+    ///
+    /// The result tells whether the thread has been unparked since the last call
+    /// to this method. A stale unpark that was consumed by `park` may still be
+    /// reported as `Unparked`, which is harmless as the caller only does some
+    /// extra work in that case.
+    pub fn park_timeout(&self, thread_id: usize, duration: core::time::Duration) -> ParkResult {
+        assert!(thread_id < self.sleep_threads.len());
+
+        self.parked[thread_id].store(true, Ordering::Release);
         std::thread::park_timeout(duration);
+        self.parked[thread_id].store(false, Ordering::Release);
+        if self.unpark_requested[thread_id].swap(false, Ordering::AcqRel) {
+            ParkResult::Unparked
+        } else {
+            ParkResult::TimedOut
+        }
     }
 
+    /// Unpark a thread.
+    ///
+    /// If the current thread is in a batch (see `batch_unparks`), the operation
+    /// is deferred until the end of the batch.
     pub fn unpark(&self, thread_id: usize) {
         assert!(thread_id < self.sleep_threads.len());
 
+        if let Some(pending_unparks) = PENDING_UNPARKS.borrow_mut().as_mut() {
+            pending_unparks[thread_id] = true;
+            return;
+        }
+        self.do_unpark(thread_id);
+    }
+
+    /// Unpark a single thread in the affinity, preferring one that is parked.
+    ///
+    /// If no thread in the affinity is parked, the first one is unparked anyway,
+    /// so that a thread that is about to park does not miss the new work.
+    /// Returns the ID of the unparked thread.
+    pub fn unpark_one_in(&self, affinity: &Affinity) -> Option<usize> {
+        let thread_id = affinity
+            .iter_ones()
+            .take_while(|thread_id| *thread_id < self.sleep_threads.len())
+            .find(|thread_id| self.parked[*thread_id].load(Ordering::Acquire))
+            .or_else(|| {
+                affinity
+                    .iter_ones()
+                    .find(|thread_id| *thread_id < self.sleep_threads.len())
+            })?;
+        self.unpark(thread_id);
+        Some(thread_id)
+    }
+
+    /// Run `f` with all the unpark operations issued by the current thread
+    /// deferred, then issue the deferred operations with a single `unpark_n`.
+    ///
+    /// This avoids an unpark storm when many tasks are woken up at once, since
+    /// each thread is unparked at most once per batch, no matter how many tasks
+    /// are enqueued to it. Nested batches are merged into the outermost one.
+    pub fn batch_unparks<R>(&self, f: impl FnOnce() -> R) -> R {
+        if PENDING_UNPARKS.borrow().is_some() {
+            return f();
+        }
+
+        PENDING_UNPARKS.replace(Some(vec![false; self.sleep_threads.len()]));
+        // End the batch even if `f` panics, otherwise all later unparks of the
+        // current thread would be deferred forever
+        let _batch = BatchGuard(self);
+        f()
+    }
+
+    /// Unpark the threads whose IDs are marked in the given mask.
+    pub fn unpark_n(&self, thread_mask: &[bool]) {
+        assert!(thread_mask.len() <= self.sleep_threads.len());
+
+        thread_mask
+            .iter()
+            .enumerate()
+            .filter(|(_, is_marked)| **is_marked)
+            .for_each(|(thread_id, _)| self.do_unpark(thread_id));
+    }
+
+    fn do_unpark(&self, thread_id: usize) {
+        #[cfg(test)]
+        NUM_UNPARKS.set(NUM_UNPARKS.get() + 1);
+
+        self.unpark_requested[thread_id].store(true, Ordering::Release);
         let sleep_thread = self.sleep_threads[thread_id].lock();
         let thread = sleep_thread.clone();
         drop(sleep_thread);
@@ -57,3 +164,111 @@ impl Parks {
         self.sleep_threads.len()
     }
 }
+
+// Ends the batch of the current thread when dropped, issuing the deferred unparks.
+struct BatchGuard<'a>(&'a Parks);
+
+impl Drop for BatchGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(pending_unparks) = PENDING_UNPARKS.replace(None) {
+            self.0.unpark_n(&pending_unparks);
+        }
+    }
+}
+
+// The threads to be unparked at the end of the current batch of the current thread
+#[thread_local]
+static PENDING_UNPARKS: RefCell<Option<Vec<bool>>> = RefCell::new(None);
+
+// The number of the unpark operations issued by the current thread
+#[cfg(test)]
+#[thread_local]
+static NUM_UNPARKS: Cell<usize> = Cell::new(0);
+
+/// Returns the number of the unpark operations issued by the current thread.
+#[cfg(test)]
+pub(crate) fn num_unparks() -> usize {
+    NUM_UNPARKS.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn park_timeout_result() {
+        let parks = Parks::new(1);
+        parks.register(0);
+        let duration = Duration::from_millis(10);
+
+        assert!(parks.park_timeout(0, duration) == ParkResult::TimedOut);
+
+        parks.unpark(0);
+        assert!(parks.park_timeout(0, duration) == ParkResult::Unparked);
+        // The unpark is reported only once
+        assert!(parks.park_timeout(0, duration) == ParkResult::TimedOut);
+
+        parks.unregister(0);
+    }
+
+    #[test]
+    fn panicking_batch() {
+        let parks = Parks::new(1);
+        parks.register(0);
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            parks.batch_unparks(|| {
+                parks.unpark(0);
+                panic!("a panic in a batch");
+            })
+        }));
+        assert!(res.is_err());
+        // The deferred unpark is issued while unwinding
+        assert!(parks.park_timeout(0, Duration::from_millis(10)) == ParkResult::Unparked);
+
+        // The later unparks are no longer deferred
+        let old_num_unparks = num_unparks();
+        parks.unpark(0);
+        assert!(num_unparks() == old_num_unparks + 1);
+
+        parks.unregister(0);
+    }
+
+    #[test]
+    fn unpark_one_in_affinity() {
+        let parks = Arc::new(Parks::new(2));
+        let park_threads: Vec<_> = (0..2)
+            .map(|thread_id| {
+                let parks = parks.clone();
+                std::thread::spawn(move || {
+                    parks.register(thread_id);
+                    let res = parks.park_timeout(thread_id, Duration::from_millis(200));
+                    parks.unregister(thread_id);
+                    res
+                })
+            })
+            .collect();
+        while !parks
+            .parked
+            .iter()
+            .all(|parked| parked.load(Ordering::Acquire))
+        {
+            core::hint::spin_loop();
+        }
+
+        // Only the thread in the affinity is unparked
+        let mut affinity = Affinity::new_empty();
+        affinity.set(1, true);
+        assert!(parks.unpark_one_in(&affinity) == Some(1));
+        let results: Vec<_> = park_threads
+            .into_iter()
+            .map(|park_thread| park_thread.join().unwrap())
+            .collect();
+        assert!(results == vec![ParkResult::TimedOut, ParkResult::Unparked]);
+
+        // Without any parked thread, the first thread in the affinity is unparked
+        affinity.set(0, true);
+        assert!(parks.unpark_one_in(&affinity) == Some(0));
+        assert!(parks.unpark_one_in(&Affinity::new_empty()) == None);
+    }
+}
@@ -43,13 +43,22 @@ impl Affinity {
     }
 
     /// Set whether the i-th thread is in the set.
+    ///
+    /// As there are only `max_threads` threads, the call is ignored if `i` is
+    /// out of range. This allows converting a mask that is wider than the number
+    /// of threads (e.g., a `cpu_set_t`) bit by bit.
     pub fn set(&mut self, i: usize, b: bool) {
+        if i >= self.bits.len() {
+            return;
+        }
         self.bits.set(i, b);
     }
 
     /// Get whether the i-th thread is in the set.
+    ///
+    /// Returns false if `i` is out of range.
     pub fn get(&self, i: usize) -> bool {
-        self.bits.get(i).unwrap()
+        self.bits.get(i).unwrap_or(false)
     }
 
     /// Returns an iterator that allows accessing the underlying bits.
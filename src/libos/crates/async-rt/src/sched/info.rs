@@ -3,6 +3,7 @@ use spin::rw_lock::RwLock;
 use crate::executor::EXECUTOR;
 use crate::prelude::*;
 use crate::sched::Affinity;
+use crate::stats::{LatencyCounter, LatencySummary};
 
 /// A per-task scheduling-related info.
 pub struct SchedInfo {
@@ -11,6 +12,9 @@ pub struct SchedInfo {
     priority: RwLock<SchedPriority>,
     #[cfg(feature = "use_latency")]
     enqueue_epochs: AtomicU64,
+    // When the task was enqueued, or zero if not measured
+    enqueue_nanos: AtomicU64,
+    sched_latency: LatencyCounter,
 }
 
 impl SchedInfo {
@@ -33,6 +37,8 @@ impl SchedInfo {
             priority,
             #[cfg(feature = "use_latency")]
             enqueue_epochs,
+            enqueue_nanos: AtomicU64::new(0),
+            sched_latency: LatencyCounter::new(),
         }
     }
 
@@ -40,6 +46,20 @@ impl SchedInfo {
         &self.affinity
     }
 
+    /// Set the affinity of the task.
+    ///
+    /// The new affinity takes effect at the next enqueue of the task. If the task
+    /// has already been enqueued to a thread outside the new affinity, it stays
+    /// there until it is dequeued. The next time the task is enqueued, the scheduler
+    /// will pick a thread that is allowed by the new affinity.
+    pub fn set_affinity(&self, affinity: &Affinity) -> Result<()> {
+        if affinity.is_empty() {
+            return_errno!(EINVAL, "the affinity must not be empty");
+        }
+        *self.affinity.write() = affinity.clone();
+        Ok(())
+    }
+
     pub fn priority(&self) -> SchedPriority {
         *self.priority.read()
     }
@@ -48,6 +68,29 @@ impl SchedInfo {
         *self.priority.write() = priority;
     }
 
+    /// Returns the summary of the scheduling latencies of the task.
+    ///
+    /// The latencies are only measured when enabled by
+    /// `stats::set_sched_latency_enabled`.
+    pub fn sched_latency(&self) -> LatencySummary {
+        self.sched_latency.snapshot()
+    }
+
+    pub(crate) fn set_enqueue_nanos(&self, nanos: u64) {
+        self.enqueue_nanos.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Returns the scheduling latency of the task (if measured) upon being dequeued.
+    pub(crate) fn on_dequeued(&self, now_nanos: u64) -> Option<u64> {
+        let enqueue_nanos = self.enqueue_nanos.swap(0, Ordering::Relaxed);
+        if enqueue_nanos == 0 {
+            return None;
+        }
+        let latency = now_nanos.saturating_sub(enqueue_nanos);
+        self.sched_latency.record(latency);
+        Some(latency)
+    }
+
     pub(crate) fn last_thread_id(&self) -> u32 {
         self.last_thread_id.load(Ordering::Relaxed)
     }
@@ -67,9 +110,70 @@ impl SchedInfo {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SchedPriority {
     High,
     Normal,
     Low,
 }
+
+impl SchedPriority {
+    /// Convert a Unix nice value in [-20, 19] to a priority band.
+    ///
+    /// Only the nice values far from zero leave the `Normal` band: those in
+    /// [-20, -11] map to `High` and those in [10, 19] map to `Low`. So ordinary
+    /// renicing does not move a task out of the band of most tasks. Out-of-range
+    /// values are treated as the nearest bound.
+    pub fn from_nice(nice: i32) -> Self {
+        if nice <= -11 {
+            SchedPriority::High
+        } else if nice < 10 {
+            SchedPriority::Normal
+        } else {
+            SchedPriority::Low
+        }
+    }
+
+    /// Convert a priority band to a representative Unix nice value.
+    ///
+    /// This is the inverse of `from_nice` in the sense that
+    /// `SchedPriority::from_nice(p.to_nice()) == p` for any `p`.
+    pub fn to_nice(&self) -> i32 {
+        match self {
+            SchedPriority::High => -15,
+            SchedPriority::Normal => 0,
+            SchedPriority::Low => 15,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nice_to_priority() {
+        let table = [
+            (-20, SchedPriority::High),
+            (-11, SchedPriority::High),
+            (-10, SchedPriority::Normal),
+            (0, SchedPriority::Normal),
+            (9, SchedPriority::Normal),
+            (10, SchedPriority::Low),
+            (19, SchedPriority::Low),
+        ];
+        for (nice, priority) in table.iter() {
+            assert_eq!(SchedPriority::from_nice(*nice), *priority);
+        }
+
+        for priority in [
+            SchedPriority::High,
+            SchedPriority::Normal,
+            SchedPriority::Low,
+        ]
+        .iter()
+        {
+            assert_eq!(SchedPriority::from_nice(priority.to_nice()), *priority);
+        }
+    }
+}
@@ -1,95 +1,513 @@
+//! BasicScheduler
+//!
+//! In the basic scheduler, each thread has its own run queue. A task is always
+//! inserted into the run queue of its last thread as long as its affinity allows.
+//!
+//! A run queue consists of one band per priority and a bitmask that indicates
+//! which bands are non-empty. The bands share the capacity of the run queue, i.e.,
+//! `MAX_QUEUED_TASKS` tasks per thread; the tasks that do not fit are kept pending
+//! until there is room. Tasks are mostly dequeued by priority: a task is
+//! taken from the highest non-empty band, falling back to lower bands. To avoid
+//! starving the lower bands by a steady stream of higher-priority tasks, once
+//! every `STARVATION_BOUND` dequeues, a lower band is served first. As the number
+//! of bands is fixed and small, both enqueue and dequeue are O(1).
+//!
+//! When the run queue of a thread is empty, the thread tries to steal a batch of
+//! tasks from the busiest thread, instead of staying idle while other threads have
+//! a backlog. Only the tasks whose affinity allows the stealing thread are stolen;
+//! the other tasks examined are put back to the front of their bands, so that the
+//! order of the victim's run queue is kept.
+
 use crate::parks::Parks;
 use crate::prelude::*;
-use crate::sched::Affinity;
+use crate::sched::{Affinity, SchedPriority};
+use crate::stats::{SchedStats, SchedStatsCounter};
 use crate::task::Task;
 
 use super::{Scheduler, MAX_QUEUED_TASKS};
 
 use flume::{Receiver, Sender, TrySendError};
 
-lazy_static! {
-    static ref PENDING_TASKS: Mutex<VecDeque<Arc<Task>>> = Mutex::new(VecDeque::new());
-    static ref HAS_PENDING: AtomicBool = AtomicBool::new(false);
-}
-
 pub struct BasicScheduler {
     parallelism: usize,
-    run_queues: Vec<Receiver<Arc<Task>>>,
-    task_senders: Vec<Sender<Arc<Task>>>,
+    run_queues: Vec<RunQueue<Arc<Task>>>,
+    // The tasks that are not pushed into a run queue as it is full
+    pending_tasks: Mutex<VecDeque<Arc<Task>>>,
+    has_pending: AtomicBool,
     parks: Arc<Parks>,
+    sched_stats: Vec<SchedStatsCounter>,
 }
 
 impl BasicScheduler {
+    /// The max number of tasks stolen at a time.
+    const MAX_STEAL_BATCH: usize = 32;
+    /// The max number of tasks examined per task to steal.
+    const MAX_EXAMINED_PER_STEAL: usize = 2;
+
     pub fn new(parks: Arc<Parks>) -> Self {
         let parallelism = parks.len();
-        let mut run_queues = Vec::with_capacity(parallelism);
-        let mut task_senders = Vec::with_capacity(parallelism);
-        for _ in 0..parallelism {
-            let (task_sender, run_queue) = flume::bounded(MAX_QUEUED_TASKS);
-            run_queues.push(run_queue);
-            task_senders.push(task_sender);
-        }
+        let run_queues = (0..parallelism)
+            .map(|_| RunQueue::new(MAX_QUEUED_TASKS))
+            .collect();
+        let sched_stats = (0..parallelism).map(|_| SchedStatsCounter::new()).collect();
 
         Self {
             parallelism,
             run_queues,
-            task_senders,
+            pending_tasks: Mutex::new(VecDeque::new()),
+            has_pending: AtomicBool::new(false),
             parks,
+            sched_stats,
         }
     }
-}
 
-impl Scheduler for BasicScheduler {
-    fn enqueue_task(&self, task: Arc<Task>) {
+    // Pick the last thread of the task if its affinity allows, otherwise the
+    // next thread allowed.
+    fn pick_thread_for(&self, task: &Arc<Task>) -> usize {
         let affinity = task.sched_info().affinity().read();
         assert!(!affinity.is_empty());
         let mut thread_id = task.sched_info().last_thread_id() as usize;
+        if affinity.get(thread_id) {
+            return thread_id;
+        }
+
         while !affinity.get(thread_id) {
             thread_id = (thread_id + 1) % Affinity::max_threads();
         }
-        drop(affinity);
+        debug_assert!(thread_id < self.parallelism);
+        self.sched_stats[thread_id].inc_affinity_fallbacks();
+        thread_id
+    }
+
+    // Steal a batch of tasks from the busiest thread other than the given one.
+    // The first stolen task is returned, and the rest are pushed into the run
+    // queue of the given thread.
+    fn steal_tasks(&self, thread_id: usize) -> Option<Arc<Task>> {
+        let (victim_id, victim_len) = self
+            .run_queues
+            .iter()
+            .enumerate()
+            .filter(|(victim_id, _)| *victim_id != thread_id)
+            .map(|(victim_id, run_queue)| (victim_id, run_queue.len()))
+            .max_by_key(|(_, len)| *len)?;
+        if victim_len == 0 {
+            return None;
+        }
 
+        let max_stolen = ((victim_len + 1) / 2).min(Self::MAX_STEAL_BATCH);
+        let max_examined = max_stolen * Self::MAX_EXAMINED_PER_STEAL;
+        let stolen_tasks = self.run_queues[victim_id].try_steal(max_stolen, max_examined, |task| {
+            task.sched_info().affinity().read().get(thread_id)
+        });
+
+        self.sched_stats[victim_id].add_migrations(stolen_tasks.len() as u64);
+
+        let mut stolen_tasks = stolen_tasks.into_iter();
+        let first_task = stolen_tasks.next()?;
+        first_task.sched_info().set_last_thread_id(thread_id as u32);
+        for task in stolen_tasks {
+            self.enqueue_task_to(task, thread_id);
+        }
+        Some(first_task)
+    }
+
+    fn enqueue_task_to(&self, task: Arc<Task>, thread_id: usize) {
         task.sched_info().set_last_thread_id(thread_id as u32);
-        let res = self.task_senders[thread_id].try_send(task);
+        let priority = task.sched_info().priority();
+        let res = self.run_queues[thread_id].push(task, priority);
 
         match res {
             Ok(()) => {
                 self.parks.unpark(thread_id);
             }
-            Err(TrySendError::Full(task)) => {
-                let mut pending_tasks = PENDING_TASKS.lock();
+            Err(task) => {
+                let affinity = task.sched_info().affinity().read().clone();
+                let mut pending_tasks = self.pending_tasks.lock();
                 pending_tasks.push_back(task);
-                HAS_PENDING.store(true, Ordering::Relaxed);
+                self.has_pending.store(true, Ordering::Relaxed);
+                drop(pending_tasks);
+                // A pending task is only moved to a run queue by a running thread,
+                // so make sure that there is one.
+                self.parks.unpark_one_in(&affinity);
             }
-            _ => panic!("task queue disconnected"),
         }
     }
+}
+
+impl Scheduler for BasicScheduler {
+    fn enqueue_task(&self, task: Arc<Task>) {
+        let thread_id = self.pick_thread_for(&task);
+        self.sched_stats[thread_id].inc_enqueues();
+        self.enqueue_task_to(task, thread_id);
+    }
 
     fn dequeue_task(&self, thread_id: usize) -> Option<Arc<Task>> {
-        let res = self.run_queues[thread_id].try_recv().ok();
+        let res = self.run_queues[thread_id]
+            .pop()
+            .or_else(|| self.steal_tasks(thread_id));
 
         // If there is any pending task, try to enqueue it
-        if HAS_PENDING.load(Ordering::Relaxed) == true {
-            let mut pending_tasks = PENDING_TASKS.lock();
+        if self.has_pending.load(Ordering::Relaxed) == true {
+            let mut pending_tasks = self.pending_tasks.lock();
             let task = pending_tasks.pop_front();
             drop(pending_tasks);
 
             if let Some(task) = task {
-                self.enqueue_task(task);
+                let thread_id = self.pick_thread_for(&task);
+                self.enqueue_task_to(task, thread_id);
             } else {
-                HAS_PENDING.store(false, Ordering::Relaxed);
+                self.has_pending.store(false, Ordering::Relaxed);
             }
         }
 
+        if res.is_some() {
+            self.sched_stats[thread_id].inc_dequeues();
+        }
         res
     }
+
+    fn load_per_vcpu(&self) -> Vec<usize> {
+        self.run_queues
+            .iter()
+            .map(|run_queue| run_queue.len())
+            .collect()
+    }
+
+    fn sched_stats_per_vcpu(&self) -> Vec<SchedStats> {
+        self.sched_stats
+            .iter()
+            .map(|stats| stats.snapshot())
+            .collect()
+    }
+}
+
+/// A run queue that consists of one band per priority, which share the capacity
+/// of the run queue.
+///
+/// The run queue may be pushed by any thread. It is popped by its owner thread,
+/// and by at most one other thread at a time that steals items from it.
+struct RunQueue<T> {
+    senders: [Sender<T>; NUM_BANDS],
+    receivers: [Receiver<T>; NUM_BANDS],
+    // The i-th bit is set if the i-th band may be non-empty. A set bit is only
+    // a hint, while a clear bit guarantees that the band is empty.
+    nonempty_bands: AtomicU8,
+    // Serialize the threads that try to steal items
+    steal_lock: Mutex<()>,
+    // The items that a thief has examined but not stolen. They are older than
+    // the items in the bands, so they are popped first.
+    returned: [Mutex<VecDeque<T>>; NUM_BANDS],
+    num_returned: AtomicU32,
+    num_pops: AtomicU32,
+    // The number of the items in all bands, including those being pushed
+    num_items: AtomicUsize,
+    capacity: usize,
+}
+
+const NUM_BANDS: usize = 3;
+const BANDS_MASK: u8 = (1 << NUM_BANDS) - 1;
+// A lower band is served first once every this number of pops
+const STARVATION_BOUND: u32 = 16;
+
+impl<T> RunQueue<T> {
+    /// Create a run queue, whose bands can hold at most `capacity` items in total.
+    pub fn new(capacity: usize) -> Self {
+        let (high_sender, high_receiver) = flume::bounded(capacity);
+        let (normal_sender, normal_receiver) = flume::bounded(capacity);
+        let (low_sender, low_receiver) = flume::bounded(capacity);
+        Self {
+            senders: [high_sender, normal_sender, low_sender],
+            receivers: [high_receiver, normal_receiver, low_receiver],
+            nonempty_bands: AtomicU8::new(0),
+            steal_lock: Mutex::new(()),
+            returned: [
+                Mutex::new(VecDeque::new()),
+                Mutex::new(VecDeque::new()),
+                Mutex::new(VecDeque::new()),
+            ],
+            num_returned: AtomicU32::new(0),
+            num_pops: AtomicU32::new(0),
+            num_items: AtomicUsize::new(0),
+            capacity,
+        }
+    }
+
+    /// Push an item into the band of the priority. If the run queue is full, the item
+    /// is returned.
+    pub fn push(&self, item: T, priority: SchedPriority) -> core::result::Result<(), T> {
+        // Reserve the room for the item first, so that the capacity is never exceeded
+        if self.num_items.fetch_add(1, Ordering::SeqCst) >= self.capacity {
+            self.num_items.fetch_sub(1, Ordering::SeqCst);
+            return Err(item);
+        }
+
+        let band = Self::band_of(priority);
+        match self.senders[band].try_send(item) {
+            Ok(()) => {
+                // The bit must be set after the item is pushed. See `pop`.
+                self.nonempty_bands.fetch_or(1 << band, Ordering::SeqCst);
+                Ok(())
+            }
+            Err(TrySendError::Full(item)) => {
+                self.num_items.fetch_sub(1, Ordering::SeqCst);
+                Err(item)
+            }
+            Err(TrySendError::Disconnected(_)) => panic!("task queue disconnected"),
+        }
+    }
+
+    /// Pop an item from the highest non-empty band.
+    ///
+    /// Once every `STARVATION_BOUND` pops, the search starts from a lower band
+    /// instead, alternating between the normal and the low bands. So each lower
+    /// band gets at least one of every `2 * STARVATION_BOUND` pops when it is
+    /// non-empty.
+    pub fn pop(&self) -> Option<T> {
+        let num_pops = self.num_pops.fetch_add(1, Ordering::Relaxed);
+        let first_band = if num_pops % STARVATION_BOUND == STARVATION_BOUND - 1 {
+            1 + (num_pops / STARVATION_BOUND) as usize % (NUM_BANDS - 1)
+        } else {
+            0
+        };
+        let (_, item) = self.pop_from(first_band)?;
+        self.num_items.fetch_sub(1, Ordering::SeqCst);
+        Some(item)
+    }
+
+    // Pop an item from the first non-empty band, searching from the given band
+    // and wrapping around to the highest band. The band of the item is returned
+    // together.
+    fn pop_from(&self, first_band: usize) -> Option<(usize, T)> {
+        let mut nonempty_bands = self.nonempty_bands.load(Ordering::SeqCst);
+        while nonempty_bands != 0 {
+            let rotated_bands = ((nonempty_bands >> first_band)
+                | (nonempty_bands << (NUM_BANDS - first_band)))
+                & BANDS_MASK;
+            let band = (rotated_bands.trailing_zeros() as usize + first_band) % NUM_BANDS;
+            if let Some(item) = self.pop_band(band) {
+                return Some((band, item));
+            }
+
+            // The band is found empty. Clear its bit, then check the band again
+            // in case an item is pushed concurrently before the bit is cleared.
+            self.nonempty_bands
+                .fetch_and(!(1 << band), Ordering::SeqCst);
+            if !self.is_band_empty(band) {
+                self.nonempty_bands.fetch_or(1 << band, Ordering::SeqCst);
+                continue;
+            }
+            nonempty_bands &= !(1 << band);
+        }
+        None
+    }
+
+    fn pop_band(&self, band: usize) -> Option<T> {
+        if self.num_returned.load(Ordering::SeqCst) > 0 {
+            if let Some(item) = self.returned[band].lock().pop_front() {
+                self.num_returned.fetch_sub(1, Ordering::SeqCst);
+                return Some(item);
+            }
+        }
+        self.receivers[band].try_recv().ok()
+    }
+
+    fn is_band_empty(&self, band: usize) -> bool {
+        self.receivers[band].is_empty() && self.returned[band].lock().is_empty()
+    }
+
+    /// Try to steal at most `max` items that satisfy the predicate, taking the
+    /// items of higher bands first and examining at most `max_examined` items.
+    ///
+    /// The examined items that do not satisfy the predicate are put back to the
+    /// front of their bands in their original order. If another thread is
+    /// stealing items from the run queue, nothing is stolen.
+    pub fn try_steal(
+        &self,
+        max: usize,
+        max_examined: usize,
+        mut pred: impl FnMut(&T) -> bool,
+    ) -> Vec<T> {
+        let mut stolen = Vec::new();
+        let _guard = match self.steal_lock.try_lock() {
+            Some(guard) => guard,
+            None => return stolen,
+        };
+
+        let mut rejected: [Vec<T>; NUM_BANDS] = [Vec::new(), Vec::new(), Vec::new()];
+        let mut num_examined = 0;
+        while stolen.len() < max && num_examined < max_examined {
+            let (band, item) = match self.pop_from(0) {
+                Some(band_and_item) => band_and_item,
+                None => break,
+            };
+            if pred(&item) {
+                stolen.push(item);
+            } else {
+                rejected[band].push(item);
+            }
+            num_examined += 1;
+        }
+
+        for (band, items) in rejected.iter_mut().enumerate() {
+            if items.is_empty() {
+                continue;
+            }
+            let mut returned = self.returned[band].lock();
+            self.num_returned
+                .fetch_add(items.len() as u32, Ordering::SeqCst);
+            for item in items.drain(..).rev() {
+                returned.push_front(item);
+            }
+            drop(returned);
+            // The bit must be set after the items are put back. See `pop`.
+            self.nonempty_bands.fetch_or(1 << band, Ordering::SeqCst);
+        }
+        // The items put back keep their room in the run queue
+        self.num_items.fetch_sub(stolen.len(), Ordering::SeqCst);
+        stolen
+    }
+
+    /// Returns the total number of items in all bands.
+    pub fn len(&self) -> usize {
+        self.num_items.load(Ordering::SeqCst)
+    }
+
+    fn band_of(priority: SchedPriority) -> usize {
+        match priority {
+            SchedPriority::High => 0,
+            SchedPriority::Normal => 1,
+            SchedPriority::Low => 2,
+        }
+    }
 }
 
 impl Drop for BasicScheduler {
     fn drop(&mut self) {
-        let pending_tasks = PENDING_TASKS.lock();
+        let pending_tasks = self.pending_tasks.lock();
         if pending_tasks.len() > 0 {
             panic!("There are some pending tasks.")
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dequeue_by_priority() {
+        let run_queue = RunQueue::new(MAX_QUEUED_TASKS);
+        run_queue.push(1, SchedPriority::Low).unwrap();
+        run_queue.push(2, SchedPriority::Normal).unwrap();
+        run_queue.push(3, SchedPriority::High).unwrap();
+        run_queue.push(4, SchedPriority::Normal).unwrap();
+        run_queue.push(5, SchedPriority::High).unwrap();
+        assert!(run_queue.len() == 5);
+
+        // Higher bands first, and FIFO within a band
+        let popped: Vec<i32> = core::iter::from_fn(|| run_queue.pop()).collect();
+        assert!(popped == vec![3, 5, 2, 4, 1]);
+        assert!(run_queue.len() == 0);
+        assert!(run_queue.nonempty_bands.load(Ordering::SeqCst) == 0);
+
+        // Lower bands are still served after the higher bands are drained
+        run_queue.push(6, SchedPriority::Low).unwrap();
+        assert!(run_queue.pop() == Some(6));
+        assert!(run_queue.pop() == None);
+    }
+
+    #[test]
+    fn no_starvation() {
+        let run_queue = RunQueue::new(MAX_QUEUED_TASKS);
+        run_queue.push(0, SchedPriority::Low).unwrap();
+        run_queue.push(1, SchedPriority::Normal).unwrap();
+
+        // Keep the high band busy
+        let mut popped = Vec::new();
+        for _ in 0..STARVATION_BOUND * 2 {
+            run_queue.push(2, SchedPriority::High).unwrap();
+            popped.push(run_queue.pop().unwrap());
+        }
+
+        // The normal and the low bands are both served once
+        assert!(popped.iter().filter(|i| **i == 1).count() == 1);
+        assert!(popped.iter().filter(|i| **i == 0).count() == 1);
+        assert!(popped[..STARVATION_BOUND as usize - 1]
+            .iter()
+            .all(|i| *i == 2));
+    }
+
+    #[test]
+    fn try_steal() {
+        let run_queue = RunQueue::new(MAX_QUEUED_TASKS);
+        for i in 1..=6 {
+            run_queue.push(i, SchedPriority::Normal).unwrap();
+        }
+        run_queue.push(7, SchedPriority::High).unwrap();
+
+        let stolen = run_queue.try_steal(3, MAX_QUEUED_TASKS, |i| i % 2 == 1);
+        assert!(stolen == vec![7, 1, 3]);
+        assert!(run_queue.len() == 4);
+
+        // The number of the examined items is bounded
+        let stolen = run_queue.try_steal(MAX_QUEUED_TASKS, 2, |i| *i > 4);
+        assert!(stolen.is_empty());
+        assert!(run_queue.len() == 4);
+
+        let stolen = run_queue.try_steal(MAX_QUEUED_TASKS, MAX_QUEUED_TASKS, |i| *i > 4);
+        assert!(stolen == vec![5, 6]);
+
+        // The items not stolen are kept in order
+        let popped: Vec<i32> = core::iter::from_fn(|| run_queue.pop()).collect();
+        assert!(popped == vec![2, 4]);
+        assert!(run_queue.len() == 0);
+    }
+
+    #[test]
+    fn full_run_queue() {
+        let run_queue = RunQueue::new(2);
+        run_queue.push(1, SchedPriority::Normal).unwrap();
+        run_queue.push(2, SchedPriority::High).unwrap();
+        // The capacity is shared by all bands
+        assert!(run_queue.push(3, SchedPriority::Low) == Err(3));
+        assert!(run_queue.push(4, SchedPriority::High) == Err(4));
+        assert!(run_queue.len() == 2);
+
+        // Popping or stealing an item makes room for another
+        assert!(run_queue.pop() == Some(2));
+        run_queue.push(5, SchedPriority::Low).unwrap();
+        assert!(run_queue.try_steal(1, 2, |i| *i == 5) == vec![5]);
+        // The items put back still take their room
+        assert!(run_queue.len() == 1);
+        run_queue.push(6, SchedPriority::High).unwrap();
+        assert!(run_queue.push(7, SchedPriority::High) == Err(7));
+    }
+
+    #[test]
+    fn count_migrations() {
+        let parks = Arc::new(Parks::new(crate::executor::parallelism()));
+        let scheduler = BasicScheduler::new(parks);
+
+        // All the tasks are queued on thread 0, while thread 1 is idle
+        const NUM_TASKS: usize = 8;
+        for _ in 0..NUM_TASKS {
+            let task = crate::task::TaskBuilder::new(async {}).build();
+            scheduler.enqueue_task_to(task, 0);
+        }
+        let old_stats = scheduler.sched_stats_per_vcpu();
+        assert!(old_stats[0].num_migrations == 0);
+
+        // Thread 1 steals half of the tasks
+        assert!(scheduler.dequeue_task(1).is_some());
+        let new_stats = scheduler.sched_stats_per_vcpu();
+        assert!(new_stats[0].num_migrations == (NUM_TASKS / 2) as u64);
+        assert!(new_stats[1].num_migrations == 0);
+
+        // Dequeuing from the own run queue does not count as migrations
+        for _ in 0..NUM_TASKS / 2 {
+            assert!(scheduler.dequeue_task(0).is_some());
+        }
+        let final_stats = scheduler.sched_stats_per_vcpu();
+        assert!(final_stats[0].num_migrations == new_stats[0].num_migrations);
+    }
+}
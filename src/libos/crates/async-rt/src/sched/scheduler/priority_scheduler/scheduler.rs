@@ -30,9 +30,11 @@
 //! workers is similar as far as possible.
 
 use super::{Injector, Worker};
+use crate::config::CONFIG;
 use crate::parks::Parks;
 use crate::prelude::*;
 use crate::sched::{SchedPriority, Scheduler, MAX_QUEUED_TASKS};
+use crate::stats::{SchedStats, SchedStatsCounter};
 use crate::task::Task;
 use spin::mutex::MutexGuard;
 
@@ -44,18 +46,18 @@ pub struct PriorityScheduler {
     rebalance_lock: Mutex<()>,
     rebalance_interval: u64,
     parks: Arc<Parks>,
+    sched_stats: Vec<SchedStatsCounter>,
 }
 
 impl PriorityScheduler {
-    const REBALANCE_BASE_INTERVAL: u64 = 128;
-
     pub fn new(parks: Arc<Parks>) -> Self {
         let parallelism = parks.len();
         let workers = (0..parallelism).map(|_| Worker::new()).collect();
         let injector = Injector::new();
         let epochs = AtomicU64::new(0);
         let rebalance_lock = Mutex::new(());
-        let rebalance_interval = Self::REBALANCE_BASE_INTERVAL * parallelism as u64;
+        let rebalance_interval = CONFIG.rebalance_interval() * parallelism as u64;
+        let sched_stats = (0..parallelism).map(|_| SchedStatsCounter::new()).collect();
         Self {
             parallelism,
             workers,
@@ -64,6 +66,7 @@ impl PriorityScheduler {
             rebalance_lock,
             rebalance_interval,
             parks,
+            sched_stats,
         }
     }
 
@@ -270,6 +273,9 @@ impl PriorityScheduler {
             if let Some((target_idx, task)) =
                 self.workers[src_idx].pop_with_priority_if_pass_check(check_func, priority)
             {
+                if target_idx != src_idx {
+                    self.sched_stats[src_idx].add_migrations(1);
+                }
                 // Try to insert task to the worker.
                 if self.insert_task(task, target_idx) {
                     // The insertion succeeds, update the length.
@@ -308,13 +314,17 @@ impl Scheduler for PriorityScheduler {
                 last_thread_id
             } else {
                 // Slow path: the affinity is changed, need pick new thread.
-                self.pick_thread_for(&task)
+                let thread_id = self.pick_thread_for(&task);
+                self.sched_stats[thread_id].inc_affinity_fallbacks();
+                thread_id
             }
         } else {
             // Slow path: the budget has run out, pick new thread.
             self.pick_thread_for(&task)
         };
 
+        debug_assert!(thread_id < self.parallelism);
+        self.sched_stats[thread_id].inc_enqueues();
         self.insert_task(task, thread_id);
     }
 
@@ -337,6 +347,7 @@ impl Scheduler for PriorityScheduler {
                     self.workers[thread_id].update_latency(latency);
                 }
 
+                self.sched_stats[thread_id].inc_dequeues();
                 Some(task)
             }
             None => {
@@ -348,4 +359,18 @@ impl Scheduler for PriorityScheduler {
             }
         }
     }
+
+    fn load_per_vcpu(&self) -> Vec<usize> {
+        self.workers
+            .iter()
+            .map(|worker| worker.total_len())
+            .collect()
+    }
+
+    fn sched_stats_per_vcpu(&self) -> Vec<SchedStats> {
+        self.sched_stats
+            .iter()
+            .map(|stats| stats.snapshot())
+            .collect()
+    }
 }
@@ -96,10 +96,56 @@ impl Worker {
         self.queue(priority).pop_if_pass_check(f)
     }
 
+    /// Remove at most `max` tasks that satisfy the predicate, taking the tasks of
+    /// higher priorities first.
+    ///
+    /// The tasks that are being executed are never removed. The order of the
+    /// remaining tasks is preserved, but some of them may be moved to the injector
+    /// if the queues become full due to concurrent insertions.
+    pub fn drain<F>(&self, max: usize, pred: F, injector: &Injector) -> Vec<Arc<Task>>
+    where
+        F: Fn(&Arc<Task>) -> bool,
+    {
+        let mut drained = Vec::new();
+        for priority in [
+            SchedPriority::High,
+            SchedPriority::Normal,
+            SchedPriority::Low,
+        ] {
+            if drained.len() == max {
+                break;
+            }
+
+            // Only visit the tasks that are in the queue before draining
+            let queue = self.queue(priority);
+            let mut remaining = Vec::new();
+            for _ in 0..queue.len() {
+                let task = match queue.pop() {
+                    Some(task) => task,
+                    None => break,
+                };
+                if drained.len() < max && !task.is_running() && pred(&task) {
+                    drained.push(task);
+                } else {
+                    remaining.push(task);
+                }
+            }
+            for task in remaining {
+                self.push(task, injector);
+            }
+        }
+        drained
+    }
+
     pub fn len(&self, priority: SchedPriority) -> usize {
         self.queue(priority).len()
     }
 
+    /// Returns the number of tasks in the queues of all priorities.
+    pub fn total_len(&self) -> usize {
+        self.high_pri_queue.len() + self.normal_pri_queue.len() + self.low_pri_queue.len()
+    }
+
     pub fn is_empty(&self, priority: SchedPriority) -> bool {
         self.queue(priority).is_empty()
     }
@@ -139,3 +185,49 @@ impl Worker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{TaskBuilder, TaskId};
+
+    #[test]
+    fn drain() {
+        let worker = Worker::new();
+        let injector = Injector::new();
+        let tasks: Vec<Arc<Task>> = (0..10)
+            .map(|_| TaskBuilder::new(async {}).build())
+            .collect();
+        for task in tasks.iter() {
+            worker.push(task.clone(), &injector);
+        }
+
+        // Drain the tasks with even indexes
+        let to_drain: Vec<TaskId> = tasks.iter().step_by(2).map(|task| task.tid()).collect();
+        let pred = |task: &Arc<Task>| to_drain.contains(&task.tid());
+        let drained = worker.drain(3, pred, &injector);
+        assert!(drained.len() == 3);
+        let drained_more = worker.drain(10, pred, &injector);
+        assert!(drained_more.len() == 2);
+        let drained_tids: Vec<TaskId> = drained
+            .iter()
+            .chain(drained_more.iter())
+            .map(|task| task.tid())
+            .collect();
+        assert!(drained_tids == to_drain);
+
+        // The remaining tasks are in the original order
+        assert!(worker.total_len() == 5);
+        assert!(injector.pop().is_none());
+        let remaining_tids: Vec<TaskId> = core::iter::from_fn(|| worker.pop())
+            .map(|task| task.tid())
+            .collect();
+        let expected_tids: Vec<TaskId> = tasks
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .map(|task| task.tid())
+            .collect();
+        assert!(remaining_tids == expected_tids);
+    }
+}
@@ -1,9 +1,27 @@
 use crate::prelude::*;
+use crate::stats::SchedStats;
 use crate::task::Task;
 
 pub const MAX_QUEUED_TASKS: usize = 1_000;
 
 pub trait Scheduler: Send + Sync {
     fn enqueue_task(&self, task: Arc<Task>);
+
+    /// Dequeue a task to run on the given thread.
+    ///
+    /// This method never blocks or spins. If no task is runnable on the thread,
+    /// `None` is returned and the executor parks the thread, which is unparked
+    /// when a task is enqueued to the thread.
     fn dequeue_task(&self, thread_id: usize) -> Option<Arc<Task>>;
+
+    /// Returns the number of runnable tasks queued on each vCPU, indexed by
+    /// the vCPU IDs.
+    ///
+    /// The numbers are only snapshots of the queue lengths, which are read
+    /// without taking any scheduler-wide lock. So this method is cheap enough
+    /// to be called frequently.
+    fn load_per_vcpu(&self) -> Vec<usize>;
+
+    /// Returns the scheduling statistics of each vCPU, indexed by the vCPU IDs.
+    fn sched_stats_per_vcpu(&self) -> Vec<SchedStats>;
 }
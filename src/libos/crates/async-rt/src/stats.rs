@@ -0,0 +1,491 @@
+//! The CPU time statistics of vCPUs and the scheduling latency statistics of tasks.
+//!
+//! Each vCPU (i.e., a thread that runs `executor::run_tasks`) keeps track of
+//! how long it has been running, how long it has been parked due to having no
+//! tasks to run, and how many times it has switched between tasks. The users of
+//! the runtime may further report the time spent in the user space via
+//! `account_user_time`, which is then deducted from the busy time of a vCPU.
+//!
+//! The scheduler also counts how many times tasks are enqueued to and dequeued
+//! from each vCPU, and how many tasks are migrated away from each vCPU by load
+//! balancing, which helps to debug the fairness of scheduling.
+//!
+//! The scheduling latency of a task is the time between the task being enqueued
+//! (i.e., becoming runnable) and being dequeued (i.e., about to be polled). Long
+//! latencies reveal starvation or over-subscription. As the measurement reads
+//! the clock twice per scheduling, it is disabled by default and can be enabled
+//! with `set_sched_latency_enabled`. Then the latencies are recorded for each
+//! task (see `SchedInfo::sched_latency`) and aggregated into a runtime-wide
+//! histogram (see `sched_latency_stats`).
+use vdso_time::{clock_gettime, ClockId};
+
+use crate::executor::EXECUTOR;
+use crate::prelude::*;
+
+/// Returns the statistics of all vCPUs, indexed by the vCPU IDs.
+pub fn vcpu_stats() -> Vec<VcpuStats> {
+    EXECUTOR
+        .vcpu_stats()
+        .iter()
+        .map(|stats| stats.snapshot())
+        .collect()
+}
+
+/// Returns the scheduling statistics of all vCPUs, indexed by the vCPU IDs.
+pub fn sched_stats_per_vcpu() -> Vec<SchedStats> {
+    EXECUTOR.sched_stats_per_vcpu()
+}
+
+/// Returns the scheduling statistics summed over all vCPUs.
+pub fn sched_stats() -> SchedStats {
+    EXECUTOR
+        .sched_stats_per_vcpu()
+        .iter()
+        .fold(SchedStats::default(), |total, stats| SchedStats {
+            num_enqueues: total.num_enqueues + stats.num_enqueues,
+            num_dequeues: total.num_dequeues + stats.num_dequeues,
+            num_affinity_fallbacks: total.num_affinity_fallbacks + stats.num_affinity_fallbacks,
+            num_migrations: total.num_migrations + stats.num_migrations,
+        })
+}
+
+/// Account the given duration as the time spent in the user space by the current vCPU.
+pub fn account_user_time(duration: Duration) {
+    let vcpu_id = match crate::task::current::try_get_vcpu_id() {
+        Some(vcpu_id) => vcpu_id as usize,
+        // The time of the threads that are not vCPUs is not accounted
+        None => return,
+    };
+    if let Some(stats) = EXECUTOR.vcpu_stats().get(vcpu_id) {
+        stats
+            .user_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Enable or disable the measurement of scheduling latencies.
+pub fn set_sched_latency_enabled(enabled: bool) {
+    SCHED_LATENCY_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the measurement of scheduling latencies is enabled.
+pub fn is_sched_latency_enabled() -> bool {
+    SCHED_LATENCY_ENABLED.load(Ordering::Relaxed)
+}
+
+static SCHED_LATENCY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Returns the histogram of the scheduling latencies of all tasks.
+pub fn sched_latency_stats() -> LatencyStats {
+    EXECUTOR.sched_latency().snapshot()
+}
+
+/// A snapshot of the statistics of a vCPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VcpuStats {
+    /// The time spent in the user space.
+    pub user_time: Duration,
+    /// The time spent in running tasks, excluding the user time.
+    pub system_time: Duration,
+    /// The time spent in being parked.
+    pub idle_time: Duration,
+    /// The number of the tasks executed.
+    pub num_switches: u64,
+}
+
+/// A snapshot of the scheduling statistics of a vCPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedStats {
+    /// The number of the tasks enqueued to the vCPU.
+    pub num_enqueues: u64,
+    /// The number of the tasks dequeued by the vCPU.
+    pub num_dequeues: u64,
+    /// The number of the enqueued tasks that could not be enqueued to their
+    /// last vCPUs due to their affinity. Frequent fallbacks indicate that the
+    /// affinity of tasks is changed frequently.
+    pub num_affinity_fallbacks: u64,
+    /// The number of the queued tasks that are migrated from the vCPU to other
+    /// vCPUs by load balancing. A count that keeps growing fast while the load
+    /// is stable indicates that the tasks are migrated back and forth.
+    pub num_migrations: u64,
+}
+
+/// The scheduling statistics of a vCPU that are updated by the scheduler.
+#[derive(Debug, Default)]
+pub(crate) struct SchedStatsCounter {
+    num_enqueues: AtomicU64,
+    num_dequeues: AtomicU64,
+    num_affinity_fallbacks: AtomicU64,
+    num_migrations: AtomicU64,
+}
+
+impl SchedStatsCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inc_enqueues(&self) {
+        self.num_enqueues.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_dequeues(&self) {
+        self.num_dequeues.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_affinity_fallbacks(&self) {
+        self.num_affinity_fallbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_migrations(&self, num: u64) {
+        self.num_migrations.fetch_add(num, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> SchedStats {
+        SchedStats {
+            num_enqueues: self.num_enqueues.load(Ordering::Relaxed),
+            num_dequeues: self.num_dequeues.load(Ordering::Relaxed),
+            num_affinity_fallbacks: self.num_affinity_fallbacks.load(Ordering::Relaxed),
+            num_migrations: self.num_migrations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The statistics of a vCPU that are updated as it runs.
+#[derive(Debug, Default)]
+pub(crate) struct VcpuStatsCounter {
+    // Zero means the vCPU has not started yet
+    start_nanos: AtomicU64,
+    idle_nanos: AtomicU64,
+    user_nanos: AtomicU64,
+    num_switches: AtomicU64,
+}
+
+impl VcpuStatsCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&self) {
+        self.start_nanos
+            .store(now_nanos().max(1), Ordering::Relaxed);
+    }
+
+    pub fn add_idle_time(&self, nanos: u64) {
+        self.idle_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    pub fn inc_switches(&self) {
+        self.num_switches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> VcpuStats {
+        let start_nanos = self.start_nanos.load(Ordering::Relaxed);
+        if start_nanos == 0 {
+            return VcpuStats::default();
+        }
+
+        let total_nanos = now_nanos().saturating_sub(start_nanos);
+        let idle_nanos = self.idle_nanos.load(Ordering::Relaxed).min(total_nanos);
+        let busy_nanos = total_nanos - idle_nanos;
+        let user_nanos = self.user_nanos.load(Ordering::Relaxed).min(busy_nanos);
+        VcpuStats {
+            user_time: Duration::from_nanos(user_nanos),
+            system_time: Duration::from_nanos(busy_nanos - user_nanos),
+            idle_time: Duration::from_nanos(idle_nanos),
+            num_switches: self.num_switches.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The number of the buckets in a latency histogram.
+pub const NUM_LATENCY_BUCKETS: usize = 32;
+
+/// A summary of latencies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencySummary {
+    /// The number of the latencies.
+    pub count: u64,
+    /// The sum of the latencies.
+    pub total: Duration,
+    /// The max of the latencies.
+    pub max: Duration,
+}
+
+impl LatencySummary {
+    /// Returns the mean of the latencies.
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::default();
+        }
+        Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+    }
+}
+
+/// A snapshot of a latency histogram.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub summary: LatencySummary,
+    /// The i-th bucket counts the latencies in `[2^i, 2^(i+1))` nanoseconds,
+    /// except that the first bucket also counts zero and the last bucket also
+    /// counts all longer latencies.
+    pub buckets: [u64; NUM_LATENCY_BUCKETS],
+}
+
+/// A counter that summarizes latencies as they are recorded.
+#[derive(Debug, Default)]
+pub(crate) struct LatencyCounter {
+    count: AtomicU64,
+    total_nanos: AtomicU64,
+    max_nanos: AtomicU64,
+}
+
+impl LatencyCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, nanos: u64) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.count.load(Ordering::Relaxed),
+            total: Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed)),
+            max: Duration::from_nanos(self.max_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A histogram that counts latencies as they are recorded.
+#[derive(Debug, Default)]
+pub(crate) struct LatencyHistogram {
+    counter: LatencyCounter,
+    buckets: [AtomicU64; NUM_LATENCY_BUCKETS],
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, nanos: u64) {
+        self.counter.record(nanos);
+        let bucket_idx = (63 - nanos.max(1).leading_zeros() as usize).min(NUM_LATENCY_BUCKETS - 1);
+        self.buckets[bucket_idx].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> LatencyStats {
+        let mut buckets = [0; NUM_LATENCY_BUCKETS];
+        for (bucket, counter) in buckets.iter_mut().zip(self.buckets.iter()) {
+            *bucket = counter.load(Ordering::Relaxed);
+        }
+        LatencyStats {
+            summary: self.counter.snapshot(),
+            buckets,
+        }
+    }
+}
+
+pub(crate) fn now_nanos() -> u64 {
+    let nanos = clock_gettime(ClockId::CLOCK_MONOTONIC).unwrap().as_nanos() as u64;
+    #[cfg(test)]
+    let nanos = nanos + CLOCK_OFFSET_NANOS.load(Ordering::Relaxed);
+    nanos
+}
+
+// The time that the clock of the runtime is ahead of the monotonic clock. It
+// only grows, so the clock stays monotonic.
+#[cfg(test)]
+static CLOCK_OFFSET_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Move the clock of the runtime forward by the given duration.
+#[cfg(test)]
+pub(crate) fn advance_clock(duration: Duration) {
+    CLOCK_OFFSET_NANOS.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vcpu_stats_move_forward() {
+        crate::task::block_on(async {
+            let old_stats = vcpu_stats();
+            assert!(old_stats.len() == crate::executor::parallelism() as usize);
+
+            for _ in 0..100 {
+                crate::sched::yield_().await;
+            }
+            account_user_time(Duration::from_micros(1));
+
+            let new_stats = vcpu_stats();
+            let total_switches = |stats: &Vec<VcpuStats>| -> u64 {
+                stats.iter().map(|stats| stats.num_switches).sum()
+            };
+            assert!(total_switches(&new_stats) >= total_switches(&old_stats) + 100);
+            for (old, new) in old_stats.iter().zip(new_stats.iter()) {
+                assert!(new.user_time >= old.user_time);
+                assert!(new.idle_time >= old.idle_time);
+                assert!(
+                    new.user_time + new.system_time + new.idle_time
+                        >= old.user_time + old.system_time + old.idle_time
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn sched_stats_add_up() {
+        crate::task::block_on(async {
+            const NUM_TASKS: u64 = 300;
+
+            let old_stats = sched_stats();
+            let old_stats_per_vcpu = sched_stats_per_vcpu();
+            assert!(old_stats_per_vcpu.len() == crate::executor::parallelism() as usize);
+
+            let join_handles: Vec<_> = (0..NUM_TASKS)
+                .map(|_| crate::task::spawn(async {}))
+                .collect();
+            for join_handle in join_handles {
+                join_handle.await;
+            }
+
+            // Each task is enqueued and dequeued at least once
+            let new_stats = sched_stats();
+            assert!(new_stats.num_enqueues >= old_stats.num_enqueues + NUM_TASKS);
+            assert!(new_stats.num_dequeues >= old_stats.num_dequeues + NUM_TASKS);
+            assert!(new_stats.num_affinity_fallbacks >= old_stats.num_affinity_fallbacks);
+            assert!(new_stats.num_migrations >= old_stats.num_migrations);
+
+            let new_stats_per_vcpu = sched_stats_per_vcpu();
+            let total_enqueues = |stats: &Vec<SchedStats>| -> u64 {
+                stats.iter().map(|stats| stats.num_enqueues).sum()
+            };
+            assert!(
+                total_enqueues(&new_stats_per_vcpu)
+                    >= total_enqueues(&old_stats_per_vcpu) + NUM_TASKS
+            );
+            for (old, new) in old_stats_per_vcpu.iter().zip(new_stats_per_vcpu.iter()) {
+                assert!(new.num_enqueues >= old.num_enqueues);
+                assert!(new.num_dequeues >= old.num_dequeues);
+            }
+        });
+    }
+
+    // Enable the measurement of scheduling latencies until the guard is dropped.
+    // The guards of the tests that run concurrently are counted, so that the
+    // measurement is restored to be disabled only after the last guard is dropped.
+    struct SchedLatencyGuard;
+
+    lazy_static! {
+        static ref NUM_SCHED_LATENCY_GUARDS: Mutex<usize> = Mutex::new(0);
+    }
+
+    impl SchedLatencyGuard {
+        fn new() -> Self {
+            let mut num_guards = NUM_SCHED_LATENCY_GUARDS.lock();
+            *num_guards += 1;
+            set_sched_latency_enabled(true);
+            Self
+        }
+    }
+
+    impl Drop for SchedLatencyGuard {
+        fn drop(&mut self) {
+            let mut num_guards = NUM_SCHED_LATENCY_GUARDS.lock();
+            *num_guards -= 1;
+            if *num_guards == 0 {
+                set_sched_latency_enabled(false);
+            }
+        }
+    }
+
+    #[test]
+    fn latency_histogram() {
+        let histogram = LatencyHistogram::new();
+        for nanos in [0, 1, 3, 1_000, 1_500, u64::MAX / 2].iter() {
+            histogram.record(*nanos);
+        }
+
+        let stats = histogram.snapshot();
+        assert!(stats.summary.count == 6);
+        assert!(stats.summary.max == Duration::from_nanos(u64::MAX / 2));
+        assert!(stats.buckets[0] == 2);
+        assert!(stats.buckets[1] == 1);
+        // 1000 is in [2^9, 2^10), and 1500 is in [2^10, 2^11)
+        assert!(stats.buckets[9] == 1);
+        assert!(stats.buckets[10] == 1);
+        assert!(stats.buckets[NUM_LATENCY_BUCKETS - 1] == 1);
+        assert!(stats.buckets.iter().sum::<u64>() == 6);
+    }
+
+    #[test]
+    fn sched_latency_is_recorded() {
+        let _guard = SchedLatencyGuard::new();
+        crate::task::block_on(async {
+            const NUM_YIELDS: u64 = 100;
+
+            let old_stats = sched_latency_stats();
+            let latency = crate::task::spawn(async {
+                for _ in 0..NUM_YIELDS {
+                    crate::sched::yield_().await;
+                }
+                let current = crate::task::current::get();
+                current.sched_info().sched_latency()
+            })
+            .await;
+            assert!(latency.count >= NUM_YIELDS);
+
+            let new_stats = sched_latency_stats();
+            assert!(new_stats.summary.count >= old_stats.summary.count + NUM_YIELDS);
+            assert!(new_stats.buckets.iter().sum::<u64>() >= new_stats.summary.count);
+        });
+    }
+
+    #[test]
+    fn sched_latency_matches_injected_delay() {
+        let _guard = SchedLatencyGuard::new();
+        crate::task::block_on(async {
+            use crate::sched::Affinity;
+            use crate::wait::{Waiter, WaiterState};
+
+            const DELAY: Duration = Duration::from_millis(20);
+
+            // Run the current task on a single vCPU
+            let vcpu_id = crate::executor::parallelism() as usize - 1;
+            let affinity = {
+                let mut affinity = Affinity::new_empty();
+                affinity.set(vcpu_id, true);
+                affinity
+            };
+            let current = crate::task::current::get();
+            current.sched_info().set_affinity(&affinity).unwrap();
+            crate::sched::yield_().await;
+
+            // Spawn a waiting task that can only run on the same vCPU
+            let waiter = Waiter::new();
+            let waker = waiter.waker();
+            let handle = crate::task::spawn(async move {
+                let current = crate::task::current::get();
+                current.sched_info().set_affinity(&affinity).unwrap();
+                waiter.wait().await.unwrap();
+                current.sched_info().sched_latency()
+            });
+            while waker.state() != WaiterState::Waiting {
+                crate::sched::yield_().await;
+            }
+
+            // Wake up the task, and let the time pass before the vCPU is yielded
+            waker.wake();
+            advance_clock(DELAY);
+            crate::sched::yield_().await;
+
+            let latency = handle.await;
+            assert!(latency.max >= DELAY);
+        });
+    }
+}
@@ -0,0 +1,185 @@
+//! A pool of OS threads for running blocking operations.
+//!
+//! A blocking operation (e.g., a synchronous I/O) stalls the vCPU that runs it,
+//! together with all the other tasks on the vCPU. `spawn_blocking` offloads such
+//! an operation to one of the OS threads of the pool, which are separate from
+//! the vCPU threads. The threads are spawned on demand, up to the number set by
+//! `config::set_blocking_threads`, and are reused for later operations.
+use std::panic::{self, AssertUnwindSafe};
+
+use flume::{Receiver, Sender};
+
+use super::join::{JoinState, OutputHandle};
+use super::JoinHandle;
+use crate::config::CONFIG;
+use crate::prelude::*;
+
+/// Run a blocking closure on the blocking thread pool.
+///
+/// The returned join handle can be awaited to get the output of the closure.
+pub fn spawn_blocking<T, F>(f: F) -> JoinHandle<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(JoinState::new()));
+    let output_handle = OutputHandle::new(&state);
+    BLOCKING_POOL.execute(Box::new(move || {
+        output_handle.set(f());
+    }));
+
+    crate::task::spawn(BlockingOutput { state })
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+lazy_static! {
+    static ref BLOCKING_POOL: BlockingPool = BlockingPool::new();
+}
+
+struct BlockingPool {
+    sender: Sender<Job>,
+    receiver: Receiver<Job>,
+    num_threads: AtomicU32,
+    num_idle_threads: AtomicU32,
+}
+
+impl BlockingPool {
+    pub fn new() -> Self {
+        let (sender, receiver) = flume::unbounded();
+        Self {
+            sender,
+            receiver,
+            num_threads: AtomicU32::new(0),
+            num_idle_threads: AtomicU32::new(0),
+        }
+    }
+
+    pub fn execute(&'static self, job: Job) {
+        self.sender.send(job).unwrap();
+
+        // Spawn a new thread if no thread is idle and the pool is not full
+        if self.num_idle_threads.load(Ordering::Relaxed) > 0 {
+            return;
+        }
+        let max_threads = CONFIG.blocking_threads();
+        let can_spawn = self
+            .num_threads
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |num| {
+                if num < max_threads {
+                    Some(num + 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok();
+        if can_spawn {
+            std::thread::spawn(move || self.run_jobs());
+        }
+    }
+
+    fn run_jobs(&self) {
+        loop {
+            self.num_idle_threads.fetch_add(1, Ordering::Relaxed);
+            let job = self.receiver.recv().unwrap();
+            self.num_idle_threads.fetch_sub(1, Ordering::Relaxed);
+
+            // Catch the panic of the job so that the thread keeps running other jobs.
+            // The output handle of the job is dropped while unwinding, which aborts
+            // the join handle of the job.
+            if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                error!("a blocking job is aborted due to a panic");
+            }
+        }
+    }
+}
+
+// The future that waits for the output of a blocking closure.
+struct BlockingOutput<T: Send + 'static> {
+    state: Arc<Mutex<JoinState<T>>>,
+}
+
+impl<T: Send + 'static> Future for BlockingOutput<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        match state.take_output(cx) {
+            Some(output) => Poll::Ready(output),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocking_does_not_stall_vcpus() {
+        crate::task::block_on(async {
+            // More closures than the threads, so that the threads are reused
+            let num_closures = CONFIG.blocking_threads() * 2;
+            let async_done = Arc::new(AtomicBool::new(false));
+
+            let blocking_handles: Vec<JoinHandle<bool>> = (0..num_closures)
+                .map(|_| {
+                    let async_done = async_done.clone();
+                    spawn_blocking(move || {
+                        std::thread::sleep(Duration::from_millis(200));
+                        async_done.load(Ordering::Acquire)
+                    })
+                })
+                .collect();
+
+            // The async tasks keep making progress while the closures are blocked
+            let async_handle = {
+                let async_done = async_done.clone();
+                crate::task::spawn(async move {
+                    for _ in 0..100 {
+                        crate::sched::yield_().await;
+                    }
+                    async_done.store(true, Ordering::Release);
+                })
+            };
+            async_handle.await;
+
+            for blocking_handle in blocking_handles {
+                assert!(blocking_handle.await);
+            }
+            assert!(BLOCKING_POOL.num_threads.load(Ordering::Relaxed) <= CONFIG.blocking_threads());
+        });
+    }
+
+    #[test]
+    fn panicking_blocking_job() {
+        crate::task::block_on(async {
+            // More panicking closures than the threads, so that the panics would
+            // use up the threads if they were not caught
+            let num_closures = CONFIG.blocking_threads() * 2;
+            let panic_handles: Vec<JoinHandle<()>> = (0..num_closures)
+                .map(|_| spawn_blocking(|| panic!("a panic in a blocking job")))
+                .collect();
+
+            // The panic is re-raised to the joiner
+            let joiner_tasks: Vec<Arc<crate::task::Task>> = panic_handles
+                .into_iter()
+                .map(|panic_handle| {
+                    let joiner_handle = crate::task::spawn(async move {
+                        panic_handle.await;
+                    });
+                    joiner_handle.task().clone()
+                })
+                .collect();
+            for joiner_task in joiner_tasks {
+                while !joiner_task.is_aborted() {
+                    crate::sched::yield_().await;
+                }
+            }
+
+            // The threads are still there to run new jobs
+            assert!(spawn_blocking(|| 1).await == 1);
+            assert!(BLOCKING_POOL.num_threads.load(Ordering::Relaxed) <= CONFIG.blocking_threads());
+        });
+    }
+}
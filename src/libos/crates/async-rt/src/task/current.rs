@@ -47,9 +47,26 @@ pub(crate) fn set_vcpu_id(vcpu_id: u32) {
     VCPU_ID.store(vcpu_id, Ordering::Relaxed);
 }
 
+/// Returns the ID of the vCPU that the current thread runs as.
+///
+/// If the current thread is not a vCPU (e.g., the timer thread), zero is returned.
+/// Use `try_get_vcpu_id` to tell the two cases apart.
 pub fn get_vcpu_id() -> u32 {
-    VCPU_ID.load(Ordering::Relaxed)
+    try_get_vcpu_id().unwrap_or(0)
 }
 
+/// Returns the ID of the vCPU that the current thread runs as, or `None` if the
+/// current thread is not a vCPU.
+pub fn try_get_vcpu_id() -> Option<u32> {
+    let vcpu_id = VCPU_ID.load(Ordering::Relaxed);
+    if vcpu_id == NONE_VCPU_ID {
+        return None;
+    }
+    Some(vcpu_id)
+}
+
+// The vCPU ID of the threads that are not vCPUs
+const NONE_VCPU_ID: u32 = u32::MAX;
+
 #[thread_local]
-static VCPU_ID: AtomicU32 = AtomicU32::new(0);
+static VCPU_ID: AtomicU32 = AtomicU32::new(NONE_VCPU_ID);
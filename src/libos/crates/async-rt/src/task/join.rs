@@ -31,6 +31,10 @@ impl<T: Send + 'static> Unpin for JoinHandle<T> {}
 impl<T: Send + 'static> Future for JoinHandle<T> {
     type Output = T;
 
+    /// Poll the output of the task.
+    ///
+    /// If the task is aborted before producing its output (e.g., due to a panic),
+    /// the panic is re-raised to the joiner.
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut state = self.state.lock();
         if let Some(output) = state.take_output(cx) {
@@ -54,20 +58,33 @@ impl<T: Send + 'static> OutputHandle<T> {
         }
     }
 
-    pub fn set(self, output: T) {
-        if let Some(state) = self.state.upgrade() {
+    pub fn set(mut self, output: T) {
+        let state = core::mem::replace(&mut self.state, Weak::new());
+        if let Some(state) = state.upgrade() {
             let mut state = state.lock();
             state.set_output(output);
         }
     }
 }
 
+impl<T: Send + 'static> Drop for OutputHandle<T> {
+    // The handle is dropped without setting the output only if the future that
+    // owns it is dropped before completion, e.g., when the future panics
+    fn drop(&mut self) {
+        if let Some(state) = self.state.upgrade() {
+            let mut state = state.lock();
+            state.set_aborted();
+        }
+    }
+}
+
 // The state of a task that is to be joined.
 #[derive(Debug)]
 pub enum JoinState<T: Send + 'static> {
     Init,
     Pending(Waker),
     Ready(T),
+    Aborted,
     Finish,
 }
 
@@ -83,12 +100,19 @@ impl<T: Send + 'static> JoinState<T> {
                 waker.wake_by_ref();
                 JoinState::Ready(value)
             }
-            JoinState::Ready(_) | JoinState::Finish => {
+            JoinState::Ready(_) | JoinState::Aborted | JoinState::Finish => {
                 panic!("a task's output must not be set twice");
             }
         };
     }
 
+    pub fn set_aborted(&mut self) {
+        if let JoinState::Pending(waker) = self {
+            waker.wake_by_ref();
+        }
+        *self = JoinState::Aborted;
+    }
+
     pub fn take_output(&mut self, cx: &mut Context<'_>) -> Option<T> {
         match self {
             JoinState::Init | JoinState::Pending(_) => {
@@ -105,6 +129,9 @@ impl<T: Send + 'static> JoinState<T> {
                     unreachable!();
                 }
             }
+            JoinState::Aborted => {
+                panic!("the joined task is aborted without an output");
+            }
             JoinState::Finish => {
                 panic!("a task's output must not be taken again");
             }
@@ -1,9 +1,10 @@
 use self::join::{JoinState, OutputHandle};
-use self::task::TaskBuilder;
+pub(crate) use self::task::TaskBuilder;
 use crate::executor::EXECUTOR;
 use crate::prelude::*;
 use crate::sched::SchedPriority;
 
+pub use self::blocking::spawn_blocking;
 pub use self::id::TaskId;
 pub use self::join::JoinHandle;
 pub use self::locals::LocalKey;
@@ -12,6 +13,7 @@ pub use self::tirqs::Tirqs;
 
 pub(crate) use self::locals::LocalsMap;
 
+mod blocking;
 pub mod current;
 mod id;
 mod join;
@@ -23,6 +25,15 @@ pub fn spawn<T: Send + 'static>(future: impl Future<Output = T> + 'static + Send
     SpawnOptions::new(future).spawn()
 }
 
+/// Spawn a new task, or return an error if the executor no longer accepts new tasks.
+///
+/// See `SpawnOptions::try_spawn`.
+pub fn try_spawn<T: Send + 'static>(
+    future: impl Future<Output = T> + 'static + Send,
+) -> Result<JoinHandle<T>> {
+    SpawnOptions::new(future).try_spawn()
+}
+
 pub fn block_on<T: Send + 'static>(future: impl Future<Output = T> + 'static + Send) -> T {
     #[cfg(any(test, feature = "auto_run"))]
     init_runner_threads();
@@ -44,7 +55,9 @@ pub fn block_on<T: Send + 'static>(future: impl Future<Output = T> + 'static + S
     };
 
     let task = TaskBuilder::new(future).build();
-    EXECUTOR.accept_task(task);
+    EXECUTOR
+        .accept_task(task)
+        .expect("a shut-down executor cannot block on a future");
     while !completed.load(Ordering::Acquire) {}
 
     let mut output_slot = output_slot.lock();
@@ -91,7 +104,28 @@ impl<T: Send + 'static> SpawnOptions<T> {
         self
     }
 
+    /// Spawn a new task.
+    ///
+    /// If the executor is being shut down, the task is rejected and aborted, so
+    /// awaiting the returned join handle panics. Use `try_spawn` to handle the
+    /// rejection.
     pub fn spawn(&mut self) -> JoinHandle<T> {
+        match self.do_spawn() {
+            (join_handle, Ok(())) => join_handle,
+            (join_handle, Err(e)) => {
+                warn!("task {:?} is rejected: {}", join_handle.task().tid(), e);
+                join_handle
+            }
+        }
+    }
+
+    /// Spawn a new task, or return an error if the executor is being shut down.
+    pub fn try_spawn(&mut self) -> Result<JoinHandle<T>> {
+        let (join_handle, res) = self.do_spawn();
+        res.map(|_| join_handle)
+    }
+
+    fn do_spawn(&mut self) -> (JoinHandle<T>, Result<()>) {
         #[cfg(any(test, feature = "auto_run"))]
         init_runner_threads();
 
@@ -108,7 +142,7 @@ impl<T: Send + 'static> SpawnOptions<T> {
         let task = TaskBuilder::new(future).priority(self.priority).build();
         let join_handle = JoinHandle::new(state, task.clone());
 
-        EXECUTOR.accept_task(task);
-        join_handle
+        let res = EXECUTOR.accept_task(task);
+        (join_handle, res)
     }
 }
@@ -3,13 +3,12 @@ use core::fmt::{self, Debug};
 
 use futures::task::ArcWake;
 
+use crate::config::CONFIG;
 use crate::executor::EXECUTOR;
 use crate::prelude::*;
 use crate::sched::{SchedInfo, SchedPriority};
 use crate::task::{LocalsMap, TaskId, Tirqs};
 
-const DEFAULT_BUDGET: u8 = 64;
-
 pub struct Task {
     tid: TaskId,
     sched_info: SchedInfo,
@@ -21,6 +20,8 @@ pub struct Task {
     // Used by executor to avoid a task consuming too much space in enqueues
     // due to a task being enqueued multiple times.
     is_enqueued: AtomicBool,
+    // Set if the future of the task panicked while being polled
+    is_aborted: AtomicBool,
     weak_self: Weak<Self>,
 }
 
@@ -59,6 +60,13 @@ impl Task {
         &self.future
     }
 
+    /// Returns whether the task is being executed.
+    ///
+    /// The executor holds the lock of the future while polling it.
+    pub(crate) fn is_running(&self) -> bool {
+        self.future.is_locked()
+    }
+
     pub(crate) fn locals(&self) -> &LocalsMap {
         &self.locals
     }
@@ -98,6 +106,18 @@ impl Task {
     pub(crate) fn reset_enqueued(&self) {
         self.is_enqueued.store(false, Ordering::Relaxed);
     }
+
+    /// Returns whether the task is aborted due to a panic in its future.
+    ///
+    /// An aborted task is never polled again. Awaiting its join handle re-raises
+    /// the panic to the joiner.
+    pub fn is_aborted(&self) -> bool {
+        self.is_aborted.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_aborted(&self) {
+        self.is_aborted.store(true, Ordering::Relaxed);
+    }
 }
 
 unsafe impl Sync for Task {}
@@ -137,7 +157,7 @@ impl TaskBuilder {
         Self {
             future: Some(future.boxed()),
             priority: SchedPriority::Normal,
-            budget: DEFAULT_BUDGET,
+            budget: CONFIG.task_budget(),
         }
     }
 
@@ -163,6 +183,7 @@ impl TaskBuilder {
         // Safety. The tirqs will be inserted into a Task before using it.
         let tirqs = unsafe { Tirqs::new() };
         let is_enqueued = AtomicBool::new(false);
+        let is_aborted = AtomicBool::new(false);
         let weak_self = Weak::new();
         let task = Task {
             tid,
@@ -173,6 +194,7 @@ impl TaskBuilder {
             consumed_budget,
             tirqs,
             is_enqueued,
+            is_aborted,
             weak_self,
         };
         // Create an Arc and update the weak_self
@@ -0,0 +1,136 @@
+use super::wheel::TIMER_WHEEL;
+use super::Instant;
+use crate::prelude::*;
+
+lazy_static! {
+    static ref GLOBAL_TEST_CLOCK: Arc<TestClock> = Arc::new(TestClock::new());
+}
+
+static USE_TEST_CLOCK: AtomicBool = AtomicBool::new(false);
+
+/// A deterministic clock for testing timer-dependent logic.
+///
+/// The time of a test clock never moves forward by itself. It only advances
+/// when `advance` is called, so timeouts and timers can be tested instantly
+/// and without any flakiness caused by the wall clock.
+#[derive(Debug)]
+pub struct TestClock {
+    // The current time in nanoseconds since the creation of the clock.
+    nanos: AtomicU64,
+}
+
+impl TestClock {
+    /// Create a test clock that starts at time zero.
+    pub fn new() -> Self {
+        Self {
+            nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Instant {
+        Instant(Duration::from_nanos(self.nanos.load(Ordering::Acquire)))
+    }
+
+    /// Move the clock forward by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::AcqRel);
+    }
+}
+
+/// Advance the test clock of the runtime and fire all the expired timers.
+///
+/// This function panics if the runtime is not configured to use the test clock
+/// (see `crate::config::use_test_clock`).
+pub fn advance(duration: Duration) {
+    let clock = global_test_clock().expect("the runtime does not use the test clock");
+    clock.advance(duration);
+    TIMER_WHEEL.make_progress();
+}
+
+pub(crate) fn enable_test_clock() {
+    USE_TEST_CLOCK.store(true, Ordering::Release);
+}
+
+/// Returns the test clock of the runtime if it is in use.
+pub(crate) fn global_test_clock() -> Option<&'static Arc<TestClock>> {
+    if USE_TEST_CLOCK.load(Ordering::Acquire) {
+        Some(&GLOBAL_TEST_CLOCK)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::task::ArcWake;
+
+    use super::super::entry::{TimerEntry, TimerFutureEntry};
+    use super::super::wheel::TimerWheel;
+    use super::*;
+
+    struct FireRecorder {
+        id: u32,
+        fired: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl ArcWake for FireRecorder {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.fired.lock().push(arc_self.id);
+        }
+    }
+
+    #[test]
+    fn fire_in_order() {
+        let clock = Arc::new(TestClock::new());
+        let wheel = TimerWheel::with_test_clock(Some(clock.clone()));
+        let fired = Arc::new(Mutex::new(Vec::new()));
+
+        // Arm the timers in an order that differs from their expiration order
+        let timeouts_ms = [30, 10, 20, 50];
+        let timers: Vec<(TimerEntry, TimerFutureEntry)> = timeouts_ms
+            .iter()
+            .enumerate()
+            .map(|(id, ms)| {
+                let entry = TimerEntry::new(Duration::from_millis(*ms));
+                let future_entry = TimerFutureEntry::new(&entry);
+                let waker = futures::task::waker(Arc::new(FireRecorder {
+                    id: id as u32,
+                    fired: fired.clone(),
+                }));
+                let mut cx = Context::from_waker(&waker);
+                assert!(future_entry.poll_on(&wheel, &mut cx).is_pending());
+                (entry, future_entry)
+            })
+            .collect();
+
+        clock.advance(Duration::from_millis(5));
+        wheel.make_progress();
+        assert!(fired.lock().is_empty());
+
+        clock.advance(Duration::from_millis(20));
+        wheel.make_progress();
+        assert_eq!(*fired.lock(), vec![1, 2]);
+
+        clock.advance(Duration::from_millis(100));
+        wheel.make_progress();
+        assert_eq!(*fired.lock(), vec![1, 2, 0, 3]);
+
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        for (_, future_entry) in timers.iter() {
+            assert!(future_entry.poll_on(&wheel, &mut cx).is_ready());
+        }
+    }
+
+    #[test]
+    fn advance_clock() {
+        let clock = TestClock::new();
+        let start = clock.now();
+        assert!(clock.now() == start);
+
+        clock.advance(Duration::from_secs(3));
+        assert!(clock.now() - start == Duration::from_secs(3));
+    }
+}
@@ -1,4 +1,4 @@
-use super::wheel::TIMER_WHEEL;
+use super::wheel::{TimerWheel, TIMER_WHEEL};
 use super::DURATION_ZERO;
 use crate::prelude::*;
 use core::task::Waker;
@@ -135,13 +135,11 @@ impl TimerFutureEntry {
     pub fn new(entry: &TimerEntry) -> Self {
         Self(entry.0.clone())
     }
-}
-
-impl Future for TimerFutureEntry {
-    type Output = ();
 
     /// Return Ready if the timer is expired, or Pending.
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    ///
+    /// The timer is inserted into the given timer wheel when it is polled for the first time.
+    pub(crate) fn poll_on(&self, wheel: &TimerWheel, cx: &mut Context<'_>) -> Poll<()> {
         let mut shared = self.0.lock();
         match &mut shared.state {
             TimerState::Init => {
@@ -153,7 +151,7 @@ impl Future for TimerFutureEntry {
 
                 // Insert the timer entry to the timer wheel.
                 let entry = TimerWheelEntry(self.0.clone());
-                let start_ticks = TIMER_WHEEL.insert_entry(entry, shared.timeout);
+                let start_ticks = wheel.insert_entry(entry, shared.timeout);
                 // Transfer to started state, set start_tick and waker.
                 let inner = StartedInner::new(start_ticks, cx.waker().clone());
                 shared.state = TimerState::Started(inner);
@@ -169,6 +167,15 @@ impl Future for TimerFutureEntry {
     }
 }
 
+impl Future for TimerFutureEntry {
+    type Output = ();
+
+    /// Return Ready if the timer is expired, or Pending.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_on(&TIMER_WHEEL, cx)
+    }
+}
+
 impl Drop for TimerFutureEntry {
     /// Make sure that the state is Init, Expired or Cancelled after drop.
     /// If the state is Started, cancel the timer before dropped.
@@ -1,3 +1,4 @@
+use super::clock::global_test_clock;
 use crate::prelude::*;
 use core::ops::{Add, AddAssign, Sub, SubAssign};
 use vdso_time::{clock_gettime, ClockId};
@@ -20,7 +21,13 @@ impl Instant {
     /// However, the clock in sgx env is untrusted, we can not guarantee that clock is real-monotonic.
     /// To ensure the instant is monotonically nondecreasing, We keep a global "latest now" instance
     /// which is returned instead of what the OS says if the OS goes backwards.
+    ///
+    /// If the runtime is configured to use the test clock, the time of the test clock is returned.
     pub fn now() -> Self {
+        if let Some(clock) = global_test_clock() {
+            return clock.now();
+        }
+
         let os_now = Instant(clock_gettime(ClockId::CLOCK_MONOTONIC).unwrap());
 
         let mut last_now = LAST_NOW.lock();
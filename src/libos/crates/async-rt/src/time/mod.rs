@@ -1,7 +1,10 @@
+pub use self::clock::{advance, TestClock};
+pub(crate) use self::clock::enable_test_clock;
 pub(crate) use self::entry::{TimerEntry, TimerFutureEntry};
 pub use self::instant::{Instant, DURATION_ZERO};
 pub use self::wheel::{run_timer_wheel_thread, wake_timer_wheel};
 
+mod clock;
 mod entry;
 mod instant;
 mod wheel;
@@ -1,3 +1,4 @@
+use super::clock::{global_test_clock, TestClock};
 use super::entry::TimerWheelEntry;
 use super::Instant;
 use crate::executor::EXECUTOR;
@@ -105,20 +106,40 @@ pub struct TimerWheel {
     ticks: AtomicU64,
     // start time of the timerwheel.
     start: Instant,
+    // the test clock that drives the timerwheel, or None if using the real clock.
+    test_clock: Option<Arc<TestClock>>,
     // status of the timerwheel.
     status: Mutex<TimerWheelStatus>,
 }
 
 impl TimerWheel {
     pub fn new() -> Self {
+        Self::with_test_clock(global_test_clock().cloned())
+    }
+
+    /// Create a timer wheel driven by the given test clock, or by the real clock if it is None.
+    pub(crate) fn with_test_clock(test_clock: Option<Arc<TestClock>>) -> Self {
+        let start = match &test_clock {
+            Some(clock) => clock.now(),
+            None => Instant::now(),
+        };
         Self {
             wheel: Mutex::new(QuadWheelWithOverflow::default()),
             ticks: AtomicU64::new(0),
-            start: Instant::now(),
+            start,
+            test_clock,
             status: Mutex::new(TimerWheelStatus::Idle),
         }
     }
 
+    /// Get the elapsed time since the start of the timer wheel.
+    fn elapsed(&self) -> Duration {
+        match &self.test_clock {
+            Some(clock) => clock.now() - self.start,
+            None => self.start.elapsed(),
+        }
+    }
+
     /// Get the status of the timer wheel
     fn status(&self) -> &Mutex<TimerWheelStatus> {
         &self.status
@@ -201,7 +222,7 @@ impl TimerWheel {
         &self,
         wheel_guard: &mut MutexGuard<QuadWheelWithOverflow<TimerWheelEntry>>,
     ) -> Vec<TimerWheelEntry> {
-        let elapsed = self.start.elapsed().as_millis() as u64;
+        let elapsed = self.elapsed().as_millis() as u64;
         // calculate the step that we need move forward, in most times, it should be 0 or 1.
         let diff = elapsed - self.latest_ticks();
         self.ticks.store(elapsed, Ordering::Release);
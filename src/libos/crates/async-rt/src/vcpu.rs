@@ -0,0 +1,124 @@
+//! vCPUs and vCPU-local storage.
+
+use alloc::collections::BTreeMap;
+use core::any::{Any, TypeId};
+use core::sync::atomic::AtomicPtr;
+use spin::Once;
+
+use crate::prelude::*;
+
+/// Set the total number of vCPUs.
+///
+/// This is the same as `crate::config::set_parallelism`. So it only takes effect
+/// before the executor is first used. After that, the total is fixed.
+pub fn set_total(total: u32) {
+    crate::config::set_parallelism(total);
+}
+
+/// Returns the total number of vCPUs, i.e., the parallelism of the executor.
+pub fn get_total() -> u32 {
+    crate::executor::parallelism()
+}
+
+/// Returns the ID of the vCPU that the current thread runs as.
+///
+/// See `crate::task::current::get_vcpu_id`.
+pub fn get_current() -> u32 {
+    crate::task::current::get_vcpu_id()
+}
+
+/// Returns the instance of `T` that is local to the current vCPU.
+///
+/// Each vCPU has its own instance of `T`, which is created with `T::default()`
+/// on its first access and is never dropped. This saves the users of per-vCPU
+/// data (e.g., caches) from passing the vCPU ID around.
+///
+/// The number of instances is `get_total()`. So this function must not be
+/// called before `set_total`.
+///
+/// # Panics
+///
+/// This function panics if the current thread is not a vCPU.
+pub fn local<T: Default + Send + Sync + 'static>() -> &'static T {
+    let vcpu_id = crate::task::current::try_get_vcpu_id()
+        .expect("vCPU-local data can only be accessed on a vCPU") as usize;
+    instances_of::<T>()[vcpu_id].call_once(T::default)
+}
+
+type LocalsMap = BTreeMap<TypeId, &'static (dyn Any + Send + Sync)>;
+
+fn instances_of<T: Default + Send + Sync + 'static>() -> &'static [Once<T>] {
+    let type_id = TypeId::of::<T>();
+
+    // Fast path: look up the instances in the latest map without any lock
+    if let Some(instances) = latest_locals().and_then(|locals| locals.get(&type_id)) {
+        return instances.downcast_ref::<Box<[Once<T>]>>().unwrap();
+    }
+
+    // Slow path: publish a new map with the instances of `T` added
+    let _guard = UPDATE_LOCK.lock();
+    let mut new_locals = latest_locals().cloned().unwrap_or_default();
+    let instances = *new_locals.entry(type_id).or_insert_with(|| {
+        let total = get_total() as usize;
+        let instances: Box<[Once<T>]> = (0..total).map(|_| Once::new()).collect();
+        let instances: &'static (dyn Any + Send + Sync) = Box::leak(Box::new(instances));
+        instances
+    });
+    // The old map is leaked, as it may still be used by the fast path. This is
+    // fine since a map is published at most once per type.
+    LATEST_LOCALS.store(Box::into_raw(Box::new(new_locals)), Ordering::Release);
+    instances.downcast_ref::<Box<[Once<T>]>>().unwrap()
+}
+
+fn latest_locals() -> Option<&'static LocalsMap> {
+    let ptr = LATEST_LOCALS.load(Ordering::Acquire);
+    // Safety. A published map is never modified or freed.
+    unsafe { ptr.as_ref() }
+}
+
+// The map from types to their per-vCPU instances, which is replaced by an
+// updated copy whenever a new type is added
+static LATEST_LOCALS: AtomicPtr<LocalsMap> = AtomicPtr::new(core::ptr::null_mut());
+
+lazy_static! {
+    // Serializes the updates of `LATEST_LOCALS`
+    static ref UPDATE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sched::Affinity;
+
+    #[derive(Default)]
+    struct Cache {
+        hits: AtomicU32,
+    }
+
+    async fn local_on_vcpu(vcpu_id: usize) -> usize {
+        let affinity = {
+            let mut affinity = Affinity::new_empty();
+            affinity.set(vcpu_id, true);
+            affinity
+        };
+        let current = crate::task::current::get();
+        current.sched_info().set_affinity(&affinity).unwrap();
+        crate::sched::yield_().await;
+        assert!(crate::task::current::get_vcpu_id() as usize == vcpu_id);
+
+        let cache = local::<Cache>();
+        cache.hits.fetch_add(1, Ordering::Relaxed);
+        // The same vCPU always gets the same instance
+        assert!(core::ptr::eq(cache, local::<Cache>()));
+        cache as *const Cache as usize
+    }
+
+    #[test]
+    fn distinct_instances_per_vcpu() {
+        crate::task::block_on(async {
+            let addr0 = crate::task::spawn(local_on_vcpu(0)).await;
+            let addr1 = crate::task::spawn(local_on_vcpu(1)).await;
+            assert!(addr0 != addr1);
+        });
+    }
+}
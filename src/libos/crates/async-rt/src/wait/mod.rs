@@ -3,7 +3,7 @@ mod waiter;
 mod waiter_queue;
 
 pub use self::macros::AutoWaiter;
-pub use self::waiter::{Waiter, Waker};
+pub use self::waiter::{Waiter, WaiterState, Waker};
 pub use self::waiter_queue::WaiterQueue;
 
 #[cfg(test)]
@@ -234,6 +234,40 @@ mod tests {
         });
     }
 
+    #[test]
+    fn wake_all_in_batch() {
+        crate::task::block_on(async {
+            const NUM_WAITERS: usize = 1000;
+            let waiter_queue = Arc::new(WaiterQueue::new());
+            let num_waiting = Arc::new(AtomicU32::new(0));
+            let join_handles: Vec<_> = (0..NUM_WAITERS)
+                .map(|_| {
+                    let waiter_queue = waiter_queue.clone();
+                    let num_waiting = num_waiting.clone();
+                    crate::task::spawn(async move {
+                        let mut waiter = Waiter::new();
+                        waiter_queue.enqueue(&mut waiter);
+                        num_waiting.fetch_add(1, Ordering::Relaxed);
+                        waiter.wait().await.unwrap();
+                        waiter_queue.dequeue(&mut waiter);
+                    })
+                })
+                .collect();
+            while num_waiting.load(Ordering::Relaxed) < NUM_WAITERS as u32 {
+                crate::sched::yield_().await;
+            }
+
+            let old_num_unparks = crate::parks::num_unparks();
+            assert!(waiter_queue.wake_all() == NUM_WAITERS);
+            let num_unparks = crate::parks::num_unparks() - old_num_unparks;
+            assert!(num_unparks <= crate::executor::parallelism() as usize);
+
+            for join_handle in join_handles {
+                join_handle.await;
+            }
+        });
+    }
+
     async fn imagined_blocking_func1(timeout: Option<&mut Duration>) {
         assert!(timeout.is_some());
 
@@ -5,6 +5,7 @@ use intrusive_collections::{LinkedList, LinkedListLink};
 use object_id::ObjectId;
 
 use super::waiter::{Waiter, WaiterInner};
+use crate::executor::EXECUTOR;
 use crate::prelude::*;
 
 /// A waiter queue.
@@ -59,9 +60,14 @@ impl WaiterQueue {
     }
 
     /// Wake up a given number of waiters.
+    ///
+    /// The woken tasks are all enqueued before any vCPU is unparked. And each vCPU
+    /// is unparked at most once, no matter how many waiters are woken up.
     pub fn wake_nr(&self, count: usize) -> usize {
-        let mut inner = self.inner.lock();
-        inner.wake_nr(count)
+        EXECUTOR.batch_unparks(|| {
+            let mut inner = self.inner.lock();
+            inner.wake_nr(count)
+        })
     }
 }
 
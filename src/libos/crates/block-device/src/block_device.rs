@@ -14,6 +14,31 @@ pub trait BlockDevice: Any + Send + Sync {
     /// Async Rust code can also await on the submission object for the
     /// completion of the I/O request.
     fn submit(&self, req: Arc<BioReq>) -> BioSubmission;
+
+    /// Submit a batch of I/O requests to the device, returning the I/O
+    /// submissions that correspond to the I/O requests in the same order.
+    ///
+    /// Each request is completed on its own, just as if it were submitted with
+    /// `submit`. The requests in a batch may be completed in any order.
+    ///
+    /// By default, the requests are submitted one by one. A device may submit
+    /// a batch of requests at a lower cost, e.g., by notifying the underlying
+    /// hardware (or the host) only once for all requests.
+    fn submit_batch(&self, reqs: Vec<Arc<BioReq>>) -> Vec<BioSubmission> {
+        reqs.into_iter().map(|req| self.submit(req)).collect()
+    }
+
+    /// Resize the device to the given total number of blocks.
+    ///
+    /// Growing a device keeps all existing blocks intact and makes the new
+    /// blocks accessible. Whether a device can be shrunk, which discards the
+    /// blocks beyond the new size, is up to the implementation.
+    ///
+    /// By default, a device cannot be resized and an `EOPNOTSUPP` error is
+    /// returned.
+    fn resize(&self, _new_total_blocks: usize) -> Result<()> {
+        Err(errno!(EOPNOTSUPP, "the device cannot be resized"))
+    }
 }
 
 impl dyn BlockDevice {
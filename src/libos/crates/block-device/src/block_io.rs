@@ -19,6 +19,34 @@ pub enum BioType {
     Write,
     /// A flush request.
     Flush,
+    /// A discard request, which tells that the data of the requested blocks
+    /// are no longer needed.
+    ///
+    /// A block device that supports discarding would read the discarded blocks
+    /// back as zeros. A block device that cannot discard may complete the
+    /// request successfully without doing anything.
+    Discard,
+}
+
+/// The priority of a block request.
+///
+/// A block device may serve the requests of a higher priority first, e.g.,
+/// latency-sensitive metadata writes before bulk data writes. Whether and how
+/// the priority is respected is up to the implementation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BioPrio {
+    /// A low-priority request.
+    Low,
+    /// A normal-priority request.
+    Normal,
+    /// A high-priority request.
+    High,
+}
+
+impl Default for BioPrio {
+    fn default() -> Self {
+        Self::Normal
+    }
 }
 
 /// A builder for `BioReq`.
@@ -26,6 +54,8 @@ pub struct BioReqBuilder {
     type_: BioType,
     addr: Option<BlockId>,
     bufs: Option<Vec<BlockBuf>>,
+    num_blocks: Option<usize>,
+    priority: BioPrio,
     ext: Option<AnyMap>,
     on_complete: Option<BioReqOnCompleteFn>,
     on_drop: Option<BioReqOnDropFn>,
@@ -38,6 +68,8 @@ impl BioReqBuilder {
             type_,
             addr: None,
             bufs: None,
+            num_blocks: None,
+            priority: BioPrio::default(),
             ext: None,
             on_complete: None,
             on_drop: None,
@@ -56,6 +88,21 @@ impl BioReqBuilder {
         self
     }
 
+    /// Specify the number of blocks of the request.
+    ///
+    /// This is only meaningful for a discard, which has no buffers. For reads
+    /// and writes, the number of blocks is determined by the buffers.
+    pub fn num_blocks(mut self, num_blocks: usize) -> Self {
+        self.num_blocks = Some(num_blocks);
+        self
+    }
+
+    /// Specify the priority of the request, which is `BioPrio::Normal` by default.
+    pub fn priority(mut self, priority: BioPrio) -> Self {
+        self.priority = priority;
+        self
+    }
+
     /// Add an extension object to the request.
     pub fn ext<T: Any + Sized>(mut self, obj: T) -> Self {
         if self.ext.is_none() {
@@ -80,16 +127,24 @@ impl BioReqBuilder {
     /// Build the request.
     pub fn build(mut self) -> BioReq {
         let type_ = self.type_;
-        if ![BioType::Read, BioType::Write].contains(&type_) {
+        if ![BioType::Read, BioType::Write, BioType::Discard].contains(&type_) {
             debug_assert!(
                 self.addr.is_none(),
-                "addr is only meaningful for a read or write",
+                "addr is only meaningful for a read, write, or discard",
             );
+        }
+        if ![BioType::Read, BioType::Write].contains(&type_) {
             debug_assert!(
                 self.bufs.is_none(),
                 "bufs is only meaningful for a read or write",
             );
         }
+        if type_ != BioType::Discard {
+            debug_assert!(
+                self.num_blocks.is_none(),
+                "num_blocks is only meaningful for a discard",
+            );
+        }
 
         let addr = self.addr.unwrap_or(0);
         debug_assert!(
@@ -103,7 +158,7 @@ impl BioReqBuilder {
             .map(|buf| buf.len())
             .fold(0_usize, |sum, len| sum.saturating_add(len));
         debug_assert!(num_bytes <= isize::MAX as usize, "# of bytes is too large");
-        let num_blocks = num_bytes / BLOCK_SIZE;
+        let num_blocks = self.num_blocks.unwrap_or(num_bytes / BLOCK_SIZE);
         debug_assert!(num_blocks <= u32::MAX as usize, "# of blocks is too large");
 
         let ext = self.ext.take().unwrap_or_else(|| AnyMap::new());
@@ -116,6 +171,7 @@ impl BioReqBuilder {
         BioReq {
             id,
             type_,
+            priority: self.priority,
             addr,
             num_blocks: num_blocks as u32,
             bufs: Mutex::new(bufs),
@@ -131,6 +187,7 @@ impl BioReqBuilder {
 pub struct BioReq {
     id: ObjectId,
     type_: BioType,
+    priority: BioPrio,
     addr: BlockId,
     num_blocks: u32,
     bufs: Mutex<Vec<BlockBuf>>,
@@ -182,9 +239,14 @@ impl BioReq {
         self.type_
     }
 
+    /// Returns the priority of the request.
+    pub fn priority(&self) -> BioPrio {
+        self.priority
+    }
+
     /// Returns the starting address of requested blocks.
     ///
-    /// The return value is meaningless if the request is not a read or write.
+    /// The return value is meaningless if the request is a flush.
     pub fn addr(&self) -> BlockId {
         self.addr
     }
@@ -222,7 +284,7 @@ impl BioReq {
         self.bufs.lock().len()
     }
 
-    /// Returns the number of blocks to read or write by this request.
+    /// Returns the number of blocks to read, write, or discard by this request.
     ///
     /// If the request is a flush, then the returned value is meaningless.
     pub fn num_blocks(&self) -> usize {
@@ -309,10 +371,14 @@ impl fmt::Debug for BioReq {
         let mut ds = f.debug_struct("BioReq");
         ds.field("id", &self.id());
         ds.field("type", &self.type_());
+        ds.field("priority", &self.priority());
         if self.type_() == BioType::Read || self.type_() == BioType::Write {
             ds.field("addr", &self.addr());
             ds.field("num_bufs", &self.num_bufs());
             ds.field("num_blocks", &self.num_blocks());
+        } else if self.type_() == BioType::Discard {
+            ds.field("addr", &self.addr());
+            ds.field("num_blocks", &self.num_blocks());
         }
         ds.field("resp", &self.response());
         ds.field("ext", &*self.ext());
@@ -390,3 +456,30 @@ impl Future for BioComplete {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem_disk::MemDisk;
+
+    #[test]
+    fn priority() {
+        async_rt::task::block_on(async move {
+            let disk = MemDisk::new(16).unwrap();
+
+            let req = BioReqBuilder::new(BioType::Flush).build();
+            assert!(req.priority() == BioPrio::Normal);
+
+            for priority in [BioPrio::High, BioPrio::Normal, BioPrio::Low] {
+                let req = BioReqBuilder::new(BioType::Flush)
+                    .priority(priority)
+                    .build();
+                let submission = disk.submit(Arc::new(req));
+                assert!(submission.req().priority() == priority);
+                let req = submission.complete().await;
+                assert!(req.response() == Some(Ok(())));
+                assert!(req.priority() == priority);
+            }
+        });
+    }
+}
@@ -0,0 +1,715 @@
+//! A disk that caches blocks in memory.
+
+use alloc::collections::BTreeMap;
+use core::fmt::{self, Debug};
+
+use crate::prelude::*;
+use crate::util::flush::{submit_flush, FlushWrite};
+use crate::util::{drop_boxed_bufs, new_merged_req_builder};
+
+/// A write-back cache disk.
+///
+/// A decorator type that keeps the recently-used blocks of any other disk in
+/// memory, up to a given number of blocks. A read that hits the cache is
+/// completed without touching the inner disk. A write only updates the cache;
+/// the dirty blocks are written back to the inner disk when they are evicted
+/// from the cache or when the disk is flushed. Upon a flush, adjacent dirty
+/// blocks are coalesced into larger writes.
+///
+/// A dirty block that is evicted stays in memory, beyond the capacity, until it
+/// is written back, and so does a block that is evicted while a flush is still
+/// writing it. Otherwise a read that misses the cache could fetch the stale
+/// data from the inner disk. These extra blocks are thus bounded by the writes
+/// in flight to the inner disk, plus the blocks that failed to be written back,
+/// which are kept until the next flush succeeds.
+///
+/// The dirty blocks that are not yet written back are lost if the disk is
+/// dropped without a flush. The inner disk is assumed to apply the writes to
+/// the same block in the order of submission.
+pub struct CachedDisk<D: BlockDevice> {
+    inner: Arc<D>,
+    cache: Arc<Mutex<Cache>>,
+}
+
+impl<D: BlockDevice> CachedDisk<D> {
+    /// Create a cache disk atop the given disk, which caches up to the given
+    /// number of blocks.
+    pub fn new(inner: D, capacity: usize) -> Result<Self> {
+        if capacity == 0 {
+            return Err(errno!(EINVAL, "the cache must be able to hold a block"));
+        }
+        Ok(Self {
+            inner: Arc::new(inner),
+            cache: Arc::new(Mutex::new(Cache::new(capacity))),
+        })
+    }
+
+    /// Returns the inner disk.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Returns the number of dirty blocks, which are not yet written back.
+    pub fn num_dirty_blocks(&self) -> usize {
+        self.cache.lock().num_dirty_blocks()
+    }
+
+    fn check_range(&self, req: &Arc<BioReq>) -> Result<()> {
+        if req.addr() + req.num_blocks() > self.inner.total_blocks() {
+            return Err(errno!(EINVAL, "invalid block range"));
+        }
+        Ok(())
+    }
+
+    fn do_read(&self, origin_req: Arc<BioReq>) {
+        fn on_complete<D: BlockDevice>(new_req: &BioReq, resp: &BioResp) {
+            let ReadCtx {
+                origin_req,
+                inner,
+                cache,
+                cached,
+                num_writes,
+            } = new_req.ext().remove::<ReadCtx<D>>().unwrap();
+
+            if resp.is_ok() {
+                let mut write_backs = Vec::new();
+                new_req.access_bufs_with(|bufs| {
+                    let read_buf = bufs[0].as_slice();
+                    let mut cache = cache.lock();
+                    // The blocks read from the inner disk may be stale if any
+                    // write has been done since the read was submitted
+                    let can_fill = cache.num_writes == num_writes;
+
+                    origin_req.access_mut_bufs_with(|bufs| {
+                        let mut idx = 0;
+                        for buf in bufs {
+                            for block in buf.as_slice_mut().chunks_mut(BLOCK_SIZE) {
+                                let addr = origin_req.addr() + idx;
+                                let read_block =
+                                    &read_buf[idx * BLOCK_SIZE..(idx + 1) * BLOCK_SIZE];
+                                // Prefer the data in memory, which are newer
+                                if let Some(data) = cache.get(addr) {
+                                    block.copy_from_slice(data);
+                                } else if let Some(data) = cached[idx].as_ref() {
+                                    block.copy_from_slice(data);
+                                } else {
+                                    block.copy_from_slice(read_block);
+                                    if can_fill {
+                                        write_backs.extend(cache.put(addr, read_block, false));
+                                    }
+                                }
+                                idx += 1;
+                            }
+                        }
+                    });
+                });
+                write_back(&inner, &cache, write_backs);
+            }
+
+            // Notify the origin request I/O completed
+            unsafe {
+                origin_req.complete(*resp);
+            }
+        }
+
+        if let Err(e) = self.check_range(&origin_req) {
+            unsafe {
+                origin_req.complete(Err(e.errno()));
+            }
+            return;
+        }
+
+        let begin_block = origin_req.addr();
+        let end_block = begin_block + origin_req.num_blocks();
+        let mut cache = self.cache.lock();
+        if (begin_block..end_block).all(|addr| cache.contains(addr)) {
+            // All blocks are in memory. So the inner disk is not touched.
+            origin_req.access_mut_bufs_with(|bufs| {
+                let mut addr = begin_block;
+                for buf in bufs {
+                    for block in buf.as_slice_mut().chunks_mut(BLOCK_SIZE) {
+                        block.copy_from_slice(cache.get(addr).unwrap());
+                        addr += 1;
+                    }
+                }
+            });
+            drop(cache);
+
+            unsafe {
+                origin_req.complete(Ok(()));
+            }
+            return;
+        }
+
+        // Take a copy of the blocks in memory, which may be written back and
+        // evicted before the read from the inner disk completes
+        let cached = (begin_block..end_block)
+            .map(|addr| cache.get(addr).map(Box::from))
+            .collect();
+        let num_writes = cache.num_writes;
+        drop(cache);
+
+        let new_req = new_merged_req_builder(&origin_req)
+            .on_complete(on_complete::<D>)
+            .ext(ReadCtx {
+                origin_req,
+                inner: self.inner.clone(),
+                cache: self.cache.clone(),
+                cached,
+                num_writes,
+            })
+            .build();
+        let _ = self.inner.submit(Arc::new(new_req));
+    }
+
+    fn do_write(&self, req: &Arc<BioReq>) -> Result<()> {
+        self.check_range(req)?;
+
+        let mut write_backs = Vec::new();
+        let mut cache = self.cache.lock();
+        cache.num_writes += 1;
+        req.access_bufs_with(|bufs| {
+            let mut addr = req.addr();
+            for buf in bufs {
+                for block in buf.as_slice().chunks(BLOCK_SIZE) {
+                    write_backs.extend(cache.put(addr, block, true));
+                    addr += 1;
+                }
+            }
+        });
+        drop(cache);
+
+        write_back(&self.inner, &self.cache, write_backs);
+        Ok(())
+    }
+
+    fn do_flush(&self, origin_req: Arc<BioReq>) {
+        fn on_write_complete(run: FlushedRun, data: &[u8], resp: &BioResp) {
+            let FlushedRun { cache, blocks } = run;
+            let mut cache = cache.lock();
+            if resp.is_ok() {
+                for (addr, seq, _) in blocks {
+                    cache.remove_flushed(addr, seq);
+                }
+                return;
+            }
+
+            // Keep the blocks dirty so that they can be written back later.
+            // The evicted blocks are still there.
+            for (idx, (addr, seq, evicted)) in blocks.iter().enumerate() {
+                if !evicted {
+                    let block = &data[idx * BLOCK_SIZE..(idx + 1) * BLOCK_SIZE];
+                    cache.redirty(*addr, *seq, block);
+                }
+            }
+        }
+
+        // Coalesce the adjacent dirty blocks
+        let dirty_blocks = self.cache.lock().take_dirty_blocks();
+        let mut runs: Vec<Vec<DirtyBlock>> = Vec::new();
+        for dirty_block in dirty_blocks {
+            match runs.last_mut() {
+                Some(run) if run.last().unwrap().addr + 1 == dirty_block.addr => {
+                    run.push(dirty_block)
+                }
+                _ => runs.push(vec![dirty_block]),
+            }
+        }
+
+        let writes = runs
+            .into_iter()
+            .map(|run| {
+                let addr = run[0].addr;
+                let mut merged_data = Vec::with_capacity(run.len() * BLOCK_SIZE);
+                let mut blocks = Vec::with_capacity(run.len());
+                for dirty_block in run {
+                    merged_data.extend_from_slice(&dirty_block.data);
+                    blocks.push((dirty_block.addr, dirty_block.seq, dirty_block.evicted));
+                }
+                FlushWrite {
+                    addr,
+                    data: merged_data.into_boxed_slice(),
+                    tag: FlushedRun {
+                        cache: self.cache.clone(),
+                        blocks,
+                    },
+                }
+            })
+            .collect();
+        submit_flush(&self.inner, origin_req, writes, on_write_complete);
+    }
+
+    fn do_discard(&self, origin_req: Arc<BioReq>) {
+        fn on_complete(new_req: &BioReq, resp: &BioResp) {
+            let OriginReq(origin_req) = new_req.ext().remove::<OriginReq>().unwrap();
+            unsafe {
+                origin_req.complete(*resp);
+            }
+        }
+
+        if let Err(e) = self.check_range(&origin_req) {
+            unsafe {
+                origin_req.complete(Err(e.errno()));
+            }
+            return;
+        }
+
+        let begin_block = origin_req.addr();
+        let end_block = begin_block + origin_req.num_blocks();
+        let mut cache = self.cache.lock();
+        cache.num_writes += 1;
+        cache.remove_range(begin_block, end_block);
+        drop(cache);
+
+        let new_req = BioReqBuilder::new(BioType::Discard)
+            .addr(begin_block)
+            .num_blocks(origin_req.num_blocks())
+            .on_complete(on_complete)
+            .ext(OriginReq(origin_req))
+            .build();
+        let _ = self.inner.submit(Arc::new(new_req));
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for CachedDisk<D> {
+    fn total_blocks(&self) -> usize {
+        self.inner.total_blocks()
+    }
+
+    fn submit(&self, req: Arc<BioReq>) -> BioSubmission {
+        // Update the status of req to submittted
+        let submission = BioSubmission::new(req);
+
+        let req = submission.req().clone();
+        match req.type_() {
+            BioType::Read => self.do_read(req),
+            BioType::Write => {
+                let resp = self.do_write(&req).map_err(|e| e.errno());
+                unsafe {
+                    req.complete(resp);
+                }
+            }
+            BioType::Flush => self.do_flush(req),
+            BioType::Discard => self.do_discard(req),
+        }
+        submission
+    }
+
+    fn resize(&self, new_total_blocks: usize) -> Result<()> {
+        self.inner.resize(new_total_blocks)?;
+
+        // Forget the blocks beyond the new end
+        let mut cache = self.cache.lock();
+        cache.num_writes += 1;
+        cache.remove_range(new_total_blocks, usize::MAX);
+        Ok(())
+    }
+}
+
+struct Cache {
+    capacity: usize,
+    // The cached blocks.
+    blocks: BTreeMap<BlockId, CachedBlock>,
+    // The addresses of the cached blocks, indexed by the time of last access.
+    lru: BTreeMap<u64, BlockId>,
+    // The blocks that are evicted but not yet written back, given as pairs of
+    // the sequence number of the eviction (or the flush) and the data.
+    evicted: BTreeMap<BlockId, (u64, Box<[u8]>)>,
+    // The logical clock for the access times and the sequence numbers.
+    clock: u64,
+    // The number of writes (and discards) that have been done.
+    num_writes: u64,
+}
+
+struct CachedBlock {
+    data: Box<[u8]>,
+    dirty: bool,
+    last_access: u64,
+    // The sequence number of the flush that is writing the block, if any.
+    flush_seq: Option<u64>,
+}
+
+// A dirty block that is evicted and to be written back.
+struct WriteBack {
+    addr: BlockId,
+    seq: u64,
+    data: Box<[u8]>,
+}
+
+// A dirty block to be written by a flush, which is either in the cache or
+// evicted.
+struct DirtyBlock {
+    addr: BlockId,
+    seq: u64,
+    evicted: bool,
+    data: Box<[u8]>,
+}
+
+impl Cache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            blocks: BTreeMap::new(),
+            lru: BTreeMap::new(),
+            evicted: BTreeMap::new(),
+            clock: 0,
+            num_writes: 0,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn contains(&self, addr: BlockId) -> bool {
+        self.blocks.contains_key(&addr) || self.evicted.contains_key(&addr)
+    }
+
+    // Returns the latest data of a block, if the block is in memory.
+    fn get(&mut self, addr: BlockId) -> Option<&[u8]> {
+        let now = self.tick();
+        if let Some(block) = self.blocks.get_mut(&addr) {
+            self.lru.remove(&block.last_access);
+            self.lru.insert(now, addr);
+            block.last_access = now;
+            return Some(&block.data);
+        }
+        self.evicted.get(&addr).map(|(_, data)| &**data)
+    }
+
+    // Put a block into the cache, returning the dirty blocks that are evicted.
+    //
+    // A clean block can only be put if the block is not in memory.
+    fn put(&mut self, addr: BlockId, data: &[u8], dirty: bool) -> Vec<WriteBack> {
+        debug_assert!(dirty || !self.contains(addr));
+        let now = self.tick();
+        self.evicted.remove(&addr);
+        match self.blocks.get_mut(&addr) {
+            Some(block) => {
+                block.data.copy_from_slice(data);
+                block.dirty |= dirty;
+                self.lru.remove(&block.last_access);
+                block.last_access = now;
+            }
+            None => {
+                let block = CachedBlock {
+                    data: Box::from(data),
+                    dirty,
+                    last_access: now,
+                    flush_seq: None,
+                };
+                self.blocks.insert(addr, block);
+            }
+        }
+        self.lru.insert(now, addr);
+        self.evict()
+    }
+
+    fn evict(&mut self) -> Vec<WriteBack> {
+        let mut write_backs = Vec::new();
+        while self.blocks.len() > self.capacity {
+            let (last_access, addr) = self
+                .lru
+                .iter()
+                .next()
+                .map(|(last_access, addr)| (*last_access, *addr))
+                .unwrap();
+            self.lru.remove(&last_access);
+            let block = self.blocks.remove(&addr).unwrap();
+            if block.dirty {
+                let seq = self.tick();
+                write_backs.push(WriteBack {
+                    addr,
+                    seq,
+                    data: block.data.clone(),
+                });
+                self.evicted.insert(addr, (seq, block.data));
+            } else if let Some(seq) = block.flush_seq {
+                // The block is still being written by a flush, which forgets
+                // it upon completion
+                self.evicted.insert(addr, (seq, block.data));
+            }
+        }
+        write_backs
+    }
+
+    // Forget an evicted block once it is written back, unless the block is
+    // evicted again or written since.
+    fn remove_evicted(&mut self, addr: BlockId, seq: u64) {
+        if self.evicted.get(&addr).map(|(s, _)| *s) == Some(seq) {
+            self.evicted.remove(&addr);
+        }
+    }
+
+    // Forget a block that is written by a flush, unless the block is evicted
+    // again or written since.
+    fn remove_flushed(&mut self, addr: BlockId, seq: u64) {
+        if let Some(block) = self.blocks.get_mut(&addr) {
+            if block.flush_seq == Some(seq) {
+                block.flush_seq = None;
+            }
+        }
+        self.remove_evicted(addr, seq);
+    }
+
+    // Make a block dirty again after it fails to be written by a flush.
+    fn redirty(&mut self, addr: BlockId, seq: u64, data: &[u8]) {
+        self.num_writes += 1;
+        if let Some(block) = self.blocks.get_mut(&addr) {
+            if block.flush_seq == Some(seq) {
+                block.flush_seq = None;
+            }
+            if !block.dirty {
+                block.data.copy_from_slice(data);
+                block.dirty = true;
+            }
+        } else if !self.evicted.contains_key(&addr) {
+            let seq = self.tick();
+            self.evicted.insert(addr, (seq, Box::from(data)));
+        }
+    }
+
+    // Take a copy of all dirty blocks in the order of addresses. The blocks in
+    // the cache become clean, and are marked with the sequence number of the
+    // flush until the flush writes them.
+    fn take_dirty_blocks(&mut self) -> Vec<DirtyBlock> {
+        let mut dirty_blocks = Vec::new();
+        for (addr, block) in self.blocks.iter_mut().filter(|(_, block)| block.dirty) {
+            self.clock += 1;
+            block.dirty = false;
+            block.flush_seq = Some(self.clock);
+            dirty_blocks.push(DirtyBlock {
+                addr: *addr,
+                seq: self.clock,
+                evicted: false,
+                data: block.data.clone(),
+            });
+        }
+        dirty_blocks.extend(self.evicted.iter().map(|(addr, (seq, data))| DirtyBlock {
+            addr: *addr,
+            seq: *seq,
+            evicted: true,
+            data: data.clone(),
+        }));
+        dirty_blocks.sort_by_key(|dirty_block| dirty_block.addr);
+        dirty_blocks
+    }
+
+    fn num_dirty_blocks(&self) -> usize {
+        self.blocks.values().filter(|block| block.dirty).count() + self.evicted.len()
+    }
+
+    // Forget the blocks in a range, no matter whether they are dirty.
+    fn remove_range(&mut self, begin_block: BlockId, end_block: BlockId) {
+        let addrs: Vec<BlockId> = self
+            .blocks
+            .range(begin_block..end_block)
+            .map(|(addr, _)| *addr)
+            .collect();
+        for addr in addrs {
+            let block = self.blocks.remove(&addr).unwrap();
+            self.lru.remove(&block.last_access);
+        }
+        self.evicted
+            .retain(|addr, _| *addr < begin_block || *addr >= end_block);
+    }
+}
+
+// Submit the writes of the evicted dirty blocks to the inner disk.
+fn write_back<D: BlockDevice>(
+    inner: &Arc<D>,
+    cache: &Arc<Mutex<Cache>>,
+    write_backs: Vec<WriteBack>,
+) {
+    fn on_complete(new_req: &BioReq, resp: &BioResp) {
+        let WriteBackCtx { cache, addr, seq } = new_req.ext().remove::<WriteBackCtx>().unwrap();
+        // A block that fails to be written back stays in memory and will be
+        // written again upon the next flush
+        if resp.is_ok() {
+            cache.lock().remove_evicted(addr, seq);
+        }
+    }
+
+    for WriteBack { addr, seq, data } in write_backs {
+        let new_req = BioReqBuilder::new(BioType::Write)
+            .addr(addr)
+            .bufs(vec![BlockBuf::from_boxed(data)])
+            .on_complete(on_complete)
+            .on_drop(drop_boxed_bufs)
+            .ext(WriteBackCtx {
+                cache: cache.clone(),
+                addr,
+                seq,
+            })
+            .build();
+        let _ = inner.submit(Arc::new(new_req));
+    }
+}
+
+// The context of a read that is submitted to the inner disk.
+struct ReadCtx<D: BlockDevice> {
+    origin_req: Arc<BioReq>,
+    inner: Arc<D>,
+    cache: Arc<Mutex<Cache>>,
+    // The copies of the blocks that are in memory upon the submission.
+    cached: Vec<Option<Box<[u8]>>>,
+    // The number of writes upon the submission.
+    num_writes: u64,
+}
+
+impl<D: BlockDevice> Debug for ReadCtx<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadCtx")
+            .field("origin_req", &self.origin_req)
+            .field("num_writes", &self.num_writes)
+            .finish()
+    }
+}
+
+// The context of a write back of an evicted block.
+struct WriteBackCtx {
+    cache: Arc<Mutex<Cache>>,
+    addr: BlockId,
+    seq: u64,
+}
+
+impl Debug for WriteBackCtx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteBackCtx")
+            .field("addr", &self.addr)
+            .field("seq", &self.seq)
+            .finish()
+    }
+}
+
+// A run of adjacent blocks written by a flush, given as tuples of the
+// addresses, the sequence numbers and whether the blocks are evicted.
+struct FlushedRun {
+    cache: Arc<Mutex<Cache>>,
+    blocks: Vec<(BlockId, u64, bool)>,
+}
+
+impl Debug for FlushedRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlushedRun")
+            .field("blocks", &self.blocks)
+            .finish()
+    }
+}
+
+/// A new-type wrapper to be used in AnyMap.
+#[derive(Debug)]
+struct OriginReq(Arc<BioReq>);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem_disk::MemDisk;
+
+    fn test_setup() -> CachedDisk<MemDisk> {
+        let total_blocks = 16;
+        let mem_disk = MemDisk::new(total_blocks).unwrap();
+        CachedDisk::new(mem_disk, 4).unwrap()
+    }
+
+    fn test_teardown(_disk: CachedDisk<MemDisk>) {}
+
+    crate::gen_unit_tests!(test_setup, test_teardown);
+
+    fn new_cached_disk(capacity: usize) -> CachedDisk<MemDisk> {
+        let mem_disk = MemDisk::new(16).unwrap();
+        CachedDisk::new(mem_disk, capacity).unwrap()
+    }
+
+    async fn read_block<D: BlockDevice>(disk: &D, addr: BlockId) -> Result<Vec<u8>> {
+        let mut buf = vec![0; BLOCK_SIZE];
+        disk.read(addr * BLOCK_SIZE, &mut buf).await?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn read_after_write_hits_cache() {
+        async_rt::task::block_on(async move {
+            let disk = new_cached_disk(4);
+
+            disk.write(BLOCK_SIZE, &[b'a'; BLOCK_SIZE]).await.unwrap();
+            assert!(disk.num_dirty_blocks() == 1);
+
+            // The block is served from the cache as the inner disk has not
+            // seen the write yet
+            assert!(read_block(disk.inner(), 1).await.unwrap() == [0; BLOCK_SIZE]);
+            assert!(read_block(&disk, 1).await.unwrap() == [b'a'; BLOCK_SIZE]);
+        });
+    }
+
+    #[test]
+    fn eviction_writes_back() {
+        async_rt::task::block_on(async move {
+            let disk = new_cached_disk(2);
+
+            disk.write(0, &[b'a'; BLOCK_SIZE]).await.unwrap();
+            disk.write(BLOCK_SIZE, &[b'b'; BLOCK_SIZE]).await.unwrap();
+            // Touch block 0 so that block 1 is the least recently used
+            assert!(read_block(&disk, 0).await.unwrap() == [b'a'; BLOCK_SIZE]);
+            disk.write(2 * BLOCK_SIZE, &[b'c'; BLOCK_SIZE])
+                .await
+                .unwrap();
+
+            // Only the evicted block is written back
+            assert!(disk.num_dirty_blocks() == 2);
+            assert!(read_block(disk.inner(), 0).await.unwrap() == [0; BLOCK_SIZE]);
+            assert!(read_block(disk.inner(), 1).await.unwrap() == [b'b'; BLOCK_SIZE]);
+            assert!(read_block(disk.inner(), 2).await.unwrap() == [0; BLOCK_SIZE]);
+
+            // All blocks can still be read back
+            assert!(read_block(&disk, 0).await.unwrap() == [b'a'; BLOCK_SIZE]);
+            assert!(read_block(&disk, 1).await.unwrap() == [b'b'; BLOCK_SIZE]);
+            assert!(read_block(&disk, 2).await.unwrap() == [b'c'; BLOCK_SIZE]);
+        });
+    }
+
+    #[test]
+    fn flush_persists_dirty_blocks() {
+        async_rt::task::block_on(async move {
+            let disk = new_cached_disk(8);
+            let num_blocks = disk.total_blocks();
+
+            // Dirty blocks in two runs, which are coalesced into two writes
+            for addr in (0..3).chain(5..8) {
+                disk.write(addr * BLOCK_SIZE, &[b'a' + addr as u8; BLOCK_SIZE])
+                    .await
+                    .unwrap();
+            }
+            assert!(disk.num_dirty_blocks() == 6);
+            assert!(read_block(disk.inner(), 0).await.unwrap() == [0; BLOCK_SIZE]);
+
+            disk.flush().await.unwrap();
+            assert!(disk.num_dirty_blocks() == 0);
+            for addr in 0..num_blocks {
+                let expected = if (0..3).contains(&addr) || (5..8).contains(&addr) {
+                    [b'a' + addr as u8; BLOCK_SIZE]
+                } else {
+                    [0; BLOCK_SIZE]
+                };
+                assert!(read_block(disk.inner(), addr).await.unwrap() == expected);
+            }
+        });
+    }
+
+    #[test]
+    fn flushing_block_stays_after_eviction() {
+        let mut cache = Cache::new(1);
+        let _ = cache.put(0, &[b'a'; BLOCK_SIZE], true);
+        let dirty_blocks = cache.take_dirty_blocks();
+        assert!(dirty_blocks.len() == 1);
+
+        // Evict the block while the flush is still writing it
+        assert!(cache.put(1, &[b'b'; BLOCK_SIZE], false).is_empty());
+        assert!(cache.get(0) == Some(&[b'a'; BLOCK_SIZE][..]));
+
+        // The block is forgotten once the flush writes it
+        cache.remove_flushed(0, dirty_blocks[0].seq);
+        assert!(!cache.contains(0));
+    }
+}
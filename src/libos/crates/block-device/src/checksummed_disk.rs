@@ -0,0 +1,494 @@
+//! A disk that verifies the integrity of blocks with checksums.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::convert::TryInto;
+use core::fmt::{self, Debug};
+
+use crate::prelude::*;
+use crate::util::flush::{submit_flush, FlushWrite};
+use crate::util::new_merged_req_builder;
+use crate::BlockDeviceExt;
+
+/// A checksummed disk.
+///
+/// A decorator type that keeps a CRC32C checksum for each block of any other
+/// disk. The checksum of a block is updated when the block is written and
+/// verified when the block is read. A read of a block that does not match its
+/// checksum fails with `EIO`.
+///
+/// The checksums are stored in the last blocks of the inner disk, which are
+/// not usable by the users. So the checksummed disk is slightly smaller than
+/// the inner disk. The checksums are kept in memory and written to the inner
+/// disk upon flushes. So the blocks that are written but not flushed may fail
+/// to be read after a crash.
+///
+/// A new, zeroed disk is a valid checksummed disk, whose blocks are all zeros.
+pub struct ChecksummedDisk<D: BlockDevice> {
+    inner: Arc<D>,
+    total_blocks: usize,
+    checksums: Arc<Mutex<Checksums>>,
+}
+
+// The number of checksums in a block.
+const CHECKSUMS_PER_BLOCK: usize = BLOCK_SIZE / core::mem::size_of::<u32>();
+
+impl<D: BlockDevice> ChecksummedDisk<D> {
+    /// Create a checksummed disk atop a new, zeroed disk.
+    pub fn new(inner: D) -> Result<Self> {
+        let (total_blocks, _) = Self::layout(inner.total_blocks())?;
+        let checksums = Checksums::new(vec![0; total_blocks]);
+        Ok(Self {
+            inner: Arc::new(inner),
+            total_blocks,
+            checksums: Arc::new(Mutex::new(checksums)),
+        })
+    }
+
+    /// Open a checksummed disk atop a disk that has been used as one, loading
+    /// the checksums from the disk.
+    pub async fn open(inner: D) -> Result<Self> {
+        let (total_blocks, num_checksum_blocks) = Self::layout(inner.total_blocks())?;
+        let mut buf = vec![0; num_checksum_blocks * BLOCK_SIZE];
+        inner.read(total_blocks * BLOCK_SIZE, &mut buf).await?;
+        let table = buf
+            .chunks(core::mem::size_of::<u32>())
+            .take(total_blocks)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        Ok(Self {
+            inner: Arc::new(inner),
+            total_blocks,
+            checksums: Arc::new(Mutex::new(Checksums::new(table))),
+        })
+    }
+
+    /// Returns the inner disk.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    // Returns the number of usable blocks and the number of blocks to store
+    // the checksums, given the total number of blocks of the inner disk.
+    fn layout(inner_blocks: usize) -> Result<(usize, usize)> {
+        if inner_blocks < 2 {
+            return Err(errno!(EINVAL, "the disk is too small to store checksums"));
+        }
+        let num_checksum_blocks = (inner_blocks + CHECKSUMS_PER_BLOCK) / (CHECKSUMS_PER_BLOCK + 1);
+        Ok((inner_blocks - num_checksum_blocks, num_checksum_blocks))
+    }
+
+    fn check_range(&self, req: &Arc<BioReq>) -> Result<()> {
+        if req.addr() + req.num_blocks() > self.total_blocks {
+            return Err(errno!(EINVAL, "invalid block range"));
+        }
+        Ok(())
+    }
+
+    fn do_read(&self, origin_req: Arc<BioReq>) {
+        fn on_complete(new_req: &BioReq, resp: &BioResp) {
+            let ReadCtx {
+                origin_req,
+                checksums,
+            } = new_req.ext().remove::<ReadCtx>().unwrap();
+
+            let resp = (*resp).and_then(|()| {
+                new_req.access_bufs_with(|bufs| {
+                    let merged_buf = bufs[0].as_slice();
+
+                    // Verify the blocks before handing them out
+                    let checksums = checksums.lock();
+                    let is_intact = merged_buf
+                        .chunks(BLOCK_SIZE)
+                        .enumerate()
+                        .all(|(idx, block)| checksums.verify(origin_req.addr() + idx, block));
+                    drop(checksums);
+                    if !is_intact {
+                        return Err(EIO);
+                    }
+
+                    origin_req.access_mut_bufs_with(|bufs| {
+                        let mut copied_len = 0;
+                        for buf in bufs {
+                            let buf_len = buf.len();
+                            buf.as_slice_mut()
+                                .copy_from_slice(&merged_buf[copied_len..copied_len + buf_len]);
+                            copied_len += buf_len;
+                        }
+                    });
+                    Ok(())
+                })
+            });
+
+            // Notify the origin request I/O completed
+            unsafe {
+                origin_req.complete(resp);
+            }
+        }
+
+        if let Err(e) = self.check_range(&origin_req) {
+            unsafe {
+                origin_req.complete(Err(e.errno()));
+            }
+            return;
+        }
+
+        let new_req = new_merged_req_builder(&origin_req)
+            .on_complete(on_complete)
+            .ext(ReadCtx {
+                origin_req,
+                checksums: self.checksums.clone(),
+            })
+            .build();
+        let _ = self.inner.submit(Arc::new(new_req));
+    }
+
+    fn do_write(&self, origin_req: Arc<BioReq>) {
+        fn on_complete(new_req: &BioReq, resp: &BioResp) {
+            let WriteCtx {
+                origin_req,
+                checksums,
+                old_checksums,
+            } = new_req.ext().remove::<WriteCtx>().unwrap();
+
+            // The old checksums are no longer valid, no matter whether the write
+            // succeeds. A block that fails to be written is considered corrupted.
+            let mut checksums = checksums.lock();
+            for (addr, old_checksum) in old_checksums {
+                checksums.remove_pending(addr, old_checksum);
+            }
+            drop(checksums);
+
+            // Notify the origin request I/O completed
+            unsafe {
+                origin_req.complete(*resp);
+            }
+        }
+
+        if let Err(e) = self.check_range(&origin_req) {
+            unsafe {
+                origin_req.complete(Err(e.errno()));
+            }
+            return;
+        }
+
+        // Update the checksums before the write is submitted. Until the write
+        // is completed, the blocks may be read with either the old or the new
+        // checksums.
+        let old_checksums = origin_req.access_bufs_with(|bufs| {
+            let mut checksums = self.checksums.lock();
+            let mut old_checksums = Vec::with_capacity(origin_req.num_blocks());
+            let mut addr = origin_req.addr();
+            for buf in bufs {
+                for block in buf.as_slice().chunks(BLOCK_SIZE) {
+                    let old_checksum = checksums.update(addr, checksum(block));
+                    old_checksums.push((addr, old_checksum));
+                    addr += 1;
+                }
+            }
+            old_checksums
+        });
+
+        let new_req = new_merged_req_builder(&origin_req)
+            .on_complete(on_complete)
+            .ext(WriteCtx {
+                origin_req: origin_req.clone(),
+                checksums: self.checksums.clone(),
+                old_checksums,
+            })
+            .build();
+        new_req.access_mut_bufs_with(|bufs| {
+            let merged_buf = bufs[0].as_slice_mut();
+            origin_req.access_bufs_with(|bufs| {
+                let mut copied_len = 0;
+                for buf in bufs {
+                    let buf_len = buf.len();
+                    merged_buf[copied_len..copied_len + buf_len].copy_from_slice(buf.as_slice());
+                    copied_len += buf_len;
+                }
+            });
+        });
+        let _ = self.inner.submit(Arc::new(new_req));
+    }
+
+    fn do_flush(&self, origin_req: Arc<BioReq>) {
+        fn on_write_complete(block: ChecksumBlock, _data: &[u8], resp: &BioResp) {
+            if resp.is_err() {
+                // The checksums are to be written again upon the next flush
+                block.checksums.lock().dirty.insert(block.idx);
+            }
+        }
+
+        // Take a copy of the checksum blocks that are modified
+        let writes = {
+            let mut checksums = self.checksums.lock();
+            let dirty = core::mem::take(&mut checksums.dirty);
+            dirty
+                .into_iter()
+                .map(|idx| FlushWrite {
+                    addr: self.total_blocks + idx,
+                    data: checksums.to_block(idx),
+                    tag: ChecksumBlock {
+                        checksums: self.checksums.clone(),
+                        idx,
+                    },
+                })
+                .collect()
+        };
+        submit_flush(&self.inner, origin_req, writes, on_write_complete);
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for ChecksummedDisk<D> {
+    fn total_blocks(&self) -> usize {
+        self.total_blocks
+    }
+
+    fn submit(&self, req: Arc<BioReq>) -> BioSubmission {
+        // Update the status of req to submittted
+        let submission = BioSubmission::new(req);
+
+        let req = submission.req().clone();
+        match req.type_() {
+            BioType::Read => self.do_read(req),
+            BioType::Write => self.do_write(req),
+            BioType::Flush => self.do_flush(req),
+            // Discarding the blocks would invalidate their checksums, so the
+            // blocks are kept as they are
+            BioType::Discard => unsafe {
+                req.complete(Ok(()));
+            },
+        }
+        submission
+    }
+}
+
+struct Checksums {
+    // The checksums of all usable blocks.
+    table: Vec<u32>,
+    // The old checksums of the blocks that are being written, which are valid
+    // until the writes are completed.
+    pending: BTreeMap<BlockId, Vec<u32>>,
+    // The indexes of the checksum blocks that are modified since the last flush.
+    dirty: BTreeSet<usize>,
+}
+
+impl Checksums {
+    fn new(table: Vec<u32>) -> Self {
+        Self {
+            table,
+            pending: BTreeMap::new(),
+            dirty: BTreeSet::new(),
+        }
+    }
+
+    fn verify(&self, addr: BlockId, block: &[u8]) -> bool {
+        let checksum = checksum(block);
+        self.table[addr] == checksum
+            || self
+                .pending
+                .get(&addr)
+                .map_or(false, |old_checksums| old_checksums.contains(&checksum))
+    }
+
+    // Update the checksum of a block, returning the old one, which stays
+    // valid until `remove_pending` is called.
+    fn update(&mut self, addr: BlockId, new_checksum: u32) -> u32 {
+        let old_checksum = core::mem::replace(&mut self.table[addr], new_checksum);
+        self.pending.entry(addr).or_default().push(old_checksum);
+        self.dirty.insert(addr / CHECKSUMS_PER_BLOCK);
+        old_checksum
+    }
+
+    fn remove_pending(&mut self, addr: BlockId, old_checksum: u32) {
+        let old_checksums = self.pending.get_mut(&addr).unwrap();
+        let pos = old_checksums
+            .iter()
+            .position(|checksum| *checksum == old_checksum)
+            .unwrap();
+        old_checksums.swap_remove(pos);
+        if old_checksums.is_empty() {
+            self.pending.remove(&addr);
+        }
+    }
+
+    // Returns the content of a checksum block.
+    fn to_block(&self, idx: usize) -> Box<[u8]> {
+        let mut block = vec![0_u8; BLOCK_SIZE];
+        let begin = idx * CHECKSUMS_PER_BLOCK;
+        let end = (begin + CHECKSUMS_PER_BLOCK).min(self.table.len());
+        for (bytes, checksum) in block.chunks_mut(4).zip(self.table[begin..end].iter()) {
+            bytes.copy_from_slice(&checksum.to_le_bytes());
+        }
+        block.into_boxed_slice()
+    }
+}
+
+// The context of a read that is submitted to the inner disk.
+struct ReadCtx {
+    origin_req: Arc<BioReq>,
+    checksums: Arc<Mutex<Checksums>>,
+}
+
+impl Debug for ReadCtx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadCtx")
+            .field("origin_req", &self.origin_req)
+            .finish()
+    }
+}
+
+// The context of a write that is submitted to the inner disk.
+struct WriteCtx {
+    origin_req: Arc<BioReq>,
+    checksums: Arc<Mutex<Checksums>>,
+    // The checksums before the write, given as pairs of block address and
+    // checksum.
+    old_checksums: Vec<(BlockId, u32)>,
+}
+
+impl Debug for WriteCtx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteCtx")
+            .field("origin_req", &self.origin_req)
+            .finish()
+    }
+}
+
+// A checksum block written by a flush.
+struct ChecksumBlock {
+    checksums: Arc<Mutex<Checksums>>,
+    idx: usize,
+}
+
+impl Debug for ChecksumBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChecksumBlock")
+            .field("idx", &self.idx)
+            .finish()
+    }
+}
+
+// The checksum of a block as it is stored. The CRC32C value is mixed with the
+// one of a zeroed block, so that a zeroed checksum is valid for a zeroed block.
+fn checksum(block: &[u8]) -> u32 {
+    const ZEROED_BLOCK_CRC: u32 = crc32c(&[0; BLOCK_SIZE]);
+    crc32c(block) ^ ZEROED_BLOCK_CRC
+}
+
+// CRC32C (Castagnoli), the same as used by iSCSI and ext4.
+const fn crc32c(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        // The reversed polynomial of CRC32C
+        const POLY: u32 = 0x82f6_3b78;
+        let mut table = [0_u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if (crc & 1) != 0 {
+                    (crc >> 1) ^ POLY
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    let mut crc = !0_u32;
+    let mut i = 0;
+    while i < data.len() {
+        crc = TABLE[((crc ^ data[i] as u32) & 0xff) as usize] ^ (crc >> 8);
+        i += 1;
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem_disk::MemDisk;
+
+    fn test_setup() -> ChecksummedDisk<MemDisk> {
+        let total_blocks = 16;
+        let mem_disk = MemDisk::new(total_blocks).unwrap();
+        ChecksummedDisk::new(mem_disk).unwrap()
+    }
+
+    fn test_teardown(_disk: ChecksummedDisk<MemDisk>) {}
+
+    crate::gen_unit_tests!(test_setup, test_teardown);
+
+    #[test]
+    fn crc32c_check_value() {
+        assert!(crc32c(b"123456789") == 0xe306_9283);
+    }
+
+    #[test]
+    fn layout() {
+        let layout = ChecksummedDisk::<MemDisk>::layout;
+        assert!(layout(1).is_err());
+        assert!(layout(2).unwrap() == (1, 1));
+        assert!(layout(CHECKSUMS_PER_BLOCK + 1).unwrap() == (CHECKSUMS_PER_BLOCK, 1));
+        assert!(layout(CHECKSUMS_PER_BLOCK + 2).unwrap() == (CHECKSUMS_PER_BLOCK, 2));
+
+        let disk = test_setup();
+        assert!(disk.total_blocks() == 15);
+    }
+
+    #[test]
+    fn corrupted_block() {
+        async_rt::task::block_on(async move {
+            let disk = test_setup();
+            let mut buf = [0_u8; BLOCK_SIZE];
+
+            disk.write(3 * BLOCK_SIZE, &[b'a'; BLOCK_SIZE])
+                .await
+                .unwrap();
+            disk.read(3 * BLOCK_SIZE, &mut buf).await.unwrap();
+            assert!(buf == [b'a'; BLOCK_SIZE]);
+
+            // Corrupt the block behind the back of the checksummed disk
+            disk.inner()
+                .write(3 * BLOCK_SIZE + 100, b"evil")
+                .await
+                .unwrap();
+            let err = disk.read(3 * BLOCK_SIZE, &mut buf).await.unwrap_err();
+            assert!(err.errno() == EIO);
+
+            // Other blocks are not affected
+            disk.read(2 * BLOCK_SIZE, &mut buf).await.unwrap();
+            assert!(buf == [0; BLOCK_SIZE]);
+        });
+    }
+
+    #[test]
+    fn reopen() {
+        async_rt::task::block_on(async move {
+            let disk = test_setup();
+            disk.write(3 * BLOCK_SIZE, &[b'a'; BLOCK_SIZE])
+                .await
+                .unwrap();
+            disk.flush().await.unwrap();
+
+            // Copy the content of the inner disk to another one
+            let mut image = vec![0_u8; 16 * BLOCK_SIZE];
+            disk.inner().read(0, &mut image).await.unwrap();
+            let mem_disk = MemDisk::new(16).unwrap();
+            mem_disk.write(0, &image).await.unwrap();
+
+            // The checksums are loaded from the disk
+            let disk = ChecksummedDisk::open(mem_disk).await.unwrap();
+            let mut buf = [0_u8; BLOCK_SIZE];
+            disk.read(3 * BLOCK_SIZE, &mut buf).await.unwrap();
+            assert!(buf == [b'a'; BLOCK_SIZE]);
+            disk.inner().write(3 * BLOCK_SIZE, b"evil").await.unwrap();
+            let err = disk.read(3 * BLOCK_SIZE, &mut buf).await.unwrap_err();
+            assert!(err.errno() == EIO);
+        });
+    }
+}
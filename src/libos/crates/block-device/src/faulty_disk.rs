@@ -0,0 +1,431 @@
+//! A disk that injects faults for crash-consistency testing.
+
+use alloc::collections::BTreeMap;
+
+use crate::prelude::*;
+use crate::util::flush::{submit_flush, FlushWrite};
+use crate::util::new_merged_req_builder;
+use crate::BlockDeviceExt;
+
+/// A fault-injection disk.
+///
+/// A decorator type that emulates a disk with a volatile write cache atop any
+/// other disk. Written blocks are kept in the volatile cache and only reach
+/// the inner disk, which plays the role of the durable storage medium, when
+/// the cache is flushed. A power loss can be simulated with `simulate_crash`,
+/// after which only the durably-flushed state is left.
+///
+/// In addition, a `FaultModel` can be given to make the disk misbehave in
+/// some well-defined ways, e.g., failing a request or ignoring a flush.
+pub struct FaultyDisk<D: BlockDevice> {
+    inner: Arc<D>,
+    model: FaultModel,
+    state: Mutex<State>,
+}
+
+/// The fault model of a `FaultyDisk`.
+///
+/// By default, no fault is injected other than the loss of unflushed writes
+/// upon simulated crashes.
+#[derive(Clone, Debug, Default)]
+pub struct FaultModel {
+    drop_writes_after_flushes: Option<usize>,
+    reorder_seed: Option<u64>,
+    failed_req: Option<u64>,
+    ignored_flush: Option<usize>,
+}
+
+impl FaultModel {
+    /// Create a fault model that injects no faults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Silently drop all writes submitted after the given number of flushes.
+    ///
+    /// The dropped writes are completed successfully, but they neither reach
+    /// the storage medium nor are visible to later reads.
+    pub fn drop_writes_after_flushes(mut self, num_flushes: usize) -> Self {
+        self.drop_writes_after_flushes = Some(num_flushes);
+        self
+    }
+
+    /// Let the unflushed writes reach the storage medium in an arbitrary order.
+    ///
+    /// Upon a simulated crash, instead of losing all unflushed writes, a
+    /// pseudo-random subset of the unflushed blocks (determined by the seed)
+    /// is persisted.
+    pub fn reorder_unflushed(mut self, seed: u64) -> Self {
+        self.reorder_seed = Some(seed);
+        self
+    }
+
+    /// Fail the request of the given sequence number with `EIO`.
+    ///
+    /// Requests of all types are numbered from zero in the order of submission.
+    pub fn fail_req(mut self, seq: u64) -> Self {
+        self.failed_req = Some(seq);
+        self
+    }
+
+    /// Ignore the flush of the given sequence number.
+    ///
+    /// Flushes are numbered from zero in the order of submission. An ignored
+    /// flush is completed successfully, but the cached writes stay volatile.
+    pub fn ignore_flush(mut self, seq: usize) -> Self {
+        self.ignored_flush = Some(seq);
+        self
+    }
+}
+
+struct State {
+    // The blocks that are written but not yet flushed.
+    unflushed: BTreeMap<BlockId, Box<[u8]>>,
+    num_reqs: u64,
+    num_flushes: usize,
+}
+
+impl<D: BlockDevice> FaultyDisk<D> {
+    /// Create a fault-injection disk atop the given disk.
+    pub fn new(inner: D, model: FaultModel) -> Self {
+        let state = State {
+            unflushed: BTreeMap::new(),
+            num_reqs: 0,
+            num_flushes: 0,
+        };
+        Self {
+            inner: Arc::new(inner),
+            model,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Returns the inner disk, i.e., the storage medium.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Simulate a crash (e.g., a power loss).
+    ///
+    /// All writes that are not durably flushed are discarded from the volatile
+    /// cache, except those that are chosen to reach the storage medium under
+    /// the `reorder_unflushed` fault model. After the crash, the disk can be
+    /// used as if it were rebooted.
+    pub async fn simulate_crash(&self) -> Result<()> {
+        let unflushed = {
+            let mut state = self.state.lock();
+            core::mem::take(&mut state.unflushed)
+        };
+
+        let seed = match self.model.reorder_seed {
+            Some(seed) => seed,
+            None => return Ok(()),
+        };
+        let mut rng = XorShift::new(seed);
+        let mut persisted: Vec<(BlockId, Box<[u8]>)> = unflushed
+            .into_iter()
+            .filter(|_| rng.next() % 2 == 0)
+            .collect();
+        // Shuffle the persisted blocks to emulate a random order
+        for i in (1..persisted.len()).rev() {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            persisted.swap(i, j);
+        }
+
+        for (addr, data) in persisted {
+            self.inner.write(addr * BLOCK_SIZE, &data).await?;
+        }
+        self.inner.flush().await
+    }
+
+    fn do_read(&self, origin_req: Arc<BioReq>) {
+        fn on_complete(new_req: &BioReq, resp: &BioResp) {
+            let ReadCtx { origin_req, cached } = new_req.ext().remove::<ReadCtx>().unwrap();
+
+            if resp.is_ok() {
+                new_req.access_mut_bufs_with(|bufs| {
+                    // Overlay the cached blocks on the data read from the medium
+                    let merged_buf = bufs[0].as_slice_mut();
+                    for (offset, data) in cached.iter() {
+                        merged_buf[*offset..*offset + BLOCK_SIZE].copy_from_slice(data);
+                    }
+
+                    origin_req.access_mut_bufs_with(|bufs| {
+                        let mut copied_len = 0;
+                        for buf in bufs {
+                            let buf_len = buf.len();
+                            buf.as_slice_mut()
+                                .copy_from_slice(&merged_buf[copied_len..copied_len + buf_len]);
+                            copied_len += buf_len;
+                        }
+                    });
+                });
+            }
+
+            // Notify the origin request I/O completed
+            unsafe {
+                origin_req.complete(*resp);
+            }
+        }
+
+        // Take a copy of the cached blocks that the read covers
+        let cached = {
+            let begin_block = origin_req.addr();
+            let end_block = begin_block + origin_req.num_blocks();
+            let state = self.state.lock();
+            state
+                .unflushed
+                .range(begin_block..end_block)
+                .map(|(addr, data)| ((addr - begin_block) * BLOCK_SIZE, data.clone()))
+                .collect()
+        };
+
+        let new_req = new_merged_req_builder(&origin_req)
+            .on_complete(on_complete)
+            .ext(ReadCtx { origin_req, cached })
+            .build();
+        let _ = self.inner.submit(Arc::new(new_req));
+    }
+
+    fn do_write(&self, req: &Arc<BioReq>) -> Result<()> {
+        let begin_block = req.addr();
+        if begin_block + req.num_blocks() > self.inner.total_blocks() {
+            return Err(errno!(EINVAL, "invalid block range"));
+        }
+
+        let mut state = self.state.lock();
+        if let Some(num_flushes) = self.model.drop_writes_after_flushes {
+            if state.num_flushes >= num_flushes {
+                // Silently drop the write
+                return Ok(());
+            }
+        }
+
+        req.access_bufs_with(|bufs| {
+            let mut addr = begin_block;
+            for buf in bufs {
+                for block in buf.as_slice().chunks(BLOCK_SIZE) {
+                    state.unflushed.insert(addr, Box::from(block));
+                    addr += 1;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn do_flush(&self, origin_req: Arc<BioReq>) {
+        // A block that fails to reach the medium is lost, like an unflushed one
+        // upon a crash
+        fn on_write_complete(_tag: (), _data: &[u8], _resp: &BioResp) {}
+
+        let unflushed = {
+            let mut state = self.state.lock();
+            let seq = state.num_flushes;
+            state.num_flushes += 1;
+            if self.model.ignored_flush == Some(seq) {
+                drop(state);
+                unsafe {
+                    origin_req.complete(Ok(()));
+                }
+                return;
+            }
+            core::mem::take(&mut state.unflushed)
+        };
+
+        let writes = unflushed
+            .into_iter()
+            .map(|(addr, data)| FlushWrite {
+                addr,
+                data,
+                tag: (),
+            })
+            .collect();
+        submit_flush(&self.inner, origin_req, writes, on_write_complete);
+    }
+
+    fn next_seq(&self) -> u64 {
+        let mut state = self.state.lock();
+        let seq = state.num_reqs;
+        state.num_reqs += 1;
+        seq
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for FaultyDisk<D> {
+    fn total_blocks(&self) -> usize {
+        self.inner.total_blocks()
+    }
+
+    fn submit(&self, req: Arc<BioReq>) -> BioSubmission {
+        // Update the status of req to submittted
+        let submission = BioSubmission::new(req);
+
+        let req = submission.req().clone();
+        if self.model.failed_req == Some(self.next_seq()) {
+            unsafe {
+                req.complete(Err(EIO));
+            }
+            return submission;
+        }
+
+        match req.type_() {
+            BioType::Read => self.do_read(req),
+            BioType::Write => {
+                let resp = self.do_write(&req).map_err(|e| e.errno());
+                unsafe {
+                    req.complete(resp);
+                }
+            }
+            BioType::Flush => self.do_flush(req),
+            // Discarding is not simulated
+            BioType::Discard => unsafe {
+                req.complete(Ok(()));
+            },
+        }
+        submission
+    }
+}
+
+// The context of a read that is submitted to the inner disk.
+#[derive(Debug)]
+struct ReadCtx {
+    origin_req: Arc<BioReq>,
+    // The cached blocks to overlay, given as pairs of byte offset and data.
+    cached: Vec<(usize, Box<[u8]>)>,
+}
+
+// A tiny pseudo-random number generator, which is good enough for picking
+// random subsets and orders deterministically.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // The state of xorshift must not be zero
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem_disk::MemDisk;
+
+    fn test_setup() -> FaultyDisk<MemDisk> {
+        let total_blocks = 16;
+        let mem_disk = MemDisk::new(total_blocks).unwrap();
+        FaultyDisk::new(mem_disk, FaultModel::new())
+    }
+
+    fn test_teardown(_disk: FaultyDisk<MemDisk>) {}
+
+    crate::gen_unit_tests!(test_setup, test_teardown);
+
+    fn new_faulty_disk(model: FaultModel) -> FaultyDisk<MemDisk> {
+        let mem_disk = MemDisk::new(16).unwrap();
+        FaultyDisk::new(mem_disk, model)
+    }
+
+    async fn read_block(disk: &FaultyDisk<MemDisk>, addr: BlockId) -> Result<Vec<u8>> {
+        let mut buf = vec![0; BLOCK_SIZE];
+        disk.read(addr * BLOCK_SIZE, &mut buf).await?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn flushed_data_survives_crash() {
+        async_rt::task::block_on(async move {
+            let disk = new_faulty_disk(FaultModel::new());
+
+            disk.write(0, &[b'a'; BLOCK_SIZE]).await.unwrap();
+            disk.flush().await.unwrap();
+            disk.write(0, &[b'b'; BLOCK_SIZE]).await.unwrap();
+            disk.write(BLOCK_SIZE, &[b'c'; BLOCK_SIZE]).await.unwrap();
+            // The unflushed writes are visible before the crash
+            assert!(read_block(&disk, 0).await.unwrap() == [b'b'; BLOCK_SIZE]);
+
+            disk.simulate_crash().await.unwrap();
+
+            assert!(read_block(&disk, 0).await.unwrap() == [b'a'; BLOCK_SIZE]);
+            assert!(read_block(&disk, 1).await.unwrap() == [0; BLOCK_SIZE]);
+        });
+    }
+
+    #[test]
+    fn ignored_flush() {
+        async_rt::task::block_on(async move {
+            let disk = new_faulty_disk(FaultModel::new().ignore_flush(0));
+
+            disk.write(0, &[b'a'; BLOCK_SIZE]).await.unwrap();
+            disk.flush().await.unwrap();
+            disk.simulate_crash().await.unwrap();
+            assert!(read_block(&disk, 0).await.unwrap() == [0; BLOCK_SIZE]);
+
+            disk.write(0, &[b'b'; BLOCK_SIZE]).await.unwrap();
+            disk.flush().await.unwrap();
+            disk.simulate_crash().await.unwrap();
+            assert!(read_block(&disk, 0).await.unwrap() == [b'b'; BLOCK_SIZE]);
+        });
+    }
+
+    #[test]
+    fn failed_req() {
+        async_rt::task::block_on(async move {
+            let disk = new_faulty_disk(FaultModel::new().fail_req(1));
+
+            disk.write(0, &[b'a'; BLOCK_SIZE]).await.unwrap();
+            let res = disk.write(0, &[b'b'; BLOCK_SIZE]).await;
+            assert!(res.unwrap_err().errno() == EIO);
+            assert!(read_block(&disk, 0).await.unwrap() == [b'a'; BLOCK_SIZE]);
+        });
+    }
+
+    #[test]
+    fn dropped_writes() {
+        async_rt::task::block_on(async move {
+            let disk = new_faulty_disk(FaultModel::new().drop_writes_after_flushes(1));
+
+            disk.write(0, &[b'a'; BLOCK_SIZE]).await.unwrap();
+            disk.flush().await.unwrap();
+            disk.write(0, &[b'b'; BLOCK_SIZE]).await.unwrap();
+            disk.flush().await.unwrap();
+            disk.simulate_crash().await.unwrap();
+            assert!(read_block(&disk, 0).await.unwrap() == [b'a'; BLOCK_SIZE]);
+        });
+    }
+
+    #[test]
+    fn reordered_unflushed_writes() {
+        async_rt::task::block_on(async move {
+            let disk = new_faulty_disk(FaultModel::new().reorder_unflushed(0xdead_beef));
+            let num_blocks = disk.total_blocks();
+
+            for addr in 0..num_blocks {
+                disk.write(addr * BLOCK_SIZE, &[b'a'; BLOCK_SIZE])
+                    .await
+                    .unwrap();
+            }
+            disk.flush().await.unwrap();
+            for addr in 0..num_blocks {
+                disk.write(addr * BLOCK_SIZE, &[b'b'; BLOCK_SIZE])
+                    .await
+                    .unwrap();
+            }
+            disk.simulate_crash().await.unwrap();
+
+            // Each block is either the old (flushed) one or the new (unflushed) one
+            for addr in 0..num_blocks {
+                let block = read_block(&disk, addr).await.unwrap();
+                assert!(block == [b'a'; BLOCK_SIZE] || block == [b'b'; BLOCK_SIZE]);
+            }
+        });
+    }
+}
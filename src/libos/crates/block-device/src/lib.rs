@@ -12,6 +12,9 @@ pub mod block_buf;
 pub mod block_device;
 pub mod block_device_ext;
 pub mod block_io;
+pub mod cached_disk;
+pub mod checksummed_disk;
+pub mod faulty_disk;
 pub mod mem_disk;
 mod prelude;
 pub mod util;
@@ -23,7 +26,8 @@ pub use self::block_buf::BlockBuf;
 pub use self::block_device::BlockDevice;
 pub use self::block_device_ext::BlockDeviceExt;
 pub use self::block_io::{
-    BioReq, BioReqBuilder, BioReqOnCompleteFn, BioReqOnDropFn, BioResp, BioSubmission, BioType,
+    BioPrio, BioReq, BioReqBuilder, BioReqOnCompleteFn, BioReqOnDropFn, BioResp, BioSubmission,
+    BioType,
 };
 pub use self::util::anymap::{Any, AnyMap};
 
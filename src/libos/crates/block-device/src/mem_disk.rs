@@ -56,6 +56,16 @@ impl MemDisk {
         Ok(())
     }
 
+    fn do_discard(&self, req: &Arc<BioReq>) -> Result<()> {
+        let (begin_offset, end_offset) = self.get_range_in_bytes(&req)?;
+
+        let mut disk = self.disk.lock();
+        disk[begin_offset..end_offset].fill(0);
+        drop(disk);
+
+        Ok(())
+    }
+
     fn do_flush(&self, _req: &Arc<BioReq>) -> Result<()> {
         // Do nothing
         Ok(())
@@ -88,6 +98,7 @@ impl BlockDevice for MemDisk {
             BioType::Read => self.do_read(req),
             BioType::Write => self.do_write(req),
             BioType::Flush => self.do_flush(req),
+            BioType::Discard => self.do_discard(req),
         };
 
         // Update the status of req to completed and set the response
@@ -0,0 +1,213 @@
+//! Error sequences for reporting writeback errors.
+//!
+//! Writeback errors are usually detected asynchronously, e.g., when the
+//! cached writes of a disk fail to reach the storage medium. An `ErrSeq`
+//! latches such an error on an object that is shared by multiple users (e.g.,
+//! a disk opened by multiple files), so that the error is reported to every
+//! user that checks for errors after it happens, exactly once per user.
+//!
+//! This follows the design of `errseq_t` in Linux. An `ErrSeq` packs the last
+//! error, a flag that indicates whether the error has been seen, and a counter
+//! that is bumped when a seen error is overwritten by a new one. Each user
+//! keeps an `ErrSeqCursor` that records the value of the `ErrSeq` when it last
+//! checked for errors.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::prelude::*;
+
+/// An error sequence.
+#[derive(Debug, Default)]
+pub struct ErrSeq(AtomicU32);
+
+/// The position of a user in an error sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ErrSeqCursor(u32);
+
+// The lowest bits hold the errno
+const ERRNO_BITS: u32 = 12;
+const ERRNO_MASK: u32 = (1 << ERRNO_BITS) - 1;
+// The next bit indicates whether the error has been seen
+const SEEN: u32 = 1 << ERRNO_BITS;
+// The remaining bits are the counter
+const COUNTER_INC: u32 = SEEN << 1;
+
+impl ErrSeq {
+    /// Create an error sequence that has no error.
+    pub fn new() -> Self {
+        Self(AtomicU32::new(0))
+    }
+
+    /// Latch a new error.
+    pub fn set(&self, errno: Errno) {
+        let errno = errno as u32;
+        debug_assert!(errno != 0 && errno <= ERRNO_MASK);
+
+        let mut old = self.0.load(Ordering::Relaxed);
+        loop {
+            let mut new = (old & !(ERRNO_MASK | SEEN)) | errno;
+            // Only when the old error has been seen by someone, do we need to
+            // bump the counter to make the new error distinguishable.
+            if old & SEEN != 0 {
+                new = new.wrapping_add(COUNTER_INC);
+            }
+            if new == old {
+                return;
+            }
+            match self
+                .0
+                .compare_exchange_weak(old, new, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => old = current,
+            }
+        }
+    }
+
+    /// Returns a cursor for a new user.
+    ///
+    /// The new user does not see the errors that have been seen by others, but
+    /// an unseen error will be reported to the new user.
+    pub fn sample(&self) -> ErrSeqCursor {
+        let old = self.0.load(Ordering::Acquire);
+        if old & SEEN == 0 {
+            ErrSeqCursor(0)
+        } else {
+            ErrSeqCursor(old)
+        }
+    }
+
+    /// Check whether there is a new error since the cursor, without advancing
+    /// the cursor.
+    pub fn check(&self, cursor: &ErrSeqCursor) -> Result<()> {
+        let cur = self.0.load(Ordering::Acquire);
+        if cur & !SEEN == cursor.0 & !SEEN {
+            return Ok(());
+        }
+        Err(Self::to_error(cur))
+    }
+
+    /// Check whether there is a new error since the cursor. If so, the error
+    /// is returned and the cursor is advanced so that the same error is not
+    /// reported to the user again.
+    pub fn check_and_advance(&self, cursor: &mut ErrSeqCursor) -> Result<()> {
+        let old = self.0.load(Ordering::Acquire);
+        if old == cursor.0 {
+            return Ok(());
+        }
+
+        // Mark the error as seen. If it fails, either someone else has marked
+        // the error as seen, or a new error has been set; in both cases, the
+        // error that we report is still a new one to the user.
+        let new = old | SEEN;
+        if new != old {
+            let _ = self
+                .0
+                .compare_exchange(old, new, Ordering::AcqRel, Ordering::Relaxed);
+        }
+        cursor.0 = new;
+        Err(Self::to_error(new))
+    }
+
+    fn to_error(value: u32) -> Error {
+        let errno = Errno::from(value & ERRNO_MASK);
+        errno!(errno, "a writeback error has occurred")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::faulty_disk::{FaultModel, FaultyDisk};
+    use crate::mem_disk::MemDisk;
+    use crate::BlockDeviceExt;
+
+    #[test]
+    fn no_error() {
+        let errseq = ErrSeq::new();
+        let mut cursor = errseq.sample();
+        assert!(errseq.check(&cursor).is_ok());
+        assert!(errseq.check_and_advance(&mut cursor).is_ok());
+    }
+
+    #[test]
+    fn report_once_per_user() {
+        let errseq = ErrSeq::new();
+        let mut cursor0 = errseq.sample();
+        let mut cursor1 = errseq.sample();
+
+        errseq.set(EIO);
+        assert!(errseq.check(&cursor0).unwrap_err().errno() == EIO);
+        assert!(errseq.check(&cursor0).is_err());
+
+        assert!(errseq.check_and_advance(&mut cursor0).unwrap_err().errno() == EIO);
+        assert!(errseq.check_and_advance(&mut cursor0).is_ok());
+        // The other user still sees the error
+        assert!(errseq.check_and_advance(&mut cursor1).unwrap_err().errno() == EIO);
+        assert!(errseq.check_and_advance(&mut cursor1).is_ok());
+
+        // A user that comes after the error has been seen does not see it
+        let mut cursor2 = errseq.sample();
+        assert!(errseq.check_and_advance(&mut cursor2).is_ok());
+    }
+
+    #[test]
+    fn unseen_error_reported_to_new_user() {
+        let errseq = ErrSeq::new();
+        errseq.set(EIO);
+        let mut cursor = errseq.sample();
+        assert!(errseq.check_and_advance(&mut cursor).is_err());
+        assert!(errseq.check_and_advance(&mut cursor).is_ok());
+    }
+
+    #[test]
+    fn same_error_again() {
+        let errseq = ErrSeq::new();
+        let mut cursor0 = errseq.sample();
+        let mut cursor1 = errseq.sample();
+
+        errseq.set(EIO);
+        assert!(errseq.check_and_advance(&mut cursor0).is_err());
+
+        // The same error happens again after being seen
+        errseq.set(EIO);
+        assert!(errseq.check_and_advance(&mut cursor0).unwrap_err().errno() == EIO);
+        assert!(errseq.check_and_advance(&mut cursor0).is_ok());
+        // Two errors are reported only once to a user that has not checked
+        assert!(errseq.check_and_advance(&mut cursor1).unwrap_err().errno() == EIO);
+        assert!(errseq.check_and_advance(&mut cursor1).is_ok());
+
+        // Setting the same unseen error is a no-op
+        errseq.set(ENOSPC);
+        errseq.set(ENOSPC);
+        assert!(errseq.check_and_advance(&mut cursor0).unwrap_err().errno() == ENOSPC);
+        assert!(errseq.check_and_advance(&mut cursor0).is_ok());
+    }
+
+    #[test]
+    fn failed_writeback() {
+        async_rt::task::block_on(async move {
+            // The request #1, i.e., the flush, fails
+            let model = FaultModel::new().fail_req(1);
+            let disk = FaultyDisk::new(MemDisk::new(16).unwrap(), model);
+            let errseq = ErrSeq::new();
+            // Two users of the disk, e.g., two opened files
+            let mut cursor0 = errseq.sample();
+            let mut cursor1 = errseq.sample();
+
+            // The write is cached and succeeds, but its writeback fails
+            disk.write(0, &[b'a'; BLOCK_SIZE]).await.unwrap();
+            if let Err(e) = disk.flush().await {
+                errseq.set(e.errno());
+            }
+
+            // Each user observes the error exactly once, even though the
+            // flush of the second user succeeds
+            assert!(errseq.check_and_advance(&mut cursor0).unwrap_err().errno() == EIO);
+            assert!(errseq.check_and_advance(&mut cursor0).is_ok());
+            disk.flush().await.unwrap();
+            assert!(errseq.check_and_advance(&mut cursor1).unwrap_err().errno() == EIO);
+            assert!(errseq.check_and_advance(&mut cursor1).is_ok());
+        });
+    }
+}
@@ -0,0 +1,140 @@
+//! Flushes that fan out into writes to an inner disk.
+//!
+//! A disk that decorates another disk (e.g., by caching or checksumming the
+//! blocks) usually has some blocks of its own to write upon a flush. Such a
+//! flush fans out into the writes of the blocks to the inner disk; once all of
+//! them are completed, the inner disk is flushed, after which the origin flush
+//! request is completed.
+
+use core::fmt::{self, Debug};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use super::drop_boxed_bufs;
+use crate::prelude::*;
+use crate::Any;
+
+/// A write to be done by a flush before the inner disk is flushed.
+pub struct FlushWrite<T> {
+    /// The address of the first block to write.
+    pub addr: BlockId,
+    /// The data of the blocks to write.
+    pub data: Box<[u8]>,
+    /// A value that is handed back to the callback upon the completion of the
+    /// write.
+    pub tag: T,
+}
+
+/// The type of the callback function invoked upon the completion of a write
+/// of a flush.
+pub type FlushWriteOnCompleteFn<T> =
+    fn(/* tag = */ T, /* data = */ &[u8], /* resp = */ &BioResp);
+
+/// Flush the inner disk on behalf of a flush request, after writing the given
+/// blocks to the inner disk.
+///
+/// The callback is invoked upon the completion of each write, e.g., to keep the
+/// blocks that fail to be written so that they can be written again later. The
+/// flush request fails if any of the writes fails, as the durability of the
+/// flush is broken, or if the flush of the inner disk fails.
+pub fn submit_flush<D: BlockDevice, T: Any>(
+    inner: &Arc<D>,
+    origin_req: Arc<BioReq>,
+    writes: Vec<FlushWrite<T>>,
+    on_write_complete: FlushWriteOnCompleteFn<T>,
+) {
+    fn on_complete<D: BlockDevice, T: Any>(new_req: &BioReq, resp: &BioResp) {
+        let WriteCtx(ctx, tag) = new_req.ext().remove::<WriteCtx<D, T>>().unwrap();
+        let mut tag = Some(tag);
+        new_req.access_bufs_with(|bufs| {
+            (ctx.on_write_complete)(tag.take().unwrap(), bufs[0].as_slice(), resp)
+        });
+        if let Err(e) = resp {
+            *ctx.resp.lock() = Err(*e);
+        }
+        if ctx.num_pending_writes.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // All writes are completed. It is time to flush the inner disk.
+            ctx.flush_inner();
+        }
+    }
+
+    let ctx = Arc::new(FlushCtx {
+        inner: inner.clone(),
+        origin_req,
+        on_write_complete,
+        num_pending_writes: AtomicUsize::new(writes.len()),
+        resp: Mutex::new(Ok(())),
+    });
+    if writes.is_empty() {
+        ctx.flush_inner();
+        return;
+    }
+
+    for FlushWrite { addr, data, tag } in writes {
+        let new_req = BioReqBuilder::new(BioType::Write)
+            .addr(addr)
+            .priority(ctx.origin_req.priority())
+            .bufs(vec![BlockBuf::from_boxed(data)])
+            .on_complete(on_complete::<D, T>)
+            .on_drop(drop_boxed_bufs)
+            .ext(WriteCtx(ctx.clone(), tag))
+            .build();
+        let _ = inner.submit(Arc::new(new_req));
+    }
+}
+
+// The context of a flush that is in progress.
+struct FlushCtx<D: BlockDevice, T> {
+    inner: Arc<D>,
+    origin_req: Arc<BioReq>,
+    on_write_complete: FlushWriteOnCompleteFn<T>,
+    num_pending_writes: AtomicUsize,
+    resp: Mutex<BioResp>,
+}
+
+impl<D: BlockDevice, T> FlushCtx<D, T> {
+    fn flush_inner(&self) {
+        fn on_complete(new_req: &BioReq, resp: &BioResp) {
+            let (origin_req, write_resp) = new_req.ext().remove::<OriginFlush>().unwrap().0;
+
+            // A failed write breaks the durability of the flush
+            let resp = write_resp.and(*resp);
+            unsafe {
+                origin_req.complete(resp);
+            }
+        }
+
+        let write_resp = *self.resp.lock();
+        let new_req = BioReqBuilder::new(BioType::Flush)
+            .priority(self.origin_req.priority())
+            .on_complete(on_complete)
+            .ext(OriginFlush((self.origin_req.clone(), write_resp)))
+            .build();
+        let _ = self.inner.submit(Arc::new(new_req));
+    }
+}
+
+impl<D: BlockDevice, T> Debug for FlushCtx<D, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlushCtx")
+            .field("origin_req", &self.origin_req)
+            .field("num_pending_writes", &self.num_pending_writes)
+            .finish()
+    }
+}
+
+/// A new-type wrapper to be used in AnyMap, which also carries the tag of the
+/// write.
+struct WriteCtx<D: BlockDevice, T>(Arc<FlushCtx<D, T>>, T);
+
+impl<D: BlockDevice, T: Debug> Debug for WriteCtx<D, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WriteCtx")
+            .field(&self.0)
+            .field(&self.1)
+            .finish()
+    }
+}
+
+/// A new-type wrapper to be used in AnyMap.
+#[derive(Debug)]
+struct OriginFlush((Arc<BioReq>, BioResp));
@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
 pub mod anymap;
+pub mod errseq;
+pub mod flush;
 pub mod test;
 
 /// Equivalent to `Box::into_inner`. The latter method is not available in
@@ -16,3 +18,34 @@ pub(crate) const fn align_down(x: usize, align: usize) -> usize {
 pub(crate) const fn align_up(x: usize, align: usize) -> usize {
     ((x + align - 1) / align) * align
 }
+
+/// Returns a builder of a request to an inner disk on behalf of the given
+/// request, which has the same type, address and priority as the given one,
+/// and a single buffer that is large enough for all of its blocks.
+///
+/// The content of the buffer is uninitialized. The buffer is freed when the
+/// new request is dropped.
+pub fn new_merged_req_builder(origin_req: &Arc<BioReq>) -> BioReqBuilder {
+    let merged_buf = {
+        let total_len = origin_req.num_blocks() * BLOCK_SIZE;
+        // Safety. The initial content is not important as it will be overwritten,
+        // either by the data to write or by the data read.
+        let boxed_slice = unsafe { Box::new_uninit_slice(total_len).assume_init() };
+        BlockBuf::from_boxed(boxed_slice)
+    };
+
+    BioReqBuilder::new(origin_req.type_())
+        .addr(origin_req.addr())
+        .priority(origin_req.priority())
+        .bufs(vec![merged_buf])
+        .on_drop(drop_boxed_bufs)
+}
+
+/// The drop callback of the requests whose buffers are created from boxed
+/// slices, which frees the buffers.
+pub fn drop_boxed_bufs(_req: &BioReq, mut bufs: Vec<BlockBuf>) {
+    bufs.drain(..).for_each(|buf| {
+        // Safety. The block buffer must be created from a boxed slice
+        drop(unsafe { BlockBuf::into_boxed(buf) });
+    });
+}
@@ -68,6 +68,47 @@ macro_rules! gen_unit_tests {
             });
         }
 
+        // Write all blocks on a disk in a batch of requests, one per block.
+        #[test]
+        fn write_batch() {
+            async_rt::task::block_on(async move {
+                let disk = $setup();
+
+                let reqs: Vec<Arc<BioReq>> = (0..disk.total_blocks())
+                    .map(|addr| {
+                        let boxed_slice = vec![addr as u8; BLOCK_SIZE].into_boxed_slice();
+                        let req = BioReqBuilder::new(BioType::Write)
+                            .addr(addr)
+                            .bufs(vec![BlockBuf::from_boxed(boxed_slice)])
+                            .on_drop(|_req: &BioReq, mut bufs: Vec<BlockBuf>| {
+                                // Free the boxed slice that we allocated before
+                                bufs.drain(..).for_each(|buf| {
+                                    // Safety. BlockBuffer is created with from_boxed
+                                    drop(unsafe { BlockBuf::into_boxed(buf) });
+                                });
+                            })
+                            .build();
+                        Arc::new(req)
+                    })
+                    .collect();
+                let submissions = disk.submit_batch(reqs);
+                assert!(submissions.len() == disk.total_blocks());
+                for submission in submissions {
+                    let req = submission.complete().await;
+                    assert!(req.response() == Some(Ok(())));
+                }
+
+                // Each block should be filled with its own value
+                for addr in 0..disk.total_blocks() {
+                    let mut read_buf = vec![0_u8; BLOCK_SIZE];
+                    disk.read(addr * BLOCK_SIZE, &mut read_buf).await.unwrap();
+                    assert!(read_buf.iter().all(|b| *b == addr as u8));
+                }
+
+                $teardown(disk);
+            });
+        }
+
         // Write a short message and then read it back
         #[test]
         fn write_read_partial_blocks() {
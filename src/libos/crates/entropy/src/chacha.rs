@@ -0,0 +1,189 @@
+use errno::prelude::*;
+use spin::mutex::Mutex;
+
+use crate::EntropySource;
+
+/// A CSPRNG that generates the keystream of ChaCha20.
+///
+/// The key of ChaCha20 is the 256-bit seed, while the nonce is always zero.
+/// The 64-bit block counter makes sure that the keystream never repeats in
+/// practice.
+pub struct ChaChaRng {
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    key: [u32; 8],
+    counter: u64,
+    block: [u8; BLOCK_SIZE],
+    // The offset of the unused bytes in the current block
+    offset: usize,
+}
+
+const BLOCK_SIZE: usize = 64;
+// "expand 32-byte k"
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+impl ChaChaRng {
+    /// Create a CSPRNG with the given seed.
+    pub fn new(seed: [u8; 32]) -> Self {
+        let mut key = [0_u32; 8];
+        for (word, bytes) in key.iter_mut().zip(seed.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        let inner = Inner {
+            key,
+            counter: 0,
+            block: [0; BLOCK_SIZE],
+            offset: BLOCK_SIZE,
+        };
+        Self {
+            inner: Mutex::new(inner),
+        }
+    }
+
+    /// Create a CSPRNG with a seed drawn from another entropy source.
+    pub fn from_source(source: &dyn EntropySource) -> Result<Self> {
+        let mut seed = [0_u8; 32];
+        source.fill(&mut seed)?;
+        Ok(Self::new(seed))
+    }
+}
+
+impl EntropySource for ChaChaRng {
+    fn fill(&self, buf: &mut [u8]) -> Result<()> {
+        let mut filled_len = 0;
+        while filled_len < buf.len() {
+            // Take the lock for one block at a time so that a large fill does not
+            // keep other fills spinning
+            let mut inner = self.inner.lock();
+            if inner.offset == BLOCK_SIZE {
+                inner.refill_block();
+            }
+            let len = (BLOCK_SIZE - inner.offset).min(buf.len() - filled_len);
+            let offset = inner.offset;
+            buf[filled_len..filled_len + len].copy_from_slice(&inner.block[offset..offset + len]);
+            // Never hand out the same bytes twice
+            inner.block[offset..offset + len].fill(0);
+            inner.offset += len;
+            filled_len += len;
+        }
+        Ok(())
+    }
+}
+
+impl Inner {
+    fn refill_block(&mut self) {
+        let mut state = [0_u32; 16];
+        state[..4].copy_from_slice(&CONSTANTS);
+        state[4..12].copy_from_slice(&self.key);
+        state[12] = self.counter as u32;
+        state[13] = (self.counter >> 32) as u32;
+
+        let mut working_state = state;
+        for _ in 0..10 {
+            // Column rounds
+            quarter_round(&mut working_state, 0, 4, 8, 12);
+            quarter_round(&mut working_state, 1, 5, 9, 13);
+            quarter_round(&mut working_state, 2, 6, 10, 14);
+            quarter_round(&mut working_state, 3, 7, 11, 15);
+            // Diagonal rounds
+            quarter_round(&mut working_state, 0, 5, 10, 15);
+            quarter_round(&mut working_state, 1, 6, 11, 12);
+            quarter_round(&mut working_state, 2, 7, 8, 13);
+            quarter_round(&mut working_state, 3, 4, 9, 14);
+        }
+
+        for (i, bytes) in self.block.chunks_exact_mut(4).enumerate() {
+            let word = working_state[i].wrapping_add(state[i]);
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        self.counter = self.counter.wrapping_add(1);
+        self.offset = 0;
+    }
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keystream() {
+        // The test vector #1 of the ChaCha20 block function in RFC 7539
+        let expected: [u8; 64] = [
+            0x76, 0xb8, 0xe0, 0xad, 0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5, 0x53, 0x86,
+            0xbd, 0x28, 0xbd, 0xd2, 0x19, 0xb8, 0xa0, 0x8d, 0xed, 0x1a, 0xa8, 0x36, 0xef, 0xcc,
+            0x8b, 0x77, 0x0d, 0xc7, 0xda, 0x41, 0x59, 0x7c, 0x51, 0x57, 0x48, 0x8d, 0x77, 0x24,
+            0xe0, 0x3f, 0xb8, 0xd8, 0x4a, 0x37, 0x6a, 0x43, 0xb8, 0xf4, 0x15, 0x18, 0xa1, 0x1c,
+            0xc3, 0x87, 0xb6, 0x69, 0xb2, 0xee, 0x65, 0x86,
+        ];
+        let rng = ChaChaRng::new([0; 32]);
+        let mut buf = [0_u8; 64];
+        rng.fill(&mut buf).unwrap();
+        assert!(buf == expected);
+    }
+
+    #[test]
+    fn unaligned_fills() {
+        let rng = ChaChaRng::new([7; 32]);
+        let mut expected = [0_u8; 300];
+        rng.fill(&mut expected).unwrap();
+
+        // The keystream does not depend on how it is split into fills
+        let rng = ChaChaRng::new([7; 32]);
+        let mut buf = [0_u8; 300];
+        let mut offset = 0;
+        for len in [1, 63, 2, 100, 0, 134].iter() {
+            rng.fill(&mut buf[offset..offset + len]).unwrap();
+            offset += len;
+        }
+        assert!(buf == expected);
+    }
+
+    #[test]
+    fn statistical_sanity() {
+        let rng = ChaChaRng::new([42; 32]);
+        let mut buf = vec![0_u8; 1 << 16];
+        rng.fill(&mut buf).unwrap();
+
+        // No constant output
+        assert!(buf.iter().any(|b| *b != buf[0]));
+
+        // No short cycles
+        let first_block = &buf[..BLOCK_SIZE];
+        assert!(buf[BLOCK_SIZE..]
+            .windows(BLOCK_SIZE)
+            .all(|window| window != first_block));
+
+        // Ones and zeros are roughly balanced
+        let num_ones: u32 = buf.iter().map(|b| b.count_ones()).sum();
+        let num_bits = (buf.len() * 8) as u32;
+        assert!(num_ones > num_bits / 2 - num_bits / 100);
+        assert!(num_ones < num_bits / 2 + num_bits / 100);
+
+        // Every byte value appears
+        let mut counts = [0_u32; 256];
+        buf.iter().for_each(|b| counts[*b as usize] += 1);
+        assert!(counts.iter().all(|count| *count > 0));
+    }
+
+    #[test]
+    fn different_seeds() {
+        let mut buf0 = [0_u8; 64];
+        let mut buf1 = [0_u8; 64];
+        ChaChaRng::new([0; 32]).fill(&mut buf0).unwrap();
+        ChaChaRng::new([1; 32]).fill(&mut buf1).unwrap();
+        assert!(buf0 != buf1);
+    }
+}
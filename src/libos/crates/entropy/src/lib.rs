@@ -0,0 +1,31 @@
+//! Pluggable sources of random bytes.
+//!
+//! This crate defines the `EntropySource` trait, which abstracts the sources
+//! that random bytes are drawn from (e.g., by `getrandom` or `/dev/urandom`).
+//! Besides the hardware RNG, which is provided by the users of this crate,
+//! there are two implementations:
+//!
+//! * `ChaChaRng`, a userspace CSPRNG based on ChaCha20. It is typically seeded
+//!   once from the hardware RNG and then generates random bytes at a much higher
+//!   throughput.
+//! * `DeterministicSource`, which always generates the same sequence of bytes
+//!   for the same seed. It enables reproducible tests, but must never be used to
+//!   generate secrets.
+
+#![cfg_attr(not(any(test, doctest)), no_std)]
+
+mod chacha;
+mod deterministic;
+mod retry;
+
+pub use self::chacha::ChaChaRng;
+pub use self::deterministic::DeterministicSource;
+pub use self::retry::fill_with_retries;
+
+use errno::prelude::*;
+
+/// A source of random bytes.
+pub trait EntropySource: Send + Sync {
+    /// Fill the buffer with random bytes.
+    fn fill(&self, buf: &mut [u8]) -> Result<()>;
+}
@@ -0,0 +1,98 @@
+use errno::prelude::*;
+
+use crate::EntropySource;
+
+/// Fill the buffer with random bytes from the source, retrying upon failures.
+///
+/// An entropy source may fail transiently (e.g., the hardware RNG runs out of
+/// entropy for a moment). The fill is retried for at most `max_retries` times,
+/// after which the error of the last try is returned.
+pub fn fill_with_retries(
+    source: &dyn EntropySource,
+    buf: &mut [u8],
+    max_retries: usize,
+) -> Result<()> {
+    let mut res = source.fill(buf);
+    for _ in 0..max_retries {
+        if res.is_ok() {
+            break;
+        }
+        res = source.fill(buf);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::DeterministicSource;
+
+    // A source that fails for the given number of times before it works.
+    struct FlakySource {
+        num_failures: AtomicUsize,
+        source: DeterministicSource,
+    }
+
+    impl FlakySource {
+        fn new(num_failures: usize) -> Self {
+            Self {
+                num_failures: AtomicUsize::new(num_failures),
+                source: DeterministicSource::new(0),
+            }
+        }
+    }
+
+    impl EntropySource for FlakySource {
+        fn fill(&self, buf: &mut [u8]) -> Result<()> {
+            let failed = self
+                .num_failures
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |num| {
+                    num.checked_sub(1)
+                })
+                .is_ok();
+            if failed {
+                return_errno!(EAGAIN, "no entropy for now");
+            }
+            self.source.fill(buf)
+        }
+    }
+
+    fn expected_bytes() -> Vec<u8> {
+        let mut buf = vec![0_u8; 100];
+        DeterministicSource::new(0).fill(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn reproducible() {
+        let gen_bytes = || -> Vec<u8> {
+            let mut buf = vec![0_u8; 100];
+            fill_with_retries(&DeterministicSource::new(0), &mut buf, 0).unwrap();
+            buf
+        };
+        assert!(gen_bytes() == gen_bytes());
+        assert!(gen_bytes() == expected_bytes());
+    }
+
+    #[test]
+    fn retry_transient_failures() {
+        let source = FlakySource::new(3);
+        let mut buf = vec![0_u8; 100];
+        fill_with_retries(&source, &mut buf, 3).unwrap();
+        assert!(buf == expected_bytes());
+    }
+
+    #[test]
+    fn bounded_retries() {
+        let source = FlakySource::new(4);
+        let mut buf = vec![0_u8; 100];
+        let err = fill_with_retries(&source, &mut buf, 3).unwrap_err();
+        assert!(err.errno() == EAGAIN);
+
+        // The source works again after the failures
+        fill_with_retries(&source, &mut buf, 0).unwrap();
+        assert!(buf == expected_bytes());
+    }
+}
@@ -157,6 +157,18 @@ impl<A: Addr + 'static, R: Runtime> Common<A, R> {
         let mut inner = self.inner.lock().unwrap();
         inner.peer_addr = None;
     }
+
+    /// Returns the size of the send buffer inside the enclave, which is None
+    /// if the default size is used.
+    pub fn send_buf_size(&self) -> Option<usize> {
+        let inner = self.inner.lock().unwrap();
+        inner.send_buf_size
+    }
+
+    pub fn set_send_buf_size(&self, size: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.send_buf_size = Some(size);
+    }
 }
 
 impl<A: Addr + 'static, R: Runtime> std::fmt::Debug for Common<A, R> {
@@ -183,6 +195,7 @@ impl<A: Addr + 'static, R: Runtime> Drop for Common<A, R> {
 struct Inner<A: Addr + 'static> {
     addr: Option<A>,
     peer_addr: Option<A>,
+    send_buf_size: Option<usize>,
 }
 
 impl<A: Addr + 'static> Inner<A> {
@@ -190,6 +203,7 @@ impl<A: Addr + 'static> Inner<A> {
         Self {
             addr: None,
             peer_addr: None,
+            send_buf_size: None,
         }
     }
 }
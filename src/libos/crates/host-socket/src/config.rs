@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Set the write-combining policy of the send buffers of stream sockets.
+///
+/// When no send is in flight and fewer than `min_bytes` bytes are buffered,
+/// the send is deferred by up to `max_delay` so that more small writes can be
+/// combined into a single send. Writes that are made while a send is in flight
+/// are always combined. A `min_bytes` of zero (the default) disables deferring.
+pub fn set_send_batch(min_bytes: usize, max_delay: Duration) {
+    SEND_BATCH_MIN_BYTES.store(min_bytes, Ordering::Relaxed);
+    SEND_BATCH_MAX_DELAY_NS.store(max_delay.as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// Returns the min number of bytes and the max delay of a send batch.
+pub(crate) fn send_batch() -> (usize, Duration) {
+    let min_bytes = SEND_BATCH_MIN_BYTES.load(Ordering::Relaxed);
+    let max_delay = Duration::from_nanos(SEND_BATCH_MAX_DELAY_NS.load(Ordering::Relaxed));
+    (min_bytes, max_delay)
+}
+
+static SEND_BATCH_MIN_BYTES: AtomicUsize = AtomicUsize::new(0);
+static SEND_BATCH_MAX_DELAY_NS: AtomicU64 = AtomicU64::new(0);
@@ -184,10 +184,24 @@ impl<A: Addr, R: Runtime> DatagramSocket<A, R> {
     /// That is because `recvfrom` doesn't privide a implicit binding. If you
     /// don't do a explicit or implicit binding, the sender doesn't know where
     /// to send the data.
-    pub async fn recvmsg(&self, bufs: &mut [&mut [u8]], flags: RecvFlags) -> Result<(usize, A)> {
+    ///
+    /// Besides the length and the source address of the received datagram,
+    /// the flags that describe the datagram (e.g., `MSG_TRUNC`) are returned.
+    pub async fn recvmsg(
+        &self,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<(usize, A, MsgFlags)> {
         self.receiver.recvmsg(bufs, flags).await
     }
 
+    /// Receive a datagram into a single buffer, returning its length and source address.
+    pub async fn recvfrom(&self, buf: &mut [u8], flags: RecvFlags) -> Result<(usize, A)> {
+        self.recvmsg(&mut [buf], flags)
+            .await
+            .map(|(ret, addr, _)| (ret, addr))
+    }
+
     pub async fn write(&self, buf: &[u8]) -> Result<usize> {
         self.writev(&[buf]).await
     }
@@ -196,6 +210,11 @@ impl<A: Addr, R: Runtime> DatagramSocket<A, R> {
         self.sendmsg(bufs, None, SendFlags::empty()).await
     }
 
+    /// Send a datagram in a single buffer to the given address.
+    pub async fn sendto(&self, buf: &[u8], flags: SendFlags, addr: &A) -> Result<usize> {
+        self.sendmsg(&[buf], Some(addr), flags).await
+    }
+
     pub async fn sendmsg(
         &self,
         bufs: &[&[u8]],
@@ -18,11 +18,17 @@ impl<A: Addr, R: Runtime> Receiver<A, R> {
         Arc::new(Self { common, inner })
     }
 
+    /// Receive a datagram.
+    ///
+    /// If the datagram is larger than the buffers, the excess bytes are
+    /// discarded and `MSG_TRUNC` is set in the returned flags. The returned
+    /// length is the number of bytes copied, unless the `MSG_TRUNC` flag is
+    /// given, in which case it is the real length of the datagram.
     pub async fn recvmsg(
         self: &Arc<Self>,
         bufs: &mut [&mut [u8]],
         flags: RecvFlags,
-    ) -> Result<(usize, A)> {
+    ) -> Result<(usize, A, MsgFlags)> {
         // Initialize the poller only when needed
         let mut poller = None;
         loop {
@@ -52,10 +58,10 @@ impl<A: Addr, R: Runtime> Receiver<A, R> {
         self: &Arc<Self>,
         bufs: &mut [&mut [u8]],
         flags: RecvFlags,
-    ) -> Result<(usize, A)> {
+    ) -> Result<(usize, A, MsgFlags)> {
         let mut inner = self.inner.lock().unwrap();
 
-        if !flags.is_empty() && flags != RecvFlags::MSG_DONTWAIT {
+        if !(flags - (RecvFlags::MSG_DONTWAIT | RecvFlags::MSG_TRUNC)).is_empty() {
             todo!("Support other flags");
         }
 
@@ -63,11 +69,22 @@ impl<A: Addr, R: Runtime> Receiver<A, R> {
         self.common.pollee().del_events(Events::IN);
 
         // Copy data from the recv buffer to the bufs
-        let recv_bytes = inner.try_copy_buf(bufs);
-        if let Some(recv_bytes) = recv_bytes {
+        if let Some((copy_len, recv_len)) = inner.try_copy_buf(bufs) {
             let recv_addr = inner.get_addr().unwrap();
+            // The excess bytes are discarded with the datagram by do_recv()
             self.do_recv(&mut inner);
-            return Ok((recv_bytes, recv_addr));
+
+            let msg_flags = if copy_len < recv_len {
+                MsgFlags::MSG_TRUNC
+            } else {
+                MsgFlags::empty()
+            };
+            let ret_len = if flags.contains(RecvFlags::MSG_TRUNC) {
+                recv_len
+            } else {
+                copy_len
+            };
+            return Ok((ret_len, recv_addr, msg_flags));
         }
 
         if let Some(errno) = inner.error {
@@ -197,7 +214,9 @@ impl Inner {
         msghdr_ptr
     }
 
-    pub fn try_copy_buf(&self, bufs: &mut [&mut [u8]]) -> Option<usize> {
+    /// Copy the received datagram (if any) to the buffers, returning the
+    /// number of bytes copied and the length of the datagram.
+    pub fn try_copy_buf(&self, bufs: &mut [&mut [u8]]) -> Option<(usize, usize)> {
         self.recv_len.map(|recv_len| {
             let mut copy_len = 0;
             for buf in bufs {
@@ -211,7 +230,7 @@ impl Inner {
                     break;
                 }
             }
-            copy_len
+            (copy_len, recv_len)
         })
     }
 
@@ -20,6 +20,7 @@ extern crate sgx_types;
 #[macro_use]
 mod prelude;
 mod common;
+pub mod config;
 mod datagram;
 pub mod ioctl;
 mod runtime;
@@ -30,4 +31,4 @@ mod util;
 pub use self::common::do_unlink;
 pub use self::datagram::DatagramSocket;
 pub use self::runtime::Runtime;
-pub use self::stream::StreamSocket;
+pub use self::stream::{KeepaliveConfig, SendBufStats, StreamSocket};
@@ -17,7 +17,7 @@ pub(crate) type HostFd = u32;
 pub(crate) use async_io::event::{Events, Observer, Pollee, Poller};
 pub(crate) use async_io::file::StatusFlags;
 pub(crate) use async_io::ioctl::IoctlCmd;
-pub(crate) use async_io::socket::{Addr, Domain, RecvFlags, SendFlags, Shutdown, Type};
+pub(crate) use async_io::socket::{Addr, Domain, MsgFlags, RecvFlags, SendFlags, Shutdown, Type};
 
 // return Err(errno) if libc return -1
 macro_rules! try_libc {
@@ -8,3 +8,65 @@ use io_uring_callback::IoUring;
 pub trait Runtime: Send + Sync + 'static {
     fn io_uring() -> &'static IoUring;
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::future::Future;
+
+    use async_io::socket::UnixAddr;
+    use io_uring_callback::Builder as IoUringBuilder;
+
+    use super::*;
+    use crate::StreamSocket;
+
+    /// The runtime of the unit tests.
+    pub(crate) struct TestRuntime;
+
+    impl TestRuntime {
+        pub(crate) fn init() {
+            use std::sync::Once;
+
+            static INIT: Once = Once::new();
+            INIT.call_once(|| {
+                async_rt::config::set_parallelism(2);
+
+                let ring = Self::io_uring();
+                unsafe {
+                    ring.start_enter_syscall_thread();
+                }
+                async_rt::task::spawn(async move {
+                    loop {
+                        ring.poll_completions();
+                        async_rt::sched::yield_().await;
+                    }
+                });
+            });
+        }
+    }
+
+    lazy_static::lazy_static! {
+        static ref IO_URING: IoUring = IoUringBuilder::new().build(4096).unwrap();
+    }
+
+    impl Runtime for TestRuntime {
+        fn io_uring() -> &'static IoUring {
+            &*IO_URING
+        }
+    }
+
+    pub(crate) type TestStream = StreamSocket<UnixAddr, TestRuntime>;
+
+    /// Run a test with a pair of connected stream sockets on the test runtime.
+    pub(crate) fn with_stream_pair<F, Fut>(nonblocking: bool, f: F)
+    where
+        F: FnOnce(TestStream, TestStream) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        TestRuntime::init();
+
+        async_rt::task::block_on(async move {
+            let (socket1, socket2) = TestStream::new_pair(nonblocking).unwrap();
+            f(socket1, socket2).await;
+        });
+    }
+}
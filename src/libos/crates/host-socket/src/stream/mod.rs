@@ -1,12 +1,33 @@
 mod states;
 
-use self::states::{ConnectedStream, ConnectingStream, InitStream, ListenerStream};
+use std::time::Duration;
+
+pub use self::states::SendBufStats;
+
+use self::states::{
+    ConnectedStream, ConnectingStream, InitStream, ListenerStream, MAX_SEND_BUF_SIZE,
+    MIN_SEND_BUF_SIZE,
+};
 use crate::common::Common;
 use crate::ioctl::*;
 use crate::prelude::*;
 use crate::runtime::Runtime;
 use crate::sockopt::*;
 
+/// The default timeout of waiting for the send buffer to be flushed when closing a socket.
+pub const DEFAULT_CLOSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The config of TCP keepalive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// The idle time before the first keepalive probe is sent.
+    pub idle: Duration,
+    /// The interval between keepalive probes.
+    pub interval: Duration,
+    /// The number of unacknowledged probes before the connection is dropped.
+    pub count: u32,
+}
+
 pub struct StreamSocket<A: Addr + 'static, R: Runtime> {
     state: RwLock<State<A, R>>,
 }
@@ -98,6 +119,16 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
     }
 
     pub async fn connect(&self, peer_addr: &A) -> Result<()> {
+        self.connect_timeout(peer_addr, None).await
+    }
+
+    /// Connect to the peer address, returning `ETIMEDOUT` if the connection is
+    /// not established before the timeout expires.
+    pub async fn connect_timeout(
+        &self,
+        peer_addr: &A,
+        timeout: Option<&mut Duration>,
+    ) -> Result<()> {
         // Create the new intermediate state of connecting and save the
         // old state of init in case of failure to connect.
         let (init_stream, connecting_stream) = {
@@ -124,19 +155,20 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
             }
         };
 
-        let res = connecting_stream.connect().await;
+        let res = connecting_stream.connect(timeout).await;
 
         // If success, then the state transits to connected; otherwise,
         // the state is restored to the init state.
         match &res {
             Ok(()) => {
+                // Hold the lock while creating the connected stream so that
+                // the send buffer size set concurrently is not missed
+                let mut state = self.state.write().unwrap();
                 let connected_stream = {
                     let common = init_stream.common().clone();
                     common.set_peer_addr(peer_addr);
                     ConnectedStream::new(common)
                 };
-
-                let mut state = self.state.write().unwrap();
                 *state = State::Connected(connected_stream);
             }
             Err(_) => {
@@ -257,6 +289,83 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
         }
     }
 
+    /// Returns the fill level of the send buffer inside the enclave.
+    pub fn send_buffer_stats(&self) -> Result<SendBufStats> {
+        let state = self.state.read().unwrap();
+        match &*state {
+            State::Connected(connected_stream) => Ok(connected_stream.send_buffer_stats()),
+            _ => return_errno!(ENOTCONN, "the socket is not connected"),
+        }
+    }
+
+    /// Set whether to disable Nagle's algorithm, i.e., the `TCP_NODELAY` option.
+    ///
+    /// Note that the option only affects the host socket. The data written to
+    /// the socket are first buffered inside the enclave and then sent to the
+    /// host socket, where multiple writes may be combined (see
+    /// `crate::config::set_send_batch`) regardless of this option.
+    pub fn set_nodelay(&self, nodelay: bool) -> Result<()> {
+        self.set_int_opt(libc::IPPROTO_TCP, libc::TCP_NODELAY, nodelay as i32)
+    }
+
+    /// Returns whether Nagle's algorithm is disabled on the host socket.
+    pub fn nodelay(&self) -> Result<bool> {
+        Ok(self.int_opt(libc::IPPROTO_TCP, libc::TCP_NODELAY)? != 0)
+    }
+
+    /// Enable TCP keepalive with the given config, or disable it if the config
+    /// is None, i.e., the `SO_KEEPALIVE` option and the `TCP_KEEP*` options.
+    ///
+    /// Like `set_nodelay`, the options only affect the host socket.
+    pub fn set_keepalive(&self, config: Option<KeepaliveConfig>) -> Result<()> {
+        let config = match config {
+            Some(config) => config,
+            None => return self.set_int_opt(libc::SOL_SOCKET, libc::SO_KEEPALIVE, 0),
+        };
+        self.set_int_opt(
+            libc::IPPROTO_TCP,
+            libc::TCP_KEEPIDLE,
+            config.idle.as_secs() as i32,
+        )?;
+        self.set_int_opt(
+            libc::IPPROTO_TCP,
+            libc::TCP_KEEPINTVL,
+            config.interval.as_secs() as i32,
+        )?;
+        self.set_int_opt(libc::IPPROTO_TCP, libc::TCP_KEEPCNT, config.count as i32)?;
+        self.set_int_opt(libc::SOL_SOCKET, libc::SO_KEEPALIVE, 1)
+    }
+
+    /// Returns the keepalive config of the host socket, which is None if
+    /// keepalive is disabled.
+    pub fn keepalive(&self) -> Result<Option<KeepaliveConfig>> {
+        if self.int_opt(libc::SOL_SOCKET, libc::SO_KEEPALIVE)? == 0 {
+            return Ok(None);
+        }
+        let idle = self.int_opt(libc::IPPROTO_TCP, libc::TCP_KEEPIDLE)?;
+        let interval = self.int_opt(libc::IPPROTO_TCP, libc::TCP_KEEPINTVL)?;
+        let count = self.int_opt(libc::IPPROTO_TCP, libc::TCP_KEEPCNT)?;
+        Ok(Some(KeepaliveConfig {
+            idle: Duration::from_secs(idle as u64),
+            interval: Duration::from_secs(interval as u64),
+            count: count as u32,
+        }))
+    }
+
+    fn set_int_opt(&self, level: i32, optname: i32, optval: i32) -> Result<()> {
+        let mut cmd = SetSockOptRawCmd::new(level, optname, &optval.to_ne_bytes());
+        cmd.execute(self.host_fd())
+    }
+
+    fn int_opt(&self, level: i32, optname: i32) -> Result<i32> {
+        let optlen = std::mem::size_of::<i32>() as u32;
+        let mut cmd = GetSockOptRawCmd::new(level, optname, optlen);
+        cmd.execute(self.host_fd())?;
+        let mut bytes = [0_u8; 4];
+        bytes.copy_from_slice(cmd.output().unwrap());
+        Ok(i32::from_ne_bytes(bytes))
+    }
+
     pub fn ioctl(&self, cmd: &mut dyn IoctlCmd) -> Result<()> {
         async_io::match_ioctl_cmd_mut!(&mut *cmd, {
             cmd: GetSockOptRawCmd => {
@@ -264,6 +373,11 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
             },
             cmd: SetSockOptRawCmd => {
                 cmd.execute(self.host_fd())?;
+                if cmd.level() == libc::SOL_SOCKET
+                    && cmd.optname() == SockOptName::SO_SNDBUF.into()
+                {
+                    self.set_send_buf_size(cmd.optval())?;
+                }
             },
             cmd: GetAcceptConnCmd => {
                 let mut is_listen = false;
@@ -324,6 +438,76 @@ impl<A: Addr, R: Runtime> StreamSocket<A, R> {
         connected_stream.shutdown(shutdown)
     }
 
+    /// Close the socket gracefully.
+    ///
+    /// For a connected socket in blocking mode, this waits until the data in
+    /// the send buffer are handed to the host, so that the last writes of an
+    /// application are not lost if it exits right after closing the socket.
+    /// The wait is bounded by the linger timeout if SO_LINGER is enabled, or
+    /// `DEFAULT_CLOSE_TIMEOUT` otherwise. A zero linger timeout skips the wait.
+    pub async fn close(&self) -> Result<()> {
+        let connected_stream = {
+            let state = self.state.read().unwrap();
+            match &*state {
+                State::Connected(connected_stream) => connected_stream.clone(),
+                _ => return Ok(()),
+            }
+        };
+        if connected_stream.common().nonblocking() {
+            return Ok(());
+        }
+
+        let mut timeout = match self.linger()? {
+            Some(linger) => linger,
+            None => DEFAULT_CLOSE_TIMEOUT,
+        };
+        if timeout.as_nanos() == 0 {
+            return Ok(());
+        }
+        connected_stream.flush(&mut timeout).await
+    }
+
+    // Resize the send buffer inside the enclave according to the value of SO_SNDBUF.
+    //
+    // The value is clamped to be within `MIN_SEND_BUF_SIZE` and `MAX_SEND_BUF_SIZE`.
+    // For a socket that is not connected yet, the size takes effect once connected.
+    fn set_send_buf_size(&self, optval: &[u8]) -> Result<()> {
+        if optval.len() < std::mem::size_of::<i32>() {
+            return_errno!(EINVAL, "the optval of SO_SNDBUF is too short");
+        }
+        let mut bytes = [0_u8; 4];
+        bytes.copy_from_slice(&optval[..4]);
+        let size = (i32::from_ne_bytes(bytes).max(0) as usize)
+            .max(MIN_SEND_BUF_SIZE)
+            .min(MAX_SEND_BUF_SIZE);
+
+        let state = self.state.read().unwrap();
+        state.common().set_send_buf_size(size);
+        if let State::Connected(connected_stream) = &*state {
+            connected_stream.resize_send_buf(size);
+        }
+        Ok(())
+    }
+
+    // Get the linger timeout of the socket, which is None if SO_LINGER is disabled.
+    fn linger(&self) -> Result<Option<Duration>> {
+        let mut cmd = GetSockOptRawCmd::new(
+            libc::SOL_SOCKET,
+            SockOptName::SO_LINGER.into(),
+            std::mem::size_of::<libc::linger>() as u32,
+        );
+        cmd.execute(self.host_fd())?;
+        let output = cmd.output().unwrap();
+        if output.len() < std::mem::size_of::<libc::linger>() {
+            return_errno!(EINVAL, "the host returns an invalid linger");
+        }
+        let linger = unsafe { std::ptr::read_unaligned(output.as_ptr() as *const libc::linger) };
+        if linger.l_onoff == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Duration::from_secs(linger.l_linger.max(0) as u64)))
+    }
+
     fn cancel_requests(&self) {
         let state = self.state.read().unwrap();
         match &*state {
@@ -1,4 +1,5 @@
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use io_uring_callback::{Fd, IoHandle};
 use sgx_untrusted_alloc::UntrustedBox;
@@ -34,7 +35,11 @@ impl<A: Addr + 'static, R: Runtime> ConnectingStream<A, R> {
     }
 
     /// Connect to the peer address.
-    pub async fn connect(self: &Arc<Self>) -> Result<()> {
+    ///
+    /// If the connection is not established before the timeout expires, the
+    /// async connect is cancelled and `ETIMEDOUT` is returned. If the connect
+    /// completes before it can be cancelled, its result is returned instead.
+    pub async fn connect(self: &Arc<Self>, mut timeout: Option<&mut Duration>) -> Result<()> {
         let pollee = self.common.pollee();
         pollee.reset_events();
 
@@ -42,20 +47,46 @@ impl<A: Addr + 'static, R: Runtime> ConnectingStream<A, R> {
 
         // Wait for the async connect to complete
         let mut poller = Poller::new();
+        let mut is_timeout = false;
         loop {
             let events = pollee.poll(Events::OUT, Some(&mut poller));
             if !events.is_empty() {
                 break;
             }
-            poller.wait().await?;
+            if let Err(e) = poller.wait_timeout(timeout.as_deref_mut()).await {
+                if e.errno() != ETIMEDOUT {
+                    return Err(e);
+                }
+
+                // The connect may still succeed or fail before it is cancelled.
+                // So wait for its callback to tell which case it is.
+                self.cancel_requests();
+                self.wait_for_callback(&mut poller).await;
+                is_timeout = true;
+                break;
+            }
         }
 
         // Finish the async connect
         let req = self.req.lock().unwrap();
-        if let Some(e) = req.errno {
-            return_errno!(e, "connect failed");
+        match req.errno {
+            None => Ok(()),
+            Some(ECANCELED) if is_timeout => {
+                return_errno!(ETIMEDOUT, "connect timed out");
+            }
+            Some(e) => {
+                return_errno!(e, "connect failed");
+            }
+        }
+    }
+
+    // Wait until the callback of the cancelled connect is invoked. Signals are
+    // ignored here, as cancelling a request completes shortly.
+    async fn wait_for_callback(&self, poller: &mut Poller) {
+        let pollee = self.common.pollee();
+        while pollee.poll(Events::OUT, Some(poller)).is_empty() {
+            let _ = poller.wait().await;
         }
-        Ok(())
     }
 
     fn initiate_async_connect(self: &Arc<Self>) {
@@ -93,6 +124,14 @@ impl<A: Addr + 'static, R: Runtime> ConnectingStream<A, R> {
         req.io_handle = Some(io_handle);
     }
 
+    fn cancel_requests(&self) {
+        let req = self.req.lock().unwrap();
+        if let Some(io_handle) = &req.io_handle {
+            let io_uring = self.common.io_uring();
+            unsafe { io_uring.cancel(io_handle) };
+        }
+    }
+
     #[allow(dead_code)]
     pub fn peer_addr(&self) -> &A {
         &self.peer_addr
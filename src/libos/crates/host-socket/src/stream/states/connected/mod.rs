@@ -7,7 +7,12 @@ use crate::runtime::Runtime;
 mod recv;
 mod send;
 
+pub use self::send::SendBufStats;
+
 pub const SEND_BUF_SIZE: usize = 32 * 1024;
+// The bounds of the send buffer size that can be set via SO_SNDBUF
+pub const MIN_SEND_BUF_SIZE: usize = 4 * 1024;
+pub const MAX_SEND_BUF_SIZE: usize = 16 * 1024 * 1024;
 pub const RECV_BUF_SIZE: usize = 32 * 1024;
 
 pub struct ConnectedStream<A: Addr + 'static, R: Runtime> {
@@ -21,7 +26,7 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         common.pollee().reset_events();
         common.pollee().add_events(Events::OUT);
 
-        let sender = Sender::new();
+        let sender = Sender::new(common.send_buf_size().unwrap_or(SEND_BUF_SIZE));
         let receiver = Receiver::new();
         let new_self = Arc::new(Self {
             common,
@@ -34,7 +34,15 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
                 Ok((received_size, index, offset)) => {
                     total_received += received_size;
 
-                    if !flags.contains(RecvFlags::MSG_WAITALL) || total_received == total_len {
+                    // Even with MSG_WAITALL, a partial read is returned upon EOF,
+                    // which is the only case where zero bytes are received. And
+                    // MSG_WAITALL has no effect on MSG_PEEK since the peeked data
+                    // are not consumed.
+                    if !flags.contains(RecvFlags::MSG_WAITALL)
+                        || flags.contains(RecvFlags::MSG_PEEK)
+                        || total_received == total_len
+                        || received_size == 0
+                    {
                         return Ok(total_received);
                     } else {
                         // save the index and offset for the next round
@@ -44,12 +52,19 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
                 }
                 Err(e) => {
                     if e.errno() != EAGAIN {
+                        // Do not lose the data that have been received
+                        if total_received > 0 {
+                            return Ok(total_received);
+                        }
                         return Err(e);
                     }
                 }
             };
 
             if self.common.nonblocking() || flags.contains(RecvFlags::MSG_DONTWAIT) {
+                if total_received > 0 {
+                    return Ok(total_received);
+                }
                 return_errno!(EAGAIN, "no data are present to be received");
             }
 
@@ -75,7 +90,9 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         let mut inner = self.receiver.inner.lock().unwrap();
 
         if !flags.is_empty()
-            && flags.intersects(!(RecvFlags::MSG_DONTWAIT | RecvFlags::MSG_WAITALL))
+            && flags.intersects(
+                !(RecvFlags::MSG_DONTWAIT | RecvFlags::MSG_WAITALL | RecvFlags::MSG_PEEK),
+            )
         {
             todo!("Support other flags: {:?}", flags);
         }
@@ -87,7 +104,14 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
 
             // save the received data from bufs[iov_buffer_index][iov_buffer_offset..]
             for (_, buf) in bufs.iter_mut().skip(iov_buffer_index).enumerate() {
-                let this_consumed = inner.recv_buf.consume(&mut buf[iov_buffer_offset..]);
+                let this_consumed = if flags.contains(RecvFlags::MSG_PEEK) {
+                    // Copy the data without consuming them
+                    inner
+                        .recv_buf
+                        .peek(&mut buf[iov_buffer_offset..], total_consumed)
+                } else {
+                    inner.recv_buf.consume(&mut buf[iov_buffer_offset..])
+                };
                 if this_consumed == 0 {
                     break;
                 }
@@ -208,7 +232,7 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
 
     pub fn bytes_to_consume(self: &Arc<Self>) -> usize {
         let inner = self.receiver.inner.lock().unwrap();
-        inner.recv_buf.consumable()
+        inner.recv_buf.len()
     }
 }
 
@@ -346,3 +370,79 @@ impl Clone for RecvReq {
         *self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::runtime::tests::with_stream_pair;
+
+    #[test]
+    fn recv_with_msg_waitall() {
+        with_stream_pair(false, |sender, receiver| async move {
+            // The data arrive in two bursts
+            sender.write(b"first;").await.unwrap();
+            let handle = async_rt::task::spawn(async move {
+                let mut buf = [0_u8; 12];
+                let len = receiver
+                    .recvmsg(&mut [&mut buf[..]], RecvFlags::MSG_WAITALL)
+                    .await
+                    .unwrap();
+                (len, buf)
+            });
+            std::thread::sleep(Duration::from_millis(50));
+            sender.write(b"second").await.unwrap();
+
+            let (len, buf) = handle.await;
+            assert!(len == buf.len());
+            assert!(&buf == b"first;second");
+        });
+    }
+
+    #[test]
+    fn recv_with_msg_peek() {
+        with_stream_pair(false, |sender, receiver| async move {
+            sender.write(b"GET /index.html").await.unwrap();
+
+            // Peek twice, then read normally
+            let mut bufs = [[0_u8; 4]; 3];
+            for (i, buf) in bufs.iter_mut().enumerate() {
+                let flags = if i < 2 {
+                    RecvFlags::MSG_PEEK | RecvFlags::MSG_WAITALL
+                } else {
+                    RecvFlags::MSG_WAITALL
+                };
+                let len = receiver.recvmsg(&mut [&mut buf[..]], flags).await.unwrap();
+                assert!(len == 4);
+            }
+            assert!(bufs.iter().all(|buf| buf == b"GET "));
+
+            // The peeked data have been consumed by the normal read
+            let mut buf = [0_u8; 11];
+            let len = receiver
+                .recvmsg(&mut [&mut buf[..]], RecvFlags::MSG_WAITALL)
+                .await
+                .unwrap();
+            assert!(&buf[..len] == b"/index.html");
+        });
+    }
+
+    #[test]
+    fn recv_with_msg_waitall_until_eof() {
+        with_stream_pair(false, |sender, receiver| async move {
+            sender.write(b"partial").await.unwrap();
+            // Closing the sender makes the receiver see EOF
+            sender.close().await.unwrap();
+            drop(sender);
+
+            // The partial data are returned upon EOF
+            let mut buf = [0_u8; 16];
+            let len = receiver
+                .recvmsg(&mut [&mut buf[..]], RecvFlags::MSG_WAITALL)
+                .await
+                .unwrap();
+            assert!(&buf[..len] == b"partial");
+        });
+    }
+}
@@ -1,7 +1,8 @@
 use std::mem::MaybeUninit;
 use std::ptr::{self};
+use std::time::Duration;
 
-use io_uring_callback::{Fd, IoHandle};
+use io_uring_callback::{Fd, IoHandle, TimeoutFlags, Timespec};
 use log::error;
 use sgx_untrusted_alloc::{MaybeUntrusted, UntrustedBox};
 
@@ -64,7 +65,9 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         let mut inner = self.sender.inner.lock().unwrap();
 
         if !flags.is_empty()
-            && flags.intersects(!(SendFlags::MSG_DONTWAIT | SendFlags::MSG_NOSIGNAL))
+            && flags.intersects(
+                !(SendFlags::MSG_DONTWAIT | SendFlags::MSG_NOSIGNAL | SendFlags::MSG_MORE),
+            )
         {
             error!("Not supported flags: {:?}", flags);
             return_errno!(EINVAL, "not supported flags");
@@ -109,10 +112,17 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
             // Mark the socket as non-writable
             self.common.pollee().del_events(Events::OUT);
         }
+        if nbytes > 0 {
+            // Mark the send buffer as not flushed
+            self.sender.flush_pollee.del_events(Events::OUT);
+            // Hint the host that more data are coming, until a write without MSG_MORE
+            inner.more = flags.contains(SendFlags::MSG_MORE);
+        }
 
-        // Since the send buffer is not empty, we can try to flush the buffer
+        // Since the send buffer is not empty, we can try to flush the buffer.
+        // If a send is in flight, the data will be sent together upon its completion.
         if inner.io_handle.is_none() {
-            self.do_send(&mut inner);
+            self.do_send_or_defer(&mut inner);
         }
 
         if nbytes > 0 {
@@ -122,6 +132,41 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
         }
     }
 
+    /// Send the buffered data right now if they are enough to make a batch, or
+    /// defer the send for a while so that more small writes can be combined.
+    fn do_send_or_defer(self: &Arc<Self>, inner: &mut MutexGuard<Inner>) {
+        let (min_bytes, max_delay) = crate::config::send_batch();
+        if inner.send_buf.len() >= min_bytes {
+            self.do_send(inner);
+            return;
+        }
+        if inner.defer_handle.is_some() {
+            // The deferred send has been scheduled
+            return;
+        }
+
+        // Init the callback invoked upon the expiration of the timeout
+        let stream = self.clone();
+        let complete_fn = move |_retval: i32| {
+            let mut inner = stream.sender.inner.lock().unwrap();
+
+            inner.defer_handle.take();
+
+            // The buffered data may have been sent as a part of a larger batch
+            if inner.io_handle.is_some() || inner.send_buf.is_empty() || inner.fatal.is_some() {
+                return;
+            }
+            stream.do_send(&mut inner);
+        };
+
+        // Submit the timeout to io_uring
+        let timespec_ptr = inner.new_defer_req(max_delay);
+        let io_uring = self.common.io_uring();
+        let handle =
+            unsafe { io_uring.timeout(timespec_ptr, 0, TimeoutFlags::empty(), complete_fn) };
+        inner.defer_handle.replace(handle);
+    }
+
     fn do_send(self: &Arc<Self>, inner: &mut MutexGuard<Inner>) {
         // This function can also be called even if the socket is set to shutdown by shutdown syscall. This is due to the
         // async behaviour that the kernel may return to user before actually issuing the request. We should
@@ -145,6 +190,8 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
                 let errno = Errno::from(-retval as u32);
                 inner.fatal = Some(errno);
                 stream.common.pollee().add_events(Events::ERR);
+                // The data in the send buffer will never be flushed, so stop waiting for it
+                stream.sender.flush_pollee.add_events(Events::OUT);
                 return;
             }
             assert!(retval != 0);
@@ -157,39 +204,137 @@ impl<A: Addr + 'static, R: Runtime> ConnectedStream<A, R> {
             // ready to write.
             stream.common.pollee().add_events(Events::OUT);
 
+            // The host no longer accesses the send buffer, so it can be resized now
+            stream.do_resize_send_buf(&mut inner);
+
             // Attempt to send again if there are available data in the buf.
             if !inner.send_buf.is_empty() {
                 stream.do_send(&mut inner);
-            } else if inner.is_shutdown == ShutdownStatus::PreShutdown {
-                inner.is_shutdown = ShutdownStatus::PostShutdown
+            } else {
+                stream.sender.flush_pollee.add_events(Events::OUT);
+                if inner.is_shutdown == ShutdownStatus::PreShutdown {
+                    inner.is_shutdown = ShutdownStatus::PostShutdown
+                }
             }
         };
 
         // Generate the async send request
         let msghdr_ptr = inner.new_send_req();
+        #[cfg(test)]
+        tests::NUM_SEND_REQS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
         // Submit the async send to io_uring
         let io_uring = self.common.io_uring();
         let host_fd = Fd(self.common.host_fd() as _);
-        let handle = unsafe { io_uring.sendmsg(host_fd, msghdr_ptr, 0, complete_fn) };
+        let flags = if inner.more { libc::MSG_MORE as u32 } else { 0 };
+        let handle = unsafe { io_uring.sendmsg(host_fd, msghdr_ptr, flags, complete_fn) };
         inner.io_handle.replace(handle);
     }
+
+    /// Returns the fill level of the send buffer.
+    pub fn send_buffer_stats(&self) -> SendBufStats {
+        self.sender.stats()
+    }
+
+    /// Resize the send buffer, preserving the data in it.
+    ///
+    /// If a send is in flight, the host may still be reading the send buffer.
+    /// So the resizing is postponed until the send completes.
+    pub fn resize_send_buf(&self, size: usize) {
+        let mut inner = self.sender.inner.lock().unwrap();
+        inner.new_send_buf_size = Some(size);
+        if inner.io_handle.is_none() {
+            self.do_resize_send_buf(&mut inner);
+        }
+    }
+
+    fn do_resize_send_buf(&self, inner: &mut MutexGuard<Inner>) {
+        debug_assert!(inner.io_handle.is_none());
+        let size = match inner.new_send_buf_size.take() {
+            Some(size) => size,
+            None => return,
+        };
+
+        inner.send_buf.resize(size);
+        if inner.send_buf.is_full() {
+            self.common.pollee().del_events(Events::OUT);
+        } else {
+            self.common.pollee().add_events(Events::OUT);
+        }
+    }
+
+    /// Wait until all the data in the send buffer are sent to the host, or the
+    /// timeout expires.
+    pub async fn flush(self: &Arc<Self>, timeout: &mut Duration) -> Result<()> {
+        // Initialize the poller only when needed
+        let mut poller = None;
+        loop {
+            {
+                let inner = self.sender.inner.lock().unwrap();
+                if let Some(errno) = inner.fatal {
+                    return_errno!(errno, "the data in the send buffer are lost");
+                }
+                if inner.send_buf.is_empty() {
+                    return Ok(());
+                }
+            }
+
+            if poller.is_none() {
+                poller = Some(Poller::new());
+            }
+            let mask = Events::OUT;
+            let events = self.sender.flush_pollee.poll(mask, poller.as_mut());
+            if events.is_empty() {
+                poller.as_ref().unwrap().wait_timeout(Some(timeout)).await?;
+            }
+        }
+    }
 }
 
 pub struct Sender {
     inner: Mutex<Inner>,
+    // The events of this pollee contain OUT if and only if the send buffer has
+    // been flushed (or can never be flushed due to errors).
+    flush_pollee: Pollee,
 }
 
 impl Sender {
-    pub fn new() -> Self {
-        let inner = Mutex::new(Inner::new());
-        Self { inner }
+    pub fn new(send_buf_size: usize) -> Self {
+        let inner = Mutex::new(Inner::new(send_buf_size));
+        let flush_pollee = Pollee::new(Events::OUT);
+        Self {
+            inner,
+            flush_pollee,
+        }
     }
 
     pub fn shutdown(&self) {
         let mut inner = self.inner.lock().unwrap();
         inner.is_shutdown = ShutdownStatus::PreShutdown;
     }
+
+    pub fn stats(&self) -> SendBufStats {
+        let inner = self.inner.lock().unwrap();
+        SendBufStats {
+            capacity: inner.send_buf.capacity(),
+            used: inner.send_buf.len(),
+            is_shutdown: inner.is_shutdown(),
+        }
+    }
+}
+
+/// The fill level of the send buffer of a stream socket.
+///
+/// This allows the users to apply backpressure, e.g., to stop producing data
+/// when the buffer exceeds a watermark, instead of waiting for EAGAIN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendBufStats {
+    /// The max number of bytes that can be buffered.
+    pub capacity: usize,
+    /// The number of bytes that are buffered and not yet sent to the host.
+    pub used: usize,
+    /// Whether the write side of the socket has been shut down.
+    pub is_shutdown: bool,
 }
 
 impl std::fmt::Debug for Sender {
@@ -202,8 +347,15 @@ impl std::fmt::Debug for Sender {
 
 struct Inner {
     send_buf: UntrustedCircularBuf,
+    // The new size of the send buffer, which is applied when no send is in flight
+    new_send_buf_size: Option<usize>,
+    // Whether the last write is made with MSG_MORE
+    more: bool,
     send_req: UntrustedBox<SendReq>,
     io_handle: Option<IoHandle>,
+    defer_req: UntrustedBox<DeferReq>,
+    // The handle to the timeout of a deferred send
+    defer_handle: Option<IoHandle>,
     is_shutdown: ShutdownStatus,
     fatal: Option<Errno>,
 }
@@ -215,11 +367,15 @@ struct Inner {
 unsafe impl Send for Inner {}
 
 impl Inner {
-    pub fn new() -> Self {
+    pub fn new(send_buf_size: usize) -> Self {
         Self {
-            send_buf: UntrustedCircularBuf::with_capacity(super::SEND_BUF_SIZE),
+            send_buf: UntrustedCircularBuf::with_capacity(send_buf_size),
+            new_send_buf_size: None,
+            more: false,
             send_req: UntrustedBox::new_uninit(),
             io_handle: None,
+            defer_req: UntrustedBox::new_uninit(),
+            defer_handle: None,
             is_shutdown: ShutdownStatus::Running,
             fatal: None,
         }
@@ -251,34 +407,41 @@ impl Inner {
         msghdr_ptr
     }
 
+    /// Constructs a new timeout request for a deferred send, returning a pointer
+    /// to the timespec that is accessible by io_uring.
+    pub fn new_defer_req(&mut self, delay: Duration) -> *const Timespec {
+        self.defer_req.timespec = Timespec {
+            tv_sec: delay.as_secs() as _,
+            tv_nsec: delay.subsec_nanos() as _,
+        };
+        &self.defer_req.timespec
+    }
+
     fn gen_iovecs_from_send_buf(&mut self) -> ([libc::iovec; 2], usize) {
         let mut iovecs_len = 0;
         let mut iovecs = unsafe { MaybeUninit::<[libc::iovec; 2]>::uninit().assume_init() };
-        self.send_buf.with_consumer_view(|part0, part1| {
-            debug_assert!(part0.len() > 0);
-
-            iovecs[0] = libc::iovec {
-                iov_base: part0.as_ptr() as _,
-                iov_len: part0.len() as _,
-            };
-
-            iovecs[1] = if part1.len() > 0 {
-                iovecs_len = 2;
-                libc::iovec {
-                    iov_base: part1.as_ptr() as _,
-                    iov_len: part1.len() as _,
-                }
-            } else {
-                iovecs_len = 1;
-                libc::iovec {
-                    iov_base: ptr::null_mut(),
-                    iov_len: 0,
-                }
-            };
+        // Only access the consumer's buffer; the bytes are consumed on completion.
+        let (part0, part1) = self.send_buf.peek_contiguous();
+        debug_assert!(part0.len() > 0);
 
-            // Only access the consumer's buffer; zero bytes consumed for now.
-            0
-        });
+        iovecs[0] = libc::iovec {
+            iov_base: part0.as_ptr() as _,
+            iov_len: part0.len() as _,
+        };
+
+        iovecs[1] = if part1.len() > 0 {
+            iovecs_len = 2;
+            libc::iovec {
+                iov_base: part1.as_ptr() as _,
+                iov_len: part1.len() as _,
+            }
+        } else {
+            iovecs_len = 1;
+            libc::iovec {
+                iov_base: ptr::null_mut(),
+                iov_len: 0,
+            }
+        };
         debug_assert!(iovecs_len > 0);
         (iovecs, iovecs_len)
     }
@@ -288,7 +451,10 @@ impl std::fmt::Debug for Inner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Inner")
             .field("send_buf", &self.send_buf)
+            .field("new_send_buf_size", &self.new_send_buf_size)
+            .field("more", &self.more)
             .field("io_handle", &self.io_handle)
+            .field("defer_handle", &self.defer_handle)
             .field("is_shutdown", &self.is_shutdown)
             .field("fatal", &self.fatal)
             .finish()
@@ -313,9 +479,162 @@ impl Clone for SendReq {
     }
 }
 
+#[repr(C)]
+struct DeferReq {
+    timespec: Timespec,
+}
+
+// Safety. DeferReq is a C-style struct.
+unsafe impl MaybeUntrusted for DeferReq {}
+
 #[derive(Debug, PartialEq)]
 enum ShutdownStatus {
     Running,      // not shutdown
     PreShutdown,  // start the shutdown process, set by calling shutdown syscall
     PostShutdown, // shutdown process is done, set when the buffer is empty
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    use super::*;
+    use crate::runtime::tests::{with_stream_pair, TestRuntime, TestStream};
+    use crate::sockopt::{SetSockOptRawCmd, SockOptName};
+
+    // The number of send requests submitted to io_uring
+    pub(crate) static NUM_SEND_REQS: AtomicUsize = AtomicUsize::new(0);
+
+    // Set the write-combining policy until the guard is dropped, which restores
+    // the old policy
+    struct SendBatchGuard {
+        old_batch: (usize, Duration),
+    }
+
+    impl SendBatchGuard {
+        fn new(min_bytes: usize, max_delay: Duration) -> Self {
+            let old_batch = crate::config::send_batch();
+            crate::config::set_send_batch(min_bytes, max_delay);
+            Self { old_batch }
+        }
+    }
+
+    impl Drop for SendBatchGuard {
+        fn drop(&mut self) {
+            let (min_bytes, max_delay) = self.old_batch;
+            crate::config::set_send_batch(min_bytes, max_delay);
+        }
+    }
+
+    #[test]
+    fn combine_small_writes() {
+        const NUM_WRITES: usize = 100;
+        const WRITE_SIZE: usize = 10;
+        let max_delay = Duration::from_millis(20);
+        let _guard = SendBatchGuard::new(4096, max_delay);
+
+        with_stream_pair(false, |sender, receiver| async move {
+            // Many small back-to-back writes are combined into a few sends
+            let start = Instant::now();
+            let num_reqs_before = NUM_SEND_REQS.load(Ordering::Relaxed);
+            for _ in 0..NUM_WRITES {
+                sender.write(&[1_u8; WRITE_SIZE]).await.unwrap();
+            }
+            let mut buf = vec![0_u8; NUM_WRITES * WRITE_SIZE];
+            let mut recv_len = 0;
+            while recv_len < buf.len() {
+                recv_len += receiver.read(&mut buf[recv_len..]).await.unwrap();
+            }
+            let num_reqs = NUM_SEND_REQS.load(Ordering::Relaxed) - num_reqs_before;
+            assert!(num_reqs < NUM_WRITES / 10);
+            assert!(buf.iter().all(|b| *b == 1));
+
+            // The data are not delayed much beyond the max delay
+            assert!(start.elapsed() < max_delay + Duration::from_millis(500));
+
+            // A single small write is sent after the max delay, not held forever
+            let start = Instant::now();
+            sender.write(&[2_u8; WRITE_SIZE]).await.unwrap();
+            let recv_len = receiver.read(&mut buf).await.unwrap();
+            assert!(recv_len == WRITE_SIZE);
+            assert!(start.elapsed() < max_delay + Duration::from_millis(500));
+        });
+    }
+
+    #[test]
+    fn set_larger_send_buf() {
+        TestRuntime::init();
+
+        async_rt::task::block_on(async move {
+            let default_len = fill_send_buf(None).await;
+            let send_buf_size = 1024 * 1024;
+            let larger_len = fill_send_buf(Some(send_buf_size)).await;
+            assert!(larger_len >= send_buf_size - WRITE_SIZE);
+            assert!(larger_len > default_len);
+        });
+    }
+
+    #[test]
+    fn send_buffer_stats() {
+        let sender = Sender::new(1024);
+        let stats = sender.stats();
+        assert!(stats.capacity == 1023);
+        assert!(stats.used == 0);
+        assert!(!stats.is_shutdown);
+
+        // Fill the buffer partway
+        sender.inner.lock().unwrap().send_buf.produce(&[0_u8; 100]);
+        assert!(sender.stats().used == 100);
+
+        sender.shutdown();
+        assert!(sender.stats().is_shutdown);
+    }
+
+    #[test]
+    fn send_with_msg_more() {
+        with_stream_pair(false, |sender, receiver| async move {
+            // A header and a body with MSG_MORE, followed by a trailer without it
+            let chunks: [(&[u8], SendFlags); 3] = [
+                (b"header;", SendFlags::MSG_MORE),
+                (b"body;", SendFlags::MSG_MORE),
+                (b"trailer", SendFlags::empty()),
+            ];
+            for (chunk, flags) in chunks.iter() {
+                sender.sendmsg(&[*chunk], *flags).await.unwrap();
+            }
+
+            let expected = b"header;body;trailer";
+            let mut buf = vec![0_u8; expected.len()];
+            let mut recv_len = 0;
+            while recv_len < buf.len() {
+                recv_len += receiver.read(&mut buf[recv_len..]).await.unwrap();
+            }
+            assert!(&buf[..] == &expected[..]);
+        });
+    }
+
+    const WRITE_SIZE: usize = 1024;
+
+    // Write to a non-blocking socket until EAGAIN, returning the number of bytes written
+    async fn fill_send_buf(send_buf_size: Option<usize>) -> usize {
+        let (sender, _receiver) = TestStream::new_pair(true).unwrap();
+        if let Some(size) = send_buf_size {
+            let optval = (size as i32).to_ne_bytes();
+            let optname = SockOptName::SO_SNDBUF.into();
+            let mut cmd = SetSockOptRawCmd::new(libc::SOL_SOCKET, optname, &optval);
+            sender.ioctl(&mut cmd).unwrap();
+        }
+
+        let mut total_len = 0;
+        loop {
+            match sender.write(&[0_u8; WRITE_SIZE]).await {
+                Ok(len) => total_len += len,
+                Err(e) => {
+                    assert!(e.errno() == EAGAIN);
+                    return total_len;
+                }
+            }
+        }
+    }
+}
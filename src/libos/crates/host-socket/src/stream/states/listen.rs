@@ -99,6 +99,10 @@ impl<A: Addr + 'static, R: Runtime> ListenerStream<A, R> {
         let common = {
             let common = Arc::new(Common::with_host_fd(accepted_fd, Type::STREAM, nonblocking));
             common.set_peer_addr(&accepted_addr);
+            // Like the host socket, the accepted socket inherits the send buffer size
+            if let Some(send_buf_size) = self.common.send_buf_size() {
+                common.set_send_buf_size(send_buf_size);
+            }
             common
         };
         let accepted_stream = ConnectedStream::new(common);
@@ -4,6 +4,6 @@ mod init;
 mod listen;
 
 pub use self::connect::ConnectingStream;
-pub use self::connected::ConnectedStream;
+pub use self::connected::{ConnectedStream, SendBufStats, MAX_SEND_BUF_SIZE, MIN_SEND_BUF_SIZE};
 pub use self::init::InitStream;
 pub use self::listen::ListenerStream;
@@ -30,6 +30,19 @@ impl UntrustedCircularBuf {
         }
     }
 
+    /// Resize the buffer, preserving the bytes in it.
+    ///
+    /// The new buffer is made larger than `capacity` if needed to hold all the
+    /// bytes in the buffer.
+    pub fn resize(&mut self, capacity: usize) {
+        let capacity = capacity.max(self.len() + 1);
+        let mut new_self = Self::with_capacity(capacity);
+        let (part0, part1) = self.peek_contiguous();
+        new_self.produce(part0);
+        new_self.produce(part1);
+        *self = new_self;
+    }
+
     /// Produce some bytes.
     pub fn produce(&mut self, buf: &[u8]) -> usize {
         self.with_producer_view(|part0, part1| {
@@ -137,6 +150,18 @@ impl UntrustedCircularBuf {
     }
 
     pub fn with_consumer_view(&mut self, f: impl FnOnce(&[u8], &[u8]) -> usize) -> usize {
+        let (part0, part1) = self.peek_contiguous();
+        let bytes_consumed = f(part0, part1);
+        self.advance_consumer(bytes_consumed)
+    }
+
+    /// Returns the two contiguous segments of the consumable bytes, without
+    /// consuming them.
+    ///
+    /// The bytes in the first segment precede those in the second one. The
+    /// second segment is non-empty only if the consumable bytes wrap around
+    /// the end of the buffer.
+    pub fn peek_contiguous(&self) -> (&[u8], &[u8]) {
         let head = self.head;
         let tail = self.tail;
         let len = self.buf.len();
@@ -146,15 +171,41 @@ impl UntrustedCircularBuf {
         } else {
             (head..len, 0..tail)
         };
+        (&self.buf[range0], &self.buf[range1])
+    }
 
-        let part0 = &self.buf[range0];
-        let part1 = &self.buf[range1];
+    /// Copy some bytes out of the buffer without consuming them, skipping the
+    /// first `offset` consumable bytes.
+    pub fn peek(&self, buf: &mut [u8], offset: usize) -> usize {
+        let (part0, part1) = self.peek_contiguous();
+        let mut offset = offset;
+        let mut total_copied = 0;
+        for part in [part0, part1].iter() {
+            if offset >= part.len() {
+                offset -= part.len();
+                continue;
+            }
+            let part = &part[offset..];
+            offset = 0;
 
-        let bytes_consumed = f(part0, part1);
-        assert!(bytes_consumed <= self.consumable());
+            let len = part.len().min(buf.len() - total_copied);
+            buf[total_copied..total_copied + len].copy_from_slice(&part[..len]);
+            total_copied += len;
+        }
+        total_copied
+    }
 
-        self.head = (head + bytes_consumed) % len;
-        bytes_consumed
+    /// Consume some bytes that have been copied out of the buffer by the
+    /// caller, e.g., via the segments returned by `peek_contiguous`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the number of bytes is greater than the number of
+    /// consumable bytes.
+    pub fn advance_consumer(&mut self, nbytes: usize) -> usize {
+        assert!(nbytes <= self.consumable());
+        self.head = (self.head + nbytes) % self.buf.len();
+        nbytes
     }
 
     pub fn consumable(&self) -> usize {
@@ -169,10 +220,21 @@ impl UntrustedCircularBuf {
         }
     }
 
+    /// Returns the max number of bytes that can be buffered.
     pub fn capacity(&self) -> usize {
         self.buf.len() - 1
     }
 
+    /// Returns the number of bytes currently buffered.
+    pub fn len(&self) -> usize {
+        self.consumable()
+    }
+
+    /// Returns the number of bytes that can be produced before the buffer is full.
+    pub fn free_space(&self) -> usize {
+        self.producible()
+    }
+
     pub fn is_full(&self) -> bool {
         self.producible() == 0
     }
@@ -312,4 +374,152 @@ mod tests {
         let produce_len = cbuf.produce(&data[beg..end]);
         assert_eq!(produce_len, 0);
     }
+
+    #[test]
+    fn test_peek_and_advance() {
+        let capacity = 16;
+        let mut cbuf = UntrustedCircularBuf::with_capacity(capacity);
+        let data: Vec<u8> = (0..capacity as u8).collect();
+
+        // Move the head and tail close to the end of the buffer
+        assert!(cbuf.produce(&data[..12]) == 12);
+        assert!(cbuf.consume_without_copy(12) == 12);
+        let (part0, part1) = cbuf.peek_contiguous();
+        assert!(part0.is_empty() && part1.is_empty());
+
+        // Fill the buffer across the wrap boundary
+        assert!(cbuf.produce(&data[..10]) == 10);
+        let (part0, part1) = cbuf.peek_contiguous();
+        assert!(part0 == &data[..4]);
+        assert!(part1 == &data[4..10]);
+
+        // Peeking does not consume
+        let (part0, part1) = cbuf.peek_contiguous();
+        assert!(part0.len() + part1.len() == 10);
+        assert!(cbuf.consumable() == 10);
+
+        // Advance by partial amounts: within the first segment...
+        assert!(cbuf.advance_consumer(3) == 3);
+        let (part0, part1) = cbuf.peek_contiguous();
+        assert!(part0 == &data[3..4]);
+        assert!(part1 == &data[4..10]);
+
+        // ...across the wrap boundary...
+        assert!(cbuf.advance_consumer(2) == 2);
+        let (part0, part1) = cbuf.peek_contiguous();
+        assert!(part0 == &data[5..10]);
+        assert!(part1.is_empty());
+
+        // ...and up to the end of the consumable bytes
+        assert!(cbuf.advance_consumer(5) == 5);
+        assert!(cbuf.is_empty());
+        assert!(cbuf.producible() == capacity - 1);
+    }
+
+    #[test]
+    fn test_occupancy() {
+        let capacity = 100;
+        let mut cbuf = UntrustedCircularBuf::with_capacity(capacity);
+        let mut data = vec![0_u8; capacity * 2];
+        let check = |cbuf: &UntrustedCircularBuf, expected_len: usize| {
+            assert!(cbuf.len() == expected_len);
+            assert!(cbuf.free_space() == cbuf.capacity() - expected_len);
+            assert!(cbuf.is_empty() == (expected_len == 0));
+            assert!(cbuf.is_full() == (expected_len == cbuf.capacity()));
+        };
+        assert!(cbuf.capacity() == capacity - 1);
+        check(&cbuf, 0);
+
+        // (produce or consume, number of bytes, expected occupancy)
+        let ops = [
+            (true, 60, 60),
+            (false, 50, 10),
+            // Wrap around when producing
+            (true, 70, 80),
+            (false, 30, 50),
+            // Wrap around when consuming
+            (false, 45, 5),
+            // Produce more than the free space
+            (true, 150, 99),
+            (false, 99, 0),
+            // Consume more than the buffered bytes
+            (true, 33, 33),
+            (false, 50, 0),
+        ];
+        let mut expected_len = 0;
+        for (is_produce, nbytes, new_len) in ops.iter() {
+            if *is_produce {
+                let produced = cbuf.produce(&data[..*nbytes]);
+                assert!(produced == new_len - expected_len);
+            } else {
+                let consumed = cbuf.consume(&mut data[..*nbytes]);
+                assert!(consumed == expected_len - new_len);
+            }
+            expected_len = *new_len;
+            check(&cbuf, expected_len);
+        }
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut cbuf = UntrustedCircularBuf::with_capacity(16);
+        let data: Vec<u8> = (0..16).collect();
+
+        // Make the bytes wrap around the end of the buffer
+        assert!(cbuf.produce(&data[..12]) == 12);
+        assert!(cbuf.consume_without_copy(8) == 8);
+        assert!(cbuf.produce(&data[12..]) == 4);
+
+        let mut buf = [0_u8; 8];
+        assert!(cbuf.peek(&mut buf, 0) == 8);
+        assert!(buf == data[8..]);
+        // Across the wrap boundary with an offset
+        let mut buf = [0_u8; 4];
+        assert!(cbuf.peek(&mut buf, 2) == 4);
+        assert!(buf == data[10..14]);
+        // Beyond the end of the consumable bytes
+        assert!(cbuf.peek(&mut buf, 6) == 2);
+        assert!(buf[..2] == data[14..]);
+        assert!(cbuf.peek(&mut buf, 8) == 0);
+
+        // Peeking does not consume
+        assert!(cbuf.len() == 8);
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut cbuf = UntrustedCircularBuf::with_capacity(16);
+        let data: Vec<u8> = (0..16).collect();
+
+        // Make the bytes wrap around the end of the buffer
+        assert!(cbuf.produce(&data[..12]) == 12);
+        assert!(cbuf.consume_without_copy(8) == 8);
+        assert!(cbuf.produce(&data[12..]) == 4);
+        assert!(cbuf.len() == 8);
+
+        // Grow the buffer
+        cbuf.resize(64);
+        assert!(cbuf.capacity() == 63);
+        assert!(cbuf.len() == 8);
+        let mut buf = [0_u8; 8];
+        assert!(cbuf.consume(&mut buf) == 8);
+        assert!(buf == data[8..]);
+
+        // Shrink the buffer to be smaller than the bytes in it
+        assert!(cbuf.produce(&data) == 16);
+        cbuf.resize(4);
+        assert!(cbuf.len() == 16);
+        assert!(cbuf.is_full());
+        let mut buf = [0_u8; 16];
+        assert!(cbuf.consume(&mut buf) == 16);
+        assert!(buf == data[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_advance_too_much() {
+        let mut cbuf = UntrustedCircularBuf::with_capacity(16);
+        cbuf.produce(&[0; 4]);
+        cbuf.advance_consumer(5);
+    }
 }
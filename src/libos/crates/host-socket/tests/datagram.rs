@@ -0,0 +1,139 @@
+//! Test datagram sockets.
+
+use async_io::socket::{Ipv4Addr, Ipv4SocketAddr, MsgFlags, RecvFlags, SendFlags, UnixAddr};
+use host_socket::DatagramSocket;
+
+use self::runtime::SocketRuntime;
+
+#[test]
+fn recv_oversized_datagram() {
+    SocketRuntime::init(2);
+
+    async_rt::task::block_on(async move {
+        let (sender, receiver) =
+            DatagramSocket::<UnixAddr, SocketRuntime>::new_pair(false).unwrap();
+        let datagram: Vec<u8> = (0..100).collect();
+
+        // Without MSG_TRUNC, the length of the truncated data is returned
+        sender.write(&datagram).await.unwrap();
+        let mut buf = [0_u8; 40];
+        let (recv_len, _, msg_flags) = receiver
+            .recvmsg(&mut [&mut buf[..]], RecvFlags::empty())
+            .await
+            .unwrap();
+        assert_eq!(recv_len, buf.len());
+        assert_eq!(msg_flags, MsgFlags::MSG_TRUNC);
+        assert!(buf[..] == datagram[..buf.len()]);
+
+        // With MSG_TRUNC, the real length of the datagram is returned
+        sender.write(&datagram).await.unwrap();
+        let mut buf = [0_u8; 40];
+        let (recv_len, _, msg_flags) = receiver
+            .recvmsg(&mut [&mut buf[..]], RecvFlags::MSG_TRUNC)
+            .await
+            .unwrap();
+        assert_eq!(recv_len, datagram.len());
+        assert_eq!(msg_flags, MsgFlags::MSG_TRUNC);
+        assert!(buf[..] == datagram[..buf.len()]);
+
+        // The excess bytes are discarded with the datagram
+        let next_datagram = [42_u8; 10];
+        sender
+            .sendmsg(&[&next_datagram], None, SendFlags::empty())
+            .await
+            .unwrap();
+        let mut buf = [0_u8; 40];
+        let (recv_len, _, msg_flags) = receiver
+            .recvmsg(&mut [&mut buf[..]], RecvFlags::MSG_TRUNC)
+            .await
+            .unwrap();
+        assert_eq!(recv_len, next_datagram.len());
+        assert_eq!(msg_flags, MsgFlags::empty());
+        assert!(buf[..recv_len] == next_datagram[..]);
+    });
+}
+
+#[test]
+fn sendto_and_recvfrom() {
+    SocketRuntime::init(2);
+
+    async_rt::task::block_on(async move {
+        let localhost = Ipv4SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1), 0);
+        let socket1 = DatagramSocket::<Ipv4SocketAddr, SocketRuntime>::new(false).unwrap();
+        socket1.bind(&localhost).unwrap();
+        let addr1 = socket1.addr().unwrap();
+        let socket2 = DatagramSocket::<Ipv4SocketAddr, SocketRuntime>::new(false).unwrap();
+        socket2.bind(&localhost).unwrap();
+        let addr2 = socket2.addr().unwrap();
+
+        // Each datagram is received as a whole, along with the address of the peer
+        for i in 0..3_u8 {
+            let datagram = [i; 16];
+            let send_len = socket1
+                .sendto(&datagram, SendFlags::empty(), &addr2)
+                .await
+                .unwrap();
+            assert_eq!(send_len, datagram.len());
+
+            let mut buf = [0_u8; 64];
+            let (recv_len, src_addr) = socket2
+                .recvfrom(&mut buf, RecvFlags::empty())
+                .await
+                .unwrap();
+            assert_eq!(recv_len, datagram.len());
+            assert!(buf[..recv_len] == datagram[..]);
+            assert_eq!(src_addr, addr1);
+
+            // Echo the datagram back to the peer
+            socket2
+                .sendto(&buf[..recv_len], SendFlags::empty(), &src_addr)
+                .await
+                .unwrap();
+            let (recv_len, src_addr) = socket1
+                .recvfrom(&mut buf, RecvFlags::empty())
+                .await
+                .unwrap();
+            assert_eq!(recv_len, datagram.len());
+            assert_eq!(src_addr, addr2);
+        }
+    });
+}
+
+mod runtime {
+    use std::sync::Once;
+
+    use host_socket::Runtime;
+    use io_uring_callback::{Builder as IoUringBuilder, IoUring};
+
+    pub struct SocketRuntime;
+
+    impl SocketRuntime {
+        pub fn init(parallelism: u32) {
+            static INIT: Once = Once::new();
+            INIT.call_once(|| {
+                async_rt::config::set_parallelism(parallelism);
+
+                let ring = Self::io_uring();
+                unsafe {
+                    ring.start_enter_syscall_thread();
+                }
+                async_rt::task::spawn(async move {
+                    loop {
+                        ring.poll_completions();
+                        async_rt::sched::yield_().await;
+                    }
+                });
+            });
+        }
+    }
+
+    lazy_static::lazy_static! {
+        static ref IO_URING: IoUring = IoUringBuilder::new().build(4096).unwrap();
+    }
+
+    impl Runtime for SocketRuntime {
+        fn io_uring() -> &'static IoUring {
+            &*IO_URING
+        }
+    }
+}
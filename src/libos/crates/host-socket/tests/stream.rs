@@ -0,0 +1,105 @@
+//! Test stream sockets.
+
+use std::time::{Duration, Instant};
+
+use async_io::socket::{Ipv4Addr, Ipv4SocketAddr};
+use errno::prelude::*;
+use host_socket::{KeepaliveConfig, StreamSocket};
+
+use self::runtime::SocketRuntime;
+
+#[test]
+fn set_nodelay() {
+    SocketRuntime::init(2);
+
+    let socket = StreamSocket::<Ipv4SocketAddr, SocketRuntime>::new(false).unwrap();
+    assert_eq!(socket.nodelay().unwrap(), false);
+    socket.set_nodelay(true).unwrap();
+    assert_eq!(socket.nodelay().unwrap(), true);
+    socket.set_nodelay(false).unwrap();
+    assert_eq!(socket.nodelay().unwrap(), false);
+}
+
+#[test]
+fn set_keepalive() {
+    SocketRuntime::init(2);
+
+    let socket = StreamSocket::<Ipv4SocketAddr, SocketRuntime>::new(false).unwrap();
+    assert_eq!(socket.keepalive().unwrap(), None);
+
+    let config = KeepaliveConfig {
+        idle: Duration::from_secs(60),
+        interval: Duration::from_secs(10),
+        count: 5,
+    };
+    socket.set_keepalive(Some(config)).unwrap();
+    assert_eq!(socket.keepalive().unwrap(), Some(config));
+
+    socket.set_keepalive(None).unwrap();
+    assert_eq!(socket.keepalive().unwrap(), None);
+}
+
+#[test]
+fn connect_timeout() {
+    SocketRuntime::init(2);
+
+    async_rt::task::block_on(async move {
+        let socket = StreamSocket::<Ipv4SocketAddr, SocketRuntime>::new(false).unwrap();
+        // A non-routable address, to which the SYNs are dropped
+        let peer_addr = Ipv4SocketAddr::new(Ipv4Addr::new(10, 255, 255, 1), 9);
+        let timeout = Duration::from_millis(200);
+
+        let start = Instant::now();
+        let mut remaining = timeout;
+        let res = socket
+            .connect_timeout(&peer_addr, Some(&mut remaining))
+            .await;
+        let errno = res.unwrap_err().errno();
+        if errno == ENETUNREACH || errno == EHOSTUNREACH {
+            // No route to the address in this environment
+            return;
+        }
+        assert!(errno == ETIMEDOUT);
+        assert!(start.elapsed() >= timeout);
+        assert!(start.elapsed() < timeout + Duration::from_millis(500));
+    });
+}
+
+mod runtime {
+    use std::sync::Once;
+
+    use host_socket::Runtime;
+    use io_uring_callback::{Builder as IoUringBuilder, IoUring};
+
+    pub struct SocketRuntime;
+
+    impl SocketRuntime {
+        pub fn init(parallelism: u32) {
+            static INIT: Once = Once::new();
+            INIT.call_once(|| {
+                async_rt::config::set_parallelism(parallelism);
+
+                let ring = Self::io_uring();
+                unsafe {
+                    ring.start_enter_syscall_thread();
+                }
+                async_rt::task::spawn(async move {
+                    loop {
+                        ring.poll_completions();
+                        async_rt::sched::yield_().await;
+                    }
+                });
+            });
+        }
+    }
+
+    lazy_static::lazy_static! {
+        static ref IO_URING: IoUring = IoUringBuilder::new().build(4096).unwrap();
+    }
+
+    impl Runtime for SocketRuntime {
+        fn io_uring() -> &'static IoUring {
+            &*IO_URING
+        }
+    }
+}
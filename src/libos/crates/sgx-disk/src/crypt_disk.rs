@@ -1,4 +1,5 @@
-use block_device::{BioReq, BioReqBuilder, BioResp, BioSubmission, BioType, BlockBuf, BlockDevice};
+use block_device::util::new_merged_req_builder;
+use block_device::{BioReq, BioResp, BioSubmission, BioType, BlockDevice};
 #[cfg(feature = "sgx")]
 use sgx_tcrypto::{rsgx_rijndael128GCM_decrypt, rsgx_rijndael128GCM_encrypt};
 #[cfg(feature = "sgx")]
@@ -51,7 +52,7 @@ impl CryptDisk {
             }
         }
 
-        let new_req = Self::new_req_builder(&origin_req)
+        let new_req = new_merged_req_builder(&origin_req)
             .on_complete(on_complete)
             .ext(OriginReq::new(origin_req.clone()))
             .build();
@@ -68,7 +69,7 @@ impl CryptDisk {
             }
         }
 
-        let new_req = Self::new_req_builder(&origin_req)
+        let new_req = new_merged_req_builder(&origin_req)
             .on_complete(on_complete)
             .ext(OriginReq::new(origin_req.clone()))
             .build();
@@ -94,32 +95,6 @@ impl CryptDisk {
     fn do_flush(&self, req: Arc<BioReq>) -> BioSubmission {
         self.inner.submit(req)
     }
-
-    fn new_req_builder(origin_req: &Arc<BioReq>) -> BioReqBuilder {
-        fn new_merged_buf(origin_req: &Arc<BioReq>) -> BlockBuf {
-            origin_req.access_bufs_with(|bufs| {
-                let total_len = bufs.iter().map(|buf| buf.len()).sum();
-                let uninit_slice = Box::new_uninit_slice(total_len);
-                // Safety. The initiail content is not important now.
-                let boxed_slice = unsafe { uninit_slice.assume_init() };
-                BlockBuf::from_boxed(boxed_slice)
-            })
-        }
-
-        fn drop_merged_buf(_new_req: &BioReq, mut bufs: Vec<BlockBuf>) {
-            debug_assert!(bufs.len() == 1);
-            let block_buf = bufs.remove(0);
-            drop(unsafe { BlockBuf::into_boxed(block_buf) });
-        }
-
-        BioReqBuilder::new(origin_req.type_())
-            .addr(origin_req.addr())
-            .bufs({
-                let merged_buf = new_merged_buf(origin_req);
-                vec![merged_buf]
-            })
-            .on_drop(drop_merged_buf)
-    }
 }
 
 impl BlockDevice for CryptDisk {
@@ -141,6 +116,11 @@ impl BlockDevice for CryptDisk {
             match type_ {
                 BioType::Read => self.do_read(req),
                 BioType::Write => self.do_write(req),
+                // Discarding the ciphertext would break the decryption of the
+                // blocks, so the blocks are kept as they are
+                BioType::Discard => unsafe {
+                    req.complete(Ok(()));
+                },
                 _ => unreachable!(),
             };
 
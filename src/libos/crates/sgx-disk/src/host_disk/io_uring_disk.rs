@@ -252,6 +252,14 @@ impl<P: IoUringProvider> IoUringDisk<P> {
         Ok(())
     }
 
+    fn do_discard(&self, req: &Arc<BioReq>) -> Result<()> {
+        // Not supported yet. The blocks are left untouched.
+        unsafe {
+            req.complete(Ok(()));
+        }
+        Ok(())
+    }
+
     fn do_flush(&self, req: &Arc<BioReq>) -> Result<()> {
         if !self.0.can_write {
             return Err(errno!(EACCES, "flush is not allowed"));
@@ -326,6 +334,7 @@ impl<P: IoUringProvider> BlockDevice for IoUringDisk<P> {
             BioType::Read => self.do_read(req),
             BioType::Write => self.do_write(req),
             BioType::Flush => self.do_flush(req),
+            BioType::Discard => self.do_discard(req),
         };
 
         // If any error returns, then the request must have failed to submit. So
@@ -0,0 +1,187 @@
+//! A watchdog that bounds the time spent on the I/O operations of a host file.
+//!
+//! A hung host file system (e.g., a network mount or a failing disk) may block
+//! a synchronous I/O operation indefinitely. As such an operation cannot be
+//! interrupted, the watchdog performs all I/O operations of a host file in a
+//! dedicated worker thread, while the callers wait for the results with a
+//! timeout. If an operation does not complete in time, the caller gives up
+//! with an `ETIMEDOUT` error. The abandoned operation continues in the worker
+//! thread and its result (e.g., whether an abandoned write reaches the host
+//! file) is undefined.
+//!
+//! The operations are performed on buffers owned by the worker thread. So an
+//! abandoned operation can never touch the memory of a caller that has given up.
+
+#[cfg(test)]
+use core::sync::atomic::{AtomicBool, Ordering};
+use fs::File;
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::thread;
+use std::time::Duration;
+
+use crate::prelude::*;
+
+pub(crate) struct IoWatchdog {
+    job_sender: Mutex<Sender<Job>>,
+    timeout: Duration,
+    // Whether the worker thread is stalled on purpose in tests
+    #[cfg(test)]
+    stall: Arc<AtomicBool>,
+}
+
+struct Job {
+    op: HostIo,
+    resp_sender: SyncSender<Result<HostIoOutput>>,
+}
+
+enum HostIo {
+    Read { offset: usize, len: usize },
+    Write { offset: usize, data: Vec<u8> },
+    Flush,
+    SetLen(u64),
+    PunchHole { offset: usize, len: usize },
+}
+
+enum HostIoOutput {
+    Read(Vec<u8>),
+    Done,
+}
+
+impl IoWatchdog {
+    /// Start a worker thread that performs the I/O operations of the file.
+    ///
+    /// The worker thread exits when the watchdog is dropped and all the pending
+    /// operations are done.
+    pub fn new(file: File, timeout: Duration) -> Result<Self> {
+        let (job_sender, job_receiver) = mpsc::channel();
+        #[cfg(test)]
+        let stall = Arc::new(AtomicBool::new(false));
+        #[cfg(test)]
+        let worker_stall = stall.clone();
+        thread::Builder::new()
+            .name("sync_io_disk_io".to_string())
+            .spawn(move || {
+                Self::run_worker(
+                    file,
+                    job_receiver,
+                    #[cfg(test)]
+                    worker_stall,
+                )
+            })
+            .map_err(|_| errno!(EAGAIN, "failed to start the I/O worker thread"))?;
+        Ok(Self {
+            job_sender: Mutex::new(job_sender),
+            timeout,
+            #[cfg(test)]
+            stall,
+        })
+    }
+
+    pub fn read(&self, offset: usize, len: usize) -> Result<Vec<u8>> {
+        match self.exec(HostIo::Read { offset, len })? {
+            HostIoOutput::Read(data) => Ok(data),
+            HostIoOutput::Done => unreachable!(),
+        }
+    }
+
+    pub fn write(&self, offset: usize, data: Vec<u8>) -> Result<()> {
+        self.exec(HostIo::Write { offset, data }).map(|_| ())
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        self.exec(HostIo::Flush).map(|_| ())
+    }
+
+    pub fn set_len(&self, len: u64) -> Result<()> {
+        self.exec(HostIo::SetLen(len)).map(|_| ())
+    }
+
+    pub fn punch_hole(&self, offset: usize, len: usize) -> Result<()> {
+        self.exec(HostIo::PunchHole { offset, len }).map(|_| ())
+    }
+
+    /// Make the worker thread stalled (or not) before performing the next
+    /// operation, as if the host file were hung.
+    #[cfg(test)]
+    pub fn set_stalled(&self, stalled: bool) {
+        self.stall.store(stalled, Ordering::Release);
+    }
+
+    fn exec(&self, op: HostIo) -> Result<HostIoOutput> {
+        let (resp_sender, resp_receiver) = mpsc::sync_channel(1);
+        let job = Job { op, resp_sender };
+        self.job_sender
+            .lock()
+            .unwrap()
+            .send(job)
+            .map_err(|_| errno!(EIO, "the I/O worker thread has exited"))?;
+
+        match resp_receiver.recv_timeout(self.timeout) {
+            Ok(res) => res,
+            Err(RecvTimeoutError::Timeout) => {
+                Err(errno!(ETIMEDOUT, "the host I/O does not complete in time"))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(errno!(EIO, "the I/O worker thread has exited"))
+            }
+        }
+    }
+
+    fn run_worker(
+        mut file: File,
+        job_receiver: Receiver<Job>,
+        #[cfg(test)] stall: Arc<AtomicBool>,
+    ) {
+        for job in job_receiver.iter() {
+            // Simulate a hung host file in tests
+            #[cfg(test)]
+            while stall.load(Ordering::Acquire) {
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            let res = job.op.exec(&mut file);
+            // The caller may have given up waiting
+            let _ = job.resp_sender.send(res);
+        }
+    }
+}
+
+impl HostIo {
+    fn exec(self, file: &mut File) -> Result<HostIoOutput> {
+        match self {
+            HostIo::Read { offset, len } => {
+                let mut data = vec![0_u8; len];
+                file.seek(SeekFrom::Start(offset as u64))?;
+                file.read_exact(&mut data)?;
+                Ok(HostIoOutput::Read(data))
+            }
+            HostIo::Write { offset, data } => {
+                file.seek(SeekFrom::Start(offset as u64))?;
+                file.write_all(&data)?;
+                Ok(HostIoOutput::Done)
+            }
+            HostIo::Flush => {
+                file.sync_data()?;
+                Ok(HostIoOutput::Done)
+            }
+            HostIo::SetLen(len) => {
+                file.set_len(len)?;
+                Ok(HostIoOutput::Done)
+            }
+            HostIo::PunchHole { offset, len } => {
+                super::sync_io_disk::punch_hole(file, offset, len)?;
+                Ok(HostIoOutput::Done)
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for IoWatchdog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IoWatchdog")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
@@ -6,6 +6,7 @@
 
 mod host_disk;
 mod io_uring_disk;
+mod io_watchdog;
 mod open_options;
 mod sync_io_disk;
 
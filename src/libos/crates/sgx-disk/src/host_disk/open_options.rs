@@ -1,6 +1,7 @@
 use fs::OpenOptions as FileOpenOptions;
 use std::marker::PhantomData;
 use std::path::Path;
+use std::time::Duration;
 
 use crate::prelude::*;
 use crate::HostDisk;
@@ -15,7 +16,9 @@ pub struct OpenOptions<D> {
     clear: bool,
     create: bool,
     create_new: bool,
+    pub(crate) allow_shrink: bool,
     pub(crate) total_blocks: Option<usize>,
+    pub(crate) io_timeout: Option<Duration>,
     _phantom: PhantomData<D>,
 }
 
@@ -28,7 +31,9 @@ impl<D: HostDisk + Sized> OpenOptions<D> {
             clear: false,
             create: false,
             create_new: false,
+            allow_shrink: false,
             total_blocks: None,
+            io_timeout: None,
             _phantom: PhantomData,
         }
     }
@@ -66,12 +71,28 @@ impl<D: HostDisk + Sized> OpenOptions<D> {
         self
     }
 
+    /// Sets the option for allowing the host disk to be shrunk by
+    /// `BlockDevice::resize`, which discards the blocks beyond the new size.
+    pub fn allow_shrink(&mut self, allow_shrink: bool) -> &mut Self {
+        self.allow_shrink = allow_shrink;
+        self
+    }
+
     /// Sets the option for the size of the host disk in blocks.
     pub fn total_blocks(&mut self, total_blocks: usize) -> &mut Self {
         self.total_blocks = Some(total_blocks);
         self
     }
 
+    /// Sets the option for the timeout of each I/O operation on the host disk.
+    ///
+    /// An I/O operation that does not complete in time fails with `ETIMEDOUT`.
+    /// Not all types of host disks support this option.
+    pub fn io_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.io_timeout = Some(timeout);
+        self
+    }
+
     /// Opens a host disk at `path` with the options specified by `self`.
     pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<D> {
         // Try to capture input errors before creating a file on the host
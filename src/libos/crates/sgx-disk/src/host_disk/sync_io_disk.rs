@@ -2,12 +2,18 @@ use block_device::{BioReq, BioSubmission, BioType, BlockDevice};
 use fs::File;
 use std::io::prelude::*;
 use std::io::{IoSlice, IoSliceMut, SeekFrom};
+use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
+use super::io_watchdog::IoWatchdog;
 use super::OpenOptions;
 use crate::prelude::*;
 use crate::HostDisk;
 
+// The maximum number of buffers in a vectored I/O on Linux
+const IOV_MAX: usize = 1024;
+
 /// A type of host disk that implements a block device interface by performing
 /// normal synchronous I/O to the underlying host file.
 ///
@@ -18,75 +24,176 @@ use crate::HostDisk;
 /// system calls from the enclave triggers enclave switching, which is costly.
 ///
 /// It is recommended to use `IoUringDisk` for an optimal performance.
+///
+/// A `SyncIoDisk` can be resized at runtime by changing the length of the
+/// underlying host file. Shrinking the disk is only allowed if the disk is
+/// opened with `OpenOptions::allow_shrink`.
+///
+/// A `SyncIoDisk` opened for writing can also be made read-only temporarily
+/// (e.g., during a consistency check) with `set_read_only`.
+///
+/// Discarding blocks is supported by punching holes in the underlying host file,
+/// after which the blocks read back as zeros.
+///
+/// To protect the users from a hung host file system, a `SyncIoDisk` can be
+/// opened with `OpenOptions::io_timeout`. Then the I/O operations are performed
+/// in a worker thread and a request that does not complete in time is completed
+/// with `ETIMEDOUT`.
 #[derive(Debug)]
 pub struct SyncIoDisk {
     file: Mutex<File>,
     path: PathBuf,
-    total_blocks: usize,
+    // Only updated with the file lock held
+    total_blocks: AtomicUsize,
     can_read: bool,
     can_write: bool,
+    // Whether writes are rejected temporarily, regardless of can_write
+    read_only: AtomicBool,
+    allow_shrink: bool,
+    // Performs the I/O operations if an I/O timeout is specified
+    io_watchdog: Option<IoWatchdog>,
 }
 
 impl SyncIoDisk {
+    /// Sets whether the disk is read-only.
+    ///
+    /// While the disk is read-only, writes and resizing are rejected with
+    /// `EACCES`. Reads are not affected. Clearing the read-only state does
+    /// not grant write access to a disk that is not opened for writing.
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::Release);
+    }
+
+    /// Returns whether the disk is read-only.
+    pub fn is_read_only(&self) -> bool {
+        !self.can_write || self.read_only.load(Ordering::Acquire)
+    }
+
     fn do_read(&self, req: &Arc<BioReq>) -> Result<()> {
         if !self.can_read {
             return Err(errno!(EACCES, "read is not allowed"));
         }
 
-        let (offset, _) = self.get_range_in_bytes(&req)?;
+        if let Some(io_watchdog) = self.io_watchdog.as_ref() {
+            // Check the range with the file lock held to prevent concurrent resizing
+            let _file = self.file.lock().unwrap();
+            let (offset, end_offset) = self.get_range_in_bytes(&req)?;
+            let data = io_watchdog.read(offset, end_offset - offset)?;
+            req.access_mut_bufs_with(|bufs| {
+                let mut copied_len = 0;
+                for buf in bufs.iter_mut() {
+                    let buf = buf.as_slice_mut();
+                    buf.copy_from_slice(&data[copied_len..copied_len + buf.len()]);
+                    copied_len += buf.len();
+                }
+            });
+            return Ok(());
+        }
 
+        // Check the range with the file lock held to prevent concurrent resizing
         let mut file = self.file.lock().unwrap();
-        file.seek(SeekFrom::Start(offset as u64))?;
+        let (offset, _) = self.get_range_in_bytes(&req)?;
         let read_len = req.access_mut_bufs_with(|bufs| {
             let mut slices: Vec<IoSliceMut<'_>> = bufs
                 .iter_mut()
                 .map(|buf| IoSliceMut::new(buf.as_slice_mut()))
                 .collect();
 
-            // TODO: fix this limitation
-            const LINUX_IOVS_MAX: usize = 1024;
-            debug_assert!(slices.len() < LINUX_IOVS_MAX);
-
-            file.read_vectored(&mut slices)
+            // Linux limits the number of buffers of a vectored I/O
+            let mut read_len = 0;
+            for batch in slices.chunks_mut(IOV_MAX) {
+                let batch_len: usize = batch.iter().map(|slice| slice.len()).sum();
+                file.seek(SeekFrom::Start((offset + read_len) as u64))?;
+                // The range is within the file, so a short read is an error. Otherwise,
+                // the following batches would be read from the wrong offsets.
+                if file.read_vectored(batch)? != batch_len {
+                    return Err(errno!(EIO, "short read of the host disk"));
+                }
+                read_len += batch_len;
+            }
+            Ok::<_, Error>(read_len)
         })?;
         drop(file);
 
-        debug_assert!(read_len / BLOCK_SIZE == req.num_blocks());
+        if read_len != req.num_blocks() * BLOCK_SIZE {
+            return Err(errno!(EIO, "the read length does not match the request"));
+        }
         Ok(())
     }
 
     fn do_write(&self, req: &Arc<BioReq>) -> Result<()> {
-        if !self.can_write {
+        if self.is_read_only() {
             return Err(errno!(EACCES, "write is not allowed"));
         }
 
-        let (offset, _) = self.get_range_in_bytes(&req)?;
+        if let Some(io_watchdog) = self.io_watchdog.as_ref() {
+            // Check the range with the file lock held to prevent concurrent resizing
+            let _file = self.file.lock().unwrap();
+            let (offset, _) = self.get_range_in_bytes(&req)?;
+            let data = req.access_bufs_with(|bufs| {
+                bufs.iter()
+                    .flat_map(|buf| buf.as_slice().iter().copied())
+                    .collect::<Vec<u8>>()
+            });
+            return io_watchdog.write(offset, data);
+        }
 
+        // Check the range with the file lock held to prevent concurrent resizing
         let mut file = self.file.lock().unwrap();
-        file.seek(SeekFrom::Start(offset as u64))?;
+        let (offset, _) = self.get_range_in_bytes(&req)?;
         let write_len = req.access_bufs_with(|bufs| {
             let slices: Vec<IoSlice<'_>> = bufs
                 .iter()
                 .map(|buf| IoSlice::new(buf.as_slice()))
                 .collect();
 
-            // TODO: fix this limitation
-            const LINUX_IOVS_MAX: usize = 1024;
-            debug_assert!(slices.len() < LINUX_IOVS_MAX);
-
-            file.write_vectored(&slices)
+            // Linux limits the number of buffers of a vectored I/O
+            let mut write_len = 0;
+            for batch in slices.chunks(IOV_MAX) {
+                let batch_len: usize = batch.iter().map(|slice| slice.len()).sum();
+                file.seek(SeekFrom::Start((offset + write_len) as u64))?;
+                // Otherwise, the following batches would be written to the wrong offsets
+                if file.write_vectored(batch)? != batch_len {
+                    return Err(errno!(EIO, "short write of the host disk"));
+                }
+                write_len += batch_len;
+            }
+            Ok::<_, Error>(write_len)
         })?;
         drop(file);
 
-        debug_assert!(write_len / BLOCK_SIZE == req.num_blocks());
+        if write_len != req.num_blocks() * BLOCK_SIZE {
+            return Err(errno!(EIO, "the write length does not match the request"));
+        }
         Ok(())
     }
 
+    fn do_discard(&self, req: &Arc<BioReq>) -> Result<()> {
+        if self.is_read_only() {
+            return Err(errno!(EACCES, "discard is not allowed"));
+        }
+
+        // Check the range with the file lock held to prevent concurrent resizing
+        let file = self.file.lock().unwrap();
+        let (offset, end_offset) = self.get_range_in_bytes(&req)?;
+        if offset == end_offset {
+            return Ok(());
+        }
+        match self.io_watchdog.as_ref() {
+            Some(io_watchdog) => io_watchdog.punch_hole(offset, end_offset - offset),
+            None => punch_hole(&file, offset, end_offset - offset),
+        }
+    }
+
     fn do_flush(&self) -> Result<()> {
         if !self.can_write {
             return Err(errno!(EACCES, "flush is not allowed"));
         }
 
+        if let Some(io_watchdog) = self.io_watchdog.as_ref() {
+            return io_watchdog.flush();
+        }
+
         let file = self.file.lock().unwrap();
         file.sync_data()?;
         drop(file);
@@ -94,10 +201,36 @@ impl SyncIoDisk {
         Ok(())
     }
 
+    fn do_resize(&self, new_total_blocks: usize) -> Result<()> {
+        if self.is_read_only() {
+            return Err(errno!(EACCES, "resize is not allowed"));
+        }
+        if new_total_blocks == 0 {
+            return Err(errno!(EINVAL, "a host disk must have a non-zero size"));
+        }
+        let new_len = new_total_blocks
+            .checked_mul(BLOCK_SIZE)
+            .ok_or_else(|| errno!(EOVERFLOW, "the disk size is too large"))?;
+
+        let file = self.file.lock().unwrap();
+        let old_total_blocks = self.total_blocks.load(Ordering::Relaxed);
+        if new_total_blocks < old_total_blocks && !self.allow_shrink {
+            return Err(errno!(EINVAL, "shrinking the disk would discard data"));
+        }
+        match self.io_watchdog.as_ref() {
+            Some(io_watchdog) => io_watchdog.set_len(new_len as u64)?,
+            None => file.set_len(new_len as u64)?,
+        }
+        self.total_blocks.store(new_total_blocks, Ordering::Relaxed);
+        drop(file);
+
+        Ok(())
+    }
+
     fn get_range_in_bytes(&self, req: &Arc<BioReq>) -> Result<(usize, usize)> {
         let begin_block = req.addr();
         let end_block = begin_block + req.num_blocks();
-        if end_block > self.total_blocks {
+        if end_block > self.total_blocks.load(Ordering::Relaxed) {
             return Err(errno!(EINVAL, "invalid block range"));
         }
         let begin_offset = begin_block * BLOCK_SIZE;
@@ -108,7 +241,7 @@ impl SyncIoDisk {
 
 impl BlockDevice for SyncIoDisk {
     fn total_blocks(&self) -> usize {
-        self.total_blocks
+        self.total_blocks.load(Ordering::Relaxed)
     }
 
     fn submit(&self, req: Arc<BioReq>) -> BioSubmission {
@@ -121,6 +254,7 @@ impl BlockDevice for SyncIoDisk {
             BioType::Read => self.do_read(req),
             BioType::Write => self.do_write(req),
             BioType::Flush => self.do_flush(),
+            BioType::Discard => self.do_discard(req),
         };
 
         // Update the status of req to completed and set the response
@@ -131,6 +265,10 @@ impl BlockDevice for SyncIoDisk {
 
         submission
     }
+
+    fn resize(&self, new_total_blocks: usize) -> Result<()> {
+        self.do_resize(new_total_blocks)
+    }
 }
 
 impl HostDisk for SyncIoDisk {
@@ -142,13 +280,24 @@ impl HostDisk for SyncIoDisk {
         });
         let can_read = options.read;
         let can_write = options.write;
+        if can_write && !is_opened_for_writing(&file)? {
+            return Err(errno!(EACCES, "the host file is not opened for writing"));
+        }
+        let allow_shrink = options.allow_shrink;
+        let io_watchdog = match options.io_timeout {
+            Some(timeout) => Some(IoWatchdog::new(file.try_clone()?, timeout)?),
+            None => None,
+        };
         let path = path.to_owned();
         let new_self = Self {
             file: Mutex::new(file),
             path,
-            total_blocks,
+            total_blocks: AtomicUsize::new(total_blocks),
             can_read,
             can_write,
+            read_only: AtomicBool::new(false),
+            allow_shrink,
+            io_watchdog,
         };
         Ok(new_self)
     }
@@ -160,11 +309,50 @@ impl HostDisk for SyncIoDisk {
 
 impl Drop for SyncIoDisk {
     fn drop(&mut self) {
-        // Ensure all data are peristed before the disk is dropped
-        let _ = self.do_flush();
+        // Ensure all data are peristed before the disk is dropped. There is
+        // nothing to persist if the disk is not opened for writing.
+        if self.can_write {
+            let _ = self.do_flush();
+        }
     }
 }
 
+/// Deallocate the space of a range of a host file, after which the range reads
+/// back as zeros.
+///
+/// If the host file system does not support punching holes, the range is left
+/// untouched. This is fine as discarding is only a hint.
+pub(super) fn punch_hole(file: &File, offset: usize, len: usize) -> Result<()> {
+    let mode = libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE;
+    let fd = file.as_raw_fd();
+    #[cfg(feature = "sgx")]
+    let ret = unsafe { libc::ocall::fallocate64(fd, mode, offset as i64, len as i64) };
+    #[cfg(not(feature = "sgx"))]
+    let ret = unsafe { libc::fallocate64(fd, mode, offset as i64, len as i64) };
+    if ret < 0 {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::EOPNOTSUPP) {
+            return Ok(());
+        }
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+// Returns whether a host file is opened with write access.
+fn is_opened_for_writing(file: &File) -> Result<bool> {
+    let fd = file.as_raw_fd();
+    #[cfg(feature = "sgx")]
+    let flags = unsafe { libc::ocall::fcntl_arg0(fd, libc::F_GETFL) };
+    #[cfg(not(feature = "sgx"))]
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let access_mode = flags & libc::O_ACCMODE;
+    Ok(access_mode == libc::O_WRONLY || access_mode == libc::O_RDWR)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -191,4 +379,242 @@ mod test {
     }
 
     block_device::gen_unit_tests!(test_setup, test_teardown);
+
+    #[test]
+    fn grow() {
+        async_rt::task::block_on(async move {
+            let disk = test_setup();
+            let old_total_blocks = disk.total_blocks();
+            let new_total_blocks = old_total_blocks * 2;
+
+            // Write near the end of the disk
+            let old_msg = b"near the old end";
+            let old_end = old_total_blocks * BLOCK_SIZE;
+            let old_offset = old_end - old_msg.len();
+            let len = disk.write(old_offset, old_msg).await.unwrap();
+            assert!(len == old_msg.len());
+
+            // The new region is inaccessible until the disk grows
+            let new_msg = b"in the new region";
+            let new_end = new_total_blocks * BLOCK_SIZE;
+            let new_offset = new_end - new_msg.len();
+            assert!(disk.write(new_offset, new_msg).await.is_err());
+
+            disk.resize(new_total_blocks).unwrap();
+            assert!(disk.total_blocks() == new_total_blocks);
+            let len = disk.write(new_offset, new_msg).await.unwrap();
+            assert!(len == new_msg.len());
+
+            // Read everything back
+            let mut buf = vec![0_u8; new_end];
+            let len = disk.read(0, &mut buf).await.unwrap();
+            assert!(len == new_end);
+            assert!(&buf[old_offset..old_end] == old_msg);
+            assert!(&buf[new_offset..new_end] == new_msg);
+            assert!(buf[..old_offset].iter().all(|b| *b == 0));
+            assert!(buf[old_end..new_offset].iter().all(|b| *b == 0));
+
+            test_teardown(disk);
+        });
+    }
+
+    #[test]
+    fn toggle_read_only() {
+        async_rt::task::block_on(async move {
+            let disk = test_setup();
+            let msg = b"hello world";
+            let mut buf = [0_u8; 11];
+            assert!(!disk.is_read_only());
+            disk.write(0, msg).await.unwrap();
+
+            // Writes fail while the disk is read-only, but reads still work
+            disk.set_read_only(true);
+            assert!(disk.is_read_only());
+            let err = disk.write(BLOCK_SIZE, msg).await.unwrap_err();
+            assert!(err.errno() == EACCES);
+            disk.read(0, &mut buf).await.unwrap();
+            assert!(&buf == msg);
+            disk.read(BLOCK_SIZE, &mut buf).await.unwrap();
+            assert!(buf.iter().all(|b| *b == 0));
+
+            // Writes succeed again after the read-only state is cleared
+            disk.set_read_only(false);
+            assert!(!disk.is_read_only());
+            disk.write(BLOCK_SIZE, msg).await.unwrap();
+            disk.read(BLOCK_SIZE, &mut buf).await.unwrap();
+            assert!(&buf == msg);
+
+            test_teardown(disk);
+        });
+    }
+
+    #[test]
+    fn shrink() {
+        let disk = test_setup();
+        let old_total_blocks = disk.total_blocks();
+        let new_total_blocks = old_total_blocks / 2;
+
+        // Shrinking is not allowed by default
+        let err = disk.resize(new_total_blocks).unwrap_err();
+        assert!(err.errno() == EINVAL);
+        assert!(disk.total_blocks() == old_total_blocks);
+        let path = disk.path().to_owned();
+        drop(disk);
+
+        // Shrinking is allowed if specified explicitly
+        let disk = SyncIoDisk::with_options()
+            .read(true)
+            .write(true)
+            .allow_shrink(true)
+            .open(&path)
+            .unwrap();
+        disk.resize(new_total_blocks).unwrap();
+        assert!(disk.total_blocks() == new_total_blocks);
+        let file_len = std::fs::metadata(&path).unwrap().len() as usize;
+        assert!(file_len == new_total_blocks * BLOCK_SIZE);
+
+        test_teardown(disk);
+    }
+
+    #[test]
+    fn discard() {
+        async_rt::task::block_on(async move {
+            let disk = test_setup();
+            let total_bytes = disk.total_blocks() * BLOCK_SIZE;
+            let data = vec![b'@'; total_bytes];
+            disk.write(0, &data).await.unwrap();
+
+            // Discard the blocks in the middle of the disk
+            let (discard_addr, discard_blocks) = (2, 4);
+            let req = BioReqBuilder::new(BioType::Discard)
+                .addr(discard_addr)
+                .num_blocks(discard_blocks)
+                .build();
+            let submission = disk.submit(Arc::new(req));
+            let req = submission.complete().await;
+            assert!(req.response() == Some(Ok(())));
+
+            // The discarded blocks read back as zeros, while the others keep the data
+            let mut buf = vec![0_u8; total_bytes];
+            disk.read(0, &mut buf).await.unwrap();
+            let discard_begin = discard_addr * BLOCK_SIZE;
+            let discard_end = discard_begin + discard_blocks * BLOCK_SIZE;
+            assert!(buf[..discard_begin].iter().all(|b| *b == b'@'));
+            assert!(buf[discard_begin..discard_end].iter().all(|b| *b == 0));
+            assert!(buf[discard_end..].iter().all(|b| *b == b'@'));
+            assert!(disk.total_blocks() * BLOCK_SIZE == total_bytes);
+
+            test_teardown(disk);
+        });
+    }
+
+    #[test]
+    fn read_write_many_bufs() {
+        fn new_bufs(num_bufs: usize, val_fn: impl Fn(usize) -> u8) -> Vec<BlockBuf> {
+            (0..num_bufs)
+                .map(|i| BlockBuf::from_boxed(vec![val_fn(i); BLOCK_SIZE].into_boxed_slice()))
+                .collect()
+        }
+
+        fn drop_bufs(_req: &BioReq, mut bufs: Vec<BlockBuf>) {
+            bufs.drain(..).for_each(|buf| {
+                // Safety. BlockBuffer is created with from_boxed
+                drop(unsafe { BlockBuf::into_boxed(buf) });
+            });
+        }
+
+        async_rt::task::block_on(async move {
+            // More buffers than a single vectored I/O can take
+            let num_bufs = IOV_MAX * 2 + 1;
+            let disk = test_setup();
+            disk.resize(num_bufs).unwrap();
+
+            let req = BioReqBuilder::new(BioType::Write)
+                .addr(0)
+                .bufs(new_bufs(num_bufs, |i| i as u8))
+                .on_drop(drop_bufs)
+                .build();
+            let req = disk.submit(Arc::new(req)).complete().await;
+            assert!(req.response() == Some(Ok(())));
+
+            let req = BioReqBuilder::new(BioType::Read)
+                .addr(0)
+                .bufs(new_bufs(num_bufs, |_| 0))
+                .on_drop(drop_bufs)
+                .build();
+            let req = disk.submit(Arc::new(req)).complete().await;
+            assert!(req.response() == Some(Ok(())));
+            req.access_bufs_with(|bufs| {
+                for (i, buf) in bufs.iter().enumerate() {
+                    assert!(buf.as_slice().iter().all(|b| *b == i as u8));
+                }
+            });
+
+            test_teardown(disk);
+        });
+    }
+
+    #[test]
+    fn open_read_only() {
+        async_rt::task::block_on(async move {
+            let disk = test_setup();
+            let msg = b"hello world";
+            disk.write(0, msg).await.unwrap();
+            let path = disk.path().to_owned();
+            drop(disk);
+
+            let disk = SyncIoDisk::with_options().read(true).open(&path).unwrap();
+            assert!(disk.is_read_only());
+            let mut buf = [0_u8; 11];
+            disk.read(0, &mut buf).await.unwrap();
+            assert!(&buf == msg);
+            let err = disk.write(0, msg).await.unwrap_err();
+            assert!(err.errno() == EACCES);
+            // Dropping a read-only disk does not flush it
+            drop(disk);
+
+            // A disk cannot be writable if the host file is not
+            let file = fs::File::open(&path).unwrap();
+            let mut options = SyncIoDisk::with_options();
+            options.read(true).write(true);
+            let err = SyncIoDisk::from_options_and_file(&options, file, &path).unwrap_err();
+            assert!(err.errno() == EACCES);
+
+            let _ = std::fs::remove_file(&path);
+        });
+    }
+
+    #[test]
+    fn io_timeout() {
+        async_rt::task::block_on(async move {
+            let disk = test_setup();
+            let path = disk.path().to_owned();
+            drop(disk);
+
+            let disk = SyncIoDisk::with_options()
+                .read(true)
+                .write(true)
+                .io_timeout(std::time::Duration::from_millis(100))
+                .open(&path)
+                .unwrap();
+            let msg = b"hello world";
+            let mut buf = [0_u8; 11];
+            disk.write(0, msg).await.unwrap();
+
+            // I/O fails in time while the host file is hung
+            let io_watchdog = disk.io_watchdog.as_ref().unwrap();
+            io_watchdog.set_stalled(true);
+            let err = disk.read(0, &mut buf).await.unwrap_err();
+            assert!(err.errno() == ETIMEDOUT);
+            let err = disk.write(BLOCK_SIZE, msg).await.unwrap_err();
+            assert!(err.errno() == ETIMEDOUT);
+
+            // I/O succeeds again after the host file recovers
+            io_watchdog.set_stalled(false);
+            disk.read(0, &mut buf).await.unwrap();
+            assert!(&buf == msg);
+
+            test_teardown(disk);
+        });
+    }
 }
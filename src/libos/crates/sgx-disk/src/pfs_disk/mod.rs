@@ -139,6 +139,8 @@ impl BlockDevice for PfsDisk {
             BioType::Read => self.do_read(req),
             BioType::Write => self.do_write(req),
             BioType::Flush => self.do_flush(),
+            // Discarding is not supported by SGX-PFS
+            BioType::Discard => Ok(()),
         };
 
         // Update the status of req to completed and set the response
@@ -61,6 +61,55 @@ impl VMRange {
         self.start == self.end
     }
 
+    // Returns whether both the start and the end are aligned.
+    pub fn is_aligned(&self, align: usize) -> bool {
+        debug_assert!(align.is_power_of_two());
+        self.start % align == 0 && self.end % align == 0
+    }
+
+    // Returns the largest aligned range within self, whose start is rounded up
+    // and end is rounded down.
+    //
+    // Post-condition: the returned range has a non-zero size.
+    pub fn align_up(&self, align: usize) -> Result<VMRange> {
+        let start = align_up(self.start, align);
+        let end = align_down(self.end, align);
+        if start >= end {
+            return_errno!(EINVAL, "the aligned range is empty");
+        }
+        Ok(VMRange { start, end })
+    }
+
+    // Returns the smallest aligned range that contains self, whose start is
+    // rounded down and end is rounded up.
+    //
+    // Post-condition: the returned range has a non-zero size.
+    pub fn align_down(&self, align: usize) -> Result<VMRange> {
+        let start = align_down(self.start, align);
+        let end = align_up(self.end, align);
+        if start >= end {
+            return_errno!(EINVAL, "the aligned range is empty");
+        }
+        Ok(VMRange { start, end })
+    }
+
+    // Returns the number of pages in self, counting a trailing partial page.
+    pub fn num_pages(&self) -> usize {
+        (self.size() + PAGE_SIZE - 1) / PAGE_SIZE
+    }
+
+    // Returns an iterator over the consecutive page-sized subranges of self,
+    // starting at the start of self. The last subrange is shorter if the size
+    // of self is not a multiple of the page size.
+    pub fn iter_pages(&self) -> impl Iterator<Item = VMRange> {
+        let end = self.end();
+        (self.start()..end)
+            .step_by(PAGE_SIZE)
+            .map(move |page_start| unsafe {
+                VMRange::from_unchecked(page_start, end.min(page_start + PAGE_SIZE))
+            })
+    }
+
     pub fn is_superset_of(&self, other: &VMRange) -> bool {
         self.start() <= other.start() && other.end() <= self.end()
     }
@@ -124,6 +173,28 @@ impl VMRange {
         }
     }
 
+    // Merges overlapping or adjacent ranges in place, so that the ranges are
+    // sorted by start addresses and are non-overlapping and non-adjacent.
+    //
+    // Post-condition: the resulting ranges have non-zero sizes.
+    pub fn merge_all(ranges: &mut Vec<VMRange>) {
+        ranges.retain(|range| !range.empty());
+        ranges.sort_unstable_by_key(|range| range.start());
+
+        let mut merged_len = 0;
+        for i in 0..ranges.len() {
+            let range = ranges[i];
+            if merged_len > 0 && range.start() <= ranges[merged_len - 1].end() {
+                let last = &mut ranges[merged_len - 1];
+                last.end = last.end().max(range.end());
+            } else {
+                ranges[merged_len] = range;
+                merged_len += 1;
+            }
+        }
+        ranges.truncate(merged_len);
+    }
+
     pub unsafe fn as_slice(&self) -> &[u8] {
         let buf_ptr = self.start() as *const u8;
         let buf_size = self.size() as usize;
@@ -148,3 +219,98 @@ impl fmt::Debug for VMRange {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_aligned_range() {
+        let range = VMRange::new(PAGE_SIZE, 3 * PAGE_SIZE).unwrap();
+        assert!(range.is_aligned(PAGE_SIZE));
+        assert!(range.align_up(PAGE_SIZE).unwrap() == range);
+        assert!(range.align_down(PAGE_SIZE).unwrap() == range);
+    }
+
+    #[test]
+    fn align_unaligned_range() {
+        let range = VMRange::new(PAGE_SIZE + 1, 3 * PAGE_SIZE + 1).unwrap();
+        assert!(!range.is_aligned(PAGE_SIZE));
+        assert!(range.is_aligned(1));
+
+        let aligned_range = range.align_up(PAGE_SIZE).unwrap();
+        assert!(aligned_range == VMRange::new(2 * PAGE_SIZE, 3 * PAGE_SIZE).unwrap());
+        assert!(aligned_range.is_aligned(PAGE_SIZE));
+        assert!(range.is_superset_of(&aligned_range));
+
+        let aligned_range = range.align_down(PAGE_SIZE).unwrap();
+        assert!(aligned_range == VMRange::new(PAGE_SIZE, 4 * PAGE_SIZE).unwrap());
+        assert!(aligned_range.is_aligned(PAGE_SIZE));
+        assert!(aligned_range.is_superset_of(&range));
+    }
+
+    #[test]
+    fn align_to_empty_range() {
+        // No page within the range
+        let range = VMRange::new(PAGE_SIZE + 1, 2 * PAGE_SIZE - 1).unwrap();
+        assert!(range.align_up(PAGE_SIZE).unwrap_err().errno() == EINVAL);
+        assert!(
+            range.align_down(PAGE_SIZE).unwrap() == VMRange::new(PAGE_SIZE, 2 * PAGE_SIZE).unwrap()
+        );
+
+        let range = VMRange::new_empty(PAGE_SIZE).unwrap();
+        assert!(range.align_up(PAGE_SIZE).unwrap_err().errno() == EINVAL);
+        assert!(range.align_down(PAGE_SIZE).unwrap_err().errno() == EINVAL);
+    }
+
+    fn ranges(raw: &[(usize, usize)]) -> Vec<VMRange> {
+        raw.iter()
+            .map(|&(start, end)| VMRange::new(start, end).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn merge_disjoint_ranges() {
+        let mut merged = ranges(&[(0x5000, 0x6000), (0x1000, 0x2000), (0x3000, 0x4000)]);
+        VMRange::merge_all(&mut merged);
+        assert!(merged == ranges(&[(0x1000, 0x2000), (0x3000, 0x4000), (0x5000, 0x6000)]));
+    }
+
+    #[test]
+    fn merge_adjacent_ranges() {
+        let mut merged = ranges(&[(0x3000, 0x4000), (0x1000, 0x2000), (0x2000, 0x3000)]);
+        VMRange::merge_all(&mut merged);
+        assert!(merged == ranges(&[(0x1000, 0x4000)]));
+    }
+
+    #[test]
+    fn merge_overlapping_ranges() {
+        let mut merged = ranges(&[
+            (0x1000, 0x5000),
+            (0x2000, 0x3000),
+            (0x4000, 0x6000),
+            (0x8000, 0x8000),
+        ]);
+        VMRange::merge_all(&mut merged);
+        assert!(merged == ranges(&[(0x1000, 0x6000)]));
+    }
+
+    #[test]
+    fn iter_pages() {
+        let range = VMRange::new_with_size(PAGE_SIZE, 2 * PAGE_SIZE + PAGE_SIZE / 2).unwrap();
+        assert!(range.num_pages() == 3);
+        let pages: Vec<VMRange> = range.iter_pages().collect();
+        assert!(
+            pages
+                == ranges(&[
+                    (PAGE_SIZE, 2 * PAGE_SIZE),
+                    (2 * PAGE_SIZE, 3 * PAGE_SIZE),
+                    (3 * PAGE_SIZE, 3 * PAGE_SIZE + PAGE_SIZE / 2),
+                ])
+        );
+
+        let range = VMRange::new_empty(PAGE_SIZE).unwrap();
+        assert!(range.num_pages() == 0);
+        assert!(range.iter_pages().next().is_none());
+    }
+}
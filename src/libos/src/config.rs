@@ -97,6 +97,7 @@ pub fn parse_key(key_str: &str) -> Result<sgx_key_128bit_t> {
 pub struct Config {
     pub resource_limits: ConfigResourceLimits,
     pub process: ConfigProcess,
+    pub entropy_source: ConfigEntropySource,
     pub env: ConfigEnv,
     pub entry_points: Vec<PathBuf>,
     pub untrusted_unix_socks: Option<Vec<ConfigUntrustedUnixSock>>,
@@ -106,6 +107,30 @@ pub struct Config {
 #[derive(Debug)]
 pub struct ConfigResourceLimits {
     pub user_space_size: usize,
+    /// The max total size of the buffers of a single vectored I/O operation.
+    pub max_io_size: usize,
+}
+
+/// The source of random bytes for `getrandom` and `/dev/[u]random`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConfigEntropySource {
+    /// The hardware RNG (i.e., the RDRAND instruction).
+    Hardware,
+    /// A ChaCha20-based CSPRNG seeded once from the hardware RNG.
+    Csprng,
+}
+
+impl ConfigEntropySource {
+    pub fn from_input(input: &str) -> Result<ConfigEntropySource> {
+        let source = match input {
+            "hardware" => ConfigEntropySource::Hardware,
+            "csprng" => ConfigEntropySource::Csprng,
+            _ => {
+                return_errno!(EINVAL, "Unsupported entropy source");
+            }
+        };
+        Ok(source)
+    }
 }
 
 #[derive(Debug)]
@@ -113,6 +138,30 @@ pub struct ConfigProcess {
     pub default_stack_size: usize,
     pub default_heap_size: usize,
     pub default_mmap_size: usize,
+    pub fault_policy: ConfigFaultPolicy,
+}
+
+/// The policy on unrecoverable faults of the LibOS (e.g., a failed assertion)
+/// that happen while serving a process.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConfigFaultPolicy {
+    /// Kill only the offending process with SIGABRT.
+    KillProcess,
+    /// Abort the whole enclave.
+    AbortEnclave,
+}
+
+impl ConfigFaultPolicy {
+    pub fn from_input(input: &str) -> Result<ConfigFaultPolicy> {
+        let policy = match input {
+            "kill_process" => ConfigFaultPolicy::KillProcess,
+            "abort_enclave" => ConfigFaultPolicy::AbortEnclave,
+            _ => {
+                return_errno!(EINVAL, "Unsupported fault policy");
+            }
+        };
+        Ok(policy)
+    }
 }
 
 #[derive(Debug)]
@@ -177,6 +226,7 @@ impl Config {
     fn from_input(input: &InputConfig) -> Result<Config> {
         let resource_limits = ConfigResourceLimits::from_input(&input.resource_limits)?;
         let process = ConfigProcess::from_input(&input.process)?;
+        let entropy_source = ConfigEntropySource::from_input(&input.entropy_source)?;
         let env = ConfigEnv::from_input(&input.env)?;
         let entry_points = {
             let mut entry_points = Vec::new();
@@ -215,6 +265,7 @@ impl Config {
         Ok(Config {
             resource_limits,
             process,
+            entropy_source,
             env,
             entry_points,
             untrusted_unix_socks,
@@ -226,7 +277,11 @@ impl Config {
 impl ConfigResourceLimits {
     fn from_input(input: &InputConfigResourceLimits) -> Result<ConfigResourceLimits> {
         let user_space_size = parse_memory_size(&input.user_space_size)?;
-        Ok(ConfigResourceLimits { user_space_size })
+        let max_io_size = parse_memory_size(&input.max_io_size)?;
+        Ok(ConfigResourceLimits {
+            user_space_size,
+            max_io_size,
+        })
     }
 }
 
@@ -235,10 +290,12 @@ impl ConfigProcess {
         let default_stack_size = parse_memory_size(&input.default_stack_size)?;
         let default_heap_size = parse_memory_size(&input.default_heap_size)?;
         let default_mmap_size = parse_memory_size(&input.default_mmap_size)?;
+        let fault_policy = ConfigFaultPolicy::from_input(&input.fault_policy)?;
         Ok(ConfigProcess {
             default_stack_size,
             default_heap_size,
             default_mmap_size,
+            fault_policy,
         })
     }
 }
@@ -335,6 +392,8 @@ struct InputConfig {
     pub resource_limits: InputConfigResourceLimits,
     #[serde(default)]
     pub process: InputConfigProcess,
+    #[serde(default = "InputConfig::get_entropy_source")]
+    pub entropy_source: String,
     #[serde(default)]
     pub env: InputConfigEnv,
     #[serde(default)]
@@ -345,23 +404,36 @@ struct InputConfig {
     pub mount: Vec<InputConfigMount>,
 }
 
+impl InputConfig {
+    fn get_entropy_source() -> String {
+        "hardware".to_string()
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 struct InputConfigResourceLimits {
     #[serde(default = "InputConfigResourceLimits::get_user_space_size")]
     pub user_space_size: String,
+    #[serde(default = "InputConfigResourceLimits::get_max_io_size")]
+    pub max_io_size: String,
 }
 
 impl InputConfigResourceLimits {
     fn get_user_space_size() -> String {
         "128MB".to_string()
     }
+
+    fn get_max_io_size() -> String {
+        "2GB".to_string()
+    }
 }
 
 impl Default for InputConfigResourceLimits {
     fn default() -> InputConfigResourceLimits {
         InputConfigResourceLimits {
             user_space_size: InputConfigResourceLimits::get_user_space_size(),
+            max_io_size: InputConfigResourceLimits::get_max_io_size(),
         }
     }
 }
@@ -374,6 +446,8 @@ struct InputConfigProcess {
     pub default_heap_size: String,
     #[serde(default = "InputConfigProcess::get_default_mmap_size")]
     pub default_mmap_size: String,
+    #[serde(default = "InputConfigProcess::get_fault_policy")]
+    pub fault_policy: String,
 }
 
 impl InputConfigProcess {
@@ -388,6 +462,10 @@ impl InputConfigProcess {
     fn get_default_mmap_size() -> String {
         "32MB".to_string()
     }
+
+    fn get_fault_policy() -> String {
+        "kill_process".to_string()
+    }
 }
 
 impl Default for InputConfigProcess {
@@ -396,6 +474,7 @@ impl Default for InputConfigProcess {
             default_stack_size: InputConfigProcess::get_default_stack_size(),
             default_heap_size: InputConfigProcess::get_default_heap_size(),
             default_mmap_size: InputConfigProcess::get_default_mmap_size(),
+            fault_policy: InputConfigProcess::get_fault_policy(),
         }
     }
 }
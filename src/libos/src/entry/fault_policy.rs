@@ -0,0 +1,141 @@
+//! The central handler of unrecoverable faults in the LibOS.
+//!
+//! An unrecoverable fault is a panic raised by the LibOS (or the crates it
+//! depends on) while serving a user thread, e.g., a failed `assert!` or
+//! `debug_assert!`, or an invariant violation detected by `unwrap`/`expect`.
+//! Such a panic is caught at the entrypoints of the LibOS (see the `thread`
+//! module) and is then handled according to the fault policy specified by
+//! the `process.fault_policy` field of Occlum.json:
+//!
+//! * `kill_process` (the default): only the offending process is killed
+//! with `SIGABRT`, while all other processes continue to run;
+//! * `abort_enclave`: the whole enclave is aborted.
+//!
+//! Note that the LibOS is shared by all processes. A panic unwinds out of
+//! whatever global state the offending thread was updating, e.g., a global
+//! `SgxMutex` is poisoned if the panic happens while it is held, and a spin
+//! lock is released with the protected data left half-updated. So the
+//! `kill_process` policy is a best-effort one. To keep the damage from
+//! spreading among innocent processes, a fault caused by a poisoned lock,
+//! which reveals that an earlier fault has broken the global state, always
+//! escalates to aborting the enclave.
+
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::config::{ConfigFaultPolicy, LIBOS_CONFIG};
+use crate::prelude::*;
+use crate::process::TermStatus;
+use crate::signal::SIGABRT;
+
+/// Returns the fault policy of the LibOS.
+pub fn fault_policy() -> ConfigFaultPolicy {
+    LIBOS_CONFIG.process.fault_policy
+}
+
+/// Wrap a future with a new future that catches any panic raised while
+/// polling the inner future.
+///
+/// The new future outputs `Err(payload)` if such a panic is caught.
+pub fn catch_fault<F: Future>(f: F) -> CatchFault<F> {
+    CatchFault { inner: f }
+}
+
+/// Handle an unrecoverable fault of the current thread according to the
+/// fault policy.
+///
+/// With the `kill_process` policy, the current process is forced to exit
+/// as if it were killed by `SIGABRT`. The current thread and its siblings
+/// shall exit at their next safe points.
+pub fn handle_fault(payload: Box<dyn Any + Send>) {
+    let msg = panic_message(&payload);
+    if is_poisoned_lock(msg) {
+        error!(
+            "unrecoverable fault: {}; the LibOS state is broken by an earlier fault, abort the enclave",
+            msg
+        );
+        std::process::abort();
+    }
+    match fault_policy() {
+        ConfigFaultPolicy::AbortEnclave => {
+            error!("unrecoverable fault: {}; abort the enclave", msg);
+            std::process::abort();
+        }
+        ConfigFaultPolicy::KillProcess => {
+            let current = current!();
+            let process = current.process();
+            error!(
+                "unrecoverable fault: {}; kill process {} (thread {})",
+                msg,
+                process.pid(),
+                current.tid()
+            );
+            if process.force_exit(TermStatus::Killed(SIGABRT)) {
+                crate::process::notify_all_threads_to_exit(process);
+            }
+        }
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&'static str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.as_str()
+    } else {
+        "unknown panic"
+    }
+}
+
+// A panic caused by unwrapping the result of locking a poisoned lock
+fn is_poisoned_lock(msg: &str) -> bool {
+    msg.contains("PoisonError")
+}
+
+pub struct CatchFault<F: Future> {
+    inner: F,
+}
+
+impl<F: Future> Future for CatchFault<F> {
+    type Output = core::result::Result<F::Output, Box<dyn Any + Send>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = unsafe { self.map_unchecked_mut(|self_| &mut self_.inner) };
+        // The states touched by the panicking thread may be inconsistent. This is
+        // acceptable for the per-process states since the offending process is
+        // going to be killed, but not for the global ones (see the module doc).
+        match panic::catch_unwind(AssertUnwindSafe(|| inner.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// Trigger an unrecoverable fault in the LibOS on purpose.
+///
+/// This Occlum-specific system call is intended for testing the fault policy,
+/// so it is only available with the `fault_injection` feature. To prevent a
+/// process from bringing down the whole enclave, it is only allowed with the
+/// `kill_process` policy.
+#[cfg(feature = "fault_injection")]
+pub async fn do_inject_fault() -> Result<isize> {
+    if fault_policy() != ConfigFaultPolicy::KillProcess {
+        return_errno!(
+            EPERM,
+            "fault injection is only allowed with the kill_process policy"
+        );
+    }
+    panic!(
+        "an invariant violation injected by process {}",
+        current!().process().pid()
+    );
+}
+
+#[cfg(not(feature = "fault_injection"))]
+pub async fn do_inject_fault() -> Result<isize> {
+    return_errno!(ENOSYS, "fault injection is not enabled");
+}
@@ -13,11 +13,13 @@
 //!
 //! In addition to all sorts of entrypoints, the subsystem also includes modules
 //! that facilitate the implementation of entrypoints, e.g., the `context_switch`
-//! module.
+//! module and the `fault_policy` module, which handles the unrecoverable faults
+//! that happen while serving user threads.
 
 pub mod context_switch;
 pub mod enclave;
 pub mod exception;
+pub mod fault_policy;
 pub mod interrupt;
 pub mod syscall;
 pub mod thread;
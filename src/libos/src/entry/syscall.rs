@@ -8,16 +8,17 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use std::ptr;
 
 use crate::fs::{
-    do_access, do_chdir, do_chmod, do_chown, do_close, do_creat, do_dup, do_dup2, do_dup3,
-    do_eventfd, do_eventfd2, do_faccessat, do_fallocate, do_fchdir, do_fchmod, do_fchmodat,
-    do_fchown, do_fchownat, do_fcntl, do_fdatasync, do_flock, do_fstat, do_fstatat, do_fstatfs,
-    do_fsync, do_ftruncate, do_futimesat, do_getcwd, do_getdents, do_getdents64, do_ioctl,
-    do_lchown, do_link, do_linkat, do_lseek, do_lstat, do_mkdir, do_mkdirat, do_mount,
-    do_mount_rootfs, do_open, do_openat, do_pipe, do_pipe2, do_pread, do_pwrite, do_read,
-    do_readlink, do_readlinkat, do_readv, do_rename, do_renameat, do_rmdir, do_sendfile, do_stat,
-    do_statfs, do_symlink, do_symlinkat, do_sync, do_truncate, do_umask, do_umount, do_unlink,
-    do_unlinkat, do_utime, do_utimensat, do_utimes, do_write, do_writev, iovec_t, utimbuf_t,
-    FileDesc, FileRef, StatBuf, Statfs,
+    do_access, do_chdir, do_chmod, do_chown, do_close, do_copy_file_range, do_creat, do_dup,
+    do_dup2, do_dup3, do_eventfd, do_eventfd2, do_faccessat, do_fadvise64, do_fallocate, do_fchdir,
+    do_fchmod, do_fchmodat, do_fchown, do_fchownat, do_fcntl, do_fdatasync, do_flock, do_fstat,
+    do_fstatat, do_fstatfs, do_fsync, do_ftruncate, do_futimesat, do_getcwd, do_getdents,
+    do_getdents64, do_ioctl, do_lchown, do_link, do_linkat, do_lseek, do_lstat, do_memfd_create,
+    do_mkdir, do_mkdirat, do_mount, do_mount_rootfs, do_open, do_openat, do_openat2, do_pipe,
+    do_pipe2, do_pread, do_preadv2, do_pwrite, do_pwritev2, do_read, do_readlink, do_readlinkat,
+    do_readv, do_rename, do_renameat, do_renameat2, do_rmdir, do_sendfile, do_splice, do_stat,
+    do_statfs, do_statx, do_symlink, do_symlinkat, do_sync, do_truncate, do_umask, do_umount,
+    do_unlink, do_unlinkat, do_utime, do_utimensat, do_utimes, do_write, do_writev, iovec_t,
+    open_how, utimbuf_t, FileDesc, FileRef, StatBuf, Statfs, Statx,
 };
 /*
 use crate::fs::{
@@ -63,9 +64,9 @@ use crate::sched::{
     do_set_priority,
 };
 use crate::signal::{
-    do_kill, do_rt_sigaction, do_rt_sigpending, do_rt_sigprocmask, do_rt_sigreturn,
-    do_rt_sigtimedwait, do_sigaltstack, do_tgkill, do_tkill, sigaction_t, siginfo_t, sigset_t,
-    stack_t,
+    do_kill, do_rt_sigaction, do_rt_sigpending, do_rt_sigprocmask, do_rt_sigqueueinfo,
+    do_rt_sigreturn, do_rt_sigsuspend, do_rt_sigtimedwait, do_rt_tgsigqueueinfo, do_sigaltstack,
+    do_tgkill, do_tkill, sigaction_t, siginfo_t, sigset_t, stack_t,
 };
 use crate::time::{
     clockid_t, do_timerfd_create, do_timerfd_gettime, do_timerfd_settime, itimerspec_t, timespec_t,
@@ -77,6 +78,7 @@ use crate::vm::{MMapFlags, MRemapFlags, MSyncFlags, VMPerms};
 use crate::{fs, process, std, vm};
 
 use super::context_switch::{self, CpuContext, Fault, FpRegs, GpRegs, CURRENT_CONTEXT};
+use super::fault_policy::do_inject_fault;
 
 pub async fn handle_syscall() -> Result<()> {
     // Extract arguments from the CPU context. The arguments follows Linux's syscall ABI.
@@ -286,8 +288,8 @@ macro_rules! process_syscall_table_with_callback {
             (Capset = 126) => handle_unsupported(),
             (RtSigpending = 127) => do_rt_sigpending(buf_ptr: *mut sigset_t, buf_size: usize),
             (RtSigtimedwait = 128) => do_rt_sigtimedwait(mask_ptr: *const sigset_t, info_ptr: *mut siginfo_t, timeout_ptr: *const timespec_t, mask_size: usize),
-            (RtSigqueueinfo = 129) => handle_unsupported(),
-            (RtSigsuspend = 130) => handle_unsupported(),
+            (RtSigqueueinfo = 129) => do_rt_sigqueueinfo(pid: pid_t, sig: c_int, info_ptr: *const siginfo_t),
+            (RtSigsuspend = 130) => do_rt_sigsuspend(mask_ptr: *const sigset_t, mask_size: usize),
             (Sigaltstack = 131) => do_sigaltstack(ss: *const stack_t, old_ss: *mut stack_t),
             (Utime = 132) => do_utime(path: *const i8, times: *const utimbuf_t),
             (Mknod = 133) => handle_unsupported(),
@@ -378,7 +380,7 @@ macro_rules! process_syscall_table_with_callback {
             (SetTidAddress = 218) => do_set_tid_address(tidptr: *mut pid_t),
             (RestartSysCall = 219) => handle_unsupported(),
             (Semtimedop = 220) => handle_unsupported(),
-            (Fadvise64 = 221) => handle_unsupported(),
+            (Fadvise64 = 221) => do_fadvise64(fd: FileDesc, offset: off_t, len: off_t, advice: i32),
             (TimerCreate = 222) => handle_unsupported(),
             (TimerSettime = 223) => handle_unsupported(),
             (TimerGettime = 224) => handle_unsupported(),
@@ -432,7 +434,7 @@ macro_rules! process_syscall_table_with_callback {
             (Unshare = 272) => handle_unsupported(),
             (SetRobustList = 273) => do_set_robust_list(list_head_ptr: *mut RobustListHead, len: usize),
             (GetRobustList = 274) => do_get_robust_list(tid: pid_t, list_head_ptr_ptr: *mut *mut RobustListHead, len_ptr: *mut usize),
-            (Splice = 275) => handle_unsupported(),
+            (Splice = 275) => do_splice(fd_in: FileDesc, off_in_ptr: *mut off_t, fd_out: FileDesc, off_out_ptr: *mut off_t, len: usize, flags: u32),
             (Tee = 276) => handle_unsupported(),
             (SyncFileRange = 277) => handle_unsupported(),
             (Vmsplice = 278) => handle_unsupported(),
@@ -454,7 +456,7 @@ macro_rules! process_syscall_table_with_callback {
             (InotifyInit1 = 294) => handle_unsupported(),
             (Preadv = 295) => handle_unsupported(),
             (Pwritev = 296) => handle_unsupported(),
-            (RtTgsigqueueinfo = 297) => handle_unsupported(),
+            (RtTgsigqueueinfo = 297) => do_rt_tgsigqueueinfo(pid: pid_t, tid: pid_t, sig: c_int, info_ptr: *const siginfo_t),
             (PerfEventOpen = 298) => handle_unsupported(),
             (Recvmmsg = 299) => handle_unsupported(),
             (FanotifyInit = 300) => handle_unsupported(),
@@ -473,21 +475,32 @@ macro_rules! process_syscall_table_with_callback {
             (FinitModule = 313) => handle_unsupported(),
             (SchedSetattr = 314) => handle_unsupported(),
             (SchedGetattr = 315) => handle_unsupported(),
-            (Renameat2 = 316) => handle_unsupported(),
+            (Renameat2 = 316) => do_renameat2(olddirfd: i32, oldpath: *const i8, newdirfd: i32, newpath: *const i8, flags: u32),
             (Seccomp = 317) => handle_unsupported(),
             (Getrandom = 318) => do_getrandom(buf: *mut u8, len: size_t, flags: u32),
-            (MemfdCreate = 319) => handle_unsupported(),
+            (MemfdCreate = 319) => do_memfd_create(name: *const i8, flags: u32),
             (KexecFileLoad = 320) => handle_unsupported(),
             (Bpf = 321) => handle_unsupported(),
             (Execveat = 322) => handle_unsupported(),
             (Userfaultfd = 323) => handle_unsupported(),
             (Membarrier = 324) => handle_unsupported(),
             (Mlock2 = 325) => handle_unsupported(),
+            (CopyFileRange = 326) => do_copy_file_range(fd_in: FileDesc, off_in_ptr: *mut off_t, fd_out: FileDesc, off_out_ptr: *mut off_t, len: usize, flags: u32),
+            (Preadv2 = 327) => do_preadv2(fd: FileDesc, iov: *mut iovec_t, count: i32, offset: off_t, offset_high: off_t, flags: i32),
+            (Pwritev2 = 328) => do_pwritev2(fd: FileDesc, iov: *const iovec_t, count: i32, offset: off_t, offset_high: off_t, flags: i32),
+            (PkeyMprotect = 329) => handle_unsupported(),
+            (PkeyAlloc = 330) => handle_unsupported(),
+            (PkeyFree = 331) => handle_unsupported(),
+            (Statx = 332) => do_statx(dirfd: i32, path: *const i8, flags: u32, mask: u32, statx_buf: *mut Statx),
 
             // Occlum-specific system calls
             (SpawnGlibc = 359) => do_spawn_for_glibc(child_pid_ptr: *mut u32, path: *const i8, argv: *const *const i8, envp: *const *const i8, fa: *const SpawnFileActions, attribute_list: *const posix_spawnattr_t),
             (SpawnMusl = 360) => do_spawn_for_musl(child_pid_ptr: *mut u32, path: *const i8, argv: *const *const i8, envp: *const *const i8, fdop_list: *const FdOp, attribute_list: *const posix_spawnattr_t),
             (MountRootFS = 363) => do_mount_rootfs(key_ptr: *const sgx_key_128bit_t, occlum_json_mac_ptr: *const sgx_aes_gcm_128bit_tag_t),
+            (InjectFault = 364) => do_inject_fault(),
+
+            // The system calls numbered after the Occlum-specific ones
+            (Openat2 = 437) => do_openat2(dirfd: i32, path: *const i8, how: *const open_how, size: usize),
         }
     };
 }
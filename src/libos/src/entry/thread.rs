@@ -1,8 +1,10 @@
 use std::future::Future;
 
 use crate::entry::context_switch::{self, CpuContext, Fault, CURRENT_CONTEXT};
+use crate::entry::fault_policy;
 use crate::prelude::*;
 use crate::process::{ThreadRef, ThreadStatus};
+use crate::time::ClockId;
 use crate::util::log::{self, LevelFilter};
 
 pub fn main_loop(
@@ -46,14 +48,21 @@ async fn __main_loop(current: ThreadRef, init_cpu_state: CpuContext) {
             async_rt::sched::yield_().await;
         }
 
-        // Continue the execution in the user space
+        // Continue the execution in the user space and account the time spent there
+        let user_start = vdso_time::clock_gettime(ClockId::CLOCK_MONOTONIC).unwrap();
         let fault = unsafe { context_switch::switch_to_user() };
+        let user_end = vdso_time::clock_gettime(ClockId::CLOCK_MONOTONIC).unwrap();
+        async_rt::stats::account_user_time(user_end.saturating_sub(user_start));
 
         // Start a new round of log messages. We will set the description for
         // this round later when we have extracted more info from the fault.
         log::next_round(None);
 
-        handle_fault(fault).await;
+        // Catch any panic raised while handling the fault so that it brings down
+        // only the offending process, unless the fault policy says otherwise.
+        if let Err(payload) = fault_policy::catch_fault(handle_fault(fault)).await {
+            fault_policy::handle_fault(payload);
+        }
     }
 }
 
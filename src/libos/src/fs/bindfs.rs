@@ -0,0 +1,31 @@
+use super::*;
+
+use rcore_fs::vfs;
+
+/// The file system of a bind mount, whose root is an existing directory.
+///
+/// The inodes under the root are those of the bound directory, so anything
+/// done through the bind mount is seen at the original path, and vice versa.
+pub struct BindFS {
+    root: Arc<dyn INode>,
+}
+
+impl BindFS {
+    pub fn new(root: Arc<dyn INode>) -> Arc<Self> {
+        Arc::new(Self { root })
+    }
+}
+
+impl FileSystem for BindFS {
+    fn sync(&self) -> vfs::Result<()> {
+        self.root.fs().sync()
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        self.root.clone()
+    }
+
+    fn info(&self) -> FsInfo {
+        self.root.fs().info()
+    }
+}
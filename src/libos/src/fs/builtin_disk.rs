@@ -2,6 +2,7 @@
 use std::lazy::SyncLazy as Lazy;
 use std::path::PathBuf;
 
+use block_device::util::errseq::ErrSeq;
 use block_device::{mem_disk::MemDisk, BlockDevice, BLOCK_SIZE};
 use lazy_static::lazy_static;
 use runtime::IoUringRuntime;
@@ -16,9 +17,9 @@ pub fn try_open_disk(fs: &FsView, fs_path: &FsPath) -> Result<Option<Arc<DiskFil
     }
 
     let abs_path = PathBuf::from(abs_path);
-    if let Some(lazy_disk) = BUILTIN_DISKS.get(&abs_path) {
+    if let Some((lazy_disk, wb_err)) = BUILTIN_DISKS.get(&abs_path) {
         let disk: Arc<dyn BlockDevice> = lazy_disk.deref().clone();
-        let disk_file = Arc::new(DiskFile::new(disk));
+        let disk_file = Arc::new(DiskFile::new(disk, wb_err.clone()));
         Ok(Some(disk_file))
     } else {
         Ok(None)
@@ -26,12 +27,12 @@ pub fn try_open_disk(fs: &FsView, fs_path: &FsPath) -> Result<Option<Arc<DiskFil
 }
 
 lazy_static! {
-    static ref BUILTIN_DISKS: HashMap<PathBuf, LazyDisk> = {
+    static ref BUILTIN_DISKS: HashMap<PathBuf, (LazyDisk, Arc<ErrSeq>)> = {
         fn new_disk_entry<F>(
             name: &str,
             disk_size: usize,
             new_disk_fn: F,
-        ) -> (PathBuf, LazyDisk)
+        ) -> (PathBuf, (LazyDisk, Arc<ErrSeq>))
             where F: Fn(/*file_path:*/ &str, /*total_blocks:*/ usize) -> Arc<dyn BlockDevice> + Send + 'static
         {
             let name = name.to_string();
@@ -41,7 +42,9 @@ lazy_static! {
                 let file_path = format!("{}.image", name);
                 (new_disk_fn)(&file_path, total_blocks)
             }));
-            (dev_path, lazy_disk)
+            // The writeback errors are tracked per disk, not per disk file
+            let wb_err = Arc::new(ErrSeq::new());
+            (dev_path, (lazy_disk, wb_err))
         }
 
         let disk_entries = vec![
@@ -72,7 +75,7 @@ lazy_static! {
                 Arc::new(disk)
             }),
         ];
-        let builtin_disks: HashMap<PathBuf, LazyDisk> = disk_entries
+        let builtin_disks: HashMap<PathBuf, (LazyDisk, Arc<ErrSeq>)> = disk_entries
             .into_iter()
             .collect();
         builtin_disks
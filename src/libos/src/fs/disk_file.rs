@@ -1,3 +1,4 @@
+use block_device::util::errseq::{ErrSeq, ErrSeqCursor};
 use block_device::{BlockDevice, BlockDeviceExt, BLOCK_SIZE};
 use std::fmt;
 
@@ -12,13 +13,19 @@ pub struct DiskFile {
     disk: Arc<dyn BlockDevice>,
     // TODO: use async lock
     offset: SgxMutex<usize>,
+    // The writeback errors of the disk, which are shared by all files of the disk
+    wb_err: Arc<ErrSeq>,
+    wb_err_cursor: SgxMutex<ErrSeqCursor>,
 }
 
 impl DiskFile {
-    pub fn new(disk: Arc<dyn BlockDevice>) -> Self {
+    pub fn new(disk: Arc<dyn BlockDevice>, wb_err: Arc<ErrSeq>) -> Self {
+        let wb_err_cursor = SgxMutex::new(wb_err.sample());
         Self {
             disk,
             offset: SgxMutex::new(0),
+            wb_err,
+            wb_err_cursor,
         }
     }
 
@@ -82,8 +89,23 @@ impl DiskFile {
         Ok(total_len)
     }
 
+    /// Flush the disk.
+    ///
+    /// A failed flush means that some writes, which may be made through other
+    /// files of the disk, are lost. So the error is latched on the disk to be
+    /// reported to every file of the disk, including this one.
     pub async fn flush(&self) -> Result<()> {
-        self.disk.flush().await
+        if let Err(e) = self.disk.flush().await {
+            self.wb_err.set(e.errno());
+        }
+        self.check_wb_err()
+    }
+
+    /// Returns the writeback error of the disk that occurred since the last
+    /// check of this file, if any.
+    pub fn check_wb_err(&self) -> Result<()> {
+        let mut cursor = self.wb_err_cursor.lock().unwrap();
+        self.wb_err.check_and_advance(&mut cursor)
     }
 
     pub fn seek(&self, pos: SeekFrom) -> Result<usize> {
@@ -20,7 +20,7 @@ pub fn do_eventfd(init_val: u32, flags: EventFileFlags) -> Result<FileDesc> {
 
     let event_file = EventFile::new(init_val as u64, is_semaphore, status_flags)?;
     let file_ref = FileRef::new_file(event_file);
-    let event_fd = current!().add_file(file_ref, close_on_spawn);
+    let event_fd = current!().add_file(file_ref, close_on_spawn)?;
     Ok(event_fd)
 }
 
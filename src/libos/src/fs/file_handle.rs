@@ -1,11 +1,12 @@
 use async_io::file::{Async, File};
+use async_io::socket::{RecvFlags, SendFlags};
 use inherit_methods_macro::inherit_methods;
 
 use std::sync::Weak;
 use std::time::Duration;
 
 use super::*;
-use crate::fs::DiskFile;
+use crate::fs::{DiskFile, PipeReader, PipeWriter};
 use crate::net::SocketFile;
 use crate::poll::EpollFile;
 use crate::time::TimerFile;
@@ -137,6 +138,43 @@ impl FileHandle {
         apply_fn_on_any_file!(&self.0.file, |file| { file.writev(bufs).await })
     }
 
+    /// Read some data into a set of buffers without blocking.
+    ///
+    /// EAGAIN is returned if the read would block, as if the file were
+    /// non-blocking. EOPNOTSUPP is returned if the file cannot read without
+    /// possibly blocking.
+    pub async fn try_readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        match &self.0.file {
+            AnyFile::File(file) => file.file().readv(bufs),
+            // Reads of inode files never block
+            AnyFile::Inode(file) => file.readv(bufs).await,
+            AnyFile::Socket(file) => file
+                .recvmsg(bufs, RecvFlags::MSG_DONTWAIT)
+                .await
+                .map(|(len, _, _)| len),
+            AnyFile::Epoll(_) | AnyFile::Timer(_) | AnyFile::Disk(_) => {
+                return_errno!(EOPNOTSUPP, "the file cannot read without blocking")
+            }
+        }
+    }
+
+    /// Write the data from a set of buffers without blocking.
+    ///
+    /// EAGAIN is returned if the write would block, as if the file were
+    /// non-blocking. EOPNOTSUPP is returned if the file cannot write without
+    /// possibly blocking.
+    pub async fn try_writev(&self, bufs: &[&[u8]]) -> Result<usize> {
+        match &self.0.file {
+            AnyFile::File(file) => file.file().writev(bufs),
+            // Writes of inode files never block
+            AnyFile::Inode(file) => file.writev(bufs).await,
+            AnyFile::Socket(file) => file.sendmsg(bufs, None, SendFlags::MSG_DONTWAIT).await,
+            AnyFile::Epoll(_) | AnyFile::Timer(_) | AnyFile::Disk(_) => {
+                return_errno!(EOPNOTSUPP, "the file cannot write without blocking")
+            }
+        }
+    }
+
     /// Returns the access mode of the file.
     pub fn access_mode(&self) -> AccessMode {
         apply_fn_on_any_file!(&self.0.file, |file| { file.access_mode() })
@@ -218,6 +256,16 @@ impl FileHandle {
         }
     }
 
+    /// Returns the underlying pipe reader if it is one.
+    pub fn as_pipe_reader(&self) -> Option<&PipeReader> {
+        self.as_async_file()?.file().as_any()?.downcast_ref()
+    }
+
+    /// Returns the underlying pipe writer if it is one.
+    pub fn as_pipe_writer(&self) -> Option<&PipeWriter> {
+        self.as_async_file()?.file().as_any()?.downcast_ref()
+    }
+
     /// Downgrade the file handle to its weak counterpart.
     pub fn downgrade(&self) -> WeakFileHandle {
         let any_weak_file = match &self.0.file {
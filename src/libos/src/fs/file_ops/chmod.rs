@@ -8,6 +8,7 @@ pub fn do_fchmodat(fs_path: &FsPath, mode: FileMode) -> Result<()> {
         let fs = current.fs().read().unwrap();
         fs.lookup_inode(fs_path)?
     };
+    fs_ops::check_mount_writable(&*inode)?;
     let mut info = inode.metadata()?;
     info.mode = mode.bits();
     inode.set_metadata(&info)?;
@@ -22,6 +23,7 @@ pub fn do_fchmod(fd: FileDesc, mode: FileMode) -> Result<()> {
         .as_inode_file()
         .ok_or_else(|| errno!(EBADF, "not an inode"))?;
     let inode = inode_file.inode();
+    fs_ops::check_mount_writable(&**inode)?;
     let mut info = inode.metadata()?;
     info.mode = mode.bits();
     inode.set_metadata(&info)?;
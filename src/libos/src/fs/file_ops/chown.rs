@@ -22,6 +22,7 @@ pub fn do_fchownat(fs_path: &FsPath, uid: u32, gid: u32, flags: ChownFlags) -> R
             fs.lookup_inode(fs_path)?
         }
     };
+    fs_ops::check_mount_writable(&*inode)?;
     let mut info = inode.metadata()?;
     info.uid = uid as usize;
     info.gid = gid as usize;
@@ -37,6 +38,7 @@ pub fn do_fchown(fd: FileDesc, uid: u32, gid: u32) -> Result<()> {
         .as_inode_file()
         .ok_or_else(|| errno!(EBADF, "not an inode"))?;
     let inode = inode_file.inode();
+    fs_ops::check_mount_writable(&**inode)?;
     let mut info = inode.metadata()?;
     info.uid = uid as usize;
     info.gid = gid as usize;
@@ -1,8 +1,23 @@
 use super::*;
 
-pub fn do_close(fd: FileDesc) -> Result<()> {
+pub async fn do_close(fd: FileDesc) -> Result<()> {
     debug!("close: fd: {}", fd);
     let current = current!();
+    let file = current.file(fd)?;
     current.close_file(fd)?;
+
+    // The fd has been closed, but a socket may need some time to flush its
+    // buffered data. The errors reported by the flushing are ignored as the
+    // socket is closed anyway.
+    if let Some(socket_file) = file.as_socket_file() {
+        if let Err(e) = socket_file.close().await {
+            warn!("close: failed to flush the socket: {:?}", e);
+        }
+    }
+    // Report the writeback error of a disk that has not been reported to this
+    // file yet. Like Linux, the fd is closed even if an error is returned.
+    if let Some(disk_file) = file.as_disk_file() {
+        disk_file.check_wb_err()?;
+    }
     Ok(())
 }
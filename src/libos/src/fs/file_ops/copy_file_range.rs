@@ -0,0 +1,100 @@
+use super::*;
+
+pub fn do_copy_file_range(
+    fd_in: FileDesc,
+    off_in: Option<off_t>,
+    fd_out: FileDesc,
+    off_out: Option<off_t>,
+    len: usize,
+    flags: u32,
+) -> Result<(usize, usize, usize)> {
+    // (len, in offset, out offset)
+    debug!(
+        "copy_file_range: in: {}, off_in: {:?}, out: {}, off_out: {:?}, len: {}, flags: {:#x}",
+        fd_in, off_in, fd_out, off_out, len, flags
+    );
+    if flags != 0 {
+        return_errno!(EINVAL, "flags must be zero");
+    }
+    if off_in.map_or(false, |off| off < 0) || off_out.map_or(false, |off| off < 0) {
+        return_errno!(EINVAL, "offset must not be negative");
+    }
+
+    let current = current!();
+    let in_file = current.file(fd_in)?;
+    let in_inode_file = in_file
+        .as_inode_file()
+        .ok_or_else(|| errno!(EINVAL, "not an inode"))?;
+    let out_file = current.file(fd_out)?;
+    let out_inode_file = out_file
+        .as_inode_file()
+        .ok_or_else(|| errno!(EINVAL, "not an inode"))?;
+    if !in_inode_file.access_mode().readable() || !out_inode_file.access_mode().writable() {
+        return_errno!(EBADF, "file not opened for reading or writing");
+    }
+    if out_inode_file.status_flags().always_append() {
+        return_errno!(EBADF, "output file is opened with O_APPEND");
+    }
+    let in_metadata = in_inode_file.inode().metadata()?;
+    let out_metadata = out_inode_file.inode().metadata()?;
+    if in_metadata.type_ == FileType::Dir || out_metadata.type_ == FileType::Dir {
+        return_errno!(EISDIR, "cannot copy from or to a directory");
+    }
+    if in_metadata.type_ != FileType::File || out_metadata.type_ != FileType::File {
+        return_errno!(EINVAL, "not a regular file");
+    }
+
+    let mut read_offset = match off_in {
+        Some(offset) => offset as usize,
+        None => in_inode_file.position(),
+    };
+    let mut write_offset = match off_out {
+        Some(offset) => offset as usize,
+        None => out_inode_file.position(),
+    };
+    // Never copy beyond the end of the source file
+    let len = len.min(in_metadata.size.saturating_sub(read_offset));
+
+    // Both fds may refer to the same underlying file, in which case the two
+    // ranges must not overlap.
+    let is_same_file =
+        in_metadata.dev == out_metadata.dev && in_metadata.inode == out_metadata.inode;
+    if is_same_file && read_offset < write_offset + len && write_offset < read_offset + len {
+        return_errno!(EINVAL, "the source and destination ranges overlap");
+    }
+
+    let mut buffer = [0_u8; 0x1000];
+    let mut bytes_copied = 0;
+    while bytes_copied < len {
+        let buf_len = buffer.len().min(len - bytes_copied);
+        let read_len = match in_inode_file.read_at(read_offset, &mut buffer[..buf_len]) {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            Err(_) if bytes_copied > 0 => break,
+            Err(e) => return Err(e),
+        };
+        let write_len = match out_inode_file.write_at(write_offset, &buffer[..read_len]) {
+            Ok(write_len) => write_len,
+            Err(_) if bytes_copied > 0 => break,
+            Err(e) => return Err(e),
+        };
+        bytes_copied += write_len;
+        // Both offsets advance by the write length, which could be smaller
+        // than the read length.
+        read_offset += write_len;
+        write_offset += write_len;
+        if write_len != read_len {
+            break;
+        }
+    }
+
+    // The file offset is adjusted only if the corresponding offset is not
+    // specified
+    if off_in.is_none() {
+        in_inode_file.seek(SeekFrom::Current(bytes_copied as i64))?;
+    }
+    if off_out.is_none() {
+        out_inode_file.seek(SeekFrom::Current(bytes_copied as i64))?;
+    }
+    Ok((bytes_copied, read_offset, write_offset))
+}
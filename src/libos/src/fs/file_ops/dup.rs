@@ -4,35 +4,50 @@ use misc::resource_t;
 pub fn do_dup(old_fd: FileDesc) -> Result<FileDesc> {
     let current = current!();
     let file = current.file(old_fd)?;
-    let new_fd = current.add_file(file, false);
+    let new_fd = current.add_file(file, false)?;
     Ok(new_fd)
 }
 
 pub fn do_dup2(old_fd: FileDesc, new_fd: FileDesc) -> Result<FileDesc> {
     let current = current!();
-    let mut files = current.files().lock().unwrap();
-    let file = files.get(old_fd)?;
-    let soft_rlimit_nofile = current!()
-        .rlimits()
-        .lock()
-        .unwrap()
-        .get(resource_t::RLIMIT_NOFILE)
-        .get_cur();
-    if new_fd as u64 >= soft_rlimit_nofile {
-        return_errno!(EBADF, "Invalid new_fd file descriptor");
-    }
+    check_new_fd(new_fd)?;
 
-    if old_fd != new_fd {
-        files.put_at(new_fd, file, false);
+    let replaced_file = {
+        let mut files = current.files().lock().unwrap();
+        let file = files.get(old_fd)?;
+        if old_fd == new_fd {
+            return Ok(new_fd);
+        }
+        files.put_at(new_fd, file, false)
+    };
+    if let Some(replaced_file) = replaced_file {
+        release_replaced_file(replaced_file);
     }
     Ok(new_fd)
 }
 
 pub fn do_dup3(old_fd: FileDesc, new_fd: FileDesc, flags: u32) -> Result<FileDesc> {
-    let creation_flags = CreationFlags::from_bits_truncate(flags);
+    let creation_flags = CreationFlags::from_bits(flags)
+        .filter(|flags| CreationFlags::O_CLOEXEC.contains(*flags))
+        .ok_or_else(|| errno!(EINVAL, "only O_CLOEXEC is allowed in flags"))?;
     let current = current!();
-    let mut files = current.files().lock().unwrap();
-    let file = files.get(old_fd)?;
+    check_new_fd(new_fd)?;
+
+    let replaced_file = {
+        let mut files = current.files().lock().unwrap();
+        let file = files.get(old_fd)?;
+        if old_fd == new_fd {
+            return_errno!(EINVAL, "old_fd must not be equal to new_fd");
+        }
+        files.put_at(new_fd, file, creation_flags.must_close_on_spawn())
+    };
+    if let Some(replaced_file) = replaced_file {
+        release_replaced_file(replaced_file);
+    }
+    Ok(new_fd)
+}
+
+fn check_new_fd(new_fd: FileDesc) -> Result<()> {
     let soft_rlimit_nofile = current!()
         .rlimits()
         .lock()
@@ -42,10 +57,15 @@ pub fn do_dup3(old_fd: FileDesc, new_fd: FileDesc, flags: u32) -> Result<FileDes
     if new_fd as u64 >= soft_rlimit_nofile {
         return_errno!(EBADF, "Invalid new_fd file descriptor");
     }
+    Ok(())
+}
 
-    if old_fd == new_fd {
-        return_errno!(EINVAL, "old_fd must not be equal to new_fd");
+// The file replaced by dup2 or dup3 is closed silently, just like Linux, which
+// ignores any error of closing the file. This is done after releasing the lock
+// of the file table, so it could never affect the newly duplicated fd.
+fn release_replaced_file(file: FileRef) {
+    if let Some(inode_file) = file.as_inode_file() {
+        inode_file.release_range_locks();
     }
-    files.put_at(new_fd, file, creation_flags.must_close_on_spawn());
-    Ok(new_fd)
+    drop(file);
 }
@@ -0,0 +1,71 @@
+use super::*;
+
+/// The advice of posix_fadvise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum FileAdvice {
+    /// No special treatment
+    Normal = 0,
+    /// Expect random accesses
+    Random = 1,
+    /// Expect sequential accesses
+    Sequential = 2,
+    /// Expect accesses in the near future
+    WillNeed = 3,
+    /// Do not expect accesses in the near future
+    DontNeed = 4,
+    /// Expect accesses only once
+    NoReuse = 5,
+}
+
+impl FileAdvice {
+    pub fn from_i32(raw: i32) -> Result<Self> {
+        Ok(match raw {
+            0 => Self::Normal,
+            1 => Self::Random,
+            2 => Self::Sequential,
+            3 => Self::WillNeed,
+            4 => Self::DontNeed,
+            5 => Self::NoReuse,
+            _ => return_errno!(EINVAL, "invalid advice"),
+        })
+    }
+
+    /// Whether the advice is about the access pattern of the whole file,
+    /// which is recorded on the file, rather than a one-off action.
+    pub fn is_access_pattern(&self) -> bool {
+        matches!(self, Self::Normal | Self::Random | Self::Sequential)
+    }
+}
+
+impl Default for FileAdvice {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+pub async fn do_fadvise(fd: FileDesc, offset: usize, len: usize, advice: FileAdvice) -> Result<()> {
+    debug!(
+        "fadvise: fd: {}, offset: {}, len: {}, advice: {:?}",
+        fd, offset, len, advice
+    );
+    let file_ref = current!().file(fd)?;
+    if let Some(inode_file) = file_ref.as_inode_file() {
+        if advice.is_access_pattern() {
+            inode_file.set_access_hint(advice);
+        } else if advice == FileAdvice::DontNeed {
+            // There is no page cache to drop, but the data in the range are
+            // expected to be written back. The whole file is synced as the
+            // file systems cannot sync a range.
+            inode_file.inode().sync_data()?;
+        }
+        Ok(())
+    } else if let Some(disk_file) = file_ref.as_disk_file() {
+        if advice == FileAdvice::DontNeed {
+            disk_file.flush().await?;
+        }
+        Ok(())
+    } else {
+        return_errno!(ESPIPE, "not a regular file");
+    }
+}
@@ -11,8 +11,12 @@ pub fn do_fallocate(fd: FileDesc, flags: FallocateFlags, offset: usize, len: usi
             return_errno!(EBADF, "File is not opened for writing");
         }
         let mode = FallocateMode::from(flags);
-        inode_file.inode().fallocate(&mode, offset, len)?;
-        Ok(())
+        let inode = inode_file.inode();
+        fs_ops::check_mount_writable(&**inode)?;
+        if let FallocateMode::PunchHoleKeepSize = mode {
+            return punch_hole(inode, offset, len);
+        }
+        inode.fallocate_zeroed(&mode, offset, len)
     } else if let Some(device_file) = file_ref.as_disk_file() {
         // do nothing
         warn!("disk_file does not support fallocate");
@@ -21,3 +25,20 @@ pub fn do_fallocate(fd: FileDesc, flags: FallocateFlags, offset: usize, len: usi
         return_errno!(EBADF, "not supported");
     }
 }
+
+/// Deallocate the range `[offset, offset + len)` of the inode, which reads back
+/// as zeros afterwards. The file size is never changed.
+fn punch_hole(inode: &Arc<dyn INode>, offset: usize, len: usize) -> Result<()> {
+    let size = inode.metadata()?.size;
+    if offset >= size {
+        return Ok(());
+    }
+    // Nothing beyond the end of the file needs to be punched
+    let len = len.min(size - offset);
+    match inode.fallocate(&FallocateMode::PunchHoleKeepSize, offset, len) {
+        Ok(()) => Ok(()),
+        // The blocks cannot be dropped, but the range still reads back as zeros
+        Err(FsError::NotSupported) => inode.write_zeros_at(offset, len),
+        Err(e) => Err(e.into()),
+    }
+}
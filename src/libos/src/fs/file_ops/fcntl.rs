@@ -1,5 +1,6 @@
 use super::*;
 use async_io::file::flock_c;
+use misc::resource_t;
 use util::mem_util::from_user;
 
 pub async fn do_fcntl(fd: FileDesc, cmd: &mut FcntlCmd<'_>) -> Result<isize> {
@@ -10,10 +11,12 @@ pub async fn do_fcntl(fd: FileDesc, cmd: &mut FcntlCmd<'_>) -> Result<isize> {
 
     let ret = match cmd {
         FcntlCmd::DupFd(min_fd) => {
+            check_dup_min_fd(&file_table, *min_fd)?;
             let dup_fd = file_table.dup(fd, *min_fd, false)?;
             dup_fd as isize
         }
         FcntlCmd::DupFdCloexec(min_fd) => {
+            check_dup_min_fd(&file_table, *min_fd)?;
             let dup_fd = file_table.dup(fd, *min_fd, true)?;
             dup_fd as isize
         }
@@ -96,6 +99,10 @@ pub async fn do_fcntl(fd: FileDesc, cmd: &mut FcntlCmd<'_>) -> Result<isize> {
         }
         FcntlCmd::SetLkWait(flock_c) => {
             let file = file_table.get(fd)?;
+            // The lock may be waited for a long time, during which the file
+            // table must be accessible to the other threads (e.g., to close
+            // the file and release the lock).
+            drop(file_table);
             let inode_file = file
                 .as_inode_file()
                 .ok_or_else(|| errno!(EBADF, "not an inode file"))?;
@@ -119,10 +126,75 @@ pub async fn do_fcntl(fd: FileDesc, cmd: &mut FcntlCmd<'_>) -> Result<isize> {
                 .await?;
             0
         }
+        FcntlCmd::GetPipeSz() => {
+            let file = file_table.get(fd)?;
+            let buf_size = if let Some(pipe_reader) = file.as_pipe_reader() {
+                pipe_reader.buf_size()
+            } else if let Some(pipe_writer) = file.as_pipe_writer() {
+                pipe_writer.buf_size()
+            } else {
+                return_errno!(EBADF, "not a pipe");
+            };
+            buf_size as isize
+        }
+        FcntlCmd::SetPipeSz(size) => {
+            let file = file_table.get(fd)?;
+            let buf_size = if let Some(pipe_reader) = file.as_pipe_reader() {
+                pipe_reader.set_buf_size(*size)?
+            } else if let Some(pipe_writer) = file.as_pipe_writer() {
+                pipe_writer.set_buf_size(*size)?
+            } else {
+                return_errno!(EBADF, "not a pipe");
+            };
+            buf_size as isize
+        }
+        FcntlCmd::AddSeals(seals) => {
+            let file = file_table.get(fd)?;
+            if !file.access_mode().writable() {
+                return_errno!(EPERM, "file not opened for writing");
+            }
+            as_memfd_inode(&file)?.add_seals(*seals)?;
+            0
+        }
+        FcntlCmd::GetSeals() => {
+            let file = file_table.get(fd)?;
+            as_memfd_inode(&file)?.seals().bits() as isize
+        }
     };
     Ok(ret)
 }
 
+fn as_memfd_inode(file: &FileRef) -> Result<&MemfdInode> {
+    file.as_inode_file()
+        .and_then(|inode_file| inode_file.inode().as_any_ref().downcast_ref::<MemfdInode>())
+        .ok_or_else(|| errno!(EINVAL, "the file does not support seals"))
+}
+
+fn check_dup_min_fd(file_table: &FileTable, min_fd: FileDesc) -> Result<()> {
+    let soft_rlimit_nofile = current!()
+        .rlimits()
+        .lock()
+        .unwrap()
+        .get(resource_t::RLIMIT_NOFILE)
+        .get_cur();
+    if min_fd as u64 >= soft_rlimit_nofile {
+        return_errno!(
+            EINVAL,
+            "min_fd exceeds the limit on the number of open files"
+        );
+    }
+    if file_table.min_free_fd(min_fd) as u64 >= soft_rlimit_nofile {
+        return_errno!(EMFILE, "the limit on the number of open files is reached");
+    }
+    Ok(())
+}
+
+// The commands that may be absent in libc
+const F_SETPIPE_SZ: c_int = 1031;
+const F_GETPIPE_SZ: c_int = 1032;
+const F_ADD_SEALS: c_int = 1033;
+const F_GET_SEALS: c_int = 1034;
+
 #[derive(Debug)]
 pub enum FcntlCmd<'a> {
     /// Duplicate the file descriptor fd using the lowest-numbered available
@@ -145,6 +217,14 @@ pub enum FcntlCmd<'a> {
     SetLk(&'a flock_c),
     /// The blocking version of SetLk
     SetLkWait(&'a flock_c),
+    /// Get the buffer size of a pipe
+    GetPipeSz(),
+    /// Set the buffer size of a pipe
+    SetPipeSz(usize),
+    /// Add seals to a memfd
+    AddSeals(FileSeals),
+    /// Get the seals of a memfd
+    GetSeals(),
 }
 
 impl<'a> FcntlCmd<'a> {
@@ -181,6 +261,10 @@ impl<'a> FcntlCmd<'a> {
                 let flock_c = unsafe { &*flock_ptr };
                 FcntlCmd::SetLkWait(flock_c)
             }
+            F_GETPIPE_SZ => FcntlCmd::GetPipeSz(),
+            F_SETPIPE_SZ => FcntlCmd::SetPipeSz(arg as usize),
+            F_ADD_SEALS => FcntlCmd::AddSeals(FileSeals::from_u32(arg as u32)?),
+            F_GET_SEALS => FcntlCmd::GetSeals(),
             _ => return_errno!(EINVAL, "unsupported command"),
         })
     }
@@ -17,6 +17,7 @@ pub fn do_linkat(old_fs_path: &FsPath, new_fs_path: &FsPath, flags: LinkFlags) -
         let (new_dir_inode, new_file_name) = fs.lookup_dirinode_and_basename(new_fs_path)?;
         (inode, new_dir_inode, new_file_name)
     };
+    fs_ops::check_mount_writable(&*new_dir_inode)?;
     new_dir_inode.link(&new_file_name, &inode)?;
     Ok(())
 }
@@ -3,18 +3,23 @@ use super::*;
 pub fn do_mkdirat(fs_path: &FsPath, mode: FileMode) -> Result<()> {
     debug!("mkdirat: fs_path: {:?}, mode: {:#o}", fs_path, mode);
 
-    let (dir_inode, file_name) = {
+    let (dir_inode, file_name, creation_defaults) = {
         let current = current!();
         let fs = current.fs().read().unwrap();
-        fs.lookup_dirinode_and_basename(fs_path)?
+        let (dir_inode, file_name) = fs.lookup_dirinode_and_basename(fs_path)?;
+        let abs_path = fs.convert_fspath_to_abs(fs_path)?;
+        let creation_defaults = fs_ops::creation_defaults_of(&abs_path);
+        (dir_inode, file_name, creation_defaults)
     };
     if dir_inode.find(&file_name).is_ok() {
         return_errno!(EEXIST, "");
     }
+    fs_ops::check_mount_writable(&*dir_inode)?;
     if !dir_inode.allow_write()? {
         return_errno!(EPERM, "dir cannot be written");
     }
-    let masked_mode = mode & !current!().process().umask();
-    dir_inode.create(&file_name, FileType::Dir, masked_mode.bits())?;
+    let masked_mode = creation_defaults.mode_of(FileType::Dir, mode, current!().process().umask());
+    let new_inode = dir_inode.create(&file_name, FileType::Dir, masked_mode.bits())?;
+    creation_defaults.set_owner_of(&new_inode)?;
     Ok(())
 }
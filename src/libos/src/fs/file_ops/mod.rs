@@ -5,8 +5,10 @@ pub use self::access::{do_faccessat, AccessibilityCheckFlags, AccessibilityCheck
 pub use self::chmod::{do_fchmod, do_fchmodat};
 pub use self::chown::{do_fchown, do_fchownat, ChownFlags};
 pub use self::close::do_close;
+pub use self::copy_file_range::do_copy_file_range;
 // pub use self::dirent::{do_getdents, do_getdents64};
 pub use self::dup::{do_dup, do_dup2, do_dup3};
+pub use self::fadvise::{do_fadvise, FileAdvice};
 pub use self::fallocate::do_fallocate;
 pub use self::fcntl::{do_fcntl, FcntlCmd};
 // pub use self::file_flags::{AccessMode, CreationFlags, StatusFlags};
@@ -19,25 +21,29 @@ pub use self::ioctl::{
 pub use self::link::{do_linkat, LinkFlags};
 pub use self::lseek::do_lseek;
 pub use self::mkdir::do_mkdirat;
-pub use self::open::do_openat;
-pub use self::read::{do_pread, do_read, do_readv};
-pub use self::rename::do_renameat;
+pub use self::open::{do_openat, do_openat2, open_how, ResolveFlags};
+pub use self::read::{do_pread, do_preadv2, do_read, do_readv};
+pub use self::rename::{do_renameat, do_renameat2, RenameFlags};
 pub use self::rmdir::do_rmdir;
+pub use self::rwf_flags::RwfFlags;
 pub use self::sendfile::do_sendfile;
-pub use self::stat::{do_fstat, do_fstatat};
+pub use self::splice::{do_splice, SpliceFlags};
+pub use self::stat::{do_fstat, do_fstatat, Statx, StatxMask};
 pub use self::symlink::{do_readlinkat, do_symlinkat};
 pub use self::truncate::{do_ftruncate, do_truncate};
 pub use self::unlink::{do_unlinkat, UnlinkFlags};
 pub use self::utimes::{
     do_utimes_fd, do_utimes_path, get_utimes, utimbuf_t, Utime, UtimeFlags, UTIME_OMIT,
 };
-pub use self::write::{do_pwrite, do_write, do_writev};
+pub use self::write::{do_pwrite, do_pwritev2, do_write, do_writev};
 
 mod access;
 mod chmod;
 mod chown;
 mod close;
+mod copy_file_range;
 mod dup;
+mod fadvise;
 mod fallocate;
 pub mod fcntl;
 mod getdents;
@@ -52,7 +58,9 @@ mod open;
 mod read;
 mod rename;
 mod rmdir;
+mod rwf_flags;
 mod sendfile;
+mod splice;
 mod stat;
 mod symlink;
 mod truncate;
@@ -1,27 +1,108 @@
 use super::builtin_disk::try_open_disk;
 use super::*;
 use crate::fs::DiskFile;
+use std::convert::TryFrom;
 
 pub fn do_openat(fs_path: &FsPath, flags: u32, mode: FileMode) -> Result<FileDesc> {
     debug!(
         "openat: fs_path: {:?}, flags: {:#o}, mode: {:#o}",
         fs_path, flags, mode
     );
+    open_with_resolve(fs_path, flags, mode, ResolveFlags::empty())
+}
 
+fn open_with_resolve(
+    fs_path: &FsPath,
+    flags: u32,
+    mode: FileMode,
+    resolve: ResolveFlags,
+) -> Result<FileDesc> {
     let current = current!();
     let fs = current.fs().read().unwrap();
-    let masked_mode = mode & !current.process().umask();
+    let creation_flags = CreationFlags::from_bits_truncate(flags);
+    let masked_mode = {
+        let umask = current.process().umask();
+        if creation_flags.can_create() {
+            let abs_path = fs.convert_fspath_to_abs(fs_path)?;
+            fs_ops::creation_defaults_of(&abs_path).mode_of(FileType::File, mode, umask)
+        } else {
+            mode & !umask
+        }
+    };
 
-    let file_ref = if let Some(disk_file) = try_open_disk(&fs, fs_path)? {
+    // The built-in disks are not in the FS tree, so they are out of the reach of
+    // a restricted path resolution
+    let disk_file = if resolve.is_empty() {
+        try_open_disk(&fs, fs_path)?
+    } else {
+        None
+    };
+    let file_ref = if let Some(disk_file) = disk_file {
         FileRef::new_disk(disk_file)
     } else {
-        let inode_file = fs.open_file(&fs_path, flags, masked_mode)?;
+        let inode_file = fs.open_file_with_resolve(&fs_path, flags, masked_mode, resolve)?;
         FileRef::new_inode(inode_file)
     };
 
-    let fd = {
-        let creation_flags = CreationFlags::from_bits_truncate(flags);
-        current.add_file(file_ref, creation_flags.must_close_on_spawn())
-    };
+    let fd = current.add_file(file_ref, creation_flags.must_close_on_spawn())?;
     Ok(fd)
 }
+
+/// The argument of openat2.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+#[allow(non_camel_case_types)]
+pub struct open_how {
+    pub flags: u64,
+    pub mode: u64,
+    pub resolve: u64,
+}
+
+bitflags::bitflags! {
+    /// The flags that restrict the path resolution of openat2.
+    pub struct ResolveFlags: u64 {
+        /// Do not cross mount points (not supported)
+        const RESOLVE_NO_XDEV = 0x01;
+        /// Do not follow magic links (not supported)
+        const RESOLVE_NO_MAGICLINKS = 0x02;
+        /// Do not follow any symlinks
+        const RESOLVE_NO_SYMLINKS = 0x04;
+        /// Do not resolve to any path outside the directory
+        const RESOLVE_BENEATH = 0x08;
+        /// Treat the directory as the root (not supported)
+        const RESOLVE_IN_ROOT = 0x10;
+        /// Only resolve with cached lookups (not supported)
+        const RESOLVE_CACHED = 0x20;
+    }
+}
+
+impl ResolveFlags {
+    pub fn from_u64(raw: u64) -> Result<Self> {
+        let flags = Self::from_bits(raw).ok_or_else(|| errno!(EINVAL, "invalid resolve flags"))?;
+        if flags.intersects(
+            Self::RESOLVE_NO_XDEV
+                | Self::RESOLVE_NO_MAGICLINKS
+                | Self::RESOLVE_IN_ROOT
+                | Self::RESOLVE_CACHED,
+        ) {
+            return_errno!(EINVAL, "the resolve flags are not supported");
+        }
+        Ok(flags)
+    }
+}
+
+pub fn do_openat2(fs_path: &FsPath, how: &open_how) -> Result<FileDesc> {
+    debug!("openat2: fs_path: {:?}, how: {:?}", fs_path, how);
+
+    let flags = u32::try_from(how.flags).map_err(|_| errno!(EINVAL, "invalid flags"))?;
+    if how.mode & !0o7777 != 0 {
+        return_errno!(EINVAL, "invalid mode");
+    }
+    let creation_flags = CreationFlags::from_bits_truncate(flags);
+    if how.mode != 0 && !creation_flags.can_create() {
+        return_errno!(EINVAL, "mode must be zero unless O_CREAT is specified");
+    }
+    let mode = FileMode::from_bits_truncate(how.mode as u16);
+    let resolve = ResolveFlags::from_u64(how.resolve)?;
+    open_with_resolve(fs_path, flags, mode, resolve)
+}
@@ -25,3 +25,30 @@ pub async fn do_pread(fd: FileDesc, buf: &mut [u8], offset: off_t) -> Result<usi
         file_ref.read(buf).await
     }
 }
+
+pub async fn do_preadv2(
+    fd: FileDesc,
+    bufs: &mut [&mut [u8]],
+    offset: Option<off_t>,
+    flags: RwfFlags,
+) -> Result<usize> {
+    debug!(
+        "preadv2: fd: {}, offset: {:?}, flags: {:?}",
+        fd, offset, flags
+    );
+    let file_ref = current!().file(fd)?;
+    if let Some(inode_file) = file_ref.as_inode_file() {
+        // Reads of inode files never block, so RWF_NOWAIT has no effect
+        match offset {
+            Some(offset) => inode_file.readv_at(offset as usize, bufs),
+            None => inode_file.readv(bufs),
+        }
+    } else {
+        // For non-inode files, we simply ignore the offset
+        if flags.contains(RwfFlags::RWF_NOWAIT) {
+            file_ref.try_readv(bufs).await
+        } else {
+            file_ref.readv(bufs).await
+        }
+    }
+}
@@ -1,9 +1,46 @@
 use super::*;
 
+bitflags::bitflags! {
+    pub struct RenameFlags: u32 {
+        /// Do not overwrite the newpath
+        const RENAME_NOREPLACE = 1 << 0;
+        /// Exchange the oldpath and the newpath
+        const RENAME_EXCHANGE = 1 << 1;
+        /// Create a whiteout object at the oldpath (not supported)
+        const RENAME_WHITEOUT = 1 << 2;
+    }
+}
+
+impl RenameFlags {
+    pub fn from_u32(raw: u32) -> Result<Self> {
+        let flags = Self::from_bits(raw).ok_or_else(|| errno!(EINVAL, "invalid rename flags"))?;
+        if flags.contains(Self::RENAME_WHITEOUT) {
+            return_errno!(EINVAL, "RENAME_WHITEOUT is not supported");
+        }
+        if flags.contains(Self::RENAME_NOREPLACE | Self::RENAME_EXCHANGE) {
+            return_errno!(
+                EINVAL,
+                "RENAME_NOREPLACE and RENAME_EXCHANGE cannot be used together"
+            );
+        }
+        Ok(flags)
+    }
+}
+
+lazy_static! {
+    /// Serialize the renames, so that the check of RENAME_NOREPLACE and the moves
+    /// of RENAME_EXCHANGE are not interleaved with other renames.
+    static ref RENAME_LOCK: SgxMutex<()> = SgxMutex::new(());
+}
+
 pub fn do_renameat(old_fs_path: &FsPath, new_fs_path: &FsPath) -> Result<()> {
+    do_renameat2(old_fs_path, new_fs_path, RenameFlags::empty())
+}
+
+pub fn do_renameat2(old_fs_path: &FsPath, new_fs_path: &FsPath, flags: RenameFlags) -> Result<()> {
     debug!(
-        "renameat: old_fs_path: {:?}, new_fs_path: {:?}",
-        old_fs_path, new_fs_path
+        "renameat2: old_fs_path: {:?}, new_fs_path: {:?}, flags: {:?}",
+        old_fs_path, new_fs_path, flags
     );
 
     let current = current!();
@@ -15,9 +52,19 @@ pub fn do_renameat(old_fs_path: &FsPath, new_fs_path: &FsPath) -> Result<()> {
     if new_path.starts_with(&old_path) && new_path != old_path {
         return_errno!(EINVAL, "newpath contains a path prefix of the oldpath");
     }
+    // The exchange also moves the newpath to the oldpath
+    if flags.contains(RenameFlags::RENAME_EXCHANGE)
+        && old_path.starts_with(&new_path)
+        && new_path != old_path
+    {
+        return_errno!(EINVAL, "oldpath contains a path prefix of the newpath");
+    }
 
+    let _rename_guard = RENAME_LOCK.lock().unwrap();
     let (old_dir_inode, old_file_name) = fs.lookup_dirinode_and_basename(old_fs_path)?;
     let (new_dir_inode, new_file_name) = fs.lookup_dirinode_and_basename(new_fs_path)?;
+    fs_ops::check_mount_writable(&*old_dir_inode)?;
+    fs_ops::check_mount_writable(&*new_dir_inode)?;
     let old_file_mode = {
         let old_file_inode = old_dir_inode.find(&old_file_name)?;
         let metadata = old_file_inode.metadata()?;
@@ -26,7 +73,73 @@ pub fn do_renameat(old_fs_path: &FsPath, new_fs_path: &FsPath) -> Result<()> {
     if old_file_mode.has_sticky_bit() {
         warn!("ignoring the sticky bit");
     }
+
+    if flags.contains(RenameFlags::RENAME_NOREPLACE) && new_dir_inode.find(&new_file_name).is_ok() {
+        return_errno!(EEXIST, "newpath exists");
+    }
+    if flags.contains(RenameFlags::RENAME_EXCHANGE) {
+        // Both of the paths must exist
+        new_dir_inode.find(&new_file_name)?;
+        if old_path == new_path {
+            return Ok(());
+        }
+        // TODO: support to modify file's absolute path
+        return exchange(
+            &old_dir_inode,
+            &old_file_name,
+            &new_dir_inode,
+            &new_file_name,
+        );
+    }
+    let replaced_inode = new_dir_inode.find(&new_file_name).ok();
     // TODO: support to modify file's absolute path
     old_dir_inode.move_(&old_file_name, &new_dir_inode, &new_file_name)?;
+    if let Some(replaced_inode) = replaced_inode {
+        fs_ops::release_space(&*replaced_inode)?;
+    }
+    Ok(())
+}
+
+/// Exchange the entry `name` of the `dir` directory with the entry `target_name`
+/// of the `target` directory.
+///
+/// As the file systems have no primitive to exchange two entries, this is done
+/// with three moves via a temporary entry in `dir`. The rename lock must be held,
+/// so no other rename sees the intermediate state, and a failed move is rolled
+/// back. But a lookup during the exchange may find the temporary entry.
+fn exchange(
+    dir: &Arc<dyn INode>,
+    name: &str,
+    target: &Arc<dyn INode>,
+    target_name: &str,
+) -> Result<()> {
+    let tmp_name = {
+        let ino = dir.find(name)?.metadata()?.inode;
+        let tmp_name = format!(".exchange_tmp_{}", ino);
+        if dir.find(&tmp_name).is_ok() {
+            return_errno!(EBUSY, "the temporary entry exists");
+        }
+        tmp_name
+    };
+
+    dir.move_(name, dir, &tmp_name)?;
+    if let Err(e) = target.move_(target_name, dir, name) {
+        rollback_move(dir, &tmp_name, dir, name);
+        return Err(e.into());
+    }
+    if let Err(e) = dir.move_(&tmp_name, target, target_name) {
+        rollback_move(dir, name, target, target_name);
+        rollback_move(dir, &tmp_name, dir, name);
+        return Err(e.into());
+    }
     Ok(())
 }
+
+fn rollback_move(dir: &Arc<dyn INode>, name: &str, target: &Arc<dyn INode>, target_name: &str) {
+    if let Err(e) = dir.move_(name, target, target_name) {
+        error!(
+            "failed to roll back the move of {:?} to {:?} in the exchange: {:?}",
+            name, target_name, e
+        );
+    }
+}
@@ -8,6 +8,7 @@ pub fn do_rmdir(fs_path: &FsPath) -> Result<()> {
         let fs = current.fs().read().unwrap();
         fs.lookup_dirinode_and_basename(fs_path)?
     };
+    fs_ops::check_mount_writable(&*dir_inode)?;
     let file_inode = dir_inode.find(&file_name)?;
     if file_inode.metadata()?.type_ != FileType::Dir {
         return_errno!(ENOTDIR, "rmdir on not directory");
@@ -0,0 +1,23 @@
+use super::*;
+
+bitflags::bitflags! {
+    /// The per-call flags of preadv2 and pwritev2.
+    pub struct RwfFlags: i32 {
+        /// High priority I/O, which is only a hint and is ignored
+        const RWF_HIPRI = 0x1;
+        /// Per-IO O_DSYNC
+        const RWF_DSYNC = 0x2;
+        /// Per-IO O_SYNC
+        const RWF_SYNC = 0x4;
+        /// Return EAGAIN if the I/O would block
+        const RWF_NOWAIT = 0x8;
+        /// Per-IO O_APPEND
+        const RWF_APPEND = 0x10;
+    }
+}
+
+impl RwfFlags {
+    pub fn from_i32(raw: i32) -> Result<Self> {
+        Self::from_bits(raw).ok_or_else(|| errno!(EINVAL, "invalid RWF flags"))
+    }
+}
@@ -0,0 +1,251 @@
+use super::*;
+
+bitflags::bitflags! {
+    pub struct SpliceFlags: u32 {
+        /// Attempt to move pages instead of copying (a hint, ignored)
+        const SPLICE_F_MOVE = 1 << 0;
+        /// Do not block on the pipe
+        const SPLICE_F_NONBLOCK = 1 << 1;
+        /// More data will be coming in a subsequent splice (a hint, ignored)
+        const SPLICE_F_MORE = 1 << 2;
+        /// Unused for splice
+        const SPLICE_F_GIFT = 1 << 3;
+    }
+}
+
+impl SpliceFlags {
+    pub fn from_u32(raw: u32) -> Result<Self> {
+        Self::from_bits(raw).ok_or_else(|| errno!(EINVAL, "invalid splice flags"))
+    }
+}
+
+/// Move data between a pipe and a file (or a socket, or another pipe).
+///
+/// The data are copied through a LibOS buffer, so that the pipe is never locked
+/// during the I/O of the other file. The data taken out of the pipe (or the input)
+/// are always written out entirely, waiting for the output to be writable if
+/// needed. They are lost only if the output fails.
+pub async fn do_splice(
+    fd_in: FileDesc,
+    off_in: Option<off_t>,
+    fd_out: FileDesc,
+    off_out: Option<off_t>,
+    len: usize,
+    flags: SpliceFlags,
+) -> Result<(usize, Option<usize>, Option<usize>)> {
+    // (len, in offset, out offset)
+    debug!(
+        "splice: in: {}, off_in: {:?}, out: {}, off_out: {:?}, len: {}, flags: {:?}",
+        fd_in, off_in, fd_out, off_out, len, flags
+    );
+    if off_in.map_or(false, |off| off < 0) || off_out.map_or(false, |off| off < 0) {
+        return_errno!(EINVAL, "offset must not be negative");
+    }
+
+    let current = current!();
+    let in_file = current.file(fd_in)?;
+    let out_file = current.file(fd_out)?;
+    if !in_file.access_mode().readable() || !out_file.access_mode().writable() {
+        return_errno!(EBADF, "file not opened for reading or writing");
+    }
+    if let Some(out_inode_file) = out_file.as_inode_file() {
+        if out_inode_file.status_flags().always_append() {
+            return_errno!(EINVAL, "output file is opened with O_APPEND");
+        }
+    }
+    // Only files with positions can be given an offset
+    if off_in.is_some() && in_file.as_inode_file().is_none()
+        || off_out.is_some() && out_file.as_inode_file().is_none()
+    {
+        return_errno!(ESPIPE, "offset is given to a pipe or socket");
+    }
+
+    if let Some(pipe_reader) = in_file.as_pipe_reader() {
+        let nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK)
+            || pipe_reader.status_flags().contains(StatusFlags::O_NONBLOCK);
+        let (bytes_spliced, write_offset) =
+            splice_from_pipe(&in_file, pipe_reader, &out_file, off_out, len, nonblocking).await?;
+        Ok((bytes_spliced, None, write_offset))
+    } else if let Some(pipe_writer) = out_file.as_pipe_writer() {
+        let nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK)
+            || pipe_writer.status_flags().contains(StatusFlags::O_NONBLOCK);
+        let (bytes_spliced, read_offset) =
+            splice_to_pipe(&in_file, off_in, &out_file, pipe_writer, len, nonblocking).await?;
+        Ok((bytes_spliced, read_offset, None))
+    } else {
+        return_errno!(EINVAL, "neither of the fds refers to a pipe");
+    }
+}
+
+async fn splice_from_pipe(
+    in_file: &FileRef,
+    pipe_reader: &PipeReader,
+    out_file: &FileRef,
+    off_out: Option<off_t>,
+    len: usize,
+    nonblocking: bool,
+) -> Result<(usize, Option<usize>)> {
+    let mut write_offset = off_out.map(|offset| offset as usize);
+    if len == 0 {
+        return Ok((0, write_offset));
+    }
+
+    // Wait until there are data in the pipe or the write end is closed
+    let mut poller = Poller::new();
+    while pipe_reader.ready_len() == 0 {
+        if pipe_reader.is_writer_closed() {
+            return Ok((0, write_offset));
+        }
+        if nonblocking {
+            return_errno!(EAGAIN, "no data in the pipe");
+        }
+        let events = in_file.poll(Events::IN, Some(&mut poller));
+        if events.is_empty() {
+            poller.wait().await?;
+        }
+    }
+
+    let mut buffer = [0_u8; SPLICE_BUF_SIZE];
+    let mut bytes_spliced = 0;
+    while bytes_spliced < len {
+        let buf_len = buffer.len().min(len - bytes_spliced);
+        let read_len = pipe_reader.consume_with(|first, second| {
+            let mut total_len = 0;
+            for data in [first, second].iter() {
+                let copy_len = data.len().min(buf_len - total_len);
+                buffer[total_len..total_len + copy_len].copy_from_slice(&data[..copy_len]);
+                total_len += copy_len;
+            }
+            Ok(total_len)
+        })?;
+        if read_len == 0 {
+            break;
+        }
+        let data = &buffer[..read_len];
+        let (write_len, res) = match (out_file.as_inode_file(), write_offset) {
+            (Some(out_inode_file), Some(offset)) => write_entirely_at(out_inode_file, offset, data),
+            _ => write_entirely(out_file, data).await,
+        };
+        bytes_spliced += write_len;
+        write_offset = write_offset.map(|offset| offset + write_len);
+        if let Err(e) = res {
+            if bytes_spliced == 0 {
+                return Err(e);
+            }
+            break;
+        }
+    }
+    Ok((bytes_spliced, write_offset))
+}
+
+async fn splice_to_pipe(
+    in_file: &FileRef,
+    off_in: Option<off_t>,
+    out_file: &FileRef,
+    pipe_writer: &PipeWriter,
+    len: usize,
+    nonblocking: bool,
+) -> Result<(usize, Option<usize>)> {
+    let mut read_offset = off_in.map(|offset| offset as usize);
+    if len == 0 {
+        return Ok((0, read_offset));
+    }
+
+    // Wait until there is free space in the pipe
+    let mut poller = Poller::new();
+    loop {
+        if pipe_writer.is_reader_closed() {
+            return_errno!(EPIPE, "the read end of the pipe is closed");
+        }
+        if pipe_writer.free_len() > 0 {
+            break;
+        }
+        if nonblocking {
+            return_errno!(EAGAIN, "no space in the pipe");
+        }
+        let events = out_file.poll(Events::OUT, Some(&mut poller));
+        if events.is_empty() {
+            poller.wait().await?;
+        }
+    }
+
+    let mut buffer = [0_u8; SPLICE_BUF_SIZE];
+    let mut bytes_spliced = 0;
+    while bytes_spliced < len {
+        let buf_len = buffer
+            .len()
+            .min(len - bytes_spliced)
+            .min(pipe_writer.free_len());
+        if buf_len == 0 {
+            break;
+        }
+        // Do not block once some data have been spliced
+        if bytes_spliced > 0 && in_file.poll(Events::IN, None).is_empty() {
+            break;
+        }
+        let res = match (in_file.as_inode_file(), read_offset) {
+            (Some(in_inode_file), Some(offset)) => {
+                in_inode_file.read_at(offset, &mut buffer[..buf_len])
+            }
+            _ => in_file.read(&mut buffer[..buf_len]).await,
+        };
+        let read_len = match res {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            Err(_) if bytes_spliced > 0 => break,
+            Err(e) => return Err(e),
+        };
+        read_offset = read_offset.map(|offset| offset + read_len);
+        // The free space of the pipe may have been taken by others, so the
+        // pipe may accept less data than the free space seen above
+        let (write_len, res) = write_entirely(out_file, &buffer[..read_len]).await;
+        bytes_spliced += write_len;
+        if let Err(e) = res {
+            if bytes_spliced == 0 {
+                return Err(e);
+            }
+            break;
+        }
+    }
+    Ok((bytes_spliced, read_offset))
+}
+
+// The size of the buffer that the data are copied through
+const SPLICE_BUF_SIZE: usize = 0x1000;
+
+// Write all the data to the file, waiting for it to be writable even if it is
+// non-blocking, as the data have been taken from their source. Returns the number
+// of bytes written, and the error that stops the writing, if any.
+async fn write_entirely(file: &FileRef, data: &[u8]) -> (usize, Result<()>) {
+    let mut poller = None;
+    let mut write_len = 0;
+    while write_len < data.len() {
+        match file.write(&data[write_len..]).await {
+            Ok(0) => return (write_len, Err(errno!(EIO, "the file accepts no data"))),
+            Ok(len) => write_len += len,
+            Err(e) if e.errno() == EAGAIN => {
+                let poller = poller.get_or_insert_with(Poller::new);
+                if file.poll(Events::OUT, Some(poller)).is_empty() {
+                    if let Err(e) = poller.wait().await {
+                        return (write_len, Err(e));
+                    }
+                }
+            }
+            Err(e) => return (write_len, Err(e)),
+        }
+    }
+    (write_len, Ok(()))
+}
+
+// Write all the data at the offset of the inode file, like `write_entirely`.
+fn write_entirely_at(file: &InodeFile, offset: usize, data: &[u8]) -> (usize, Result<()>) {
+    let mut write_len = 0;
+    while write_len < data.len() {
+        match file.write_at(offset + write_len, &data[write_len..]) {
+            Ok(0) => return (write_len, Err(errno!(EIO, "the file accepts no data"))),
+            Ok(len) => write_len += len,
+            Err(e) => return (write_len, Err(e)),
+        }
+    }
+    (write_len, Ok(()))
+}
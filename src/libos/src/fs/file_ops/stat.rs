@@ -37,3 +37,147 @@ pub fn do_fstatat(fs_path: &FsPath, flags: StatFlags) -> Result<StatBuf> {
 
     Ok(stat)
 }
+
+/// The buffer of the statx syscall, i.e., `struct statx`.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Statx {
+    /// mask of the fields that are filled
+    pub mask: u32,
+    /// blocksize for filesystem I/O
+    pub blksize: u32,
+    /// extra file attributes
+    pub attributes: u64,
+    /// number of hard links
+    pub nlink: u32,
+    /// user ID of owner
+    pub uid: u32,
+    /// group ID of owner
+    pub gid: u32,
+    /// file type and mode
+    pub mode: u16,
+    _spare0: u16,
+    /// inode number
+    pub ino: u64,
+    /// total size, in bytes
+    pub size: u64,
+    /// number of 512B blocks allocated
+    pub blocks: u64,
+    /// mask of the supported attributes
+    pub attributes_mask: u64,
+    /// last access time
+    pub atime: StatxTimestamp,
+    /// creation time
+    pub btime: StatxTimestamp,
+    /// last status change time
+    pub ctime: StatxTimestamp,
+    /// last modification time
+    pub mtime: StatxTimestamp,
+    /// device ID (if special file)
+    pub rdev_major: u32,
+    pub rdev_minor: u32,
+    /// ID of device containing file
+    pub dev_major: u32,
+    pub dev_minor: u32,
+    _spare2: [u64; 14],
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StatxTimestamp {
+    pub sec: i64,
+    pub nsec: u32,
+    _reserved: i32,
+}
+
+bitflags::bitflags! {
+    pub struct StatxMask: u32 {
+        const STATX_TYPE = 0x0001;
+        const STATX_MODE = 0x0002;
+        const STATX_NLINK = 0x0004;
+        const STATX_UID = 0x0008;
+        const STATX_GID = 0x0010;
+        const STATX_ATIME = 0x0020;
+        const STATX_MTIME = 0x0040;
+        const STATX_CTIME = 0x0080;
+        const STATX_INO = 0x0100;
+        const STATX_SIZE = 0x0200;
+        const STATX_BLOCKS = 0x0400;
+        const STATX_BASIC_STATS = 0x07ff;
+        const STATX_BTIME = 0x0800;
+    }
+}
+
+impl Statx {
+    /// Fill the fields that are requested by the mask with the stat.
+    ///
+    /// The creation time is not tracked by the file systems, so `STATX_BTIME`
+    /// is never reported. The fields that are not masked (e.g., the device
+    /// IDs) are always filled.
+    pub fn new(stat: &StatBuf, mask: StatxMask) -> Self {
+        let mask = mask & StatxMask::STATX_BASIC_STATS;
+        let mut statx = Self {
+            mask: mask.bits(),
+            blksize: stat.blksize as u32,
+            rdev_major: dev_major(stat.rdev),
+            rdev_minor: dev_minor(stat.rdev),
+            dev_major: dev_major(stat.dev),
+            dev_minor: dev_minor(stat.dev),
+            ..Default::default()
+        };
+
+        if mask.contains(StatxMask::STATX_TYPE) {
+            statx.mode |= (stat.mode & StatMode::TYPE_MASK).bits() as u16;
+        }
+        if mask.contains(StatxMask::STATX_MODE) {
+            statx.mode |= (stat.mode - StatMode::TYPE_MASK).bits() as u16;
+        }
+        if mask.contains(StatxMask::STATX_NLINK) {
+            statx.nlink = stat.nlink as u32;
+        }
+        if mask.contains(StatxMask::STATX_UID) {
+            statx.uid = stat.uid;
+        }
+        if mask.contains(StatxMask::STATX_GID) {
+            statx.gid = stat.gid;
+        }
+        if mask.contains(StatxMask::STATX_ATIME) {
+            statx.atime = StatxTimestamp::from(stat.atime);
+        }
+        if mask.contains(StatxMask::STATX_MTIME) {
+            statx.mtime = StatxTimestamp::from(stat.mtime);
+        }
+        if mask.contains(StatxMask::STATX_CTIME) {
+            statx.ctime = StatxTimestamp::from(stat.ctime);
+        }
+        if mask.contains(StatxMask::STATX_INO) {
+            statx.ino = stat.ino;
+        }
+        if mask.contains(StatxMask::STATX_SIZE) {
+            statx.size = stat.size;
+        }
+        if mask.contains(StatxMask::STATX_BLOCKS) {
+            statx.blocks = stat.blocks;
+        }
+        statx
+    }
+}
+
+impl From<Timespec> for StatxTimestamp {
+    fn from(time: Timespec) -> Self {
+        Self {
+            sec: time.sec as i64,
+            nsec: time.nsec as u32,
+            _reserved: 0,
+        }
+    }
+}
+
+// The same encoding of device IDs as glibc's major() and minor()
+fn dev_major(dev: u64) -> u32 {
+    (((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff)) as u32
+}
+
+fn dev_minor(dev: u64) -> u32 {
+    ((dev & 0xff) | ((dev >> 12) & !0xff)) as u32
+}
@@ -38,6 +38,7 @@ pub fn do_symlinkat(target: &str, link_path: &FsPath) -> Result<usize> {
         let fs = current.fs().read().unwrap();
         fs.lookup_dirinode_and_basename(link_path)?
     };
+    fs_ops::check_mount_writable(&*dir_inode)?;
     if !dir_inode.allow_write()? {
         return_errno!(EPERM, "symlink cannot be created");
     }
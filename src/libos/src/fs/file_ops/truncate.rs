@@ -7,7 +7,8 @@ pub fn do_truncate(fs_path: &FsPath, len: usize) -> Result<()> {
         let fs = current.fs().read().unwrap();
         fs.lookup_inode(fs_path)?
     };
-    inode.resize(len)?;
+    fs_ops::check_mount_writable(&*inode)?;
+    inode.resize_zeroed(len)?;
     Ok(())
 }
 
@@ -18,7 +19,8 @@ pub fn do_ftruncate(fd: FileDesc, len: usize) -> Result<()> {
         if !inode_file.access_mode().writable() {
             return_errno!(EBADF, "File is not opened for writing");
         }
-        inode_file.inode().resize(len)?;
+        fs_ops::check_mount_writable(&**inode_file.inode())?;
+        inode_file.inode().resize_zeroed(len)?;
         Ok(())
     } else if let Some(disk_file) = file_ref.as_disk_file() {
         warn!("disk_file does not support ftruncate");
@@ -22,6 +22,7 @@ fn do_unlink(fs_path: &FsPath) -> Result<()> {
         let fs = current.fs().read().unwrap();
         fs.lookup_dirinode_and_basename(fs_path)?
     };
+    fs_ops::check_mount_writable(&*dir_inode)?;
     let file_inode = dir_inode.find(&file_name)?;
     let metadata = file_inode.metadata()?;
     if metadata.type_ == FileType::Dir {
@@ -40,5 +41,6 @@ fn do_unlink(fs_path: &FsPath) -> Result<()> {
         warn!("ignoring the sticky bit");
     }
     dir_inode.unlink(&file_name)?;
+    fs_ops::release_space(&*file_inode)?;
     Ok(())
 }
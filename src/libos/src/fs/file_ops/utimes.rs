@@ -98,6 +98,7 @@ pub fn do_utimes_fd(fd: FileDesc, atime: Utime, mtime: Utime, flags: i32) -> Res
         .as_inode_file()
         .ok_or_else(|| errno!(EBADF, "not an inode"))?;
     let inode = inode_file.inode();
+    fs_ops::check_mount_writable(&**inode)?;
     let mut info = inode.metadata()?;
     if let Utime::UTIME(atime) = atime {
         info.atime = atime;
@@ -129,6 +130,7 @@ pub fn do_utimes_path(
             fs.lookup_inode(fs_path)?
         }
     };
+    fs_ops::check_mount_writable(&*inode)?;
     let mut info = inode.metadata()?;
     if let Utime::UTIME(atime) = atime {
         info.atime = atime;
@@ -25,3 +25,55 @@ pub async fn do_pwrite(fd: FileDesc, buf: &[u8], offset: off_t) -> Result<usize>
         file_ref.write(buf).await
     }
 }
+
+pub async fn do_pwritev2(
+    fd: FileDesc,
+    bufs: &[&[u8]],
+    offset: Option<off_t>,
+    flags: RwfFlags,
+) -> Result<usize> {
+    debug!(
+        "pwritev2: fd: {}, offset: {:?}, flags: {:?}",
+        fd, offset, flags
+    );
+    let file_ref = current!().file(fd)?;
+    let len = if let Some(inode_file) = file_ref.as_inode_file() {
+        // Writes of inode files never block, so RWF_NOWAIT has no effect
+        if flags.contains(RwfFlags::RWF_APPEND) {
+            // Append to the end of the file regardless of the offset. The file
+            // offset is updated only if no offset is given.
+            match offset {
+                Some(_) => {
+                    let file_size = inode_file.inode().metadata()?.size;
+                    inode_file.writev_at(file_size, bufs)?
+                }
+                None => inode_file.appendv(bufs)?,
+            }
+        } else {
+            match offset {
+                Some(offset) => inode_file.writev_at(offset as usize, bufs)?,
+                None => inode_file.writev(bufs)?,
+            }
+        }
+    } else {
+        // For non-inode files, we simply ignore the offset
+        if flags.contains(RwfFlags::RWF_NOWAIT) {
+            file_ref.try_writev(bufs).await?
+        } else {
+            file_ref.writev(bufs).await?
+        }
+    };
+
+    if flags.intersects(RwfFlags::RWF_DSYNC | RwfFlags::RWF_SYNC) {
+        if let Some(inode_file) = file_ref.as_inode_file() {
+            if flags.contains(RwfFlags::RWF_SYNC) {
+                inode_file.inode().sync_all()?;
+            } else {
+                inode_file.inode().sync_data()?;
+            }
+        } else if let Some(disk_file) = file_ref.as_disk_file() {
+            disk_file.flush().await?;
+        }
+    }
+    Ok(len)
+}
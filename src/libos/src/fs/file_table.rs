@@ -8,6 +8,9 @@ pub type FileDesc = u32;
 #[repr(C)]
 pub struct FileTable {
     table: Vec<Option<FileTableEntry>>,
+    // The bitmap of the file descriptors in use, which makes finding the
+    // lowest free fd efficient even when the table is large
+    used_fds: FdBitmap,
     num_fds: usize,
 }
 
@@ -15,6 +18,7 @@ impl FileTable {
     pub fn new() -> FileTable {
         FileTable {
             table: Vec::with_capacity(4),
+            used_fds: FdBitmap::new(),
             num_fds: 0,
         }
     }
@@ -23,6 +27,7 @@ impl FileTable {
         &self.table
     }
 
+    /// Duplicate the file of `fd` to the lowest free fd that is not less than `min_fd`.
     pub fn dup(
         &mut self,
         fd: FileDesc,
@@ -30,72 +35,51 @@ impl FileTable {
         close_on_spawn: bool,
     ) -> Result<FileDesc> {
         let file_ref = self.get(fd)?;
-
-        let min_fd = min_fd as usize;
-        let min_free_fd = {
-            let mut table = &mut self.table;
-
-            // Make sure that min_fd does not exceed the capacity of the table
-            if min_fd >= table.len() {
-                let expand_size = min_fd - table.len() + 1;
-                for _ in 0..expand_size {
-                    table.push(None);
-                }
-            }
-
-            let free_fd = table
-                .iter()
-                .enumerate()
-                .skip(min_fd as usize)
-                .find(|&(idx, opt)| opt.is_none());
-
-            if let Some((index, _)) = free_fd {
-                index
-            } else {
-                // Span table when no free fd is found
-                table.push(None);
-                table.len() - 1
-            }
-        } as FileDesc;
-
+        let min_free_fd = self.min_free_fd(min_fd);
         self.put_at(min_free_fd, file_ref, close_on_spawn);
-
         Ok(min_free_fd)
     }
 
+    /// Put a file at the lowest free fd.
     pub fn put(&mut self, file: FileRef, close_on_spawn: bool) -> FileDesc {
-        let mut table = &mut self.table;
-
-        let min_free_fd = if self.num_fds < table.len() {
-            table
-                .iter()
-                .enumerate()
-                .find(|&(idx, opt)| opt.is_none())
-                .unwrap()
-                .0
-        } else {
-            table.push(None);
-            table.len() - 1
-        };
-
-        table[min_free_fd as usize] = Some(FileTableEntry::new(file, close_on_spawn));
-        self.num_fds += 1;
-
-        min_free_fd as FileDesc
+        let min_free_fd = self.min_free_fd(0);
+        self.put_at(min_free_fd, file, close_on_spawn);
+        min_free_fd
     }
 
-    pub fn put_at(&mut self, fd: FileDesc, file: FileRef, close_on_spawn: bool) {
-        let mut table = &mut self.table;
+    /// Put a file at the given fd.
+    ///
+    /// If the fd is in use, the old file is closed and replaced in one step, so
+    /// that there is no moment when the fd is invalid. The replaced file is
+    /// returned to let the caller release it outside the lock of the table.
+    pub fn put_at(&mut self, fd: FileDesc, file: FileRef, close_on_spawn: bool) -> Option<FileRef> {
+        let table = &mut self.table;
         let mut table_entry = Some(FileTableEntry::new(file, close_on_spawn));
         if fd as usize >= table.len() {
             table.resize(fd as usize + 1, None);
         }
         std::mem::swap(&mut table_entry, &mut table[fd as usize]);
-        if table_entry.is_none() {
-            self.num_fds += 1;
+        match table_entry {
+            Some(replaced_entry) => {
+                replaced_entry.remove_from_epoll(fd);
+                Some(replaced_entry.file)
+            }
+            None => {
+                self.used_fds.set(fd as usize);
+                self.num_fds += 1;
+                None
+            }
         }
     }
 
+    /// Returns the lowest free fd that is not less than `min_fd`.
+    ///
+    /// The returned fd may be beyond the current size of the table, which
+    /// only grows when a file is actually put at the fd.
+    pub fn min_free_fd(&self, min_fd: FileDesc) -> FileDesc {
+        self.used_fds.first_zero_from(min_fd as usize) as FileDesc
+    }
+
     pub fn fds(&self) -> Vec<FileDesc> {
         let table = &self.table;
         table
@@ -145,6 +129,7 @@ impl FileTable {
         std::mem::swap(&mut del_table_entry, &mut table[fd as usize]);
         match del_table_entry {
             Some(del_table_entry) => {
+                self.used_fds.clear(fd as usize);
                 self.num_fds -= 1;
                 del_table_entry.remove_from_epoll(fd);
                 Ok(del_table_entry.file)
@@ -159,14 +144,15 @@ impl FileTable {
         for (fd, entry) in self
             .table
             .iter_mut()
-            .filter(|entry| entry.is_some())
             .enumerate()
+            .filter(|(_, entry)| entry.is_some())
         {
             let deleted_entry = entry.as_ref().unwrap();
             deleted_files.push(deleted_entry.file.clone());
             deleted_entry.remove_from_epoll(fd as FileDesc);
             *entry = None;
         }
+        self.used_fds = FdBitmap::new();
         self.num_fds = 0;
         deleted_files
     }
@@ -185,6 +171,7 @@ impl FileTable {
                 deleted_files.push(deleted_entry.file.clone());
                 deleted_entry.remove_from_epoll(fd as FileDesc);
                 *entry = None;
+                self.used_fds.clear(fd);
                 self.num_fds -= 1;
             }
         }
@@ -196,6 +183,7 @@ impl Clone for FileTable {
     fn clone(&self) -> Self {
         FileTable {
             table: self.table.clone(),
+            used_fds: self.used_fds.clone(),
             num_fds: self.num_fds,
         }
     }
@@ -207,6 +195,88 @@ impl Default for FileTable {
     }
 }
 
+/// A two-level bitmap of file descriptors.
+///
+/// Besides the bits of fds, there is one summary bit for each word of the
+/// bitmap, which is set when all fds of the word are in use. Thus, looking
+/// for a free fd skips full words quickly, similar to `full_fds_bits` of Linux.
+#[derive(Debug, Clone)]
+struct FdBitmap {
+    words: Vec<u64>,
+    full_words: Vec<u64>,
+}
+
+impl FdBitmap {
+    const BITS: usize = 64;
+
+    pub fn new() -> Self {
+        Self {
+            words: Vec::new(),
+            full_words: Vec::new(),
+        }
+    }
+
+    pub fn set(&mut self, fd: usize) {
+        let (word_idx, bit_idx) = (fd / Self::BITS, fd % Self::BITS);
+        if word_idx >= self.words.len() {
+            self.words.resize(word_idx + 1, 0);
+            let num_full_words = (self.words.len() + Self::BITS - 1) / Self::BITS;
+            self.full_words.resize(num_full_words, 0);
+        }
+
+        self.words[word_idx] |= 1 << bit_idx;
+        if self.words[word_idx] == !0 {
+            self.full_words[word_idx / Self::BITS] |= 1 << (word_idx % Self::BITS);
+        }
+    }
+
+    pub fn clear(&mut self, fd: usize) {
+        let (word_idx, bit_idx) = (fd / Self::BITS, fd % Self::BITS);
+        if word_idx >= self.words.len() {
+            return;
+        }
+
+        self.words[word_idx] &= !(1 << bit_idx);
+        self.full_words[word_idx / Self::BITS] &= !(1 << (word_idx % Self::BITS));
+    }
+
+    /// Returns the first unset bit that is not less than `from`.
+    pub fn first_zero_from(&self, from: usize) -> usize {
+        let (word_idx, bit_idx) = (from / Self::BITS, from % Self::BITS);
+        if word_idx >= self.words.len() {
+            return from;
+        }
+
+        // Search the first word, ignoring the bits before `from`
+        let word = self.words[word_idx] | ((1u64 << bit_idx) - 1);
+        if word != !0 {
+            return word_idx * Self::BITS + (!word).trailing_zeros() as usize;
+        }
+
+        // Search the rest words by skipping the full ones
+        let next_word_idx = self
+            .first_non_full_word_from(word_idx + 1)
+            .unwrap_or(self.words.len());
+        if next_word_idx >= self.words.len() {
+            return self.words.len() * Self::BITS;
+        }
+        next_word_idx * Self::BITS + (!self.words[next_word_idx]).trailing_zeros() as usize
+    }
+
+    fn first_non_full_word_from(&self, from: usize) -> Option<usize> {
+        let (summary_idx, bit_idx) = (from / Self::BITS, from % Self::BITS);
+        let mut mask: u64 = (1 << bit_idx) - 1;
+        for (idx, summary) in self.full_words.iter().enumerate().skip(summary_idx) {
+            let summary = *summary | mask;
+            if summary != !0 {
+                return Some(idx * Self::BITS + (!summary).trailing_zeros() as usize);
+            }
+            mask = 0;
+        }
+        None
+    }
+}
+
 #[derive(Debug)]
 pub struct FileTableEntry {
     file: FileRef,
@@ -2,7 +2,11 @@ use super::*;
 
 pub use self::chdir::{do_chdir, do_fchdir};
 pub use self::getcwd::do_getcwd;
-pub use self::mount::{do_mount, do_mount_rootfs, do_umount, MountOptions};
+pub use self::mount::{
+    account_open_inode, check_mount_writable, creation_defaults_of, do_bind_mount, do_mount,
+    do_mount_rootfs, do_remount, do_umount, is_readonly_mount, release_space, reserve_space,
+    size_limit_of, CreationDefaults, MountOptions, SizeLimit,
+};
 pub use self::statfs::{do_fstatfs, do_statfs, Statfs};
 pub use self::sync::do_sync;
 
@@ -1,16 +1,33 @@
 use config::{parse_key, parse_mac, ConfigMount, ConfigMountFsType, ConfigMountOptions};
 use rcore_fs_mountfs::MNode;
 use std::convert::TryFrom;
-use std::path::PathBuf;
-use std::sync::Once;
+use std::path::{Path, PathBuf};
+use std::sync::{Once, Weak};
 use util::host_file_util::{write_host_file, HostFile};
 use util::mem_util::from_user;
 
-use super::rootfs::{mount_nonroot_fs_according_to, open_root_fs_according_to, umount_nonroot_fs};
+use super::bindfs::BindFS;
+use super::fs_view::MAX_SYMLINKS;
+use super::rootfs::{
+    mount_fs_at, mount_nonroot_fs_according_to, open_root_fs_according_to, umount_nonroot_fs,
+};
 use super::*;
 
 lazy_static! {
     static ref MOUNT_ONCE: Once = Once::new();
+    /// The creation defaults of the mounts, keyed by the absolute paths of the
+    /// mount points. The non-root mounts specified in Occlum.json are recorded
+    /// here as well, without any creation defaults.
+    static ref CREATION_DEFAULTS: RwLock<Vec<(String, CreationDefaults)>> =
+        RwLock::new(Vec::new());
+    /// The size limits of the mounts made by the mount syscall, keyed by the
+    /// absolute paths of the mount points. A bind mount shares the size limit
+    /// of the mount that it binds.
+    static ref SIZE_LIMITS: RwLock<Vec<(String, Arc<dyn FileSystem>, Arc<SizeLimit>)>> =
+        RwLock::new(Vec::new());
+    /// The read-only mounts, keyed by the absolute paths of the mount points.
+    static ref READONLY_MOUNTS: RwLock<Vec<(String, Arc<dyn FileSystem>)>> =
+        RwLock::new(Vec::new());
 }
 
 pub fn do_mount_rootfs(
@@ -30,6 +47,24 @@ pub fn do_mount_rootfs(
         *rootfs = new_rootfs;
         *ENTRY_POINTS.write().unwrap() = user_config.entry_points.to_owned();
     });
+    // Record the non-root mounts, so that they can be remounted or unmounted
+    // like those made by the mount syscall. The image SEFS in the layers of the
+    // root UnionFS is never written, as the writes go to the container layer.
+    // But the one mounted on its own is made read-only here.
+    for mc in user_config
+        .mount
+        .iter()
+        .filter(|mc| mc.target != Path::new("/"))
+    {
+        let target = mc.target.to_string_lossy().into_owned();
+        let fs = ROOT_FS
+            .read()
+            .unwrap()
+            .root_inode()
+            .lookup_follow(&target, MAX_SYMLINKS)?
+            .fs();
+        register_mount(target, fs, Default::default(), None, is_image_sefs(mc));
+    }
 
     // Write resolv.conf file into mounted file system
     write_host_file(HostFile::RESOLV_CONF)?;
@@ -77,28 +112,31 @@ pub fn do_mount(
         return_errno!(EINVAL, "Only support to create a new mount");
     }
 
+    let (creation_defaults, size_limit) = match &options {
+        MountOptions::RamFS(ramfs_options) => (ramfs_options.creation_defaults, ramfs_options.size),
+        _ => (Default::default(), None),
+    };
+    let target_str = target.to_string_lossy().into_owned();
     let (mount_configs, user_key) = match options {
         MountOptions::UnionFS(unionfs_options) => {
             let mc = {
-                let image_mc = ConfigMount {
+                let sefs_mc = |dir: &PathBuf| ConfigMount {
                     type_: ConfigMountFsType::TYPE_SEFS,
                     target: target.clone(),
-                    source: Some(unionfs_options.lower_dir.clone()),
-                    options: Default::default(),
-                };
-                let container_mc = ConfigMount {
-                    type_: ConfigMountFsType::TYPE_SEFS,
-                    target: target.clone(),
-                    source: Some(unionfs_options.upper_dir.clone()),
+                    source: Some(dir.clone()),
                     options: Default::default(),
                 };
+                // The image layers in priority order, followed by the container layer
+                let mut layers: Vec<ConfigMount> =
+                    unionfs_options.lower_dirs.iter().map(sefs_mc).collect();
+                layers.push(sefs_mc(&unionfs_options.upper_dir));
 
                 ConfigMount {
                     type_: ConfigMountFsType::TYPE_UNIONFS,
                     target,
                     source: None,
                     options: ConfigMountOptions {
-                        layers: Some(vec![image_mc, container_mc]),
+                        layers: Some(layers),
                         ..Default::default()
                     },
                 }
@@ -126,7 +164,7 @@ pub fn do_mount(
             };
             (vec![mc], None)
         }
-        MountOptions::RamFS => {
+        MountOptions::RamFS(_) => {
             let mc = ConfigMount {
                 type_: ConfigMountFsType::TYPE_RAMFS,
                 target,
@@ -147,9 +185,142 @@ pub fn do_mount(
         &user_key,
         follow_symlink,
     )?;
+    let fs = rootfs
+        .root_inode()
+        .lookup_follow(&target_str, MAX_SYMLINKS)?
+        .fs();
+    let size_limit = size_limit.map(|capacity| Arc::new(SizeLimit::new(capacity)));
+    let read_only = flags.contains(MountFlags::MS_RDONLY) || is_image_sefs(&mount_configs[0]);
+    register_mount(target_str, fs, creation_defaults, size_limit, read_only);
+    Ok(())
+}
+
+/// Bind the file or directory of `source` to `target`, so that the same inodes
+/// can also be accessed through `target`.
+pub fn do_bind_mount(source: &str, target: &str, flags: MountFlags) -> Result<()> {
+    debug!(
+        "bind mount: source: {}, target: {}, flags: {:?}",
+        source, target, flags
+    );
+
+    if flags.contains(MountFlags::MS_REMOUNT)
+        || flags.contains(MountFlags::MS_SHARED)
+        || flags.contains(MountFlags::MS_PRIVATE)
+        || flags.contains(MountFlags::MS_SLAVE)
+        || flags.contains(MountFlags::MS_UNBINDABLE)
+        || flags.contains(MountFlags::MS_MOVE)
+    {
+        return_errno!(EINVAL, "Only support to create a new bind mount");
+    }
+
+    let (source_inode, source_path, target_path) = {
+        let thread = current!();
+        let fs = thread.fs().read().unwrap();
+        let source_fs_path = FsPath::try_from(source)?;
+        let source_inode = fs.lookup_inode(&source_fs_path)?;
+        let source_path = fs.convert_fspath_to_abs(&source_fs_path)?;
+        let target_path = if target == "/" {
+            return_errno!(EPERM, "can not mount on root");
+        } else {
+            fs.convert_fspath_to_abs(&FsPath::try_from(target)?)?
+        };
+        (source_inode, source_path, target_path)
+    };
+    // The bind mount is seen as part of the source, not the parent of the target
+    let creation_defaults = creation_defaults_of(&source_path);
+    let size_limit = size_limit_of(&source_inode.fs());
+    let read_only = flags.contains(MountFlags::MS_RDONLY) || is_readonly_mount(&source_inode.fs());
+
+    let mut rootfs = ROOT_FS.write().unwrap();
+    rootfs.sync()?;
+    let follow_symlink = !flags.contains(MountFlags::MS_NOSYMFOLLOW);
+    mount_fs_at(
+        BindFS::new(source_inode),
+        &rootfs.root_inode(),
+        Path::new(&target_path),
+        follow_symlink,
+    )?;
+    let fs = rootfs
+        .root_inode()
+        .lookup_follow(&target_path, MAX_SYMLINKS)?
+        .fs();
+    register_mount(target_path, fs, creation_defaults, size_limit, read_only);
     Ok(())
 }
 
+/// Change the mount flags of an existing mount, which is made by the mount
+/// syscall or specified in Occlum.json.
+///
+/// Only MS_RDONLY is supported, which makes the mount read-only or writable.
+/// The inodes and the open files are kept, but the files that have been opened
+/// for writing can no longer be written once the mount is read-only. Neither
+/// can the shared mappings of the files be written back to them.
+pub fn do_remount(target: &str, flags: MountFlags) -> Result<()> {
+    debug!("remount: target: {}, flags: {:?}", target, flags);
+
+    let target = if target == "/" {
+        return_errno!(EPERM, "can not remount root");
+    } else {
+        let fs_path = FsPath::try_from(target)?;
+        let thread = current!();
+        let fs = thread.fs().read().unwrap();
+        fs.convert_fspath_to_abs(&fs_path)?
+    };
+    if !CREATION_DEFAULTS
+        .read()
+        .unwrap()
+        .iter()
+        .any(|(mount_point, _)| mount_point == &target)
+    {
+        return_errno!(EINVAL, "target is not a mount point");
+    }
+
+    let rootfs = ROOT_FS.read().unwrap();
+    let fs = rootfs
+        .root_inode()
+        .lookup_follow(&target, MAX_SYMLINKS)?
+        .fs();
+    let mut readonly_mounts = READONLY_MOUNTS.write().unwrap();
+    readonly_mounts.retain(|(mount_point, _)| mount_point != &target);
+    if flags.contains(MountFlags::MS_RDONLY) {
+        readonly_mounts.push((target, fs));
+    }
+    Ok(())
+}
+
+/// Returns whether the mount is of an image SEFS, which is integrity-protected
+/// by the MAC given in the options and must not be modified.
+fn is_image_sefs(mc: &ConfigMount) -> bool {
+    mc.type_ == ConfigMountFsType::TYPE_SEFS && mc.options.mac.is_some()
+}
+
+fn register_mount(
+    mount_point: String,
+    fs: Arc<dyn FileSystem>,
+    creation_defaults: CreationDefaults,
+    size_limit: Option<Arc<SizeLimit>>,
+    read_only: bool,
+) {
+    if let Some(size_limit) = size_limit {
+        SIZE_LIMITS
+            .write()
+            .unwrap()
+            .push((mount_point.clone(), fs.clone(), size_limit));
+    }
+    if read_only {
+        READONLY_MOUNTS
+            .write()
+            .unwrap()
+            .push((mount_point.clone(), fs));
+    }
+    // Register the mount even without any creation defaults, so that it is not
+    // covered by the creation defaults of a parent mount.
+    CREATION_DEFAULTS
+        .write()
+        .unwrap()
+        .push((mount_point, creation_defaults));
+}
+
 pub fn do_umount(target: &str, flags: UmountFlags) -> Result<()> {
     debug!("umount: target: {}, flags: {:?}", target, flags);
 
@@ -167,15 +338,132 @@ pub fn do_umount(target: &str, flags: UmountFlags) -> Result<()> {
     rootfs.sync()?;
     let follow_symlink = !flags.contains(UmountFlags::UMOUNT_NOFOLLOW);
     umount_nonroot_fs(&rootfs.root_inode(), &target, follow_symlink)?;
+    let mut creation_defaults = CREATION_DEFAULTS.write().unwrap();
+    if let Some(idx) = creation_defaults
+        .iter()
+        .rposition(|(mount_point, _)| mount_point == &target)
+    {
+        creation_defaults.remove(idx);
+    }
+    let mut size_limits = SIZE_LIMITS.write().unwrap();
+    if let Some(idx) = size_limits
+        .iter()
+        .rposition(|(mount_point, _, _)| mount_point == &target)
+    {
+        size_limits.remove(idx);
+    }
+    let mut readonly_mounts = READONLY_MOUNTS.write().unwrap();
+    if let Some(idx) = readonly_mounts
+        .iter()
+        .rposition(|(mount_point, _)| mount_point == &target)
+    {
+        readonly_mounts.remove(idx);
+    }
     Ok(())
 }
 
+/// Returns the creation defaults of the mount that the absolute path is on.
+pub fn creation_defaults_of(abs_path: &str) -> CreationDefaults {
+    let creation_defaults = CREATION_DEFAULTS.read().unwrap();
+    creation_defaults
+        .iter()
+        .filter(|(mount_point, _)| Path::new(abs_path).starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.len())
+        .map(|(_, defaults)| *defaults)
+        .unwrap_or_default()
+}
+
+/// Returns the size limit of the file system, if any.
+pub fn size_limit_of(fs: &Arc<dyn FileSystem>) -> Option<Arc<SizeLimit>> {
+    let size_limits = SIZE_LIMITS.read().unwrap();
+    size_limits
+        .iter()
+        .find(|(_, limited_fs, _)| is_same_fs(limited_fs, fs))
+        .map(|(_, _, size_limit)| size_limit.clone())
+}
+
+/// Reserve the space for the inode to grow to `new_size`, which fails with
+/// ENOSPC if the size limit of the mount that the inode is on would be exceeded.
+///
+/// This must be called before any write or resize that may grow the inode.
+pub fn reserve_space(inode: &dyn INode, new_size: usize) -> Result<()> {
+    match limited_file_of(inode)? {
+        Some((size_limit, metadata)) => size_limit.reserve(&metadata, new_size),
+        None => Ok(()),
+    }
+}
+
+/// Account the space of a newly opened inode to the size limit of the mount
+/// that it is on, which is not released before the inode is closed.
+///
+/// This must be called when opening an inode as a file.
+pub fn account_open_inode(inode: &Arc<dyn INode>) -> Result<()> {
+    if let Some((size_limit, metadata)) = limited_file_of(&**inode)? {
+        size_limit.account_open(inode, &metadata);
+    }
+    Ok(())
+}
+
+/// Release the space that the inode no longer uses, i.e., the shrunk part of
+/// the inode, or the whole inode once it is unlinked and closed.
+///
+/// This must be called after shrinking or unlinking the inode.
+pub fn release_space(inode: &dyn INode) -> Result<()> {
+    if let Some((size_limit, metadata)) = limited_file_of(inode)? {
+        size_limit.release(&metadata);
+    }
+    Ok(())
+}
+
+/// Returns the size limit and the metadata of the inode, if it is a regular
+/// file on a mount with a size limit.
+fn limited_file_of(inode: &dyn INode) -> Result<Option<(Arc<SizeLimit>, Metadata)>> {
+    if SIZE_LIMITS.read().unwrap().is_empty() {
+        return Ok(None);
+    }
+    let size_limit = match size_limit_of(&inode.fs()) {
+        Some(size_limit) => size_limit,
+        None => return Ok(None),
+    };
+    let metadata = inode.metadata()?;
+    if metadata.type_ != FileType::File {
+        return Ok(None);
+    }
+    Ok(Some((size_limit, metadata)))
+}
+
+/// Returns EROFS if the inode is on a read-only mount.
+///
+/// This must be called before modifying the inode, or the entries of it if it
+/// is a directory.
+pub fn check_mount_writable(inode: &dyn INode) -> Result<()> {
+    if READONLY_MOUNTS.read().unwrap().is_empty() {
+        return Ok(());
+    }
+    if is_readonly_mount(&inode.fs()) {
+        return_errno!(EROFS, "the mount is read-only");
+    }
+    Ok(())
+}
+
+/// Returns whether the file system is mounted read-only.
+pub fn is_readonly_mount(fs: &Arc<dyn FileSystem>) -> bool {
+    let readonly_mounts = READONLY_MOUNTS.read().unwrap();
+    readonly_mounts
+        .iter()
+        .any(|(_, readonly_fs)| is_same_fs(readonly_fs, fs))
+}
+
+fn is_same_fs(fs: &Arc<dyn FileSystem>, other: &Arc<dyn FileSystem>) -> bool {
+    Arc::as_ptr(fs) as *const u8 == Arc::as_ptr(other) as *const u8
+}
+
 #[derive(Debug)]
 pub enum MountOptions {
     UnionFS(UnionFSMountOptions),
     SEFS(SEFSMountOptions),
     HostFS(PathBuf),
-    RamFS,
+    RamFS(RamFSMountOptions),
 }
 
 impl MountOptions {
@@ -213,7 +501,17 @@ impl MountOptions {
                 };
                 Self::HostFS(dir)
             }
-            ConfigMountFsType::TYPE_RAMFS => Self::RamFS,
+            ConfigMountFsType::TYPE_RAMFS => {
+                let ramfs_mount_options = if options.is_null() {
+                    Default::default()
+                } else {
+                    let options = from_user::clone_cstring_safely(options)?
+                        .to_string_lossy()
+                        .into_owned();
+                    RamFSMountOptions::from_input(options.as_str())?
+                };
+                Self::RamFS(ramfs_mount_options)
+            }
             _ => {
                 return_errno!(EINVAL, "unsupported fs type");
             }
@@ -223,7 +521,8 @@ impl MountOptions {
 
 #[derive(Debug)]
 pub struct UnionFSMountOptions {
-    lower_dir: PathBuf,
+    /// The lower layers, from the highest priority to the lowest
+    lower_dirs: Vec<PathBuf>,
     upper_dir: PathBuf,
     key: Option<sgx_key_128bit_t>,
 }
@@ -232,10 +531,18 @@ impl UnionFSMountOptions {
     pub fn from_input(input: &str) -> Result<Self> {
         let options: Vec<&str> = input.split(",").collect();
 
-        let lower_dir = options
+        let lower_dirs = options
             .iter()
             .find_map(|s| s.strip_prefix("lowerdir="))
-            .ok_or_else(|| errno!(EINVAL, "no lowerdir options"))?;
+            .ok_or_else(|| errno!(EINVAL, "no lowerdir options"))?
+            .split(":")
+            .map(|dir| {
+                if dir.is_empty() {
+                    return_errno!(EINVAL, "empty lowerdir options");
+                }
+                Ok(PathBuf::from(dir))
+            })
+            .collect::<Result<Vec<_>>>()?;
         let upper_dir = options
             .iter()
             .find_map(|s| s.strip_prefix("upperdir="))
@@ -246,7 +553,7 @@ impl UnionFSMountOptions {
         };
 
         Ok(Self {
-            lower_dir: PathBuf::from(lower_dir),
+            lower_dirs,
             upper_dir: PathBuf::from(upper_dir),
             key,
         })
@@ -284,3 +591,226 @@ impl SEFSMountOptions {
         })
     }
 }
+
+#[derive(Debug, Default)]
+pub struct RamFSMountOptions {
+    creation_defaults: CreationDefaults,
+    /// The maximum number of bytes of the file data, which is unlimited if absent
+    size: Option<usize>,
+}
+
+impl RamFSMountOptions {
+    pub fn from_input(input: &str) -> Result<Self> {
+        let options: Vec<&str> = input.split(",").collect();
+
+        let creation_defaults = CreationDefaults::from_input(input)?;
+        // As in tmpfs, a size of zero means unlimited
+        let size = match options.iter().find_map(|s| s.strip_prefix("size=")) {
+            Some(size_str) => Some(parse_size(size_str)?).filter(|size| *size > 0),
+            None => None,
+        };
+
+        Ok(Self {
+            creation_defaults,
+            size,
+        })
+    }
+}
+
+/// Parse a size in bytes, which may be suffixed with k, m or g.
+fn parse_size(size_str: &str) -> Result<usize> {
+    let (num_str, unit) = match size_str.chars().last() {
+        Some('k') | Some('K') => (&size_str[..size_str.len() - 1], 1 << 10),
+        Some('m') | Some('M') => (&size_str[..size_str.len() - 1], 1 << 20),
+        Some('g') | Some('G') => (&size_str[..size_str.len() - 1], 1 << 30),
+        _ => (size_str, 1),
+    };
+    num_str
+        .parse::<usize>()
+        .ok()
+        .and_then(|num| num.checked_mul(unit))
+        .ok_or_else(|| errno!(EINVAL, "invalid size options"))
+}
+
+/// The size limit of a RamFS mount.
+///
+/// The usage is accounted per inode when it is opened, grown or shrunk, so no
+/// walk of the files is needed to get it. An unlinked inode keeps its space
+/// until all the files that open it are closed.
+pub struct SizeLimit {
+    capacity: usize,
+    usage: SgxMutex<SizeUsage>,
+}
+
+#[derive(Default)]
+struct SizeUsage {
+    used: usize,
+    /// The accounted inodes, keyed by the inode numbers
+    inodes: HashMap<usize, InodeUsage>,
+    /// The unlinked inodes whose space is not released yet
+    unlinked_inodes: Vec<usize>,
+}
+
+struct InodeUsage {
+    size: usize,
+    /// The inodes held by the open files
+    open_inodes: Vec<Weak<dyn INode>>,
+}
+
+impl SizeLimit {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            usage: SgxMutex::new(SizeUsage::default()),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn used(&self) -> usize {
+        let mut usage = self.usage.lock().unwrap();
+        usage.release_unlinked();
+        usage.used
+    }
+
+    fn reserve(&self, metadata: &Metadata, new_size: usize) -> Result<()> {
+        let mut usage = self.usage.lock().unwrap();
+        usage.release_unlinked();
+        let size = usage.inode_usage(metadata).size;
+        if new_size <= size {
+            return Ok(());
+        }
+        if usage.used + (new_size - size) > self.capacity {
+            return_errno!(ENOSPC, "the size limit of the mount is exceeded");
+        }
+        usage.used += new_size - size;
+        usage.inode_usage(metadata).size = new_size;
+        Ok(())
+    }
+
+    fn account_open(&self, inode: &Arc<dyn INode>, metadata: &Metadata) {
+        let mut usage = self.usage.lock().unwrap();
+        usage.resize(metadata);
+        let inode_usage = usage.inode_usage(metadata);
+        inode_usage
+            .open_inodes
+            .retain(|open_inode| open_inode.strong_count() > 0);
+        inode_usage.open_inodes.push(Arc::downgrade(inode));
+    }
+
+    fn release(&self, metadata: &Metadata) {
+        let mut usage = self.usage.lock().unwrap();
+        usage.resize(metadata);
+        if metadata.nlinks == 0 && !usage.unlinked_inodes.contains(&metadata.inode) {
+            usage.unlinked_inodes.push(metadata.inode);
+        }
+        usage.release_unlinked();
+    }
+}
+
+impl SizeUsage {
+    fn inode_usage(&mut self, metadata: &Metadata) -> &mut InodeUsage {
+        let used = &mut self.used;
+        self.inodes.entry(metadata.inode).or_insert_with(|| {
+            *used += metadata.size;
+            InodeUsage {
+                size: metadata.size,
+                open_inodes: Vec::new(),
+            }
+        })
+    }
+
+    /// Account the inode with its actual size.
+    fn resize(&mut self, metadata: &Metadata) {
+        let size = self.inode_usage(metadata).size;
+        self.used = self.used + metadata.size - size;
+        self.inode_usage(metadata).size = metadata.size;
+    }
+
+    /// Release the space of the unlinked inodes that are no longer open.
+    fn release_unlinked(&mut self) {
+        let inodes = &mut self.inodes;
+        let used = &mut self.used;
+        self.unlinked_inodes.retain(|ino| {
+            let is_open = inodes[ino]
+                .open_inodes
+                .iter()
+                .any(|open_inode| open_inode.strong_count() > 0);
+            if !is_open {
+                *used -= inodes.remove(ino).unwrap().size;
+            }
+            is_open
+        });
+    }
+}
+
+/// The mode and the ownership of the inodes that are newly created on a mount,
+/// which override those determined by the creating process.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CreationDefaults {
+    uid: Option<u32>,
+    gid: Option<u32>,
+    umask: Option<FileMode>,
+    mode: Option<FileMode>,
+}
+
+impl CreationDefaults {
+    pub fn from_input(input: &str) -> Result<Self> {
+        let options: Vec<&str> = input.split(",").collect();
+
+        let find_option = |prefix: &str| options.iter().find_map(|s| s.strip_prefix(prefix));
+        let parse_id = |id_str: &str| {
+            id_str
+                .parse::<u32>()
+                .map_err(|_| errno!(EINVAL, "invalid uid or gid options"))
+        };
+        let parse_mode = |mode_str: &str| {
+            u16::from_str_radix(mode_str, 8)
+                .ok()
+                .and_then(FileMode::from_bits)
+                .ok_or_else(|| errno!(EINVAL, "invalid umask or mode options"))
+        };
+        let uid = find_option("uid=").map(parse_id).transpose()?;
+        let gid = find_option("gid=").map(parse_id).transpose()?;
+        let umask = find_option("umask=").map(parse_mode).transpose()?;
+        let mode = find_option("mode=").map(parse_mode).transpose()?;
+
+        Ok(Self {
+            uid,
+            gid,
+            umask,
+            mode,
+        })
+    }
+
+    /// Returns the mode of a new inode given the mode requested by the process
+    /// and the umask of the process.
+    ///
+    /// The `mode` option only applies to regular files, as a directory with the
+    /// same mode would not be searchable. The `umask` option replaces the umask
+    /// of the process.
+    pub fn mode_of(&self, type_: FileType, mode: FileMode, umask: FileMode) -> FileMode {
+        match self.mode {
+            Some(mode) if type_ == FileType::File => mode,
+            _ => mode & !self.umask.unwrap_or(umask),
+        }
+    }
+
+    /// Set the ownership of a newly created inode.
+    pub fn set_owner_of(&self, inode: &Arc<dyn INode>) -> Result<()> {
+        if self.uid.is_none() && self.gid.is_none() {
+            return Ok(());
+        }
+        let mut info = inode.metadata()?;
+        if let Some(uid) = self.uid {
+            info.uid = uid as usize;
+        }
+        if let Some(gid) = self.gid {
+            info.gid = gid as usize;
+        }
+        inode.set_metadata(&info)?;
+        Ok(())
+    }
+}
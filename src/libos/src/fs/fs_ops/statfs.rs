@@ -10,10 +10,7 @@ pub fn do_fstatfs(fd: FileDesc) -> Result<Statfs> {
     let inode_file = file_ref
         .as_inode_file()
         .ok_or_else(|| errno!(EBADF, "not an inode"))?;
-    let statfs = {
-        let fs_info = inode_file.inode().fs().info();
-        Statfs::try_from(fs_info)?
-    };
+    let statfs = statfs_of(&inode_file.inode().fs())?;
     trace!("fstatfs result: {:?}", statfs);
     Ok(statfs)
 }
@@ -26,14 +23,30 @@ pub fn do_statfs(path: &FsPath) -> Result<Statfs> {
         let fs = current.fs().read().unwrap();
         fs.lookup_inode(path)?
     };
-    let statfs = {
-        let fs_info = inode.fs().info();
-        Statfs::try_from(fs_info)?
-    };
+    let statfs = statfs_of(&inode.fs())?;
     trace!("statfs result: {:?}", statfs);
     Ok(statfs)
 }
 
+fn statfs_of(fs: &Arc<dyn FileSystem>) -> Result<Statfs> {
+    let mut statfs = Statfs::try_from(fs.info())?;
+    // Report the capacity of a size-limited mount
+    if let Some(size_limit) = size_limit_of(fs) {
+        const BLOCK_SIZE: usize = 4096;
+        let free = size_limit.capacity().saturating_sub(size_limit.used());
+        statfs.f_bsize = BLOCK_SIZE;
+        statfs.f_frsize = BLOCK_SIZE;
+        statfs.f_blocks = size_limit.capacity() / BLOCK_SIZE;
+        statfs.f_bfree = free / BLOCK_SIZE;
+        statfs.f_bavail = free / BLOCK_SIZE;
+    }
+    if is_readonly_mount(fs) {
+        const ST_RDONLY: usize = 1;
+        statfs.f_flags |= ST_RDONLY;
+    }
+    Ok(statfs)
+}
+
 #[derive(Default, Debug)]
 #[repr(C)]
 pub struct Statfs {
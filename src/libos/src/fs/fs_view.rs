@@ -1,6 +1,7 @@
 /// Present a per-process view of FS.
 use super::*;
 
+use super::file_ops::ResolveFlags;
 use super::fspath::FsPathInner;
 
 #[derive(Debug, Clone)]
@@ -48,6 +49,9 @@ impl FsView {
     /// Open a file on the process. But DO NOT add it to file table.
     pub fn open_file(&self, fs_path: &FsPath, flags: u32, mode: FileMode) -> Result<INodeFile> {
         let creation_flags = CreationFlags::from_bits_truncate(flags);
+        if creation_flags.is_tmpfile() {
+            return self.open_tmpfile(fs_path, flags, mode);
+        }
         let inode = if creation_flags.no_follow_symlink() {
             match self.lookup_inode_no_follow(fs_path) {
                 Ok(inode) => {
@@ -71,10 +75,13 @@ impl FsView {
                 }
                 Err(e) if e.errno() == ENOENT && creation_flags.can_create() => {
                     let (dir_inode, file_name) = self.lookup_dirinode_and_basename(fs_path)?;
+                    fs_ops::check_mount_writable(&*dir_inode)?;
                     if !dir_inode.allow_write()? {
                         return_errno!(EPERM, "file cannot be created");
                     }
-                    dir_inode.create(&file_name, FileType::File, mode.bits())?
+                    let new_inode = dir_inode.create(&file_name, FileType::File, mode.bits())?;
+                    self.set_owner_of_new_inode(fs_path, &new_inode)?;
+                    new_inode
                 }
                 Err(e) => return Err(e),
             }
@@ -96,10 +103,13 @@ impl FsView {
                 }
                 Err(e) if e.errno() == ENOENT && creation_flags.can_create() => {
                     let (dir_inode, file_name) = self.lookup_real_dirinode_and_basename(fs_path)?;
+                    fs_ops::check_mount_writable(&*dir_inode)?;
                     if !dir_inode.allow_write()? {
                         return_errno!(EPERM, "file cannot be created");
                     }
-                    dir_inode.create(&file_name, FileType::File, mode.bits())?
+                    let new_inode = dir_inode.create(&file_name, FileType::File, mode.bits())?;
+                    self.set_owner_of_new_inode(fs_path, &new_inode)?;
+                    new_inode
                 }
                 Err(e) => return Err(e),
             }
@@ -108,6 +118,25 @@ impl FsView {
         Ok(INodeFile::open(inode, flags, open_path)?)
     }
 
+    /// Open an unnamed regular file in the directory of `fs_path`, i.e., O_TMPFILE.
+    ///
+    /// The arguments are checked as by Linux. But none of the file systems can
+    /// create an inode without a directory entry, so EOPNOTSUPP is returned.
+    fn open_tmpfile(&self, fs_path: &FsPath, flags: u32, _mode: FileMode) -> Result<INodeFile> {
+        let creation_flags = CreationFlags::from_bits_truncate(flags);
+        if !creation_flags.contains(CreationFlags::O_DIRECTORY) {
+            return_errno!(EINVAL, "O_TMPFILE must be specified with O_DIRECTORY");
+        }
+        if !AccessMode::from_u32(flags)?.writable() {
+            return_errno!(EINVAL, "O_TMPFILE must be specified with write access");
+        }
+        let dir_inode = self.lookup_inode(fs_path)?;
+        if dir_inode.metadata()?.type_ != FileType::Dir {
+            return_errno!(ENOTDIR, "the path of O_TMPFILE is not a directory");
+        }
+        return_errno!(EOPNOTSUPP, "the file system cannot create unnamed files");
+    }
+
     /// Lookup INode, dereference symlink
     pub fn lookup_inode(&self, fs_path: &FsPath) -> Result<Arc<dyn INode>> {
         debug!("lookup_inode: cwd: {:?}, path: {:?}", self.cwd(), fs_path);
@@ -124,6 +153,157 @@ impl FsView {
         self.lookup_inode_inner(fs_path, false)
     }
 
+    /// Open a file like `open_file`, but resolve the path with the restrictions
+    /// of the resolve flags of openat2.
+    ///
+    /// The file is opened with the inode found by the restricted walk itself, so
+    /// the path cannot be changed to escape the restrictions after it is checked.
+    pub fn open_file_with_resolve(
+        &self,
+        fs_path: &FsPath,
+        flags: u32,
+        mode: FileMode,
+        resolve: ResolveFlags,
+    ) -> Result<INodeFile> {
+        if resolve.is_empty() {
+            return self.open_file(fs_path, flags, mode);
+        }
+        let creation_flags = CreationFlags::from_bits_truncate(flags);
+        if creation_flags.is_tmpfile() {
+            return self.open_tmpfile(fs_path, flags, mode);
+        }
+        let follow_last_symlink = !creation_flags.no_follow_symlink();
+        let inode = match self.lookup_inode_with_resolve(fs_path, resolve, follow_last_symlink)? {
+            ResolvedInode::Found(inode) => {
+                let type_ = inode.metadata()?.type_;
+                let status_flags = StatusFlags::from_bits_truncate(flags);
+                if type_ == FileType::SymLink && !status_flags.is_fast_open() {
+                    return_errno!(ELOOP, "file is a symlink");
+                }
+                if creation_flags.can_create() && creation_flags.is_exclusive() {
+                    return_errno!(EEXIST, "file exists");
+                }
+                if creation_flags.must_be_directory() && type_ != FileType::Dir {
+                    return_errno!(
+                        ENOTDIR,
+                        "O_DIRECTORY is specified but file is not a directory"
+                    );
+                }
+                inode
+            }
+            ResolvedInode::NotFound(dir_inode, file_name) if creation_flags.can_create() => {
+                fs_ops::check_mount_writable(&*dir_inode)?;
+                if !dir_inode.allow_write()? {
+                    return_errno!(EPERM, "file cannot be created");
+                }
+                let new_inode = dir_inode.create(&file_name, FileType::File, mode.bits())?;
+                self.set_owner_of_new_inode(fs_path, &new_inode)?;
+                new_inode
+            }
+            ResolvedInode::NotFound(..) => return_errno!(ENOENT, "file does not exist"),
+        };
+        let open_path = self.convert_fspath_to_abs(fs_path)?;
+        Ok(INodeFile::open(inode, flags, open_path)?)
+    }
+
+    /// Lookup INode with the restrictions of the resolve flags of openat2.
+    ///
+    /// The path is walked component by component. A symlink is rejected with
+    /// ELOOP if RESOLVE_NO_SYMLINKS is given; an absolute path, an absolute
+    /// symlink or a ".." that escapes the starting directory is rejected with
+    /// EXDEV if RESOLVE_BENEATH is given. If only the last component is missing,
+    /// its parent directory and name are returned for the file to be created.
+    fn lookup_inode_with_resolve(
+        &self,
+        fs_path: &FsPath,
+        resolve: ResolveFlags,
+        follow_last_symlink: bool,
+    ) -> Result<ResolvedInode> {
+        let no_symlinks = resolve.contains(ResolveFlags::RESOLVE_NO_SYMLINKS);
+        let beneath = resolve.contains(ResolveFlags::RESOLVE_BENEATH);
+
+        let (start_inode, path) = match fs_path.inner() {
+            FsPathInner::Absolute(path) => {
+                if beneath {
+                    return_errno!(EXDEV, "absolute path is not beneath the directory");
+                }
+                (ROOT_FS.read().unwrap().root_inode(), *path)
+            }
+            FsPathInner::CwdRelative(path) => (self.lookup_inode_cwd(self.cwd())?, *path),
+            FsPathInner::FdRelative(dirfd, path) => (self.lookup_inode_from_fd(*dirfd)?, *path),
+            FsPathInner::Cwd | FsPathInner::Fd(_) => {
+                return Ok(ResolvedInode::Found(self.lookup_inode(fs_path)?));
+            }
+        };
+
+        // The directories walked so far, starting from the starting directory
+        let mut dir_inodes = vec![start_inode];
+        // The components to walk, in reverse order
+        let mut components: Vec<String> = path.rsplit('/').map(String::from).collect();
+        let mut symlink_count = 0;
+        while let Some(name) = components.pop() {
+            let dir_inode = dir_inodes.last().unwrap().clone();
+            if name.is_empty() || name == "." {
+                continue;
+            }
+            if name == ".." {
+                if dir_inodes.len() > 1 {
+                    dir_inodes.pop();
+                } else if beneath {
+                    return_errno!(EXDEV, "\"..\" escapes the directory");
+                } else {
+                    dir_inodes[0] = dir_inode.find("..")?;
+                }
+                continue;
+            }
+
+            let is_last = components.iter().all(|name| name.is_empty());
+            let inode = match dir_inode.find(&name) {
+                Ok(inode) => inode,
+                Err(FsError::EntryNotFound) if is_last => {
+                    return Ok(ResolvedInode::NotFound(dir_inode, name));
+                }
+                Err(e) => return Err(Error::from(e)),
+            };
+            let type_ = inode.metadata()?.type_;
+            if type_ == FileType::SymLink {
+                if is_last && !follow_last_symlink {
+                    return Ok(ResolvedInode::Found(inode));
+                }
+                if no_symlinks {
+                    return_errno!(ELOOP, "symlinks are not allowed in the path");
+                }
+                symlink_count += 1;
+                if symlink_count > MAX_SYMLINKS {
+                    return_errno!(ELOOP, "too many symlinks");
+                }
+                let target = {
+                    let mut content = vec![0u8; PATH_MAX];
+                    let len = inode.read_at(0, &mut content)?;
+                    std::str::from_utf8(&content[..len])
+                        .map_err(|_| errno!(ENOENT, "invalid symlink content"))?
+                        .to_owned()
+                };
+                if target.starts_with('/') {
+                    if beneath {
+                        return_errno!(EXDEV, "absolute symlink is not beneath the directory");
+                    }
+                    dir_inodes = vec![ROOT_FS.read().unwrap().root_inode()];
+                }
+                components.extend(target.rsplit('/').map(String::from));
+                continue;
+            }
+            if is_last {
+                return Ok(ResolvedInode::Found(inode));
+            }
+            if type_ != FileType::Dir {
+                return_errno!(ENOTDIR, "a component of the path is not a directory");
+            }
+            dir_inodes.push(inode);
+        }
+        Ok(ResolvedInode::Found(dir_inodes.pop().unwrap()))
+    }
+
     fn lookup_inode_inner(&self, fs_path: &FsPath, follow_symlink: bool) -> Result<Arc<dyn INode>> {
         let inode = match fs_path.inner() {
             FsPathInner::Absolute(path) | FsPathInner::CwdRelative(path) => {
@@ -296,6 +476,12 @@ impl FsView {
         }
     }
 
+    /// Set the ownership of a newly created inode according to its mount.
+    fn set_owner_of_new_inode(&self, fs_path: &FsPath, inode: &Arc<dyn INode>) -> Result<()> {
+        let abs_path = self.convert_fspath_to_abs(fs_path)?;
+        fs_ops::creation_defaults_of(&abs_path).set_owner_of(inode)
+    }
+
     /// Convert the FsPath to the absolute path.
     /// This function is used to record the open path for a file.
     ///
@@ -338,6 +524,14 @@ impl FsView {
     }
 }
 
+/// The result of `FsView::lookup_inode_with_resolve`.
+enum ResolvedInode {
+    /// The inode of the path
+    Found(Arc<dyn INode>),
+    /// The last component of the path is missing in the directory
+    NotFound(Arc<dyn INode>, String),
+}
+
 impl Default for FsView {
     fn default() -> Self {
         Self::new()
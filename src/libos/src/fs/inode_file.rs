@@ -1,5 +1,6 @@
-use super::file_ops::{ioctl::TcGets, ioctl::TcSets, NonBuiltinIoctlCmd};
+use super::file_ops::{ioctl::TcGets, ioctl::TcSets, FileAdvice, NonBuiltinIoctlCmd};
 use super::*;
+use rcore_fs::vfs::AnyExt;
 use rcore_fs_sefs::dev::SefsMac;
 
 // TODO: rename all INodeFile to InodeFile
@@ -11,11 +12,16 @@ pub struct INodeFile {
     offset: SgxMutex<usize>,
     access_mode: AccessMode,
     status_flags: RwLock<StatusFlags>,
+    access_hint: RwLock<FileAdvice>,
+    zero_fill_lock: Option<Arc<ZeroFillLock>>,
 }
 
 impl INodeFile {
     pub fn open(inode: Arc<dyn INode>, flags: u32, open_path: String) -> Result<Self> {
         let access_mode = AccessMode::from_u32(flags)?;
+        if access_mode.writable() {
+            fs_ops::check_mount_writable(&*inode)?;
+        }
         if (access_mode.readable() && !inode.allow_read()?) {
             return_errno!(EACCES, "File not readable");
         }
@@ -26,13 +32,20 @@ impl INodeFile {
             return_errno!(EISDIR, "Directory cannot be open to write");
         }
         let creation_flags = CreationFlags::from_bits_truncate(flags);
-        if creation_flags.should_truncate()
-            && inode.metadata()?.type_ == FileType::File
-            && access_mode.writable()
-        {
-            // truncate the length to 0
+        if creation_flags.should_truncate() && inode.metadata()?.type_ == FileType::File {
+            // Like Linux, O_TRUNC truncates the file even if it is opened read-only,
+            // which requires the file to be writable
+            fs_ops::check_mount_writable(&*inode)?;
+            if !inode.allow_write()? {
+                return_errno!(EACCES, "File not writable");
+            }
             inode.resize(0)?;
         }
+        fs_ops::account_open_inode(&inode)?;
+        let zero_fill_lock = zero_fill_lock_of(&*inode, &inode.metadata()?);
+        // Reads never update the access time of the inode, so O_NOATIME is
+        // always honored. And as all users are privileged, O_NOATIME is never
+        // denied with EPERM.
         let status_flags = StatusFlags::from_bits_truncate(flags);
         Ok(INodeFile {
             inode,
@@ -40,6 +53,8 @@ impl INodeFile {
             offset: SgxMutex::new(0),
             access_mode,
             status_flags: RwLock::new(status_flags),
+            access_hint: RwLock::new(FileAdvice::default()),
+            zero_fill_lock,
         })
     }
 
@@ -57,11 +72,13 @@ impl INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File not writable");
         }
+        fs_ops::check_mount_writable(&*self.inode)?;
         let mut offset = self.offset.lock().unwrap();
         if self.status_flags.read().unwrap().always_append() {
             let info = self.inode.metadata()?;
             *offset = info.size;
         }
+        let _zero_fill_guard = self.prepare_write(*offset, *offset + buf.len())?;
         let len = self.inode.write_at(*offset, buf)?;
         *offset += len;
         Ok(len)
@@ -79,6 +96,8 @@ impl INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File not writable");
         }
+        fs_ops::check_mount_writable(&*self.inode)?;
+        let _zero_fill_guard = self.prepare_write(offset, offset + buf.len())?;
         let len = self.inode.write_at(offset, buf)?;
         Ok(len)
     }
@@ -94,6 +113,9 @@ impl INodeFile {
                 Ok(len) => {
                     total_len += len;
                     *offset += len;
+                    if len < buf.len() {
+                        break;
+                    }
                 }
                 Err(_) if total_len != 0 => break,
                 Err(e) => return Err(e.into()),
@@ -103,20 +125,84 @@ impl INodeFile {
     }
 
     pub fn writev(&self, bufs: &[&[u8]]) -> Result<usize> {
+        let append = self.status_flags.read().unwrap().always_append();
+        self.do_writev(bufs, append)
+    }
+
+    /// Write to the end of the file as if it were opened with `O_APPEND`.
+    ///
+    /// Finding the end of the file, writing the data and updating the file offset
+    /// are done in one step under the lock of the file offset.
+    pub fn appendv(&self, bufs: &[&[u8]]) -> Result<usize> {
+        self.do_writev(bufs, true)
+    }
+
+    fn do_writev(&self, bufs: &[&[u8]], append: bool) -> Result<usize> {
         if !self.access_mode.writable() {
             return_errno!(EBADF, "File not writable");
         }
+        fs_ops::check_mount_writable(&*self.inode)?;
         let mut offset = self.offset.lock().unwrap();
-        if self.status_flags.read().unwrap().always_append() {
+        if append {
             let info = self.inode.metadata()?;
             *offset = info.size;
         }
+        let bufs_len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        let _zero_fill_guard = self.prepare_write(*offset, *offset + bufs_len)?;
         let mut total_len = 0;
         for buf in bufs {
             match self.inode.write_at(*offset, buf) {
                 Ok(len) => {
                     total_len += len;
                     *offset += len;
+                    if len < buf.len() {
+                        break;
+                    }
+                }
+                Err(_) if total_len != 0 => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(total_len)
+    }
+
+    pub fn readv_at(&self, mut offset: usize, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        if !self.access_mode.readable() {
+            return_errno!(EBADF, "File not readable");
+        }
+        let mut total_len = 0;
+        for buf in bufs {
+            match self.inode.read_at(offset, buf) {
+                Ok(len) => {
+                    total_len += len;
+                    offset += len;
+                    if len < buf.len() {
+                        break;
+                    }
+                }
+                Err(_) if total_len != 0 => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(total_len)
+    }
+
+    pub fn writev_at(&self, mut offset: usize, bufs: &[&[u8]]) -> Result<usize> {
+        if !self.access_mode.writable() {
+            return_errno!(EBADF, "File not writable");
+        }
+        fs_ops::check_mount_writable(&*self.inode)?;
+        let bufs_len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        let _zero_fill_guard = self.prepare_write(offset, offset + bufs_len)?;
+        let mut total_len = 0;
+        for buf in bufs {
+            match self.inode.write_at(offset, buf) {
+                Ok(len) => {
+                    total_len += len;
+                    offset += len;
+                    if len < buf.len() {
+                        break;
+                    }
                 }
                 Err(_) if total_len != 0 => break,
                 Err(e) => return Err(e.into()),
@@ -173,6 +259,15 @@ impl INodeFile {
         *status_flags
     }
 
+    /// Record the access pattern advised by posix_fadvise.
+    ///
+    /// The file systems do not read ahead yet, so the hint has no effect for
+    /// now.
+    pub fn set_access_hint(&self, advice: FileAdvice) {
+        debug_assert!(advice.is_access_pattern());
+        *self.access_hint.write().unwrap() = advice;
+    }
+
     /// Get the full path of the file when opened.
     ///
     /// Limitation: If file is renamed, the path will be invalid.
@@ -346,6 +441,24 @@ impl INodeFile {
     pub fn inode(&self) -> &Arc<dyn INode> {
         &self.inode
     }
+
+    /// Prepare to write the range `[offset, end)` of the inode, which reserves the
+    /// space and zeros the hole before `offset` if the file system does not.
+    ///
+    /// The returned guard must be held until the write is done, so that the hole
+    /// is not zeroed over the data written by another file of the inode.
+    fn prepare_write(&self, offset: usize, end: usize) -> Result<Option<SgxMutexGuard<()>>> {
+        fs_ops::reserve_space(&*self.inode, end)?;
+        let zero_fill_guard = match self.zero_fill_lock.as_ref() {
+            Some(zero_fill_lock) => {
+                let zero_fill_guard = zero_fill_lock.0.lock().unwrap();
+                self.inode.fill_hole_before(offset)?;
+                Some(zero_fill_guard)
+            }
+            None => None,
+        };
+        Ok(zero_fill_guard)
+    }
 }
 
 impl Debug for INodeFile {
@@ -369,6 +482,10 @@ impl Drop for INodeFile {
 
 pub trait INodeExt {
     fn read_as_vec(&self) -> Result<Vec<u8>>;
+    fn write_zeros_at(&self, offset: usize, len: usize) -> Result<()>;
+    fn resize_zeroed(&self, new_size: usize) -> Result<()>;
+    fn fallocate_zeroed(&self, mode: &FallocateMode, offset: usize, len: usize) -> Result<()>;
+    fn fill_hole_before(&self, offset: usize) -> Result<()>;
     fn allow_write(&self) -> Result<bool>;
     fn allow_read(&self) -> Result<bool>;
 }
@@ -384,6 +501,81 @@ impl INodeExt for dyn INode {
         Ok(buf)
     }
 
+    /// Write zeros to the range `[offset, offset + len)` of the inode.
+    fn write_zeros_at(&self, offset: usize, len: usize) -> Result<()> {
+        const ZEROS_BUF_SIZE: usize = 4096;
+        let zeros = [0u8; ZEROS_BUF_SIZE];
+        let end = offset
+            .checked_add(len)
+            .ok_or_else(|| errno!(EOVERFLOW, "file offset overflow"))?;
+        let mut offset = offset;
+        while offset < end {
+            let write_len = (end - offset).min(ZEROS_BUF_SIZE);
+            let len = self.write_at(offset, &zeros[..write_len])?;
+            if len == 0 {
+                return_errno!(EIO, "failed to write zeros");
+            }
+            offset += len;
+        }
+        Ok(())
+    }
+
+    /// Resize the inode and guarantee that the extended region reads back as zeros.
+    ///
+    /// SEFS and UnionFS leave the extended region as a hole, which reads back as
+    /// zeros, except for the tail of the last block beyond the old end. The tail
+    /// may keep the stale content of a truncated inode, so it is zeroed.
+    fn resize_zeroed(&self, new_size: usize) -> Result<()> {
+        fs_ops::reserve_space(self, new_size)?;
+        let zero_fill_lock = zero_fill_lock_of(self, &self.metadata()?);
+        let _zero_fill_guard = zero_fill_lock.as_ref().map(|lock| lock.0.lock().unwrap());
+        let old_size = self.metadata()?.size;
+        self.resize(new_size)?;
+        if new_size > old_size {
+            if zero_fill_lock.is_some() {
+                self.zero_tail_block(old_size, new_size)?;
+            }
+        } else {
+            fs_ops::release_space(self)?;
+        }
+        Ok(())
+    }
+
+    /// Allocate the space of the inode and guarantee that the extended region
+    /// reads back as zeros, like `resize_zeroed`.
+    fn fallocate_zeroed(&self, mode: &FallocateMode, offset: usize, len: usize) -> Result<()> {
+        fs_ops::reserve_space(self, offset + len)?;
+        let zero_fill_lock = zero_fill_lock_of(self, &self.metadata()?);
+        let _zero_fill_guard = zero_fill_lock.as_ref().map(|lock| lock.0.lock().unwrap());
+        let old_size = self.metadata()?.size;
+        self.fallocate(mode, offset, len)?;
+        let new_size = self.metadata()?.size;
+        if new_size > old_size && zero_fill_lock.is_some() {
+            self.zero_tail_block(old_size, new_size)?;
+        }
+        Ok(())
+    }
+
+    /// Zero the hole between the end of the inode and `offset`, if any, like
+    /// `resize_zeroed`.
+    ///
+    /// This must be called with the zero-fill lock of the inode held, before
+    /// writing at an offset beyond the end of the inode.
+    fn fill_hole_before(&self, offset: usize) -> Result<()> {
+        let size = self.metadata()?.size;
+        self.zero_tail_block(size, offset)
+    }
+
+    /// Zero the part of `[size, end)` in the last block of the inode, where
+    /// `size` is the old end of the inode.
+    fn zero_tail_block(&self, size: usize, end: usize) -> Result<()> {
+        let tail_end = align_up(size, ZERO_FILL_BLOCK_SIZE).min(end);
+        if tail_end > size {
+            self.write_zeros_at(size, tail_end - size)?;
+        }
+        Ok(())
+    }
+
     fn allow_write(&self) -> Result<bool> {
         let info = self.metadata()?;
         let file_mode = FileMode::from_bits_truncate(info.mode);
@@ -396,3 +588,36 @@ impl INodeExt for dyn INode {
         Ok(file_mode.is_readable())
     }
 }
+
+// The block size of SEFS and UnionFS
+const ZERO_FILL_BLOCK_SIZE: usize = 4096;
+
+/// The lock that serializes the zero-filling of an inode with the writes to it,
+/// which is kept in the extension of the inode.
+#[derive(Default)]
+struct ZeroFillLock(SgxMutex<()>);
+
+impl AnyExt for ZeroFillLock {}
+
+/// Returns the lock that serializes the zero-filling of the inode with the
+/// writes to it, or None if the inode needs no zero-filling.
+///
+/// Only the regular files on SEFS and UnionFS are zero-filled, as the other file
+/// systems guarantee that the holes and the extended regions read back as
+/// zeros.
+fn zero_fill_lock_of(inode: &dyn INode, metadata: &Metadata) -> Option<Arc<ZeroFillLock>> {
+    if metadata.type_ != FileType::File {
+        return None;
+    }
+    let magic = inode.fs().info().magic;
+    if magic != rcore_fs_sefs::SEFS_MAGIC as usize && magic != rcore_fs_unionfs::UNIONFS_MAGIC {
+        return None;
+    }
+    match inode.ext() {
+        Some(ext) => Some(ext.get_or_put_default::<ZeroFillLock>()),
+        None => {
+            warn!("Inode extension is not supported, the inode is not zero-filled");
+            None
+        }
+    }
+}
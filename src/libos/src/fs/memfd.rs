@@ -0,0 +1,204 @@
+use super::*;
+
+use crate::vm::USER_SPACE_VM_MANAGER;
+use rcore_fs::vfs;
+use rcore_fs_ramfs::RamFS;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The maximum length of the name of a memfd, excluding the terminating null byte
+pub const MEMFD_NAME_MAX: usize = 249;
+
+pub fn do_memfd_create(name: &str, flags: MemfdFlags) -> Result<FileDesc> {
+    debug!("memfd_create: name: {:?}, flags: {:?}", name, flags);
+
+    let inode = {
+        static MEMFD_ID: AtomicUsize = AtomicUsize::new(0);
+
+        // The file is created with a unique name and unlinked at once, so that
+        // it is anonymous and lives as long as it is referred to.
+        let root_inode = MEMFD_FS.root_inode();
+        let file_name = format!("memfd_{}", MEMFD_ID.fetch_add(1, Ordering::Relaxed));
+        let inode = root_inode.create(&file_name, FileType::File, 0o777)?;
+        root_inode.unlink(&file_name)?;
+        inode
+    };
+    let seals = if flags.contains(MemfdFlags::MFD_ALLOW_SEALING) {
+        FileSeals::empty()
+    } else {
+        FileSeals::F_SEAL_SEAL
+    };
+    let memfd_inode: Arc<dyn INode> = Arc::new(MemfdInode::new(inode, seals));
+
+    let open_path = format!("/memfd:{} (deleted)", name);
+    let inode_file = InodeFile::open(memfd_inode, AccessMode::O_RDWR as u32, open_path)?;
+    let file_ref = FileRef::new_inode(inode_file);
+    let close_on_spawn = flags.contains(MemfdFlags::MFD_CLOEXEC);
+    current!().add_file(file_ref, close_on_spawn)
+}
+
+lazy_static! {
+    /// The file system to hold the inodes of all memfds
+    static ref MEMFD_FS: Arc<dyn FileSystem> = RamFS::new();
+}
+
+bitflags::bitflags! {
+    pub struct MemfdFlags: u32 {
+        /// Close on exec
+        const MFD_CLOEXEC = 1 << 0;
+        /// Allow sealing operations on the file
+        const MFD_ALLOW_SEALING = 1 << 1;
+        /// Create the file in the hugetlbfs (not supported)
+        const MFD_HUGETLB = 1 << 2;
+    }
+}
+
+impl MemfdFlags {
+    pub fn from_u32(raw: u32) -> Result<Self> {
+        let flags = Self::from_bits(raw).ok_or_else(|| errno!(EINVAL, "invalid memfd flags"))?;
+        if flags.contains(Self::MFD_HUGETLB) {
+            return_errno!(EINVAL, "MFD_HUGETLB is not supported");
+        }
+        Ok(flags)
+    }
+}
+
+bitflags::bitflags! {
+    pub struct FileSeals: u32 {
+        /// Prevent further seals from being set
+        const F_SEAL_SEAL = 1 << 0;
+        /// Prevent the file from shrinking
+        const F_SEAL_SHRINK = 1 << 1;
+        /// Prevent the file from growing
+        const F_SEAL_GROW = 1 << 2;
+        /// Prevent writes to the file
+        const F_SEAL_WRITE = 1 << 3;
+    }
+}
+
+impl FileSeals {
+    pub fn from_u32(raw: u32) -> Result<Self> {
+        Self::from_bits(raw).ok_or_else(|| errno!(EINVAL, "invalid seals"))
+    }
+}
+
+/// The inode of a memfd, which is backed by a RamFS inode and enforces the
+/// seals of the file.
+pub struct MemfdInode {
+    inner: Arc<dyn INode>,
+    seals: SgxMutex<FileSeals>,
+}
+
+impl MemfdInode {
+    fn new(inner: Arc<dyn INode>, seals: FileSeals) -> Self {
+        Self {
+            inner,
+            seals: SgxMutex::new(seals),
+        }
+    }
+
+    pub fn seals(&self) -> FileSeals {
+        *self.seals.lock().unwrap()
+    }
+
+    pub fn add_seals(&self, new_seals: FileSeals) -> Result<()> {
+        if self.seals().contains(FileSeals::F_SEAL_SEAL) {
+            return_errno!(EPERM, "the seals cannot be changed");
+        }
+        // The mappings are checked without holding the seals, since writing back
+        // a mapping locks the seals while the VM is locked
+        if new_seals.contains(FileSeals::F_SEAL_WRITE) && self.has_writable_mapping() {
+            return_errno!(EBUSY, "the file has shared writable mappings");
+        }
+
+        let mut seals = self.seals.lock().unwrap();
+        if seals.contains(FileSeals::F_SEAL_SEAL) {
+            return_errno!(EPERM, "the seals cannot be changed");
+        }
+        *seals |= new_seals;
+        Ok(())
+    }
+
+    /// Check whether the file can be mapped as shared and writable.
+    pub fn check_writable_mapping(&self) -> Result<()> {
+        if self.seals().contains(FileSeals::F_SEAL_WRITE) {
+            return_errno!(EPERM, "the file is sealed against writes");
+        }
+        Ok(())
+    }
+
+    fn has_writable_mapping(&self) -> bool {
+        let self_ptr = self as *const Self as *const ();
+        USER_SPACE_VM_MANAGER.has_writable_file_mapping(|file| {
+            file.as_inode_file().map_or(false, |inode_file| {
+                Arc::as_ptr(inode_file.inode()) as *const () == self_ptr
+            })
+        })
+    }
+}
+
+impl INode for MemfdInode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        self.inner.read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        // Hold the seals so that no seal could be added during the write
+        let seals = self.seals.lock().unwrap();
+        if seals.contains(FileSeals::F_SEAL_WRITE) {
+            return Err(FsError::PermError);
+        }
+        if seals.contains(FileSeals::F_SEAL_GROW)
+            && offset + buf.len() > self.inner.metadata()?.size
+        {
+            return Err(FsError::PermError);
+        }
+        self.inner.write_at(offset, buf)
+    }
+
+    fn poll(&self) -> vfs::Result<vfs::PollStatus> {
+        self.inner.poll()
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        self.inner.metadata()
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> vfs::Result<()> {
+        self.inner.set_metadata(metadata)
+    }
+
+    fn sync_all(&self) -> vfs::Result<()> {
+        self.inner.sync_all()
+    }
+
+    fn sync_data(&self) -> vfs::Result<()> {
+        self.inner.sync_data()
+    }
+
+    fn resize(&self, len: usize) -> vfs::Result<()> {
+        let seals = self.seals.lock().unwrap();
+        let size = self.inner.metadata()?.size;
+        if (len < size && seals.contains(FileSeals::F_SEAL_SHRINK))
+            || (len > size && seals.contains(FileSeals::F_SEAL_GROW))
+        {
+            return Err(FsError::PermError);
+        }
+        self.inner.resize(len)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.inner.fs()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Debug for MemfdInode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemfdInode")
+            .field("seals", &self.seals())
+            .finish()
+    }
+}
@@ -29,7 +29,7 @@ use crate::config::ConfigMount;
 pub use self::disk_file::DiskFile;
 pub use self::event_file::{EventFile, EventFileFlags};
 pub use self::file_handle::{FileHandle as FileRef, WeakFileHandle as WeakFileRef};
-pub use self::file_ops::utimbuf_t;
+pub use self::file_ops::{open_how, utimbuf_t, Statx};
 pub use self::file_table::{FileDesc, FileTable};
 pub use self::fs_ops::Statfs;
 pub use self::fs_view::FsView;
@@ -40,10 +40,13 @@ pub use self::locks::{
     FileRange, Flock, FlockList, FlockOps, FlockType, RangeLock, RangeLockBuilder, RangeLockList,
     RangeLockType, OFFSET_MAX,
 };
+pub use self::memfd::{FileSeals, MemfdFlags, MemfdInode, MEMFD_NAME_MAX};
+pub use self::pipe::{PipeReader, PipeWriter};
 pub use self::rootfs::ROOT_FS;
 pub use self::stdio::{HostStdioFds, StdinFile, StdoutFile};
 pub use self::syscalls::*;
 
+mod bindfs;
 mod builtin_disk;
 mod dev_fs;
 mod disk_file;
@@ -60,6 +63,7 @@ mod host_fd;
 mod hostfs;
 mod inode_file;
 mod locks;
+mod memfd;
 mod pipe;
 mod procfs;
 mod rootfs;
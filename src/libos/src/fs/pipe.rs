@@ -1,11 +1,16 @@
 use super::*;
 
+use crate::vm::PAGE_SIZE;
 use async_io::ioctl::IoctlCmd;
 pub use async_io::util::channel::{Channel, Consumer, Producer};
+use std::any::Any;
 
-// TODO: Add F_SETPIPE_SZ in fcntl to dynamically change the size of pipe
-// to improve memory efficiency. This value is got from /proc/sys/fs/pipe-max-size on linux.
+// This value is got from /proc/sys/fs/pipe-max-size on linux. The size of a pipe
+// can be changed by F_SETPIPE_SZ in fcntl to improve memory efficiency.
 pub const DEFAULT_BUF_SIZE: usize = 1024 * 1024;
+/// The maximum size of a pipe that can be set by F_SETPIPE_SZ, which is like
+/// /proc/sys/fs/pipe-max-size on Linux but leaves room to grow from the default.
+pub const MAX_BUF_SIZE: usize = 16 * DEFAULT_BUF_SIZE;
 
 pub fn do_pipe2(flags: u32) -> Result<[FileDesc; 2]> {
     let creation_flags = CreationFlags::from_bits_truncate(flags);
@@ -18,8 +23,12 @@ pub fn do_pipe2(flags: u32) -> Result<[FileDesc; 2]> {
 
     let close_on_spawn = creation_flags.must_close_on_spawn();
     let current = current!();
-    let reader_fd = current.add_file(pipe_reader, close_on_spawn);
-    let writer_fd = current.add_file(pipe_writer, close_on_spawn);
+    let reader_fd = current.add_file(pipe_reader, close_on_spawn)?;
+    let writer_fd = current.add_file(pipe_writer, close_on_spawn).map_err(|e| {
+        // Do not leave a half-created pipe in the file table
+        let _ = current.close_file(reader_fd);
+        e
+    })?;
     debug!(
         "pipe2: returns reader_fd = {}, writer_fd = {}",
         reader_fd, writer_fd
@@ -38,6 +47,36 @@ pub struct PipeReader {
     consumer: Consumer<u8>,
 }
 
+impl PipeReader {
+    /// Hand the data in the pipe to `f`, and consume the number of bytes that `f`
+    /// returns. No one else can read the pipe while `f` runs.
+    pub fn consume_with<F>(&self, f: F) -> Result<usize>
+    where
+        F: FnOnce(&[u8], &[u8]) -> Result<usize>,
+    {
+        self.consumer.consume_with(f)
+    }
+
+    pub fn ready_len(&self) -> usize {
+        self.consumer.ready_len()
+    }
+
+    pub fn is_writer_closed(&self) -> bool {
+        self.consumer.peer_is_shutdown()
+    }
+
+    pub fn buf_size(&self) -> usize {
+        self.consumer.capacity()
+    }
+
+    /// Resize the buffer of the pipe, see `round_buf_size` for the actual size.
+    pub fn set_buf_size(&self, size: usize) -> Result<usize> {
+        let size = round_buf_size(size)?;
+        self.consumer.resize(size)?;
+        Ok(size)
+    }
+}
+
 impl File for PipeReader {
     fn read(&self, buf: &mut [u8]) -> Result<usize> {
         self.consumer.read(buf)
@@ -88,6 +127,10 @@ impl File for PipeReader {
             ..Default::default()
         }
     }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
 }
 
 #[derive(Debug)]
@@ -95,6 +138,40 @@ pub struct PipeWriter {
     producer: Producer<u8>,
 }
 
+impl PipeWriter {
+    pub fn free_len(&self) -> usize {
+        self.producer.free_len()
+    }
+
+    pub fn is_reader_closed(&self) -> bool {
+        self.producer.peer_is_shutdown()
+    }
+
+    pub fn buf_size(&self) -> usize {
+        self.producer.capacity()
+    }
+
+    /// Resize the buffer of the pipe, see `round_buf_size` for the actual size.
+    pub fn set_buf_size(&self, size: usize) -> Result<usize> {
+        let size = round_buf_size(size)?;
+        self.producer.resize(size)?;
+        Ok(size)
+    }
+}
+
+/// Like Linux, the size of a pipe is rounded up to a power-of-two multiple of
+/// the page size. A size larger than `MAX_BUF_SIZE` is not permitted.
+fn round_buf_size(size: usize) -> Result<usize> {
+    if size > (1 << 31) {
+        return_errno!(EINVAL, "pipe size is too large");
+    }
+    let size = size.max(PAGE_SIZE).next_power_of_two();
+    if size > MAX_BUF_SIZE {
+        return_errno!(EPERM, "pipe size exceeds the max");
+    }
+    Ok(size)
+}
+
 impl File for PipeWriter {
     fn write(&self, buf: &[u8]) -> Result<usize> {
         self.producer.write(buf)
@@ -140,4 +217,8 @@ impl File for PipeWriter {
             ..Default::default()
         }
     }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
 }
@@ -9,6 +9,8 @@ use self::exe::ProcExeSymINode;
 use self::fd::LockedProcFdDirINode;
 use self::root::ProcRootSymINode;
 use self::stat::ProcStatINode;
+use self::statm::ProcStatmINode;
+use self::status::ProcStatusINode;
 
 mod cmdline;
 mod comm;
@@ -17,6 +19,8 @@ mod exe;
 mod fd;
 mod root;
 mod stat;
+mod statm;
+mod status;
 
 pub struct LockedPidDirINode(RwLock<PidDirINode>);
 
@@ -60,6 +64,12 @@ impl LockedPidDirINode {
         // stat
         let stat_inode = ProcStatINode::new(&file.process_ref);
         file.entries.insert(String::from("stat"), stat_inode);
+        // statm
+        let statm_inode = ProcStatmINode::new(&file.process_ref);
+        file.entries.insert(String::from("statm"), statm_inode);
+        // status
+        let status_inode = ProcStatusINode::new(&file.process_ref);
+        file.entries.insert(String::from("status"), status_inode);
 
         Ok(())
     }
@@ -0,0 +1,37 @@
+use super::*;
+use crate::vm::PAGE_SIZE;
+
+pub struct ProcStatmINode(ProcessRef);
+
+impl ProcStatmINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcStatmINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let main_thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
+        let vm = main_thread.vm();
+
+        // All the values are measured in pages. The mapped memory is always
+        // resident since all the pages of the enclave are committed in advance.
+        let size = vm.get_mapped_size(self.0.pid()) / PAGE_SIZE;
+        let resident = size;
+        let shared = 0;
+        let text = vm
+            .get_elf_ranges()
+            .iter()
+            .fold(0, |acc, range| acc + range.size())
+            / PAGE_SIZE;
+        let lib = 0;
+        let data = size.saturating_sub(text);
+        let dt = 0;
+
+        Ok(format!(
+            "{} {} {} {} {} {} {}\n",
+            size, resident, shared, text, lib, data, dt
+        )
+        .into_bytes())
+    }
+}
@@ -0,0 +1,67 @@
+use super::*;
+
+pub struct ProcStatusINode(ProcessRef);
+
+impl ProcStatusINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcStatusINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let main_thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
+
+        let name = String::from_utf8_lossy(main_thread.name().as_c_str().to_bytes()).into_owned();
+        let umask = self.0.umask().bits();
+        let state = match self.0.status() {
+            ProcessStatus::Running => "R (running)",
+            ProcessStatus::Stopped => "T (stopped)",
+            ProcessStatus::Zombie => "Z (zombie)",
+        };
+        let pid = self.0.pid();
+        let ppid = self.0.parent().pid();
+        // All the processes are run by root and never traced
+        let tracer_pid = 0;
+        let (uid, gid) = (0, 0);
+        // The mapped memory is always resident since all the pages of the
+        // enclave are committed in advance.
+        let vm_size = main_thread.vm().get_mapped_size(pid) / 1024;
+        let vm_rss = vm_size;
+        let threads = self.0.threads().len();
+
+        Ok(format!(
+            "Name:\t{}\n\
+            Umask:\t{:04o}\n\
+            State:\t{}\n\
+            Tgid:\t{}\n\
+            Pid:\t{}\n\
+            PPid:\t{}\n\
+            TracerPid:\t{}\n\
+            Uid:\t{}\t{}\t{}\t{}\n\
+            Gid:\t{}\t{}\t{}\t{}\n\
+            VmSize:\t{:>8} kB\n\
+            VmRSS:\t{:>8} kB\n\
+            Threads:\t{}\n",
+            name,
+            umask,
+            state,
+            pid,
+            pid,
+            ppid,
+            tracer_pid,
+            uid,
+            uid,
+            uid,
+            uid,
+            gid,
+            gid,
+            gid,
+            gid,
+            vm_size,
+            vm_rss,
+            threads
+        )
+        .into_bytes())
+    }
+}
@@ -0,0 +1,64 @@
+use super::*;
+use std::time::Duration;
+
+use crate::time::{do_clock_gettime, up_time, ClockId};
+use async_rt::stats::{vcpu_stats, VcpuStats};
+
+pub struct StatINode;
+
+// The unit of the CPU time in /proc/stat, i.e., USER_HZ
+const JIFFIES_PER_SEC: u128 = 100;
+
+impl StatINode {
+    pub fn new() -> Arc<dyn INode> {
+        Arc::new(File::new(Self))
+    }
+}
+
+impl ProcINode for StatINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let vcpu_stats = vcpu_stats();
+        let total_stats = vcpu_stats
+            .iter()
+            .fold(VcpuStats::default(), |total, stats| VcpuStats {
+                user_time: total.user_time + stats.user_time,
+                system_time: total.system_time + stats.system_time,
+                idle_time: total.idle_time + stats.idle_time,
+                num_switches: total.num_switches + stats.num_switches,
+            });
+        let boot_time = {
+            let now = do_clock_gettime(ClockId::CLOCK_REALTIME)
+                .unwrap()
+                .as_duration();
+            let up_time = up_time::get().unwrap_or_default();
+            now.checked_sub(up_time).unwrap_or_default().as_secs()
+        };
+
+        let mut result = cpu_line("cpu ", &total_stats);
+        for (vcpu_id, stats) in vcpu_stats.iter().enumerate() {
+            result += &cpu_line(&format!("cpu{}", vcpu_id), stats);
+        }
+        result += &format!(
+            "ctxt {}\n\
+             btime {}\n",
+            total_stats.num_switches, boot_time,
+        );
+        Ok(result.into_bytes())
+    }
+}
+
+// The columns are user, nice, system, idle, iowait, irq, softirq, steal, guest
+// and guest_nice. Only user, system and idle are accounted.
+fn cpu_line(name: &str, stats: &VcpuStats) -> String {
+    format!(
+        "{} {} 0 {} {} 0 0 0 0 0 0\n",
+        name,
+        to_jiffies(&stats.user_time),
+        to_jiffies(&stats.system_time),
+        to_jiffies(&stats.idle_time),
+    )
+}
+
+fn to_jiffies(duration: &Duration) -> u128 {
+    duration.as_millis() * JIFFIES_PER_SEC / 1000
+}
@@ -137,22 +137,24 @@ pub fn mount_nonroot_fs_according_to(
                     .layers
                     .as_ref()
                     .ok_or_else(|| errno!(EINVAL, "Invalid layers for unionfs"))?;
-                let image_fs_mc = layer_mcs
-                    .get(0)
-                    .ok_or_else(|| errno!(EINVAL, "Invalid image layer"))?;
-                let container_fs_mc = layer_mcs
-                    .get(1)
+                // The image layers come first, from the highest priority to
+                // the lowest, and the container layer is the last one.
+                let (container_fs_mc, image_fs_mcs) = layer_mcs
+                    .split_last()
                     .ok_or_else(|| errno!(EINVAL, "Invalid container layer"))?;
-                let unionfs = match (&image_fs_mc.type_, &container_fs_mc.type_) {
-                    (TYPE_SEFS, TYPE_SEFS) => {
-                        let image_sefs = open_or_create_sefs_according_to(image_fs_mc, user_key)?;
-                        let container_sefs =
-                            open_or_create_sefs_according_to(container_fs_mc, user_key)?;
-                        UnionFS::new(vec![container_sefs, image_sefs])?
-                    }
-                    (_, _) => {
-                        return_errno!(EINVAL, "Unsupported fs type inside unionfs");
+                if image_fs_mcs.is_empty() {
+                    return_errno!(EINVAL, "Invalid image layer");
+                }
+                if layer_mcs.iter().any(|mc| mc.type_ != TYPE_SEFS) {
+                    return_errno!(EINVAL, "Unsupported fs type inside unionfs");
+                }
+                let unionfs = {
+                    let mut layers: Vec<Arc<dyn FileSystem>> = Vec::new();
+                    layers.push(open_or_create_sefs_according_to(container_fs_mc, user_key)?);
+                    for image_fs_mc in image_fs_mcs {
+                        layers.push(open_or_create_sefs_according_to(image_fs_mc, user_key)?);
                     }
+                    UnionFS::new(layers)?
                 };
                 mount_fs_at(unionfs, root, &mc.target, follow_symlink)?;
             }
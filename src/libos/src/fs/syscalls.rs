@@ -1,7 +1,7 @@
 use super::file_ops::{
-    self, do_utimes_fd, do_utimes_path, get_utimes, utimbuf_t, AccessibilityCheckFlags,
-    AccessibilityCheckMode, ChownFlags, FcntlCmd, IoctlRawCmd, LinkFlags, UnlinkFlags, Utime,
-    UtimeFlags, UTIME_OMIT,
+    self, do_utimes_fd, do_utimes_path, get_utimes, open_how, utimbuf_t, AccessibilityCheckFlags,
+    AccessibilityCheckMode, ChownFlags, FcntlCmd, FileAdvice, IoctlRawCmd, LinkFlags, RenameFlags,
+    RwfFlags, SpliceFlags, Statx, StatxMask, UnlinkFlags, Utime, UtimeFlags, UTIME_OMIT,
 };
 use super::fs_ops::{self, MountOptions};
 use super::time::{timespec_t, timeval_t};
@@ -54,6 +54,43 @@ pub async fn do_openat(dirfd: i32, path: *const i8, flags: u32, mode: u16) -> Re
     Ok(fd as isize)
 }
 
+pub async fn do_openat2(
+    dirfd: i32,
+    path: *const i8,
+    how: *const open_how,
+    size: usize,
+) -> Result<isize> {
+    let how = {
+        const OPEN_HOW_SIZE: usize = std::mem::size_of::<open_how>();
+        if size < OPEN_HOW_SIZE {
+            return_errno!(EINVAL, "the size of open_how is too small");
+        }
+        if size > crate::vm::PAGE_SIZE {
+            return_errno!(E2BIG, "the size of open_how is too large");
+        }
+        from_user::check_array(how as *const u8, size)?;
+        // The extension fields unknown to us must be zeros
+        let ext_bytes = unsafe {
+            std::slice::from_raw_parts((how as *const u8).add(OPEN_HOW_SIZE), size - OPEN_HOW_SIZE)
+        };
+        if ext_bytes.iter().any(|byte| *byte != 0) {
+            return_errno!(E2BIG, "unknown fields of open_how are not zeros");
+        }
+        unsafe { how.read_unaligned() }
+    };
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    if path.is_empty() {
+        return_errno!(ENOENT, "path is an empty string");
+    } else if path.len() > MAX_PATH_LEN {
+        return_errno!(ENAMETOOLONG, "path name too long");
+    }
+    let fs_path = FsPath::new(&path, dirfd)?;
+    let fd = file_ops::do_openat2(&fs_path, &how)?;
+    Ok(fd as isize)
+}
+
 pub async fn do_umask(mask: u16) -> Result<isize> {
     let new_mask = FileMode::from_bits_truncate(mask).to_umask();
     let old_mask = current!().process().set_umask(new_mask);
@@ -61,7 +98,7 @@ pub async fn do_umask(mask: u16) -> Result<isize> {
 }
 
 pub async fn do_close(fd: FileDesc) -> Result<isize> {
-    file_ops::do_close(fd)?;
+    file_ops::do_close(fd).await?;
     Ok(0)
 }
 
@@ -84,24 +121,7 @@ pub async fn do_write(fd: FileDesc, buf: *const u8, size: usize) -> Result<isize
 }
 
 pub async fn do_writev(fd: FileDesc, iov: *const iovec_t, count: i32) -> Result<isize> {
-    let count = {
-        if count < 0 {
-            return_errno!(EINVAL, "Invalid count of iovec");
-        }
-        count as usize
-    };
-
-    from_user::check_array(iov, count)?;
-    let bufs_vec = {
-        let mut bufs_vec = Vec::with_capacity(count);
-        for iov_i in 0..count {
-            let iov_ptr = unsafe { iov.offset(iov_i as isize) };
-            let iov = unsafe { &*iov_ptr };
-            let buf = unsafe { std::slice::from_raw_parts(iov.base as *const u8, iov.len) };
-            bufs_vec.push(buf);
-        }
-        bufs_vec
-    };
+    let bufs_vec = bufs_from_iovs(iov, count)?;
     let bufs = &bufs_vec[..];
 
     let len = file_ops::do_writev(fd, bufs).await?;
@@ -109,6 +129,15 @@ pub async fn do_writev(fd: FileDesc, iov: *const iovec_t, count: i32) -> Result<
 }
 
 pub async fn do_readv(fd: FileDesc, iov: *mut iovec_t, count: i32) -> Result<isize> {
+    let mut bufs_vec = mut_bufs_from_iovs(iov, count)?;
+    let bufs = &mut bufs_vec[..];
+
+    let len = file_ops::do_readv(fd, bufs).await?;
+    Ok(len as isize)
+}
+
+// Check a user array of iovecs and turn it into a vector of buffers.
+fn bufs_from_iovs<'a>(iov: *const iovec_t, count: i32) -> Result<Vec<&'a [u8]>> {
     let count = {
         if count < 0 {
             return_errno!(EINVAL, "Invalid count of iovec");
@@ -117,20 +146,42 @@ pub async fn do_readv(fd: FileDesc, iov: *mut iovec_t, count: i32) -> Result<isi
     };
 
     from_user::check_array(iov, count)?;
-    let mut bufs_vec = {
-        let mut bufs_vec = Vec::with_capacity(count);
-        for iov_i in 0..count {
-            let iov_ptr = unsafe { iov.offset(iov_i as isize) };
-            let iov = unsafe { &*iov_ptr };
-            let buf = unsafe { std::slice::from_raw_parts_mut(iov.base as *mut u8, iov.len) };
-            bufs_vec.push(buf);
+    {
+        let iovs = unsafe { std::slice::from_raw_parts(iov, count) };
+        from_user::check_iovs_total_len(iovs.iter().map(|iov| iov.len), EINVAL)?;
+    }
+    let mut bufs_vec = Vec::with_capacity(count);
+    for iov_i in 0..count {
+        let iov_ptr = unsafe { iov.offset(iov_i as isize) };
+        let iov = unsafe { &*iov_ptr };
+        let buf = unsafe { std::slice::from_raw_parts(iov.base as *const u8, iov.len) };
+        bufs_vec.push(buf);
+    }
+    Ok(bufs_vec)
+}
+
+// Check a user array of iovecs and turn it into a vector of mutable buffers.
+fn mut_bufs_from_iovs<'a>(iov: *mut iovec_t, count: i32) -> Result<Vec<&'a mut [u8]>> {
+    let count = {
+        if count < 0 {
+            return_errno!(EINVAL, "Invalid count of iovec");
         }
-        bufs_vec
+        count as usize
     };
-    let bufs = &mut bufs_vec[..];
 
-    let len = file_ops::do_readv(fd, bufs).await?;
-    Ok(len as isize)
+    from_user::check_array(iov, count)?;
+    {
+        let iovs = unsafe { std::slice::from_raw_parts(iov, count) };
+        from_user::check_iovs_total_len(iovs.iter().map(|iov| iov.len), EINVAL)?;
+    }
+    let mut bufs_vec = Vec::with_capacity(count);
+    for iov_i in 0..count {
+        let iov_ptr = unsafe { iov.offset(iov_i as isize) };
+        let iov = unsafe { &*iov_ptr };
+        let buf = unsafe { std::slice::from_raw_parts_mut(iov.base as *mut u8, iov.len) };
+        bufs_vec.push(buf);
+    }
+    Ok(bufs_vec)
 }
 
 pub async fn do_pread(fd: FileDesc, buf: *mut u8, size: usize, offset: off_t) -> Result<isize> {
@@ -151,6 +202,50 @@ pub async fn do_pwrite(fd: FileDesc, buf: *const u8, size: usize, offset: off_t)
     Ok(len as isize)
 }
 
+pub async fn do_preadv2(
+    fd: FileDesc,
+    iov: *mut iovec_t,
+    count: i32,
+    offset: off_t,
+    _offset_high: off_t,
+    flags: i32,
+) -> Result<isize> {
+    // An offset of -1 means using and updating the file offset
+    let offset = match offset {
+        -1 => None,
+        offset if offset < 0 => return_errno!(EINVAL, "the offset is negative"),
+        offset => Some(offset),
+    };
+    let flags = RwfFlags::from_i32(flags)?;
+    let mut bufs_vec = mut_bufs_from_iovs(iov, count)?;
+    let bufs = &mut bufs_vec[..];
+
+    let len = file_ops::do_preadv2(fd, bufs, offset, flags).await?;
+    Ok(len as isize)
+}
+
+pub async fn do_pwritev2(
+    fd: FileDesc,
+    iov: *const iovec_t,
+    count: i32,
+    offset: off_t,
+    _offset_high: off_t,
+    flags: i32,
+) -> Result<isize> {
+    // An offset of -1 means using and updating the file offset
+    let offset = match offset {
+        -1 => None,
+        offset if offset < 0 => return_errno!(EINVAL, "the offset is negative"),
+        offset => Some(offset),
+    };
+    let flags = RwfFlags::from_i32(flags)?;
+    let bufs_vec = bufs_from_iovs(iov, count)?;
+    let bufs = &bufs_vec[..];
+
+    let len = file_ops::do_pwritev2(fd, bufs, offset, flags).await?;
+    Ok(len as isize)
+}
+
 pub async fn do_fstat(fd: FileDesc, stat_buf: *mut StatBuf) -> Result<isize> {
     from_user::check_mut_ptr(stat_buf)?;
 
@@ -197,6 +292,34 @@ pub async fn do_fstatat(
     Ok(0)
 }
 
+pub async fn do_statx(
+    dirfd: i32,
+    path: *const i8,
+    flags: u32,
+    mask: u32,
+    statx_buf: *mut Statx,
+) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    // The flags of AT_STATX_SYNC_TYPE are about the consistency with remote
+    // file systems, which are irrelevant here.
+    const AT_STATX_SYNC_TYPE: u32 = 0x6000;
+    let flags = StatFlags::from_bits(flags & !AT_STATX_SYNC_TYPE)
+        .ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let mask = StatxMask::from_bits_truncate(mask);
+    if path.is_empty() && !flags.contains(StatFlags::AT_EMPTY_PATH) {
+        return_errno!(ENOENT, "path is an empty string");
+    }
+    let fs_path = FsPath::new(&path, dirfd)?;
+    from_user::check_mut_ptr(statx_buf)?;
+    let stat = file_ops::do_fstatat(&fs_path, flags)?;
+    unsafe {
+        statx_buf.write(Statx::new(&stat, mask));
+    }
+    Ok(0)
+}
+
 pub async fn do_access(path: *const i8, mode: u32) -> Result<isize> {
     self::do_faccessat(AT_FDCWD, path, mode, 0).await
 }
@@ -381,6 +504,31 @@ pub async fn do_renameat(
     Ok(0)
 }
 
+pub async fn do_renameat2(
+    olddirfd: i32,
+    oldpath: *const i8,
+    newdirfd: i32,
+    newpath: *const i8,
+    flags: u32,
+) -> Result<isize> {
+    let flags = RenameFlags::from_u32(flags)?;
+    let oldpath = from_user::clone_cstring_safely(oldpath)?
+        .to_string_lossy()
+        .into_owned();
+    let newpath = from_user::clone_cstring_safely(newpath)?
+        .to_string_lossy()
+        .into_owned();
+    if oldpath.is_empty() || newpath.is_empty() {
+        return_errno!(ENOENT, "oldpath or newpath is an empty string");
+    } else if oldpath.len() > MAX_PATH_LEN || newpath.len() > MAX_PATH_LEN {
+        return_errno!(ENAMETOOLONG, "oldpath or newpath name too long");
+    }
+    let old_fs_path = FsPath::new(&oldpath, olddirfd)?;
+    let new_fs_path = FsPath::new(&newpath, newdirfd)?;
+    file_ops::do_renameat2(&old_fs_path, &new_fs_path, flags)?;
+    Ok(0)
+}
+
 pub async fn do_mkdir(path: *const i8, mode: u16) -> Result<isize> {
     self::do_mkdirat(AT_FDCWD, path, mode).await
 }
@@ -622,6 +770,15 @@ pub async fn do_mount(
         .to_string_lossy()
         .into_owned();
     let flags = MountFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    // The fs type and the options are ignored for remounts and bind mounts
+    if flags.contains(MountFlags::MS_REMOUNT) {
+        fs_ops::do_remount(&target, flags)?;
+        return Ok(0);
+    }
+    if flags.contains(MountFlags::MS_BIND) {
+        fs_ops::do_bind_mount(&source, &target, flags)?;
+        return Ok(0);
+    }
     let mount_options = {
         let fs_type = {
             let fs_type = from_user::clone_cstring_safely(fs_type)?
@@ -658,6 +815,17 @@ pub async fn do_fallocate(fd: FileDesc, mode: u32, offset: off_t, len: off_t) ->
     Ok(0)
 }
 
+pub async fn do_fadvise64(fd: FileDesc, offset: off_t, len: off_t, advice: i32) -> Result<isize> {
+    if len < 0 {
+        return_errno!(EINVAL, "len is negative");
+    }
+    let advice = FileAdvice::from_i32(advice)?;
+    // A negative offset is not an error, but is treated as 0
+    let offset = offset.max(0) as usize;
+    file_ops::do_fadvise(fd, offset, len as usize, advice).await?;
+    Ok(0)
+}
+
 pub async fn do_fstatfs(fd: FileDesc, statfs_buf: *mut Statfs) -> Result<isize> {
     from_user::check_mut_ptr(statfs_buf)?;
 
@@ -705,6 +873,91 @@ pub async fn do_sendfile(
     Ok(written_len as isize)
 }
 
+pub async fn do_copy_file_range(
+    fd_in: FileDesc,
+    off_in_ptr: *mut off_t,
+    fd_out: FileDesc,
+    off_out_ptr: *mut off_t,
+    len: usize,
+    flags: u32,
+) -> Result<isize> {
+    let off_in = if off_in_ptr.is_null() {
+        None
+    } else {
+        from_user::check_mut_ptr(off_in_ptr)?;
+        Some(unsafe { off_in_ptr.read() })
+    };
+    let off_out = if off_out_ptr.is_null() {
+        None
+    } else {
+        from_user::check_mut_ptr(off_out_ptr)?;
+        Some(unsafe { off_out_ptr.read() })
+    };
+
+    let (copied_len, in_offset, out_offset) =
+        file_ops::do_copy_file_range(fd_in, off_in, fd_out, off_out, len, flags)?;
+    if !off_in_ptr.is_null() {
+        unsafe {
+            off_in_ptr.write(in_offset as off_t);
+        }
+    }
+    if !off_out_ptr.is_null() {
+        unsafe {
+            off_out_ptr.write(out_offset as off_t);
+        }
+    }
+    Ok(copied_len as isize)
+}
+
+pub async fn do_splice(
+    fd_in: FileDesc,
+    off_in_ptr: *mut off_t,
+    fd_out: FileDesc,
+    off_out_ptr: *mut off_t,
+    len: usize,
+    flags: u32,
+) -> Result<isize> {
+    let flags = SpliceFlags::from_u32(flags)?;
+    let off_in = if off_in_ptr.is_null() {
+        None
+    } else {
+        from_user::check_mut_ptr(off_in_ptr)?;
+        Some(unsafe { off_in_ptr.read() })
+    };
+    let off_out = if off_out_ptr.is_null() {
+        None
+    } else {
+        from_user::check_mut_ptr(off_out_ptr)?;
+        Some(unsafe { off_out_ptr.read() })
+    };
+
+    let (spliced_len, in_offset, out_offset) =
+        file_ops::do_splice(fd_in, off_in, fd_out, off_out, len, flags).await?;
+    if let Some(in_offset) = in_offset {
+        unsafe {
+            off_in_ptr.write(in_offset as off_t);
+        }
+    }
+    if let Some(out_offset) = out_offset {
+        unsafe {
+            off_out_ptr.write(out_offset as off_t);
+        }
+    }
+    Ok(spliced_len as isize)
+}
+
+pub async fn do_memfd_create(name: *const i8, flags: u32) -> Result<isize> {
+    let name = from_user::clone_cstring_safely(name)?
+        .to_string_lossy()
+        .into_owned();
+    if name.len() > MEMFD_NAME_MAX {
+        return_errno!(EINVAL, "name is too long");
+    }
+    let flags = MemfdFlags::from_u32(flags)?;
+    let fd = super::memfd::do_memfd_create(&name, flags)?;
+    Ok(fd as isize)
+}
+
 pub async fn do_flock(fd: FileDesc, operation: i32) -> Result<isize> {
     let flock_ops = FlockOps::from_i32(operation)?;
 
@@ -1,4 +1,33 @@
 use super::*;
+use crate::config::{ConfigEntropySource, LIBOS_CONFIG};
+use entropy::{fill_with_retries, ChaChaRng, EntropySource};
+
+lazy_static! {
+    /// The entropy source specified by the `entropy_source` field of Occlum.json.
+    static ref ENTROPY_SOURCE: Box<dyn EntropySource> = match LIBOS_CONFIG.entropy_source {
+        ConfigEntropySource::Hardware => Box::new(HwRng) as Box<dyn EntropySource>,
+        ConfigEntropySource::Csprng => new_csprng(),
+    };
+}
+
+// The max number of retries when the entropy source fails. Note that each try of
+// the hardware RNG already retries the RDRAND instruction for several times.
+const MAX_RETRIES: usize = 10;
+
+fn new_csprng() -> Box<dyn EntropySource> {
+    let mut seed = [0_u8; 32];
+    match fill_with_retries(&HwRng, &mut seed, MAX_RETRIES) {
+        Ok(()) => Box::new(ChaChaRng::new(seed)),
+        Err(e) => {
+            // Every later fill tries the hardware RNG again and reports its error
+            warn!(
+                "failed to seed the CSPRNG: {:?}; fall back to the hardware RNG",
+                e
+            );
+            Box::new(HwRng)
+        }
+    }
+}
 
 pub fn do_getrandom(rand_buf: &mut [u8], flags: RandFlags) -> Result<()> {
     debug!("getrandom: flags: {:?}", flags);
@@ -19,15 +48,23 @@ bitflags! {
 }
 
 fn get_random_blocking(rand: &mut [u8]) -> Result<()> {
-    loop {
-        if get_random(rand).is_ok() {
-            break;
-        }
-    }
-    Ok(())
+    fill_with_retries(&**ENTROPY_SOURCE, rand, MAX_RETRIES)
 }
 
 pub fn get_random(rand: &mut [u8]) -> Result<()> {
+    ENTROPY_SOURCE.fill(rand)
+}
+
+/// The hardware RNG of SGX.
+struct HwRng;
+
+impl EntropySource for HwRng {
+    fn fill(&self, rand: &mut [u8]) -> Result<()> {
+        read_hw_rand(rand)
+    }
+}
+
+fn read_hw_rand(rand: &mut [u8]) -> Result<()> {
     use sgx_types::sgx_status_t;
     extern "C" {
         fn sgx_read_rand(rand_buf: *mut u8, buf_size: usize) -> sgx_status_t;
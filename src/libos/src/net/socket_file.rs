@@ -1,5 +1,5 @@
 use async_io::ioctl::IoctlCmd;
-use async_io::socket::{RecvFlags, SendFlags, Shutdown};
+use async_io::socket::{MsgFlags, RecvFlags, SendFlags, Shutdown};
 
 use self::impls::{Ipv4Datagram, Ipv4Stream, Ipv6Stream, UnixDatagram};
 use super::unix::trusted::Stream as TrustedStream;
@@ -326,39 +326,63 @@ impl SocketFile {
         buf: &mut [u8],
         flags: RecvFlags,
     ) -> Result<(usize, Option<AnyAddr>)> {
-        self.recvmsg(&mut [buf], flags).await
+        self.recvmsg(&mut [buf], flags)
+            .await
+            .map(|(bytes_recv, addr_recv, _)| (bytes_recv, addr_recv))
     }
 
     pub async fn recvmsg(
         &self,
         bufs: &mut [&mut [u8]],
         flags: RecvFlags,
-    ) -> Result<(usize, Option<AnyAddr>)> {
-        // TODO: support msg_flags and msg_control
+    ) -> Result<(usize, Option<AnyAddr>, MsgFlags)> {
+        // The files received along with the bytes, if any, are closed
+        self.recvmsg_with_files(bufs, flags)
+            .await
+            .map(|(bytes_recv, addr_recv, msg_flags, _)| (bytes_recv, addr_recv, msg_flags))
+    }
+
+    /// Receive a message, together with the files sent along with it (i.e., with
+    /// `SCM_RIGHTS`). Only the trusted unix stream sockets can receive files.
+    pub async fn recvmsg_with_files(
+        &self,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<(usize, Option<AnyAddr>, MsgFlags, Vec<FileRef>)> {
         Ok(match &self.socket {
             AnySocket::Ipv4Stream(ipv4_stream) => {
                 let bytes_recv = ipv4_stream.recvmsg(bufs, flags).await?;
-                (bytes_recv, None)
+                (bytes_recv, None, MsgFlags::empty(), Vec::new())
             }
             AnySocket::Ipv6Stream(ipv6_stream) => {
                 let bytes_recv = ipv6_stream.recvmsg(bufs, flags).await?;
-                (bytes_recv, None)
+                (bytes_recv, None, MsgFlags::empty(), Vec::new())
             }
             AnySocket::UnixStream(unix_stream) => {
-                let bytes_recv = unix_stream.recvmsg(bufs, flags).await?;
-                (bytes_recv, None)
+                let (bytes_recv, files) = unix_stream.recvmsg(bufs, flags).await?;
+                (bytes_recv, None, MsgFlags::empty(), files)
             }
             AnySocket::TrustedUDS(trusted_stream) => {
-                let bytes_recv = trusted_stream.recvmsg(bufs, flags).await?;
-                (bytes_recv, None)
+                let (bytes_recv, files) = trusted_stream.recvmsg(bufs, flags).await?;
+                (bytes_recv, None, MsgFlags::empty(), files)
             }
             AnySocket::Ipv4Datagram(ipv4_datagram) => {
-                let (bytes_recv, addr_recv) = ipv4_datagram.recvmsg(bufs, flags).await?;
-                (bytes_recv, Some(AnyAddr::Ipv4(addr_recv)))
+                let (bytes_recv, addr_recv, msg_flags) = ipv4_datagram.recvmsg(bufs, flags).await?;
+                (
+                    bytes_recv,
+                    Some(AnyAddr::Ipv4(addr_recv)),
+                    msg_flags,
+                    Vec::new(),
+                )
             }
             AnySocket::UnixDatagram(unix_datagram) => {
-                let (bytes_recv, addr_recv) = unix_datagram.recvmsg(bufs, flags).await?;
-                (bytes_recv, Some(AnyAddr::Unix(addr_recv)))
+                let (bytes_recv, addr_recv, msg_flags) = unix_datagram.recvmsg(bufs, flags).await?;
+                (
+                    bytes_recv,
+                    Some(AnyAddr::Unix(addr_recv)),
+                    msg_flags,
+                    Vec::new(),
+                )
             }
             _ => {
                 return_errno!(EINVAL, "recvfrom is not supported");
@@ -381,6 +405,26 @@ impl SocketFile {
         addr: Option<AnyAddr>,
         flags: SendFlags,
     ) -> Result<usize> {
+        self.sendmsg_with_files(bufs, addr, flags, Vec::new()).await
+    }
+
+    /// Send a message, together with the files to be sent along with it (i.e.,
+    /// with `SCM_RIGHTS`). Only the trusted unix stream sockets can send files.
+    pub async fn sendmsg_with_files(
+        &self,
+        bufs: &[&[u8]],
+        addr: Option<AnyAddr>,
+        flags: SendFlags,
+        files: Vec<FileRef>,
+    ) -> Result<usize> {
+        let is_unix_stream = matches!(
+            &self.socket,
+            AnySocket::UnixStream(_) | AnySocket::TrustedUDS(_)
+        );
+        if !files.is_empty() && !is_unix_stream {
+            return_errno!(EINVAL, "files can only be sent over unix stream sockets");
+        }
+
         let res = match &self.socket {
             AnySocket::Ipv4Stream(ipv4_stream) => {
                 if addr.is_some() {
@@ -398,13 +442,13 @@ impl SocketFile {
                 if addr.is_some() {
                     return_errno!(EISCONN, "addr should be none");
                 }
-                unix_stream.sendmsg(bufs, flags).await
+                unix_stream.sendmsg(bufs, flags, files).await
             }
             AnySocket::TrustedUDS(trusted_stream) => {
                 if addr.is_some() {
                     return_errno!(EISCONN, "addr should be none");
                 }
-                trusted_stream.sendmsg(bufs, flags).await
+                trusted_stream.sendmsg(bufs, flags, files).await
             }
             AnySocket::Ipv4Datagram(ipv4_datagram) => {
                 let ip_addr = if let Some(addr) = addr.as_ref() {
@@ -473,6 +517,18 @@ impl SocketFile {
             }
         }
     }
+    /// Prepare for closing the socket.
+    ///
+    /// For the stream sockets backed by the host, this waits for the data
+    /// in the send buffer to be flushed.
+    pub async fn close(&self) -> Result<()> {
+        match &self.socket {
+            AnySocket::Ipv4Stream(ipv4_stream) => ipv4_stream.close().await,
+            AnySocket::Ipv6Stream(ipv6_stream) => ipv6_stream.close().await,
+            AnySocket::UnixStream(unix_stream) => unix_stream.close().await,
+            _ => Ok(()),
+        }
+    }
 }
 
 mod impls {
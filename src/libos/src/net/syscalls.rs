@@ -2,7 +2,7 @@ use std::convert::TryFrom;
 use std::mem::MaybeUninit;
 
 use async_io::ioctl::IoctlCmd;
-use async_io::socket::{RecvFlags, SendFlags, Shutdown, Type};
+use async_io::socket::{MsgFlags, RecvFlags, SendFlags, Shutdown, Type};
 use host_socket::sockopt::{
     GetAcceptConnCmd, GetDomainCmd, GetPeerNameCmd, GetSockOptRawCmd, GetTypeCmd, SetSockOptRawCmd,
     SockOptName,
@@ -50,7 +50,7 @@ pub async fn do_socket(domain: c_int, type_and_flags: c_int, protocol: c_int) ->
     let file_ref = FileRef::new_socket(socket_file);
 
     let close_on_spawn = flags.contains(SocketFlags::SOCK_CLOEXEC);
-    let fd = current!().add_file(file_ref, close_on_spawn);
+    let fd = current!().add_file(file_ref, close_on_spawn)?;
     Ok(fd as isize)
 }
 
@@ -91,8 +91,13 @@ pub async fn do_socketpair(
     let file_ref1 = FileRef::new_socket(socket_file1);
     let file_ref2 = FileRef::new_socket(socket_file2);
     let close_on_spawn = flags.contains(SocketFlags::SOCK_CLOEXEC);
-    sock_pair[0] = current!().add_file(file_ref1, close_on_spawn);
-    sock_pair[1] = current!().add_file(file_ref2, close_on_spawn);
+    let current = current!();
+    sock_pair[0] = current.add_file(file_ref1, close_on_spawn)?;
+    sock_pair[1] = current.add_file(file_ref2, close_on_spawn).map_err(|e| {
+        // Do not leave a half-created socket pair in the file table
+        let _ = current.close_file(sock_pair[0]);
+        e
+    })?;
     debug!("socketpair: ({}, {})", sock_pair[0], sock_pair[1]);
     Ok(0)
 }
@@ -192,7 +197,7 @@ pub async fn do_accept4(
     let new_fd = {
         let new_file_ref = FileRef::new_socket(accepted_socket);
         let close_on_spawn = flags.contains(SocketFlags::SOCK_CLOEXEC);
-        current!().add_file(new_file_ref, close_on_spawn)
+        current!().add_file(new_file_ref, close_on_spawn)?
     };
     Ok(new_fd as isize)
 }
@@ -275,11 +280,11 @@ pub async fn do_sendmsg(fd: c_int, msg_ptr: *const libc::msghdr, flags: c_int) -
         .as_socket_file()
         .ok_or_else(|| errno!(ENOTSOCK, "not a socket"))?;
 
-    let (addr, bufs) = extract_msghdr_from_user(msg_ptr)?;
+    let (addr, bufs, files) = extract_msghdr_from_user(msg_ptr)?;
     let flags = SendFlags::from_bits_truncate(flags);
 
     socket_file
-        .sendmsg(&bufs[..], addr, flags)
+        .sendmsg_with_files(&bufs[..], addr, flags, files)
         .await
         .map(|bytes_send| bytes_send as isize)
 }
@@ -298,7 +303,8 @@ pub async fn do_recvmsg(fd: c_int, msg_mut_ptr: *mut libc::msghdr, flags: c_int)
     let (mut msg, mut addr, mut bufs) = extract_msghdr_mut_from_user(msg_mut_ptr)?;
     let flags = RecvFlags::from_bits_truncate(flags);
 
-    let (bytes_recv, recv_addr) = socket_file.recvmsg(&mut bufs[..], flags).await?;
+    let (bytes_recv, recv_addr, mut msg_flags, files) =
+        socket_file.recvmsg_with_files(&mut bufs[..], flags).await?;
 
     if let Some(addr) = addr {
         if let Some(recv_addr) = recv_addr {
@@ -306,6 +312,9 @@ pub async fn do_recvmsg(fd: c_int, msg_mut_ptr: *mut libc::msghdr, flags: c_int)
             copy_sock_addr_to_user(c_addr_storage, c_addr_len, addr, &mut msg.msg_namelen);
         }
     }
+    let close_on_spawn = flags.contains(RecvFlags::MSG_CMSG_CLOEXEC);
+    msg_flags |= copy_files_to_control(msg, files, close_on_spawn)?;
+    msg.msg_flags = msg_flags.bits();
 
     Ok(bytes_recv as isize)
 }
@@ -332,10 +341,10 @@ pub async fn do_sendmmsg(
 
     let mut send_count = 0;
     for mmsg in (msgvec) {
-        let (addr, bufs) = extract_msghdr_from_user(&mmsg.msg_hdr)?;
+        let (addr, bufs, files) = extract_msghdr_from_user(&mmsg.msg_hdr)?;
 
         if socket_file
-            .sendmsg(&bufs[..], addr, flags)
+            .sendmsg_with_files(&bufs[..], addr, flags, files)
             .await
             .map(|bytes_send| {
                 mmsg.msg_len += bytes_send as c_uint;
@@ -608,7 +617,7 @@ fn copy_bytes_to_user(src_buf: &[u8], dst_buf: &mut [u8], dst_len: &mut u32) {
 
 fn extract_msghdr_from_user<'a>(
     msg_ptr: *const libc::msghdr,
-) -> Result<(Option<AnyAddr>, Vec<&'a [u8]>)> {
+) -> Result<(Option<AnyAddr>, Vec<&'a [u8]>, Vec<FileRef>)> {
     let msg = from_user::make_ref(msg_ptr)?;
 
     let msg_name = msg.msg_name;
@@ -635,6 +644,7 @@ fn extract_msghdr_from_user<'a>(
         Vec::new()
     } else {
         let iovs = from_user::make_slice(msg_iov, msg_iovlen)?;
+        from_user::check_iovs_total_len(iovs.iter().map(|iov| iov.iov_len), EMSGSIZE)?;
         let mut bufs = Vec::with_capacity(msg_iovlen);
         for iov in iovs {
             let buf = from_user::make_slice(iov.iov_base as *const u8, iov.iov_len)?;
@@ -643,7 +653,9 @@ fn extract_msghdr_from_user<'a>(
         bufs
     };
 
-    Ok((name, bufs))
+    let files = extract_files_from_control(msg)?;
+
+    Ok((name, bufs, files))
 }
 
 fn extract_msghdr_mut_from_user<'a>(
@@ -678,6 +690,7 @@ fn extract_msghdr_mut_from_user<'a>(
         Vec::new()
     } else {
         let iovs = from_user::make_mut_slice(msg_iov, msg_iovlen)?;
+        from_user::check_iovs_total_len(iovs.iter().map(|iov| iov.iov_len), EMSGSIZE)?;
         let mut bufs = Vec::with_capacity(msg_iovlen);
         for iov in iovs {
             let buf = from_user::make_mut_slice(iov.iov_base as *mut u8, iov.iov_len)?;
@@ -688,3 +701,111 @@ fn extract_msghdr_mut_from_user<'a>(
 
     Ok((msg_mut, name, bufs))
 }
+
+// The length of the header of a control message, including the padding
+const CMSG_HDR_LEN: usize =
+    (std::mem::size_of::<libc::cmsghdr>() + CMSG_ALIGN - 1) & !(CMSG_ALIGN - 1);
+const CMSG_ALIGN: usize = std::mem::size_of::<usize>();
+// The max number of files that can be sent in a message, the same as Linux
+const SCM_MAX_FD: usize = 253;
+
+/// Get the files to be sent from the `SCM_RIGHTS` control messages of a msghdr.
+///
+/// The received files are shared with the receiver as if they were opened by
+/// it, so the receiver should trust them no more than the sender. The other
+/// types of control messages are ignored.
+fn extract_files_from_control(msg: &libc::msghdr) -> Result<Vec<FileRef>> {
+    if msg.msg_controllen == 0 {
+        return Ok(Vec::new());
+    }
+    let control = from_user::make_slice(msg.msg_control as *const u8, msg.msg_controllen)?;
+
+    let current = current!();
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while control.len() - offset >= CMSG_HDR_LEN {
+        let cmsg = unsafe { (control[offset..].as_ptr() as *const libc::cmsghdr).read_unaligned() };
+        let cmsg_len = cmsg.cmsg_len as usize;
+        if cmsg_len < CMSG_HDR_LEN || cmsg_len > control.len() - offset {
+            return_errno!(EINVAL, "invalid length of the control message");
+        }
+
+        if cmsg.cmsg_level == libc::SOL_SOCKET && cmsg.cmsg_type == libc::SCM_RIGHTS {
+            let fds = &control[offset + CMSG_HDR_LEN..offset + cmsg_len];
+            for fd in fds.chunks_exact(std::mem::size_of::<c_int>()) {
+                if files.len() == SCM_MAX_FD {
+                    return_errno!(EINVAL, "too many files to send");
+                }
+                let fd = c_int::from_ne_bytes([fd[0], fd[1], fd[2], fd[3]]);
+                files.push(current.file(fd as FileDesc)?);
+            }
+        } else {
+            warn!(
+                "control message (level = {}, type = {}) is not supported",
+                cmsg.cmsg_level, cmsg.cmsg_type
+            );
+        }
+        offset += align_up(cmsg_len, CMSG_ALIGN).min(control.len() - offset);
+    }
+    Ok(files)
+}
+
+/// Install the received files into the file table and put their fds into the
+/// `SCM_RIGHTS` control message of a msghdr.
+///
+/// The files that do not fit in the control buffer are closed, and MSG_CTRUNC
+/// is returned.
+fn copy_files_to_control(
+    msg: &mut libc::msghdr,
+    files: Vec<FileRef>,
+    close_on_spawn: bool,
+) -> Result<MsgFlags> {
+    let control_len = msg.msg_controllen;
+    msg.msg_controllen = 0;
+    if files.is_empty() {
+        return Ok(MsgFlags::empty());
+    }
+
+    let fd_len = std::mem::size_of::<c_int>();
+    let max_fds = control_len.saturating_sub(CMSG_HDR_LEN) / fd_len;
+    if max_fds == 0 {
+        return Ok(MsgFlags::MSG_CTRUNC);
+    }
+    let control = from_user::make_mut_slice(msg.msg_control as *mut u8, control_len)?;
+
+    let current = current!();
+    let num_files = files.len();
+    let mut fds = Vec::with_capacity(max_fds.min(num_files));
+    for file in files.into_iter().take(max_fds) {
+        match current.add_file(file, close_on_spawn) {
+            Ok(fd) => fds.push(fd),
+            Err(_) => break,
+        }
+    }
+    if fds.is_empty() {
+        return Ok(MsgFlags::MSG_CTRUNC);
+    }
+
+    let cmsg_len = CMSG_HDR_LEN + fds.len() * fd_len;
+    let cmsg = libc::cmsghdr {
+        cmsg_len: cmsg_len as _,
+        cmsg_level: libc::SOL_SOCKET,
+        cmsg_type: libc::SCM_RIGHTS,
+    };
+    unsafe {
+        (control.as_mut_ptr() as *mut libc::cmsghdr).write_unaligned(cmsg);
+    }
+    for (fd, fd_bytes) in fds
+        .iter()
+        .zip(control[CMSG_HDR_LEN..].chunks_exact_mut(fd_len))
+    {
+        fd_bytes.copy_from_slice(&(*fd as c_int).to_ne_bytes());
+    }
+    msg.msg_controllen = align_up(cmsg_len, CMSG_ALIGN).min(control_len);
+
+    if fds.len() < num_files {
+        Ok(MsgFlags::MSG_CTRUNC)
+    } else {
+        Ok(MsgFlags::empty())
+    }
+}
@@ -179,12 +179,35 @@ impl UnixStream {
         }
     }
 
-    pub async fn recvmsg(&self, buf: &mut [&mut [u8]], flags: RecvFlags) -> Result<usize> {
-        apply_fn_on_any_stream!(self.inner(), |stream| { stream.recvmsg(buf, flags).await })
+    pub async fn recvmsg(
+        &self,
+        buf: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<(usize, Vec<FileRef>)> {
+        match &*self.inner() {
+            StreamInner::Trusted(stream_t) => stream_t.recvmsg(buf, flags).await,
+            StreamInner::Untrusted(stream_u) => {
+                let bytes_recv = stream_u.recvmsg(buf, flags).await?;
+                Ok((bytes_recv, Vec::new()))
+            }
+        }
     }
 
-    pub async fn sendmsg(&self, bufs: &[&[u8]], flags: SendFlags) -> Result<usize> {
-        apply_fn_on_any_stream!(self.inner(), |stream| { stream.sendmsg(bufs, flags).await })
+    pub async fn sendmsg(
+        &self,
+        bufs: &[&[u8]],
+        flags: SendFlags,
+        files: Vec<FileRef>,
+    ) -> Result<usize> {
+        match &*self.inner() {
+            StreamInner::Trusted(stream_t) => stream_t.sendmsg(bufs, flags, files).await,
+            StreamInner::Untrusted(stream_u) => {
+                if !files.is_empty() {
+                    return_errno!(EOPNOTSUPP, "files cannot be sent to the host");
+                }
+                stream_u.sendmsg(bufs, flags).await
+            }
+        }
     }
 
     pub fn peer_addr(&self) -> Result<AnyAddr> {
@@ -204,6 +227,13 @@ impl UnixStream {
     pub fn shutdown(&self, how: Shutdown) -> Result<()> {
         apply_fn_on_any_stream!(self.inner(), |stream| { stream.shutdown(how) })
     }
+
+    pub async fn close(&self) -> Result<()> {
+        match &*self.inner() {
+            StreamInner::Trusted(_) => Ok(()),
+            StreamInner::Untrusted(u_stream) => u_stream.close().await,
+        }
+    }
 }
 
 // Implement the common methods required by FileHandle
@@ -3,6 +3,7 @@ use async_io::event::Poller;
 use async_io::event::{Events, Observer};
 use async_io::file::StatusFlags;
 use async_io::util::channel::{Channel, Consumer, Producer};
+use spin::Mutex;
 use std::any::Any;
 use std::sync::{Arc, Weak};
 
@@ -23,17 +24,23 @@ pub fn end_pair(nonblocking: bool) -> Result<(SockEnd, SockEnd)> {
 
     let (pro_a, con_a) = Channel::with_capacity_and_flags(DEFAULT_BUF_SIZE, status_flag)?.split();
     let (pro_b, con_b) = Channel::with_capacity_and_flags(DEFAULT_BUF_SIZE, status_flag)?.split();
+    let files_a = Arc::new(Mutex::new(FileQueue::new()));
+    let files_b = Arc::new(Mutex::new(FileQueue::new()));
 
     let mut end_a = Arc::new(Inner {
         addr: RwLock::new(None),
         reader: con_a,
+        reader_files: files_a.clone(),
         writer: pro_b,
+        writer_files: files_b.clone(),
         peer: Weak::default(),
     });
     let end_b = Arc::new(Inner {
         addr: RwLock::new(None),
         reader: con_b,
+        reader_files: files_b,
         writer: pro_a,
+        writer_files: files_a,
         peer: Arc::downgrade(&end_a),
     });
 
@@ -48,7 +55,11 @@ pub fn end_pair(nonblocking: bool) -> Result<(SockEnd, SockEnd)> {
 pub struct Inner {
     addr: RwLock<Option<TrustedAddr>>,
     reader: Consumer<u8>,
+    // The files sent along with the bytes of the reader
+    reader_files: Arc<Mutex<FileQueue>>,
     writer: Producer<u8>,
+    // The files sent along with the bytes of the writer
+    writer_files: Arc<Mutex<FileQueue>>,
     peer: Weak<Self>,
 }
 
@@ -83,12 +94,116 @@ impl Inner {
         cons_nonblocking
     }
 
-    pub async fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
-        self.reader.readv_async(bufs).await
+    /// Read the bytes sent by the peer, together with the files sent along with
+    /// the bytes.
+    ///
+    /// Like Linux, a read neither goes into nor goes past the bytes that some
+    /// files are sent along with, unless the read starts at the bytes. So the files
+    /// are received by exactly one read, which drops (i.e., closes) the files if
+    /// the caller does not take them.
+    pub async fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<(usize, Vec<FileRef>)> {
+        let total_len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        if total_len == 0 {
+            return Ok((0, Vec::new()));
+        }
+
+        let mut poller = None;
+        loop {
+            if self.reader.is_shutdown() {
+                return_errno!(EPIPE, "the socket is shut down for reading");
+            }
+
+            let mut files = Vec::new();
+            let nbytes = self.reader.consume_with(|first, second| {
+                if first.is_empty() && second.is_empty() {
+                    return Ok(0);
+                }
+
+                let mut queue = self.reader_files.lock();
+                let read_pos = queue.read_pos;
+                let max_len = match queue.msgs.front().map(|msg| msg.start) {
+                    Some(start) if start == read_pos => {
+                        let msg = queue.msgs.pop_front().unwrap();
+                        files = msg.files;
+                        (msg.end - msg.start) as usize
+                    }
+                    Some(start) => (start - read_pos) as usize,
+                    None => usize::MAX,
+                };
+                let nbytes = copy_slices(&[first, second], bufs, max_len);
+                queue.read_pos += nbytes as u64;
+                Ok(nbytes)
+            })?;
+            if nbytes > 0 {
+                return Ok((nbytes, files));
+            }
+
+            if self.reader.peer_is_shutdown() {
+                return Ok((0, Vec::new()));
+            }
+
+            if self.reader.is_nonblocking() {
+                return_errno!(EAGAIN, "no data are present to be received");
+            }
+
+            if poller.is_none() {
+                poller = Some(Poller::new());
+            }
+            let mask = Events::IN;
+            let events = self.reader.pollee().poll(mask, poller.as_mut());
+            if events.is_empty() {
+                poller.as_ref().unwrap().wait().await?;
+            }
+        }
     }
 
-    pub async fn writev(&self, bufs: &[&[u8]]) -> Result<usize> {
-        self.writer.writev_async(bufs).await
+    /// Write the bytes to the peer, sending the files along with the bytes.
+    ///
+    /// The files are sent along with the bytes written by the first write, which
+    /// may be only part of the bytes. If no byte is written, the files are not
+    /// sent.
+    pub async fn writev(&self, bufs: &[&[u8]], files: Vec<FileRef>) -> Result<usize> {
+        let total_len: usize = bufs.iter().map(|buf| buf.len()).sum();
+        if total_len == 0 {
+            return Ok(0);
+        }
+
+        let mut files = Some(files).filter(|files| !files.is_empty());
+        let mut poller = None;
+        loop {
+            let nbytes = self.writer.produce_with(|first, second| {
+                let nbytes = copy_slices(bufs, &mut [first, second], usize::MAX);
+                if nbytes == 0 {
+                    return Ok(0);
+                }
+
+                // The files are queued before the bytes become visible to the reader
+                let mut queue = self.writer_files.lock();
+                let start = queue.write_pos;
+                let end = start + nbytes as u64;
+                if let Some(files) = files.take() {
+                    queue.msgs.push_back(FileMsg { start, end, files });
+                }
+                queue.write_pos = end;
+                Ok(nbytes)
+            })?;
+            if nbytes > 0 {
+                return Ok(nbytes);
+            }
+
+            if self.writer.is_nonblocking() {
+                return_errno!(EAGAIN, "buffer is full");
+            }
+
+            if poller.is_none() {
+                poller = Some(Poller::new());
+            }
+            let mask = Events::OUT;
+            let events = self.writer.pollee().poll(mask, poller.as_mut());
+            if events.is_empty() {
+                poller.as_ref().unwrap().wait().await?;
+            }
+        }
     }
 
     pub fn bytes_to_read(&self) -> usize {
@@ -147,3 +262,57 @@ impl Inner {
         self.peer.upgrade().is_some()
     }
 }
+
+/// The files sent over one direction of a connected unix socket.
+///
+/// The bytes of the direction are numbered by their positions in the byte
+/// stream. Each group of files is sent along with a range of the bytes.
+struct FileQueue {
+    // The number of bytes that have been written
+    write_pos: u64,
+    // The number of bytes that have been read
+    read_pos: u64,
+    msgs: VecDeque<FileMsg>,
+}
+
+struct FileMsg {
+    // The range of the bytes that the files are sent along with
+    start: u64,
+    end: u64,
+    files: Vec<FileRef>,
+}
+
+impl FileQueue {
+    fn new() -> Self {
+        Self {
+            write_pos: 0,
+            read_pos: 0,
+            msgs: VecDeque::new(),
+        }
+    }
+}
+
+// Copy at most `max_len` bytes from the source slices to the destination slices.
+fn copy_slices(srcs: &[&[u8]], dsts: &mut [&mut [u8]], max_len: usize) -> usize {
+    let mut nbytes = 0;
+    let mut dst_idx = 0;
+    let mut dst_offset = 0;
+    for src in srcs {
+        let mut src_offset = 0;
+        while src_offset < src.len() && dst_idx < dsts.len() && nbytes < max_len {
+            let dst = &mut dsts[dst_idx];
+            let len = (src.len() - src_offset)
+                .min(dst.len() - dst_offset)
+                .min(max_len - nbytes);
+            dst[dst_offset..dst_offset + len].copy_from_slice(&src[src_offset..src_offset + len]);
+            src_offset += len;
+            dst_offset += len;
+            nbytes += len;
+            if dst_offset == dst.len() {
+                dst_idx += 1;
+                dst_offset = 0;
+            }
+        }
+    }
+    nbytes
+}
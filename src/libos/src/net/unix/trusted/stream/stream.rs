@@ -35,15 +35,25 @@ impl Stream {
     }
 
     pub async fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
-        self.recvmsg(bufs, RecvFlags::empty()).await
+        // The files received along with the bytes, if any, are closed
+        self.recvmsg(bufs, RecvFlags::empty())
+            .await
+            .map(|(bytes_recv, _)| bytes_recv)
     }
 
     /// Linux behavior:
     /// Unlike datagram socket, `recvfrom` / `recvmsg` of stream socket will
     /// ignore the address even if user specified it. For stream socket, If user
     /// want to get source address , user should use `getpeername` syscall.
+    ///
+    /// The files sent by the peer along with the received bytes (i.e., with
+    /// `SCM_RIGHTS`) are returned, too.
     /// TODO: handle flags
-    pub async fn recvmsg(&self, bufs: &mut [&mut [u8]], flags: RecvFlags) -> Result<usize> {
+    pub async fn recvmsg(
+        &self,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<(usize, Vec<FileRef>)> {
         let addr = self.peer_addr().ok();
         debug!("recvfrom {:?}", addr);
 
@@ -55,9 +65,7 @@ impl Stream {
                 }
             }
         };
-        let data_len = connected_stream.readv(bufs).await?;
-
-        Ok(data_len)
+        connected_stream.readv(bufs).await
     }
 
     pub async fn write(&self, buf: &[u8]) -> Result<usize> {
@@ -65,11 +73,22 @@ impl Stream {
     }
 
     pub async fn writev(&self, bufs: &[&[u8]]) -> Result<usize> {
-        self.sendmsg(bufs, SendFlags::empty()).await
+        self.sendmsg(bufs, SendFlags::empty(), Vec::new()).await
     }
 
+    /// Send the bytes, with the files to be sent along with the bytes (i.e., with
+    /// `SCM_RIGHTS`).
+    ///
+    /// The files are shared with the peer as if they were opened by the peer.
+    /// A process receiving files should trust them no more than their sender,
+    /// e.g., a received file may be written or locked by the sender concurrently.
     // TODO: handle flags
-    pub async fn sendmsg(&self, bufs: &[&[u8]], flags: SendFlags) -> Result<usize> {
+    pub async fn sendmsg(
+        &self,
+        bufs: &[&[u8]],
+        flags: SendFlags,
+        files: Vec<FileRef>,
+    ) -> Result<usize> {
         let addr = self.peer_addr().ok();
         debug!("recvfrom {:?}", addr);
 
@@ -81,9 +100,7 @@ impl Stream {
                 }
             }
         };
-        let data_len = connected_stream.writev(bufs).await?;
-
-        Ok(data_len)
+        connected_stream.writev(bufs, files).await
     }
 
     pub fn status_flags(&self) -> StatusFlags {
@@ -14,6 +14,8 @@ pub struct EpollEntry {
     fd: FileDesc,
     file: WeakFileRef,
     inner: SgxMutex<Inner>,
+    // Whether the entry is added with EpollFlags::EXCLUSIVE, which cannot be changed
+    is_exclusive: bool,
     // Whether the entry is in the ready list
     is_ready: AtomicBool,
     // Whether the entry has been deleted from the interest list
@@ -45,6 +47,7 @@ impl EpollEntry {
             fd,
             file,
             inner: SgxMutex::new(Inner { event, flags }),
+            is_exclusive: flags.contains(EpollFlags::EXCLUSIVE),
             is_ready: AtomicBool::new(false),
             is_deleted: AtomicBool::new(false),
             weak_epoll,
@@ -105,6 +108,11 @@ impl EpollEntry {
         *inner = Inner { event, flags }
     }
 
+    /// Returns whether the epoll entry monitors its file exclusively.
+    pub fn is_exclusive(&self) -> bool {
+        self.is_exclusive
+    }
+
     /// Returns whether the epoll entry is in the ready list.
     pub fn is_ready(&self) -> bool {
         self.is_ready.load(Relaxed)
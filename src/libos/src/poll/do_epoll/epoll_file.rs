@@ -1,4 +1,5 @@
 use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Weak;
 use std::time::Duration;
 
@@ -20,6 +21,12 @@ use crate::prelude::*;
 /// on the files. To do so, the `EpollFile` registers itself as an `Observer` to
 /// the monotored files. Thus, we can add a file to the ready list when an interesting
 /// event happens on the file.
+///
+/// A file added with `EpollFlags::EXCLUSIVE` is monitored by an exclusive
+/// observer. Among all the epoll files that monitor the same file exclusively,
+/// only one that has some threads waiting on it is woken up for each event.
+/// This avoids the thundering herd problem when many threads, each with its
+/// own epoll file, wait for the same listening socket.
 pub struct EpollFile {
     // All interesting entries.
     interest: SgxMutex<HashMap<FileDesc, Arc<EpollEntry>>>,
@@ -27,6 +34,8 @@ pub struct EpollFile {
     ready: SgxMutex<VecDeque<Arc<EpollEntry>>>,
     // EpollFile itself is also pollable
     pollee: Pollee,
+    // The number of threads that are waiting on the epoll file.
+    num_waiters: AtomicUsize,
     // Any EpollFile is wrapped with Arc when created.
     weak_self: Weak<Self>,
 }
@@ -40,6 +49,7 @@ impl EpollFile {
             interest: Default::default(),
             ready: Default::default(),
             pollee: Pollee::new(Events::empty()),
+            num_waiters: AtomicUsize::new(0),
             weak_self: Weak::new(),
         };
         new_self_ref_arc!(new_self)
@@ -65,6 +75,9 @@ impl EpollFile {
         let mut file_table = current.files().lock().unwrap();
         let mut file_entry = file_table.get_entry_mut(fd)?;
         let file = file_entry.get_file().clone();
+        if ep_flags.contains(EpollFlags::EXCLUSIVE) {
+            Self::check_exclusive_interest(&file, &ep_event, &ep_flags)?;
+        }
         let weak_file = FileRef::downgrade(&file);
         let mask = ep_event.events;
         let entry = EpollEntry::new(fd, weak_file, ep_event, ep_flags, self.weak_self.clone());
@@ -122,6 +135,9 @@ impl EpollFile {
         new_ep_flags: EpollFlags,
     ) -> Result<()> {
         self.warn_unsupported_flags(&new_ep_flags);
+        if new_ep_flags.contains(EpollFlags::EXCLUSIVE) {
+            return_errno!(EINVAL, "EPOLLEXCLUSIVE is not allowed in EPOLL_CTL_MOD");
+        }
 
         // Update the epoll entry
         let interest = self.interest.lock().unwrap();
@@ -131,6 +147,9 @@ impl EpollFile {
         if entry.is_deleted() {
             return_errno!(ENOENT, "fd is not in the interest list");
         }
+        if entry.is_exclusive() {
+            return_errno!(EINVAL, "an exclusive entry cannot be modified");
+        }
         let new_mask = new_ep_event.events;
         entry.update(new_ep_event, new_ep_flags);
         let entry = entry.clone();
@@ -148,6 +167,24 @@ impl EpollFile {
         Ok(())
     }
 
+    fn check_exclusive_interest(
+        file: &FileRef,
+        ep_event: &EpollEvent,
+        ep_flags: &EpollFlags,
+    ) -> Result<()> {
+        if ep_flags.contains(EpollFlags::ONE_SHOT) {
+            return_errno!(EINVAL, "EPOLLEXCLUSIVE cannot be used with EPOLLONESHOT");
+        }
+        let allowed_events = Events::IN | Events::OUT | Events::ERR | Events::HUP;
+        if !allowed_events.contains(ep_event.events) {
+            return_errno!(EINVAL, "EPOLLEXCLUSIVE cannot be used with these events");
+        }
+        if file.as_epoll_file().is_some() {
+            return_errno!(EINVAL, "EPOLLEXCLUSIVE cannot be used on an epoll file");
+        }
+        Ok(())
+    }
+
     fn unregister_from_file(&self, fd: FileDesc) {
         let current = current!();
         let mut file_table = current.files().lock().unwrap();
@@ -167,6 +204,17 @@ impl EpollFile {
     /// When `max_events` equals to zero, the method returns when the timeout
     /// expires or a signal arrives.
     pub async fn wait(
+        &self,
+        max_events: usize,
+        timeout: Option<&mut Duration>,
+    ) -> Result<Vec<EpollEvent>> {
+        self.num_waiters.fetch_add(1, Ordering::AcqRel);
+        let res = self.do_wait(max_events, timeout).await;
+        self.num_waiters.fetch_sub(1, Ordering::AcqRel);
+        res
+    }
+
+    async fn do_wait(
         &self,
         max_events: usize,
         mut timeout: Option<&mut Duration>,
@@ -281,8 +329,12 @@ impl EpollFile {
         count_events
     }
 
+    fn has_waiters(&self) -> bool {
+        self.num_waiters.load(Ordering::Acquire) > 0
+    }
+
     fn warn_unsupported_flags(&self, flags: &EpollFlags) {
-        if flags.intersects(EpollFlags::EXCLUSIVE | EpollFlags::WAKE_UP) {
+        if flags.intersects(EpollFlags::WAKE_UP) {
             warn!("{:?} contains unsupported flags", flags);
         }
     }
@@ -326,6 +378,23 @@ impl Observer for EpollEntry {
         let epoll_file = self.epoll_file();
         epoll_file.push_ready(self.self_arc());
     }
+
+    fn is_exclusive(&self) -> bool {
+        EpollEntry::is_exclusive(self)
+    }
+
+    fn on_exclusive_events(&self, _pollee_id: u64, events: Events) -> bool {
+        if self.is_deleted() {
+            return false;
+        }
+
+        // Like Linux, the events are added to the ready list anyway, but they are
+        // claimed only if some thread is waiting on the epoll file for them. Otherwise,
+        // the next exclusive epoll entry of the file gets a chance to wake up its waiters.
+        let epoll_file = self.epoll_file();
+        epoll_file.push_ready(self.self_arc());
+        epoll_file.has_waiters() && self.event().events.intersects(events)
+    }
 }
 
 impl std::fmt::Debug for EpollFile {
@@ -26,7 +26,7 @@ pub async fn do_epoll_create1(raw_flags: c_int) -> Result<isize> {
     let epoll_file: Arc<EpollFile> = EpollFile::new();
     let file_ref = FileRef::new_epoll(epoll_file);
     let close_on_spawn = flags.contains(CreationFlags::O_CLOEXEC);
-    let epfd = current!().add_file(file_ref, close_on_spawn);
+    let epfd = current!().add_file(file_ref, close_on_spawn)?;
     Ok(epfd as isize)
 }
 
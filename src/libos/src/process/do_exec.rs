@@ -1,7 +1,7 @@
 use std::ffi::{CStr, CString};
 use std::path::Path;
 
-use super::do_exit::{exit_old_process_for_execve, notify_all_threads_to_exit};
+use super::do_exit::{exit_old_process_for_execve, wait_for_other_threads_to_exit};
 use super::do_spawn::new_process_for_exec;
 use super::do_vfork::{check_vfork_for_exec, vfork_return_to_parent};
 use super::process::ProcessFilter;
@@ -93,7 +93,11 @@ pub async fn do_exec(
         // pass process signal waiter queue to the task
         let irq_waiters = new_process_ref.sig_waiters();
 
-        // Finally, enqueue the new thread for execution
+        // Finally, enqueue the new thread for execution.
+        //
+        // The new thread runs in a new task, so none of the scheduling history
+        // of the old task (e.g., the last vCPU) is carried over to the new program.
+        // Only the affinity is inherited via the sched agent of the new thread.
         async_rt::task::spawn(crate::entry::thread::main_loop(
             new_main_thread,
             init_cpu_state,
@@ -105,35 +109,3 @@ pub async fn do_exec(
         return Err(error);
     }
 }
-
-// Blocking wait until there is only one thread in the calling process
-async fn wait_for_other_threads_to_exit(current_ref: &ThreadRef) {
-    use super::do_futex::{self};
-    use crate::time::{timespec_t, ClockId};
-    use core::time::Duration;
-
-    // Set timeout to 50ms
-    let timeout = Duration::from_millis(50);
-    // Use calling process's pointer as futex value
-    let futex_val = Arc::as_ptr(current_ref.process()) as *const i32;
-    loop {
-        // Do this for every loop in case a new thread is created just after the notification
-        notify_all_threads_to_exit(current_ref.process());
-
-        // Must yield here for other threads to run
-        async_rt::sched::yield_().await;
-
-        let thread_num = current_ref.process().threads().len();
-        if thread_num == 1 {
-            return;
-        }
-        // Blocking wait here. When a thread exit, it will notify us.
-        unsafe {
-            do_futex::futex_wait(
-                Arc::as_ptr(current_ref.process()) as *const i32,
-                *futex_val,
-                &Some(timeout),
-            )
-        };
-    }
-}
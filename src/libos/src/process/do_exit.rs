@@ -1,5 +1,6 @@
 use std::intrinsics::atomic_store;
 use std::sync::Weak;
+use std::time::Duration;
 
 use super::do_futex::futex_wake;
 use super::do_vfork::{is_vforked_child_process, vfork_return_to_parent};
@@ -14,20 +15,24 @@ use crate::signal::constants::*;
 use crate::signal::{KernelSignal, SigNum};
 use crate::vm::USER_SPACE_VM_MANAGER;
 
-pub fn do_exit_group(status: i32) -> Result<isize> {
+pub async fn do_exit_group(status: i32) -> Result<isize> {
     if is_vforked_child_process() {
         let current = current!();
         let mut curr_user_ctxt = CURRENT_CONTEXT.with(|context| context.as_ptr());
         return vfork_return_to_parent(curr_user_ctxt as *mut _, &current);
-    } else {
-        let term_status = TermStatus::Exited(status as u8);
-        let current = current!();
-        current.process().force_exit(term_status);
-        exit_thread(term_status);
+    }
 
-        notify_all_threads_to_exit(current.process());
-        Ok(0)
+    let term_status = TermStatus::Exited(status as u8);
+    let current = current!();
+    // Only the first thread that forces the process to exit is responsible for
+    // stopping its siblings. Any other thread (e.g., one that calls exit_group
+    // concurrently) just exits itself, leaving the process teardown to the last
+    // exiting thread.
+    if current.process().force_exit(term_status) {
+        wait_for_other_threads_to_exit(&current).await;
     }
+    exit_thread(current.process().term_status().unwrap());
+    Ok(0)
 }
 
 // Interrupt all threads in the process to ensure that they exit
@@ -43,6 +48,31 @@ pub fn notify_all_threads_to_exit(current_process: &ProcessRef) {
     });
 }
 
+// Blocking wait until there is only one thread in the calling process
+pub(super) async fn wait_for_other_threads_to_exit(current_ref: &ThreadRef) {
+    use super::do_futex;
+
+    // Set timeout to 50ms
+    let timeout = Duration::from_millis(50);
+    // Use calling process's pointer as futex value
+    let futex_addr = Arc::as_ptr(current_ref.process()) as *const i32;
+    loop {
+        // Do this for every loop in case a new thread is created just after the notification
+        notify_all_threads_to_exit(current_ref.process());
+
+        // Must yield here for other threads to run
+        async_rt::sched::yield_().await;
+
+        let thread_num = current_ref.process().threads().len();
+        if thread_num == 1 {
+            return;
+        }
+        // Blocking wait here. When a thread exit, it will notify us.
+        let futex_val = unsafe { *futex_addr };
+        let _ = do_futex::futex_wait(futex_addr, futex_val, &Some(timeout)).await;
+    }
+}
+
 pub fn do_exit(status: i32) {
     let term_status = TermStatus::Exited(status as u8);
     exit_thread(term_status);
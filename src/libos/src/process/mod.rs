@@ -19,7 +19,7 @@ use self::pgrp::ProcessGrp;
 use self::process::{ProcessBuilder, ProcessInner};
 use self::thread::{ThreadBuilder, ThreadId, ThreadInner};
 
-pub use self::do_exit::handle_force_exit;
+pub use self::do_exit::{handle_force_exit, notify_all_threads_to_exit};
 pub use self::do_futex::{futex_wait, futex_wake};
 pub use self::do_robust_list::RobustListHead;
 pub use self::do_spawn::do_spawn_root;
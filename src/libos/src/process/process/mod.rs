@@ -187,8 +187,9 @@ impl Process {
     /// 2. Performing exit_group syscall.
     ///
     /// A process may be forced to exit many times, but only the first time counts.
-    pub fn force_exit(&self, term_status: TermStatus) {
-        self.forced_exit_status.force_exit(term_status);
+    /// Returns whether this call is the first one.
+    pub fn force_exit(&self, term_status: TermStatus) -> bool {
+        self.forced_exit_status.force_exit(term_status)
     }
 
     /// Get the internal representation of the process.
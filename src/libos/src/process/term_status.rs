@@ -21,11 +21,14 @@ impl ForcedExitStatus {
         self.exited.load(Ordering::SeqCst)
     }
 
-    pub fn force_exit(&self, status: TermStatus) {
+    /// Returns whether this is the first time that the status is forced to exit.
+    pub fn force_exit(&self, status: TermStatus) -> bool {
         let mut old_status = self.status.lock().unwrap();
         // set the bool after getting the status lock
         self.exited.store(true, Ordering::SeqCst);
+        let is_first = old_status.is_none();
         old_status.get_or_insert(status);
+        is_first
     }
 
     pub fn term_status(&self) -> Option<TermStatus> {
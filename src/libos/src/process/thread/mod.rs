@@ -6,6 +6,7 @@ use super::{
     FileTableRef, ForcedExitStatus, FsViewRef, NiceValueRef, ProcessRef, ProcessVM, ProcessVMRef,
     ResourceLimitsRef, RobustListHead, SchedAgentRef, TermStatus, ThreadRef,
 };
+use crate::misc::resource_t;
 use crate::prelude::*;
 use crate::signal::{SigQueues, SigSet, SigStack};
 
@@ -116,9 +117,21 @@ impl Thread {
         self.files().lock().unwrap().get(fd)
     }
 
-    /// Add a file to the file table.
-    pub fn add_file(&self, new_file: FileRef, close_on_spawn: bool) -> FileDesc {
-        self.files().lock().unwrap().put(new_file, close_on_spawn)
+    /// Add a file to the file table at the lowest free fd.
+    ///
+    /// The fd must be below the soft limit of RLIMIT_NOFILE, otherwise EMFILE is returned.
+    pub fn add_file(&self, new_file: FileRef, close_on_spawn: bool) -> Result<FileDesc> {
+        let soft_rlimit_nofile = self
+            .rlimits()
+            .lock()
+            .unwrap()
+            .get(resource_t::RLIMIT_NOFILE)
+            .get_cur();
+        let mut files = self.files().lock().unwrap();
+        if files.min_free_fd(0) as u64 >= soft_rlimit_nofile {
+            return_errno!(EMFILE, "the limit on the number of open files is reached");
+        }
+        Ok(files.put(new_file, close_on_spawn))
     }
 
     /// Close a file from the file table. It will release the POSIX advisory locks owned
@@ -211,6 +224,10 @@ impl Thread {
             .lock()
             .unwrap()
             .attach(async_rt::task::current::get());
+        if let Some(task) = self.task() {
+            let priority = self.nice().read().unwrap().to_sched_priority();
+            task.sched_info().set_priority(priority);
+        }
         Tirqs::set_mask(self.sig_mask().to_c() as u64);
 
         self.inner().start();
@@ -1,4 +1,5 @@
 use super::priority::{NiceValue, PrioWhich};
+use crate::misc::resource_t;
 use crate::prelude::*;
 use crate::process::table::{get_all_processes, get_pgrp, get_process};
 
@@ -8,12 +9,45 @@ pub fn do_set_priority(which: PrioWhich, who: i32, prio: NiceValue) -> Result<()
         which, who, prio
     );
 
+    // Like Linux, raising the priority (i.e., lowering the nice value) is limited
+    // by RLIMIT_NICE of the calling process
+    let nice_rlimit = current!()
+        .rlimits()
+        .lock()
+        .unwrap()
+        .get(resource_t::RLIMIT_NICE)
+        .get_cur();
+
     let processes = get_processes(which, who)?;
-    for process in processes.iter() {
-        let main_thread = process
-            .main_thread()
-            .ok_or_else(|| errno!(ESRCH, "invalid pid"))?;
+    let main_threads = processes
+        .iter()
+        .map(|process| {
+            process
+                .main_thread()
+                .ok_or_else(|| errno!(ESRCH, "invalid pid"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Check all the processes before renicing any of them, so that a failure
+    // leaves all the processes unchanged
+    if prio.to_rlimit_val() as u64 > nice_rlimit {
+        for main_thread in main_threads.iter() {
+            if prio < *main_thread.nice().read().unwrap() {
+                return_errno!(EACCES, "raising the priority beyond RLIMIT_NICE");
+            }
+        }
+    }
+
+    for (process, main_thread) in processes.iter().zip(main_threads.iter()) {
         *main_thread.nice().write().unwrap() = prio;
+
+        // Threads that are not started yet pick up the new priority when they start
+        let sched_priority = prio.to_sched_priority();
+        for thread in process.threads().iter() {
+            if let Some(task) = thread.task() {
+                task.sched_info().set_priority(sched_priority);
+            }
+        }
     }
     Ok(())
 }
@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use async_rt::sched::SchedPriority;
 use core::convert::TryFrom;
 
 #[allow(non_camel_case_types)]
@@ -50,6 +51,17 @@ impl NiceValue {
     pub fn to_rlimit_val(&self) -> i32 {
         Self::MAX_PRIO - self.value + 1
     }
+
+    /// Convert to the priority band of the async_rt scheduler.
+    pub fn to_sched_priority(&self) -> SchedPriority {
+        SchedPriority::from_nice(self.value)
+    }
+}
+
+impl From<SchedPriority> for NiceValue {
+    fn from(priority: SchedPriority) -> Self {
+        Self::from(priority.to_nice())
+    }
 }
 
 impl From<i32> for NiceValue {
@@ -1,3 +1,4 @@
+use super::c_types::{sigval_t, SI_TKILL};
 use super::constants::*;
 use super::sig_queues::{enqueue_process_signal, enqueue_thread_signal};
 use super::signals::{KernelSignal, UserSignal, UserSignalKind};
@@ -66,6 +67,11 @@ fn get_processes(filter: &ProcessFilter) -> Result<Vec<ProcessRef>> {
     Ok(processes)
 }
 
+/// Send a signal to a specific thread.
+///
+/// The signal is enqueued to the thread's own signal queues, instead of the
+/// ones shared by the process. If `pid` is given, the thread must belong to
+/// the process of `pid`.
 pub fn do_tgkill(pid: Option<pid_t>, tid: pid_t, signum: SigNum) -> Result<()> {
     debug!(
         "do_tgkill: pid: {:?}, tid: {:?}, signum: {:?}",
@@ -76,12 +82,12 @@ pub fn do_tgkill(pid: Option<pid_t>, tid: pid_t, signum: SigNum) -> Result<()> {
     let process = thread.process();
     if let Some(pid) = pid {
         if pid != process.pid() {
-            return_errno!(EINVAL, "the combination of pid and tid is not valid");
+            return_errno!(ESRCH, "the thread does not belong to the process");
         }
     }
 
     if thread.status() == ThreadStatus::Exited {
-        return Ok(());
+        return_errno!(ESRCH, "the thread has exited");
     }
 
     let signal = {
@@ -97,3 +103,75 @@ pub fn do_tgkill(pid: Option<pid_t>, tid: pid_t, signum: SigNum) -> Result<()> {
     enqueue_thread_signal(&thread, signal);
     Ok(())
 }
+
+/// Queue a signal with a payload to a process.
+///
+/// The `code` and `value` are supplied by the sender and will be received by
+/// the handler of the signal as `si_code` and `si_value`.
+pub fn do_rt_sigqueueinfo(pid: pid_t, signum: SigNum, code: i32, value: sigval_t) -> Result<()> {
+    debug!(
+        "do_rt_sigqueueinfo: pid: {:?}, signum: {:?}, code: {:?}",
+        &pid, &signum, &code
+    );
+
+    check_sigqueue_code(code, pid)?;
+    let process = table::get_process(pid)?;
+    if process.status() == ProcessStatus::Zombie {
+        return Ok(());
+    }
+
+    let signal = new_sigqueue_signal(signum, code, value);
+    enqueue_process_signal(&process, signal);
+    Ok(())
+}
+
+/// Queue a signal with a payload to a specific thread.
+///
+/// Similar to `do_rt_sigqueueinfo`, except that the signal is directed to the
+/// thread of `tid`, which must belong to the process of `pid`.
+pub fn do_rt_tgsigqueueinfo(
+    pid: pid_t,
+    tid: pid_t,
+    signum: SigNum,
+    code: i32,
+    value: sigval_t,
+) -> Result<()> {
+    debug!(
+        "do_rt_tgsigqueueinfo: pid: {:?}, tid: {:?}, signum: {:?}, code: {:?}",
+        &pid, &tid, &signum, &code
+    );
+
+    check_sigqueue_code(code, pid)?;
+    let thread = table::get_thread(tid)?;
+    if pid != thread.process().pid() {
+        return_errno!(ESRCH, "the thread does not belong to the process");
+    }
+    if thread.status() == ThreadStatus::Exited {
+        return_errno!(ESRCH, "the thread has exited");
+    }
+
+    let signal = new_sigqueue_signal(signum, code, value);
+    enqueue_thread_signal(&thread, signal);
+    Ok(())
+}
+
+// A process is not allowed to impersonate the kernel or kill/tkill when sending
+// signals to other processes. This follows the checks of Linux.
+fn check_sigqueue_code(code: i32, dst_pid: pid_t) -> Result<()> {
+    let src_pid = current!().process().pid();
+    if (code >= 0 || code == SI_TKILL) && src_pid != dst_pid {
+        return_errno!(EPERM, "the si_code is not allowed to be sent by a user");
+    }
+    Ok(())
+}
+
+fn new_sigqueue_signal(signum: SigNum, code: i32, value: sigval_t) -> Box<UserSignal> {
+    let src_pid = current!().process().pid();
+    let src_uid = 0;
+    Box::new(UserSignal::new(
+        signum,
+        UserSignalKind::Sigqueue(code, value),
+        src_pid,
+        src_uid,
+    ))
+}
@@ -81,6 +81,12 @@ pub fn deliver_signal() {
     } else {
         forced_signal_flag::reset();
     }
+
+    // If no user signal handler has taken the mask saved by sigsuspend, then
+    // restore the mask right now.
+    if let Some(saved_mask) = saved_sig_mask::take() {
+        thread.set_sig_mask(saved_mask);
+    }
 }
 
 fn do_deliver_signal(thread: &ThreadRef) {
@@ -135,7 +141,10 @@ fn handle_signal(signal: Box<dyn Signal>, thread: &ThreadRef) -> bool {
                 SigDefaultAction::Ign => true,
                 SigDefaultAction::Term | SigDefaultAction::Core => {
                     let term_status = TermStatus::Killed(signal.num());
-                    thread.process().force_exit(term_status);
+                    if thread.process().force_exit(term_status) {
+                        // Stop the sibling threads at their next safe point
+                        crate::process::notify_all_threads_to_exit(thread.process());
+                    }
                     false
                 }
                 SigDefaultAction::Stop => {
@@ -192,6 +201,9 @@ fn handle_signals_by_user(
         new_sig_mask + signal.num()
     };
     let old_sig_mask = thread.set_sig_mask(new_sig_mask);
+    // If the signal interrupts sigsuspend, the mask to restore upon sigreturn
+    // is the one before sigsuspend, not the temporary one.
+    let old_sig_mask = saved_sig_mask::take().unwrap_or(old_sig_mask);
 
     // Represent the user stack in a memory safe way
     let mut user_stack = {
@@ -446,3 +458,23 @@ mod forced_signal_flag {
         static HAS_FORCED_SIGNAL: Cell<bool> = Cell::new(false);
     }
 }
+
+/// The signal mask saved by sigsuspend, which is to be restored after delivering
+/// a signal.
+pub(super) mod saved_sig_mask {
+    use core::cell::Cell;
+
+    use super::SigSet;
+
+    pub fn set(mask: SigSet) {
+        SAVED_SIG_MASK.with(|saved_mask| saved_mask.set(Some(mask)))
+    }
+
+    pub fn take() -> Option<SigSet> {
+        SAVED_SIG_MASK.with(|saved_mask| saved_mask.take())
+    }
+
+    task_local! {
+        static SAVED_SIG_MASK: Cell<Option<SigSet>> = Cell::new(None);
+    }
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use async_rt::waiter_loop;
+
+use super::constants::*;
+use super::do_sigreturn::saved_sig_mask;
+use super::SigSet;
+use crate::prelude::*;
+
+/// Temporarily replace the signal mask of the current thread and wait until
+/// a signal that is not blocked by the temporary mask is pending.
+///
+/// The old signal mask is restored after the signal is delivered, i.e., after
+/// the signal handler returns. So this function always returns `EINTR`.
+pub async fn do_sigsuspend(mask: SigSet) -> Result<()> {
+    debug!("do_rt_sigsuspend: mask: {:?}", mask);
+
+    let thread = current!();
+    let process = thread.process().clone();
+
+    // SIGKILL and SIGSTOP cannot be blocked
+    let mask = mask - SIGKILL - SIGSTOP;
+    // Install the temporary mask before checking for pending signals. This way,
+    // a signal is either found pending by the loop below or its enqueuing wakes
+    // us up, so it is never missed.
+    let old_mask = thread.set_sig_mask(mask);
+    saved_sig_mask::set(old_mask);
+
+    let mut timeout = None::<Duration>;
+    let _ = waiter_loop!(process.sig_waiters(), timeout, false, {
+        let pending = thread.sig_queues().read().unwrap().pending()
+            | process.sig_queues().read().unwrap().pending();
+        if !(pending & !mask).is_empty() || process.is_forced_to_exit() {
+            break Ok(());
+        }
+    });
+
+    // The signal will be delivered upon returning to the user space, after which
+    // the old mask will be restored.
+    return_errno!(EINTR, "interrupted by a signal");
+}
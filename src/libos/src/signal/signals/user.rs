@@ -15,7 +15,9 @@ pub struct UserSignal {
 pub enum UserSignalKind {
     Kill,
     Tkill,
-    Sigqueue(sigval_t),
+    /// A signal queued by rt_sigqueueinfo or rt_tgsigqueueinfo, along with the
+    /// `si_code` and `si_value` supplied by the sender.
+    Sigqueue(i32, sigval_t),
 }
 
 unsafe impl Sync for UserSignalKind {}
@@ -53,13 +55,13 @@ impl Signal for UserSignal {
         let code = match self.kind {
             UserSignalKind::Kill => SI_USER,
             UserSignalKind::Tkill => SI_TKILL,
-            UserSignalKind::Sigqueue(_) => SI_QUEUE,
+            UserSignalKind::Sigqueue(code, _) => code,
         };
 
         let mut info = siginfo_t::new(self.num, code);
         info.set_si_pid(self.pid);
         info.set_si_uid(self.uid);
-        if let UserSignalKind::Sigqueue(val) = self.kind {
+        if let UserSignalKind::Sigqueue(_, val) = self.kind {
             info.set_si_value(val);
         }
 
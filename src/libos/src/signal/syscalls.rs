@@ -66,18 +66,52 @@ pub async fn do_kill(pid: i32, sig: c_int) -> Result<isize> {
 }
 
 pub async fn do_tkill(tid: pid_t, sig: c_int) -> Result<isize> {
+    if (tid as i32) <= 0 {
+        return_errno!(EINVAL, "invalid tid");
+    }
     let signum = SigNum::from_u8(sig as u8)?;
     super::do_kill::do_tgkill(None, tid, signum)?;
     Ok(0)
 }
 
 pub async fn do_tgkill(pid: i32, tid: pid_t, sig: c_int) -> Result<isize> {
-    let pid = if pid >= 0 { Some(pid as pid_t) } else { None };
+    if pid <= 0 || (tid as i32) <= 0 {
+        return_errno!(EINVAL, "invalid pid or tid");
+    }
+    let pid = Some(pid as pid_t);
     let signum = SigNum::from_u8(sig as u8)?;
     super::do_kill::do_tgkill(pid, tid, signum)?;
     Ok(0)
 }
 
+pub async fn do_rt_sigqueueinfo(
+    pid: pid_t,
+    sig: c_int,
+    info_ptr: *const siginfo_t,
+) -> Result<isize> {
+    from_user::check_ptr(info_ptr)?;
+    let info = unsafe { &*info_ptr };
+    let signum = SigNum::from_u8(sig as u8)?;
+    super::do_kill::do_rt_sigqueueinfo(pid, signum, info.si_code, info.si_value())?;
+    Ok(0)
+}
+
+pub async fn do_rt_tgsigqueueinfo(
+    pid: pid_t,
+    tid: pid_t,
+    sig: c_int,
+    info_ptr: *const siginfo_t,
+) -> Result<isize> {
+    if (pid as i32) <= 0 || (tid as i32) <= 0 {
+        return_errno!(EINVAL, "invalid pid or tid");
+    }
+    from_user::check_ptr(info_ptr)?;
+    let info = unsafe { &*info_ptr };
+    let signum = SigNum::from_u8(sig as u8)?;
+    super::do_kill::do_rt_tgsigqueueinfo(pid, tid, signum, info.si_code, info.si_value())?;
+    Ok(0)
+}
+
 pub async fn do_rt_sigprocmask(
     how: c_int,
     set_ptr: *const sigset_t,
@@ -122,6 +156,18 @@ pub async fn do_rt_sigpending(buf_ptr: *mut sigset_t, buf_size: usize) -> Result
     Ok(0)
 }
 
+pub async fn do_rt_sigsuspend(mask_ptr: *const sigset_t, mask_size: usize) -> Result<isize> {
+    let mask: SigSet = {
+        if mask_size != std::mem::size_of::<sigset_t>() {
+            return_errno!(EINVAL, "unexpected sigset size");
+        }
+        from_user::check_ptr(mask_ptr)?;
+        SigSet::from_c(unsafe { *mask_ptr })
+    };
+    super::do_sigsuspend::do_sigsuspend(mask).await?;
+    Ok(0)
+}
+
 pub async fn do_sigaltstack(new_ss_c: *const stack_t, old_ss_c: *mut stack_t) -> Result<isize> {
     // C types -> Rust types
     let new_ss = {
@@ -23,7 +23,7 @@ pub async fn do_timerfd_create(clockid: clockid_t, flags: i32) -> Result<isize>
     let fd = current!().add_file(
         file_ref,
         timer_create_flags.contains(TimerCreationFlags::TFD_CLOEXEC),
-    );
+    )?;
     Ok(fd as isize)
 }
 
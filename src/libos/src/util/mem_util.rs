@@ -43,6 +43,21 @@ pub mod from_user {
         check_array(user_buf, count)
     }
 
+    /// Check the total length of the buffers of a vectored I/O operation
+    ///
+    /// The total length must not overflow, which is an `EINVAL` error, and must
+    /// not exceed the max I/O size in the config, which is an error of the given
+    /// errno. This check is supposed to be done before any allocation or I/O.
+    pub fn check_iovs_total_len(lens: impl Iterator<Item = usize>, errno: Errno) -> Result<usize> {
+        let total_len = lens
+            .try_fold(0_usize, |total_len, len| total_len.checked_add(len))
+            .ok_or_else(|| errno!(EINVAL, "the total length of iovecs overflows"))?;
+        if total_len > crate::config::LIBOS_CONFIG.resource_limits.max_io_size {
+            return_errno!(errno, "the total length of iovecs exceeds the max I/O size");
+        }
+        Ok(total_len)
+    }
+
     pub fn make_slice<'a, T>(user_buf: *const T, count: usize) -> Result<&'a [T]> {
         check_array(user_buf, count)?;
         Ok(unsafe { slice::from_raw_parts(user_buf, count) })
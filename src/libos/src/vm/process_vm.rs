@@ -7,6 +7,7 @@ use super::user_space_vm::USER_SPACE_VM_MANAGER;
 use super::vm_area::VMArea;
 use super::vm_perms::VMPerms;
 use super::vm_util::{VMInitializer, VMMapAddr, VMMapOptions, VMMapOptionsBuilder, VMRemapOptions};
+use crate::fs::MemfdInode;
 use std::collections::HashSet;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
@@ -421,6 +422,31 @@ impl ProcessVM {
         free_size
     }
 
+    /// Get the total size of the memory mapped by the process of `pid`.
+    ///
+    /// As all the pages of the enclave are committed at the initialization
+    /// stage, the mapped memory is also resident.
+    pub fn get_mapped_size(&self, pid: pid_t) -> usize {
+        let process_chunks = self.mem_chunks.read().unwrap();
+        process_chunks
+            .iter()
+            .fold(0, |acc, chunk| match chunk.internal() {
+                ChunkType::SingleVMA(vma) => acc + vma.lock().unwrap().size(),
+                ChunkType::MultiVMA(internal_manager) => {
+                    let mut internal_manager = internal_manager.lock().unwrap();
+                    let mapped_size: usize = internal_manager
+                        .chunk_manager()
+                        .vmas()
+                        .iter()
+                        .map(|vma_obj| vma_obj.vma())
+                        .filter(|vma| vma.pid() == pid)
+                        .map(|vma| vma.size())
+                        .sum();
+                    acc + mapped_size
+                }
+            })
+    }
+
     pub fn mmap(
         &self,
         addr: usize,
@@ -463,6 +489,15 @@ impl ProcessVM {
         } else {
             None
         };
+        // A memfd sealed against writes cannot be mapped as shared and writable
+        if let (Some((file, _)), true) = (&writeback_file, perms.can_write()) {
+            let memfd_inode = file.as_inode_file().and_then(|inode_file| {
+                inode_file.inode().as_any_ref().downcast_ref::<MemfdInode>()
+            });
+            if let Some(memfd_inode) = memfd_inode {
+                memfd_inode.check_writable_mapping()?;
+            }
+        }
         let mmap_options = VMMapOptionsBuilder::default()
             .size(size)
             .addr(addr_option)
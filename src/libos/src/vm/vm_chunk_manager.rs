@@ -402,6 +402,29 @@ impl ChunkManager {
         }
     }
 
+    /// Returns whether any VMA is a shared, writable memory mapping of a file that
+    /// satisfies the condition.
+    pub fn has_writable_file_vma_with_cond<F: Fn(&FileRef) -> bool>(&self, cond_fn: &F) -> bool {
+        for vma_obj in &self.vmas {
+            if Self::is_writable_file_vma_with_cond(&vma_obj.vma(), cond_fn) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns whether the VMA is a shared, writable memory mapping of a file that
+    /// satisfies the condition.
+    pub fn is_writable_file_vma_with_cond<F: Fn(&FileRef) -> bool>(
+        vma: &VMArea,
+        cond_fn: &F,
+    ) -> bool {
+        match vma.writeback_file().as_ref() {
+            None => false,
+            Some((file, _)) => vma.perms().can_write() && cond_fn(file),
+        }
+    }
+
     /// Flush a file-backed VMA to its file. This has no effect on anonymous VMA.
     pub fn flush_file_vma(vma: &VMArea) {
         Self::flush_file_vma_with_cond(vma, |_| true)
@@ -421,7 +444,11 @@ impl ChunkManager {
         if !cond_fn(file) {
             return;
         }
-        inode_file.write_at(*file_offset, unsafe { vma.as_slice() });
+        // The write fails if, e.g., the mount of the file has been remounted
+        // read-only, in which case the changes to the mapping are lost
+        if let Err(e) = inode_file.write_at(*file_offset, unsafe { vma.as_slice() }) {
+            warn!("failed to write back the file-backed VMA: {:?}", e);
+        }
     }
 
     pub fn find_mmap_region(&self, addr: usize) -> Result<VMRange> {
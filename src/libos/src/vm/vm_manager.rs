@@ -418,6 +418,22 @@ impl VMManager {
             });
     }
 
+    /// Returns whether a file that satisfies the condition has any shared, writable
+    /// memory mapping in any process.
+    pub fn has_writable_file_mapping<F: Fn(&FileRef) -> bool>(&self, cond_fn: F) -> bool {
+        let internal = self.internal();
+        internal.chunks.iter().any(|chunk| match chunk.internal() {
+            ChunkType::MultiVMA(manager) => manager
+                .lock()
+                .unwrap()
+                .chunk_manager()
+                .has_writable_file_vma_with_cond(&cond_fn),
+            ChunkType::SingleVMA(vma) => {
+                ChunkManager::is_writable_file_vma_with_cond(&vma.lock().unwrap(), &cond_fn)
+            }
+        })
+    }
+
     pub fn mremap(&self, options: &VMRemapOptions) -> Result<usize> {
         let old_addr = options.old_addr();
         let old_size = options.old_size();
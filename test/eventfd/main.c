@@ -144,6 +144,84 @@ int test_read_write() {
     return 0;
 }
 
+int test_read_drains_counter() {
+    int event_fd = eventfd(3, EFD_NONBLOCK);
+    if (event_fd < 0) {
+        THROW_ERROR("failed to create an eventfd");
+    }
+
+    uint64_t data = 4;
+    if (write(event_fd, &data, sizeof(data)) != sizeof(data)) {
+        close(event_fd);
+        THROW_ERROR("failed to write the eventfd");
+    }
+    // The whole counter is returned and reset to zero
+    data = 0;
+    if (read(event_fd, &data, sizeof(data)) != sizeof(data) || data != 7) {
+        close(event_fd);
+        THROW_ERROR("failed to read the whole counter");
+    }
+    if (read(event_fd, &data, sizeof(data)) != -1 || errno != EAGAIN) {
+        close(event_fd);
+        THROW_ERROR("read is expected to fail with EAGAIN after the counter is drained");
+    }
+
+    close(event_fd);
+    return 0;
+}
+
+int test_read_with_semaphore() {
+    int event_fd = eventfd(2, EFD_SEMAPHORE | EFD_NONBLOCK);
+    if (event_fd < 0) {
+        THROW_ERROR("failed to create an eventfd");
+    }
+
+    // Each read returns 1 and decrements the counter by 1
+    for (int i = 0; i < 2; i++) {
+        uint64_t data = 0;
+        if (read(event_fd, &data, sizeof(data)) != sizeof(data) || data != 1) {
+            close(event_fd);
+            THROW_ERROR("failed to read the semaphore");
+        }
+    }
+    uint64_t data = 0;
+    if (read(event_fd, &data, sizeof(data)) != -1 || errno != EAGAIN) {
+        close(event_fd);
+        THROW_ERROR("read is expected to fail with EAGAIN when the counter is zero");
+    }
+    close(event_fd);
+
+    // A blocking read waits until the counter becomes nonzero
+    event_fd = eventfd(0, EFD_SEMAPHORE);
+    if (event_fd < 0) {
+        THROW_ERROR("failed to create an eventfd");
+    }
+    struct thread_arg child_arg = { .fd = event_fd, .data = TEST_DATA };
+    if (create_child(&child_arg) != 0) {
+        close(event_fd);
+        THROW_ERROR("failed to create a child");
+    }
+    for (int i = 0; i < TEST_DATA; i++) {
+        data = 0;
+        if (read(event_fd, &data, sizeof(data)) != sizeof(data) || data != 1) {
+            close(event_fd);
+            THROW_ERROR("failed to read the semaphore written by the child");
+        }
+    }
+    if (pthread_join(child_arg.tid, NULL) != 0) {
+        close(event_fd);
+        THROW_ERROR("pthread_join");
+    }
+    struct pollfd pollfds[] = { { .fd = event_fd, .events = POLLIN } };
+    if (poll(pollfds, 1, 0) != 0) {
+        close(event_fd);
+        THROW_ERROR("the semaphore is expected to be zero");
+    }
+
+    close(event_fd);
+    return 0;
+}
+
 int test_select_with_socket() {
     fd_set rfds, wfds;
     int ret = 0;
@@ -251,6 +329,8 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_fcntl_set_flags),
     TEST_CASE(test_create_with_flags),
     TEST_CASE(test_read_write),
+    TEST_CASE(test_read_drains_counter),
+    TEST_CASE(test_read_with_semaphore),
     TEST_CASE(test_epoll_with_socket),
     TEST_CASE(test_poll_with_socket),
     TEST_CASE(test_select_with_socket),
@@ -5,7 +5,12 @@
 #include <unistd.h>
 #include <pthread.h>
 #include <stdio.h>
+#include <sys/wait.h>
 #include <linux/futex.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <string.h>
 #include "test.h"
 
 // ============================================================================
@@ -64,14 +69,103 @@ int test_exit_group_to_force_threads_terminate(void) {
     return 0;
 }
 
+// exit_group syscall called by a non-main thread should terminate all threads,
+// including the main thread, and the process should exit exactly once.
+#define CHILD_EXIT_STATUS 42
+
+static void *exit_group_thread_func(void *_) {
+    // Sleep for a while to make sure other threads are running
+    usleep(100 * 1000);
+    syscall(SYS_exit_group, CHILD_EXIT_STATUS);
+    return NULL;
+}
+
+static int exit_group_child(void) {
+    pthread_t threads[3];
+    void *(*thread_funcs[3])(void *) = {
+        busyloop_thread_func, sleeping_thread_func, futex_wait_thread_func
+    };
+    for (int i = 0; i < 3; i++) {
+        if (pthread_create(&threads[i], NULL, thread_funcs[i], NULL) < 0) {
+            printf("ERROR: pthread_create failed\n");
+            return -1;
+        }
+    }
+    pthread_t exit_group_thread;
+    if (pthread_create(&exit_group_thread, NULL, exit_group_thread_func, NULL) < 0) {
+        printf("ERROR: pthread_create failed\n");
+        return -1;
+    }
+
+    // The main thread is blocked in a syscall when exit_group is called
+    pthread_join(exit_group_thread, NULL);
+    // Should never reach here
+    return -1;
+}
+
+static volatile int sigchld_count = 0;
+
+static void sigchld_handler(int signum) {
+    sigchld_count++;
+}
+
+int test_exit_group_from_non_main_thread(void) {
+    struct sigaction new_action, old_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_handler = sigchld_handler;
+    if (sigaction(SIGCHLD, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering signal handler failed");
+    }
+
+    // Repeat multiple times to check that the resources of the exited child
+    // processes are indeed freed by the LibOS
+    for (int i = 0; i < 3; i++) {
+        pid_t child_pid;
+        char *child_argv[] = {"exit_group", "exit_group_child", NULL};
+        int status;
+        sigchld_count = 0;
+
+        int ret = posix_spawn(&child_pid, "/bin/exit_group", NULL, NULL, child_argv, NULL);
+        if (ret < 0) {
+            THROW_ERROR("failed to spawn a child process");
+        }
+        ret = wait4(child_pid, &status, 0, NULL);
+        if (ret < 0) {
+            THROW_ERROR("failed to wait4 the child process");
+        }
+        if (!WIFEXITED(status) || WEXITSTATUS(status) != CHILD_EXIT_STATUS) {
+            THROW_ERROR("child process is expected to exit with the status of exit_group");
+        }
+        if (sigchld_count != 1) {
+            THROW_ERROR("SIGCHLD is expected to be sent exactly once");
+        }
+    }
+
+    if (sigaction(SIGCHLD, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite
 // ============================================================================
 
 static test_case_t test_cases[] = {
+    TEST_CASE(test_exit_group_from_non_main_thread),
     TEST_CASE(test_exit_group_to_force_threads_terminate)
 };
 
-int main() {
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        const char *cmd = argv[1];
+        if (strcmp(cmd, "exit_group_child") == 0) {
+            return exit_group_child();
+        } else {
+            fprintf(stderr, "ERROR: unknown command: %s\n", cmd);
+            return EXIT_FAILURE;
+        }
+    }
+
     return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
 }
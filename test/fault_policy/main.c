@@ -0,0 +1,153 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <pthread.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+
+#include "test.h"
+
+// An Occlum-specific system call that triggers an invariant violation in the
+// LibOS on purpose, which is only available in non-release builds
+#define SYS_INJECT_FAULT 364
+
+#define BYSTANDER_EXIT_CODE 42
+// The exit code of a faulty child if fault injection is disabled, e.g., in
+// release builds
+#define FAULT_INJECTION_DISABLED_EXIT_CODE 43
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+static int faulty_child() {
+    if (syscall(SYS_INJECT_FAULT) < 0 && errno == ENOSYS) {
+        return FAULT_INJECTION_DISABLED_EXIT_CODE;
+    }
+    // Should never reach here
+    return EXIT_FAILURE;
+}
+
+static void *sleeping_thread(void *arg) {
+    while (1) {
+        sleep(1);
+    }
+    return NULL;
+}
+
+static int faulty_multithreaded_child() {
+    pthread_t thread;
+    if (pthread_create(&thread, NULL, sleeping_thread, NULL) != 0) {
+        return EXIT_FAILURE;
+    }
+    return faulty_child();
+}
+
+static int bystander_child() {
+    // Keep running while the faulty sibling is being killed
+    usleep(200 * 1000);
+    return BYSTANDER_EXIT_CODE;
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int spawn_child(const char *cmd, pid_t *child_pid) {
+    char *child_argv[] = {"fault_policy", (char *)cmd, NULL};
+    return posix_spawn(child_pid, "/bin/fault_policy", NULL, NULL, child_argv, NULL);
+}
+
+// Returns 1 if fault injection is disabled, in which case the test should be skipped
+static int wait_for_faulty_child(const char *cmd) {
+    pid_t child_pid;
+    int status;
+
+    if (spawn_child(cmd, &child_pid) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    if (waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the child process");
+    }
+    if (WIFEXITED(status) && WEXITSTATUS(status) == FAULT_INJECTION_DISABLED_EXIT_CODE) {
+        return 1;
+    }
+    if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGABRT) {
+        THROW_ERROR("the faulty child process is expected to be killed by SIGABRT");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_kill_only_faulty_process() {
+    // Repeat multiple times to check that a fault leaves the LibOS in a usable state
+    for (int i = 0; i < 3; i++) {
+        pid_t bystander_pid;
+        int status;
+
+        int ret;
+
+        if (spawn_child("bystander_child", &bystander_pid) != 0) {
+            THROW_ERROR("failed to spawn the bystander child process");
+        }
+        if ((ret = wait_for_faulty_child("faulty_child")) < 0) {
+            THROW_ERROR("failed to kill the faulty child process");
+        }
+        if (waitpid(bystander_pid, &status, 0) != bystander_pid) {
+            THROW_ERROR("failed to wait for the bystander child process");
+        }
+        if (!WIFEXITED(status) || WEXITSTATUS(status) != BYSTANDER_EXIT_CODE) {
+            THROW_ERROR("the bystander child process should exit normally");
+        }
+        if (ret == 1) {
+            printf("Warning: %s is skipped since fault injection is disabled\n", __func__);
+            return 0;
+        }
+    }
+
+    // The current process should be unaffected
+    if (getpid() <= 0) {
+        THROW_ERROR("the current process should continue to run");
+    }
+    return 0;
+}
+
+int test_kill_faulty_multithreaded_process() {
+    int ret = wait_for_faulty_child("faulty_multithreaded_child");
+    if (ret == 1) {
+        printf("Warning: %s is skipped since fault injection is disabled\n", __func__);
+        return 0;
+    }
+    return ret;
+}
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_kill_only_faulty_process),
+    TEST_CASE(test_kill_faulty_multithreaded_process),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        const char *cmd = argv[1];
+        if (strcmp(cmd, "faulty_child") == 0) {
+            return faulty_child();
+        } else if (strcmp(cmd, "faulty_multithreaded_child") == 0) {
+            return faulty_multithreaded_child();
+        } else if (strcmp(cmd, "bystander_child") == 0) {
+            return bystander_child();
+        } else {
+            fprintf(stderr, "ERROR: unknown command: %s\n", cmd);
+            return EXIT_FAILURE;
+        }
+    }
+
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
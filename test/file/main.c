@@ -4,12 +4,21 @@
 #include <errno.h>
 #include <fcntl.h>
 #include <stdlib.h>
+#include <stdint.h>
+#include <sys/syscall.h>
 #include "test_fs.h"
 
 // ============================================================================
 // Helper function
 // ============================================================================
 
+#ifndef RWF_NOWAIT
+#define RWF_NOWAIT 0x00000008
+#endif
+#ifndef RWF_APPEND
+#define RWF_APPEND 0x00000010
+#endif
+
 #define KB (1024)
 #define BLK_SIZE (4 * KB)
 
@@ -132,6 +141,42 @@ static int __test_writev_readv(const char *file_path) {
     return 0;
 }
 
+static int __test_writev_readv_with_huge_iovs(const char *file_path) {
+    char buf[16] = { 0 };
+    struct iovec iov[2];
+    int fd;
+
+    fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file");
+    }
+
+    // The total length exceeds the max I/O size (2GB by default)
+    iov[0].iov_base = buf;
+    iov[0].iov_len = 3UL << 30;
+    iov[1].iov_base = buf;
+    iov[1].iov_len = 3UL << 30;
+    if (writev(fd, iov, 2) >= 0 || errno != EINVAL) {
+        THROW_ERROR("writev with oversized iovecs should fail with EINVAL");
+    }
+    if (readv(fd, iov, 2) >= 0 || errno != EINVAL) {
+        THROW_ERROR("readv with oversized iovecs should fail with EINVAL");
+    }
+
+    // The total length overflows
+    iov[0].iov_len = SIZE_MAX;
+    iov[1].iov_len = 2;
+    if (writev(fd, iov, 2) >= 0 || errno != EINVAL) {
+        THROW_ERROR("writev with overflowing iovecs should fail with EINVAL");
+    }
+    if (readv(fd, iov, 2) >= 0 || errno != EINVAL) {
+        THROW_ERROR("readv with overflowing iovecs should fail with EINVAL");
+    }
+
+    close(fd);
+    return 0;
+}
+
 static int __test_lseek(const char *file_path) {
     char *write_str = "Hello World\n";
     char read_buf[128] = { 0 };
@@ -167,6 +212,46 @@ static int __test_lseek(const char *file_path) {
     return 0;
 }
 
+static int __test_read_with_noatime(const char *file_path) {
+    char *write_str = "Hello World\n";
+    char read_buf[128] = { 0 };
+    struct stat stat_before, stat_after;
+    int fd, flags;
+
+    fd = open(file_path, O_WRONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file to write");
+    }
+    if (write(fd, write_str, strlen(write_str)) <= 0) {
+        THROW_ERROR("failed to write");
+    }
+    close(fd);
+
+    fd = open(file_path, O_RDONLY | O_NOATIME);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file with O_NOATIME");
+    }
+    flags = fcntl(fd, F_GETFL);
+    if (flags < 0 || (flags & O_NOATIME) == 0) {
+        THROW_ERROR("O_NOATIME is not kept in the status flags");
+    }
+    if (fstat(fd, &stat_before) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    if (read(fd, read_buf, sizeof(read_buf)) != strlen(write_str)) {
+        THROW_ERROR("failed to read");
+    }
+    if (fstat(fd, &stat_after) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    if (stat_before.st_atim.tv_sec != stat_after.st_atim.tv_sec ||
+            stat_before.st_atim.tv_nsec != stat_after.st_atim.tv_nsec) {
+        THROW_ERROR("the atime is updated by a read with O_NOATIME");
+    }
+    close(fd);
+    return 0;
+}
+
 static int __test_posix_fallocate(const char *file_path) {
     int fd = open(file_path, O_RDONLY);
     if (fd < 0) {
@@ -325,6 +410,59 @@ static int __test_fallocate_punch_hole(const char *file_path) {
     return 0;
 }
 
+static int __test_fallocate_punch_hole_in_middle(const char *file_path) {
+    int fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file to read/write");
+    }
+
+    off_t len = 3 * BLK_SIZE;
+    if (fill_file_with_repeated_bytes(fd, len, 0xFF) < 0) {
+        THROW_ERROR("failed to fill file");
+    }
+
+    // The hole spans across the block boundaries
+    int mode = FALLOC_FL_PUNCH_HOLE | FALLOC_FL_KEEP_SIZE;
+    off_t offset = BLK_SIZE / 2;
+    off_t hole_len = BLK_SIZE;
+    if (fallocate(fd, mode, offset, hole_len) < 0) {
+        THROW_ERROR("failed to call fallocate with FALLOC_FL_PUNCH_HOLE");
+    }
+    // Punching beyond the end of file does not change the size
+    if (fallocate(fd, mode, len - BLK_SIZE / 2, BLK_SIZE) < 0) {
+        THROW_ERROR("failed to punch hole beyond the end of file");
+    }
+
+    struct stat stat_buf;
+    if (fstat(fd, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat file");
+    }
+    if (stat_buf.st_size != len) {
+        THROW_ERROR("failed to check the len after fallocate");
+    }
+
+    if (lseek(fd, 0, SEEK_SET) != 0 ||
+            check_file_with_repeated_bytes(fd, offset, 0xFF) < 0) {
+        THROW_ERROR("failed to check the data before the hole");
+    }
+    if (lseek(fd, offset, SEEK_SET) != offset ||
+            check_file_with_repeated_bytes(fd, hole_len, 0x00) < 0) {
+        THROW_ERROR("failed to check the data in the hole");
+    }
+    off_t after_hole = offset + hole_len;
+    if (lseek(fd, after_hole, SEEK_SET) != after_hole ||
+            check_file_with_repeated_bytes(fd, len - BLK_SIZE / 2 - after_hole, 0xFF) < 0) {
+        THROW_ERROR("failed to check the data after the hole");
+    }
+    if (lseek(fd, len - BLK_SIZE / 2, SEEK_SET) != len - BLK_SIZE / 2 ||
+            check_file_with_repeated_bytes(fd, BLK_SIZE / 2, 0x00) < 0) {
+        THROW_ERROR("failed to check the data in the hole at the end of file");
+    }
+
+    close(fd);
+    return 0;
+}
+
 static int __test_fallocate_zero_range(const char *file_path) {
     int fd = open(file_path, O_RDWR);
     if (fd < 0) {
@@ -468,6 +606,159 @@ static int __test_fallocate_collapse_range(const char *file_path) {
     return 0;
 }
 
+static int __test_copy_file_range(const char *file_path) {
+    const char *dst_path = "/root/test_filesystem_copy_file_range.txt";
+    const char *write_str = "Hello copy_file_range!";
+    const char *expected_str = "copy_file_range";
+    const size_t copy_len = strlen(expected_str);
+    char read_buf[128] = { 0 };
+
+    int src_fd = open(file_path, O_RDWR);
+    if (src_fd < 0) {
+        THROW_ERROR("failed to open the source file");
+    }
+    if (write(src_fd, write_str, strlen(write_str)) != strlen(write_str)) {
+        THROW_ERROR("failed to write the source file");
+    }
+    int dst_fd = open(dst_path, O_RDWR | O_CREAT | O_TRUNC, 00666);
+    if (dst_fd < 0) {
+        THROW_ERROR("failed to create the destination file");
+    }
+
+    // Copy with explicit offsets, which are updated but the file offsets are not
+    loff_t off_in = 6;
+    loff_t off_out = 1;
+    ssize_t ret = syscall(SYS_copy_file_range, src_fd, &off_in, dst_fd, &off_out, copy_len, 0);
+    if (ret != copy_len) {
+        THROW_ERROR("failed to copy_file_range with offsets");
+    }
+    if (off_in != 6 + copy_len || off_out != 1 + copy_len) {
+        THROW_ERROR("the offsets are not updated");
+    }
+    if (lseek(src_fd, 0, SEEK_CUR) != strlen(write_str) || lseek(dst_fd, 0, SEEK_CUR) != 0) {
+        THROW_ERROR("the file offsets should not be changed");
+    }
+    if (pread(dst_fd, read_buf, sizeof(read_buf), 1) != copy_len ||
+            strcmp(read_buf, expected_str) != 0) {
+        THROW_ERROR("failed to check the copied contents");
+    }
+
+    // Copy with the file offsets, which are updated; the copy stops at EOF
+    if (lseek(src_fd, 6, SEEK_SET) != 6 || lseek(dst_fd, 0, SEEK_END) != 1 + copy_len) {
+        THROW_ERROR("failed to lseek the files");
+    }
+    ret = syscall(SYS_copy_file_range, src_fd, NULL, dst_fd, NULL, sizeof(read_buf), 0);
+    if (ret != strlen(write_str) - 6) {
+        THROW_ERROR("failed to copy_file_range without offsets");
+    }
+    if (lseek(src_fd, 0, SEEK_CUR) != strlen(write_str) ||
+            lseek(dst_fd, 0, SEEK_CUR) != 1 + copy_len + ret) {
+        THROW_ERROR("the file offsets are not updated");
+    }
+    memset(read_buf, 0, sizeof(read_buf));
+    if (pread(dst_fd, read_buf, sizeof(read_buf), 1 + copy_len) != ret ||
+            strcmp(read_buf, write_str + 6) != 0) {
+        THROW_ERROR("failed to check the copied contents");
+    }
+
+    // Overlapping ranges of the same file are rejected
+    off_in = 0;
+    off_out = 1;
+    ret = syscall(SYS_copy_file_range, src_fd, &off_in, src_fd, &off_out, copy_len, 0);
+    if (ret >= 0 || errno != EINVAL) {
+        THROW_ERROR("failed to check copy_file_range with overlapping ranges");
+    }
+
+    close(src_fd);
+    close(dst_fd);
+    if (remove_file(dst_path) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+static int __test_pwritev2_with_rwf_append(const char *file_path) {
+    const char *write_str = "Hello ";
+    const char *append_str = "World";
+    const char *expected_str = "Hello World";
+    char read_buf[128] = { 0 };
+
+    int fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file to read/write");
+    }
+    if (write(fd, write_str, strlen(write_str)) != strlen(write_str)) {
+        THROW_ERROR("failed to write");
+    }
+    if (lseek(fd, 0, SEEK_SET) != 0) {
+        THROW_ERROR("failed to lseek the file");
+    }
+
+    // The offset is ignored and the data is appended to the end of the file
+    struct iovec iov = { .iov_base = (void *)append_str, .iov_len = strlen(append_str) };
+    if (syscall(SYS_pwritev2, fd, &iov, 1, (off_t)0, 0, RWF_APPEND) != strlen(append_str)) {
+        THROW_ERROR("failed to pwritev2 with RWF_APPEND");
+    }
+    // The file offset is not changed as an offset is given
+    if (lseek(fd, 0, SEEK_CUR) != 0) {
+        THROW_ERROR("the file offset should not be changed");
+    }
+    if (pread(fd, read_buf, sizeof(read_buf), 0) != strlen(expected_str) ||
+            strcmp(read_buf, expected_str) != 0) {
+        THROW_ERROR("failed to check the appended contents");
+    }
+
+    close(fd);
+    return 0;
+}
+
+static int __test_posix_fadvise(const char *file_path) {
+    int advices[] = {
+        POSIX_FADV_NORMAL, POSIX_FADV_RANDOM, POSIX_FADV_SEQUENTIAL,
+        POSIX_FADV_WILLNEED, POSIX_FADV_DONTNEED, POSIX_FADV_NOREUSE,
+    };
+    int pipe_fds[2];
+
+    int fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file to read/write");
+    }
+    if (fill_file_with_repeated_bytes(fd, BLK_SIZE, 0xFF) < 0) {
+        THROW_ERROR("failed to fill file");
+    }
+
+    for (int i = 0; i < ARRAY_SIZE(advices); i++) {
+        if (posix_fadvise(fd, 0, BLK_SIZE, advices[i]) != 0) {
+            THROW_ERROR("failed to call posix_fadvise with advice %d", advices[i]);
+        }
+    }
+    // The advices are only hints, which keep the contents untouched
+    if (lseek(fd, 0, SEEK_SET) != 0) {
+        THROW_ERROR("failed to lseek the file");
+    }
+    if (check_file_with_repeated_bytes(fd, BLK_SIZE, 0xFF) < 0) {
+        THROW_ERROR("failed to check the contents after posix_fadvise");
+    }
+
+    if (posix_fadvise(fd, 0, BLK_SIZE, 100) != EINVAL) {
+        THROW_ERROR("failed to check posix_fadvise with invalid advice");
+    }
+    if (posix_fadvise(fd, 0, -1, POSIX_FADV_NORMAL) != EINVAL) {
+        THROW_ERROR("failed to check posix_fadvise with negative len");
+    }
+    close(fd);
+
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    if (posix_fadvise(pipe_fds[0], 0, 0, POSIX_FADV_NORMAL) != ESPIPE) {
+        THROW_ERROR("failed to check posix_fadvise on a pipe");
+    }
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return 0;
+}
+
 typedef int(*test_file_func_t)(const char *);
 
 static int test_file_framework(test_file_func_t fn) {
@@ -497,10 +788,18 @@ static int test_writev_readv() {
     return test_file_framework(__test_writev_readv);
 }
 
+static int test_writev_readv_with_huge_iovs() {
+    return test_file_framework(__test_writev_readv_with_huge_iovs);
+}
+
 static int test_lseek() {
     return test_file_framework(__test_lseek);
 }
 
+static int test_read_with_noatime() {
+    return test_file_framework(__test_read_with_noatime);
+}
+
 static int test_posix_fallocate() {
     return test_file_framework(__test_posix_fallocate);
 }
@@ -517,6 +816,10 @@ static int test_fallocate_punch_hole() {
     return test_file_framework(__test_fallocate_punch_hole);
 }
 
+static int test_fallocate_punch_hole_in_middle() {
+    return test_file_framework(__test_fallocate_punch_hole_in_middle);
+}
+
 static int test_fallocate_zero_range() {
     return test_file_framework(__test_fallocate_zero_range);
 }
@@ -529,6 +832,46 @@ static int test_fallocate_collapse_range() {
     return test_file_framework(__test_fallocate_collapse_range);
 }
 
+static int test_posix_fadvise() {
+    return test_file_framework(__test_posix_fadvise);
+}
+
+static int test_copy_file_range() {
+    return test_file_framework(__test_copy_file_range);
+}
+
+static int test_pwritev2_with_rwf_append() {
+    return test_file_framework(__test_pwritev2_with_rwf_append);
+}
+
+static int test_preadv2_with_rwf_nowait() {
+    char read_buf[16];
+    int pipe_fds[2];
+
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    // No data in the pipe, so the read would block
+    struct iovec iov = { .iov_base = read_buf, .iov_len = sizeof(read_buf) };
+    int ret = syscall(SYS_preadv2, pipe_fds[0], &iov, 1, (off_t)-1, 0, RWF_NOWAIT);
+    if (ret >= 0 || errno != EAGAIN) {
+        THROW_ERROR("failed to check preadv2 with RWF_NOWAIT on an empty pipe");
+    }
+
+    if (write(pipe_fds[1], "a", 1) != 1) {
+        THROW_ERROR("failed to write the pipe");
+    }
+    ret = syscall(SYS_preadv2, pipe_fds[0], &iov, 1, (off_t)-1, 0, RWF_NOWAIT);
+    if (ret != 1 || read_buf[0] != 'a') {
+        THROW_ERROR("failed to preadv2 with RWF_NOWAIT on a ready pipe");
+    }
+
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -537,14 +880,21 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_write_read),
     TEST_CASE(test_pwrite_pread),
     TEST_CASE(test_writev_readv),
+    TEST_CASE(test_writev_readv_with_huge_iovs),
     TEST_CASE(test_lseek),
+    TEST_CASE(test_read_with_noatime),
     TEST_CASE(test_posix_fallocate),
     TEST_CASE(test_fallocate_with_invalid_mode),
     TEST_CASE(test_fallocate_keep_size),
     TEST_CASE(test_fallocate_punch_hole),
+    TEST_CASE(test_fallocate_punch_hole_in_middle),
     TEST_CASE(test_fallocate_zero_range),
     TEST_CASE(test_fallocate_insert_range),
     TEST_CASE(test_fallocate_collapse_range),
+    TEST_CASE(test_posix_fadvise),
+    TEST_CASE(test_copy_file_range),
+    TEST_CASE(test_pwritev2_with_rwf_append),
+    TEST_CASE(test_preadv2_with_rwf_nowait),
 };
 
 int main(int argc, const char *argv[]) {
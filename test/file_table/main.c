@@ -0,0 +1,327 @@
+#define _GNU_SOURCE
+#include <sys/resource.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <stdbool.h>
+#include <stdlib.h>
+#include <time.h>
+#include "test.h"
+
+// ============================================================================
+// Helper function
+// ============================================================================
+
+// Open fds until the table contains fds in [0, max_fd], all referring to stdin
+static int open_fds_up_to(int max_fd) {
+    int fd;
+    while ((fd = dup(STDIN_FILENO)) < max_fd) {
+        if (fd < 0) {
+            THROW_ERROR("failed to dup");
+        }
+    }
+    if (fd != max_fd) {
+        THROW_ERROR("fds are not allocated sequentially");
+    }
+    return 0;
+}
+
+static void close_fds_from(int min_fd, int max_fd) {
+    for (int fd = min_fd; fd <= max_fd; fd++) {
+        close(fd);
+    }
+}
+
+static int create_file_with_content(const char *file_path, const char *content) {
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (write(fd, content, strlen(content)) != strlen(content)) {
+        close(fd);
+        THROW_ERROR("failed to write the file");
+    }
+    if (lseek(fd, 0, SEEK_SET) != 0) {
+        close(fd);
+        THROW_ERROR("failed to lseek the file");
+    }
+    return fd;
+}
+
+static long elapsed_ms(struct timespec *start, struct timespec *end) {
+    return (end->tv_sec - start->tv_sec) * 1000 + (end->tv_nsec - start->tv_nsec) / 1000000;
+}
+
+// ============================================================================
+// Test cases for fd allocation
+// ============================================================================
+
+static int test_reuse_lowest_freed_fd() {
+    const int max_fd = 200;
+    if (open_fds_up_to(max_fd) < 0) {
+        THROW_ERROR("failed to open fds");
+    }
+
+    // Free a few fds in an order different from their numbers
+    const int freed_fds[] = { 150, 10, 70 };
+    const int expected_fds[] = { 10, 70, 150 };
+    for (int i = 0; i < ARRAY_SIZE(freed_fds); i++) {
+        close(freed_fds[i]);
+    }
+
+    int ret = 0;
+    for (int i = 0; i < ARRAY_SIZE(expected_fds); i++) {
+        int fd = dup(STDIN_FILENO);
+        if (fd != expected_fds[i]) {
+            printf("\t\tERROR: expect fd %d, but got fd %d\n", expected_fds[i], fd);
+            ret = -1;
+            goto out;
+        }
+    }
+
+    // No free fd remains below max_fd
+    int fd = open("/dev/null", O_RDONLY);
+    if (fd != max_fd + 1) {
+        printf("\t\tERROR: expect fd %d, but got fd %d\n", max_fd + 1, fd);
+        ret = -1;
+    }
+
+out:
+    close_fds_from(3, max_fd + 1);
+    return ret;
+}
+
+static int test_fcntl_dupfd_with_min_fd() {
+    const int max_fd = 100;
+    if (open_fds_up_to(max_fd) < 0) {
+        THROW_ERROR("failed to open fds");
+    }
+    close(80);
+    close(90);
+
+    int ret = 0;
+    int fd = fcntl(STDIN_FILENO, F_DUPFD, 85);
+    if (fd != 90) {
+        printf("\t\tERROR: expect fd 90, but got fd %d\n", fd);
+        ret = -1;
+        goto out;
+    }
+    fd = fcntl(STDIN_FILENO, F_DUPFD_CLOEXEC, 85);
+    if (fd != max_fd + 1) {
+        printf("\t\tERROR: expect fd %d, but got fd %d\n", max_fd + 1, fd);
+        ret = -1;
+        goto out;
+    }
+    if (fcntl(fd, F_GETFD) != FD_CLOEXEC) {
+        printf("\t\tERROR: the close-on-exec flag is not set\n");
+        ret = -1;
+        goto out;
+    }
+
+out:
+    close_fds_from(3, max_fd + 1);
+    return ret;
+}
+
+static int test_open_until_rlimit_nofile() {
+    struct rlimit rlim;
+    if (getrlimit(RLIMIT_NOFILE, &rlim) < 0) {
+        THROW_ERROR("failed to getrlimit");
+    }
+    int max_fd = rlim.rlim_cur - 1;
+    if (open_fds_up_to(max_fd) < 0) {
+        THROW_ERROR("failed to open fds");
+    }
+
+    int ret = 0;
+    if (dup(STDIN_FILENO) != -1 || errno != EMFILE) {
+        printf("\t\tERROR: dup is expected to fail with EMFILE\n");
+        ret = -1;
+        goto out;
+    }
+    if (fcntl(STDIN_FILENO, F_DUPFD, max_fd + 1) != -1 || errno != EINVAL) {
+        printf("\t\tERROR: F_DUPFD beyond the limit is expected to fail with EINVAL\n");
+        ret = -1;
+        goto out;
+    }
+
+    // Allocating a low fd stays cheap when there are lots of fds
+    const int low_fd = 5;
+    const int num_rounds = 10000;
+    struct timespec start, end;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    for (int i = 0; i < num_rounds; i++) {
+        close(low_fd);
+        if (dup(STDIN_FILENO) != low_fd) {
+            printf("\t\tERROR: the freed fd %d is not reused\n", low_fd);
+            ret = -1;
+            goto out;
+        }
+    }
+    clock_gettime(CLOCK_MONOTONIC, &end);
+    if (elapsed_ms(&start, &end) > 1000) {
+        printf("\t\tERROR: allocating fds is too slow\n");
+        ret = -1;
+    }
+
+out:
+    close_fds_from(3, max_fd);
+    return ret;
+}
+
+// ============================================================================
+// Test cases for dup2 and dup3
+// ============================================================================
+
+static const char *old_file_path = "/root/test_file_table_old.txt";
+static const char *new_file_path = "/root/test_file_table_new.txt";
+
+static int test_dup2_over_open_fd() {
+    int target_fd = create_file_with_content(old_file_path, "old content");
+    int new_fd = create_file_with_content(new_file_path, "new content");
+    if (target_fd < 0 || new_fd < 0) {
+        THROW_ERROR("failed to create files");
+    }
+
+    int ret = 0;
+    if (dup2(new_fd, target_fd) != target_fd) {
+        printf("\t\tERROR: failed to dup2\n");
+        ret = -1;
+        goto out;
+    }
+
+    // The old file is no longer accessible via the target fd, which is
+    // immediately usable and shares the file offset with new_fd
+    char buf[32] = {0};
+    if (read(target_fd, buf, 3) != 3 || strncmp(buf, "new", 3) != 0) {
+        printf("\t\tERROR: the target fd does not refer to the new file\n");
+        ret = -1;
+        goto out;
+    }
+    memset(buf, 0, sizeof(buf));
+    if (read(new_fd, buf, sizeof(buf)) != strlen(" content") ||
+            strcmp(buf, " content") != 0) {
+        printf("\t\tERROR: the file offset is not shared\n");
+        ret = -1;
+        goto out;
+    }
+
+    // Dup2 to itself is a no-op
+    if (dup2(new_fd, new_fd) != new_fd || fcntl(new_fd, F_GETFD) < 0) {
+        printf("\t\tERROR: failed to dup2 to itself\n");
+        ret = -1;
+    }
+
+out:
+    close_files(2, target_fd, new_fd);
+    unlink(old_file_path);
+    unlink(new_file_path);
+    return ret;
+}
+
+static int test_dup3_with_cloexec() {
+    int fd = create_file_with_content(new_file_path, "new content");
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    const int target_fd = 100;
+
+    int ret = 0;
+    if (dup3(fd, target_fd, O_CLOEXEC) != target_fd ||
+            fcntl(target_fd, F_GETFD) != FD_CLOEXEC) {
+        printf("\t\tERROR: the close-on-exec flag is not set by dup3\n");
+        ret = -1;
+        goto out;
+    }
+    // Dup3 resets the close-on-exec flag without O_CLOEXEC
+    if (dup3(fd, target_fd, 0) != target_fd || fcntl(target_fd, F_GETFD) != 0) {
+        printf("\t\tERROR: the close-on-exec flag is not cleared by dup3\n");
+        ret = -1;
+        goto out;
+    }
+    if (dup3(fd, fd, 0) != -1 || errno != EINVAL) {
+        printf("\t\tERROR: dup3 to itself is expected to fail with EINVAL\n");
+        ret = -1;
+        goto out;
+    }
+    if (dup3(fd, target_fd, O_NONBLOCK) != -1 || errno != EINVAL) {
+        printf("\t\tERROR: dup3 with invalid flags is expected to fail with EINVAL\n");
+        ret = -1;
+    }
+
+out:
+    close_files(2, fd, target_fd);
+    unlink(new_file_path);
+    return ret;
+}
+
+#define DUP2_ROUNDS     10000
+static volatile bool dup2_done = false;
+static volatile int num_transient_ebadf = 0;
+
+static void *probe_target_fd(void *arg) {
+    int target_fd = *(int *)arg;
+    while (!dup2_done) {
+        if (fcntl(target_fd, F_GETFD) < 0 && errno == EBADF) {
+            num_transient_ebadf++;
+        }
+    }
+    return NULL;
+}
+
+static int test_dup2_without_transient_ebadf() {
+    int fds[2] = {
+        create_file_with_content(old_file_path, "old content"),
+        create_file_with_content(new_file_path, "new content"),
+    };
+    if (fds[0] < 0 || fds[1] < 0) {
+        THROW_ERROR("failed to create files");
+    }
+    int target_fd = dup(fds[0]);
+    if (target_fd < 0) {
+        THROW_ERROR("failed to dup");
+    }
+
+    pthread_t prober;
+    dup2_done = false;
+    num_transient_ebadf = 0;
+    if (pthread_create(&prober, NULL, probe_target_fd, &target_fd) != 0) {
+        THROW_ERROR("failed to create a thread");
+    }
+    int ret = 0;
+    for (int i = 0; i < DUP2_ROUNDS; i++) {
+        if (dup2(fds[i % 2], target_fd) != target_fd) {
+            printf("\t\tERROR: failed to dup2\n");
+            ret = -1;
+            break;
+        }
+    }
+    dup2_done = true;
+    pthread_join(prober, NULL);
+
+    if (num_transient_ebadf > 0) {
+        printf("\t\tERROR: the target fd of dup2 is observed to be invalid\n");
+        ret = -1;
+    }
+
+    close_files(3, fds[0], fds[1], target_fd);
+    unlink(old_file_path);
+    unlink(new_file_path);
+    return ret;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_reuse_lowest_freed_fd),
+    TEST_CASE(test_fcntl_dupfd_with_min_fd),
+    TEST_CASE(test_open_until_rlimit_nofile),
+    TEST_CASE(test_dup2_over_open_fd),
+    TEST_CASE(test_dup3_with_cloexec),
+    TEST_CASE(test_dup2_without_transient_ebadf),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,208 @@
+#define _GNU_SOURCE
+#include <sys/syscall.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#include "test.h"
+
+// ============================================================================
+// Helper macros and functions
+// ============================================================================
+
+#ifndef MFD_CLOEXEC
+#define MFD_CLOEXEC         0x0001U
+#define MFD_ALLOW_SEALING   0x0002U
+#endif
+
+#ifndef F_ADD_SEALS
+#define F_ADD_SEALS         1033
+#define F_GET_SEALS         1034
+#define F_SEAL_SEAL         0x0001
+#define F_SEAL_SHRINK       0x0002
+#define F_SEAL_GROW         0x0004
+#define F_SEAL_WRITE        0x0008
+#endif
+
+static int create_memfd(const char *name, unsigned int flags) {
+    return syscall(SYS_memfd_create, name, flags);
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_create_and_read_write() {
+    const char *write_str = "Hello memfd";
+    char read_buf[32] = {0};
+
+    int fd = create_memfd("test", MFD_CLOEXEC);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    if ((fcntl(fd, F_GETFD) & FD_CLOEXEC) == 0) {
+        THROW_ERROR("the memfd should be close-on-exec");
+    }
+    if (write(fd, write_str, strlen(write_str)) != strlen(write_str)) {
+        THROW_ERROR("failed to write the memfd");
+    }
+    if (pread(fd, read_buf, sizeof(read_buf), 0) != strlen(write_str)) {
+        THROW_ERROR("failed to read the memfd");
+    }
+    if (strcmp(write_str, read_buf) != 0) {
+        THROW_ERROR("the data read is not the data written");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_add_seals_without_allow_sealing() {
+    int fd = create_memfd("test", 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    if (fcntl(fd, F_GET_SEALS) != F_SEAL_SEAL) {
+        THROW_ERROR("the memfd should be sealed against sealing");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) != -1 || errno != EPERM) {
+        THROW_ERROR("adding seals should fail with EPERM");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_write_after_seal_write() {
+    const char *write_str = "Hello memfd";
+
+    int fd = create_memfd("test", MFD_ALLOW_SEALING);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    if (fcntl(fd, F_GET_SEALS) != 0) {
+        THROW_ERROR("the memfd should have no seals");
+    }
+    if (write(fd, write_str, strlen(write_str)) != strlen(write_str)) {
+        THROW_ERROR("failed to write the memfd");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) < 0) {
+        THROW_ERROR("failed to add F_SEAL_WRITE");
+    }
+    if (fcntl(fd, F_GET_SEALS) != F_SEAL_WRITE) {
+        THROW_ERROR("failed to get the seals");
+    }
+    if (write(fd, write_str, strlen(write_str)) != -1 || errno != EPERM) {
+        THROW_ERROR("writing a write-sealed memfd should fail with EPERM");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_resize_after_seal_shrink_and_grow() {
+    const char *write_str = "Hello memfd";
+    off_t len = strlen(write_str);
+
+    int fd = create_memfd("test", MFD_ALLOW_SEALING);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    if (write(fd, write_str, len) != len) {
+        THROW_ERROR("failed to write the memfd");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_SEAL) < 0) {
+        THROW_ERROR("failed to add the seals");
+    }
+    if (ftruncate(fd, len - 1) != -1 || errno != EPERM) {
+        THROW_ERROR("shrinking a shrink-sealed memfd should fail with EPERM");
+    }
+    if (ftruncate(fd, len + 1) != -1 || errno != EPERM) {
+        THROW_ERROR("growing a grow-sealed memfd should fail with EPERM");
+    }
+    if (pwrite(fd, write_str, len, len) != -1 || errno != EPERM) {
+        THROW_ERROR("writing beyond the end of a grow-sealed memfd should fail with EPERM");
+    }
+    if (pwrite(fd, write_str, len, 0) != len) {
+        THROW_ERROR("failed to overwrite a grow-sealed memfd");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) != -1 || errno != EPERM) {
+        THROW_ERROR("adding seals after F_SEAL_SEAL should fail with EPERM");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_seal_write_with_shared_mapping() {
+    size_t len = 4096;
+
+    int fd = create_memfd("test", MFD_ALLOW_SEALING);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    if (ftruncate(fd, len) < 0) {
+        THROW_ERROR("failed to resize the memfd");
+    }
+
+    // A shared writable mapping prevents F_SEAL_WRITE
+    void *addr = mmap(NULL, len, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (addr == MAP_FAILED) {
+        THROW_ERROR("failed to map the memfd");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) != -1 || errno != EBUSY) {
+        THROW_ERROR("adding F_SEAL_WRITE with a shared writable mapping should fail with EBUSY");
+    }
+    if (munmap(addr, len) < 0) {
+        THROW_ERROR("failed to unmap the memfd");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) < 0) {
+        THROW_ERROR("failed to add F_SEAL_WRITE");
+    }
+
+    // A write-sealed memfd cannot be mapped as shared and writable, but can be
+    // mapped as read-only
+    addr = mmap(NULL, len, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (addr != MAP_FAILED || errno != EPERM) {
+        THROW_ERROR("mapping a write-sealed memfd as writable should fail with EPERM");
+    }
+    addr = mmap(NULL, len, PROT_READ, MAP_SHARED, fd, 0);
+    if (addr == MAP_FAILED) {
+        THROW_ERROR("failed to map a write-sealed memfd as read-only");
+    }
+    munmap(addr, len);
+    close(fd);
+    return 0;
+}
+
+static int test_seals_on_regular_file() {
+    const char *file_path = "/root/test_memfd_regular_file.txt";
+
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file");
+    }
+    if (fcntl(fd, F_GET_SEALS) != -1 || errno != EINVAL) {
+        THROW_ERROR("getting seals of a regular file should fail with EINVAL");
+    }
+    close(fd);
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_create_and_read_write),
+    TEST_CASE(test_add_seals_without_allow_sealing),
+    TEST_CASE(test_write_after_seal_write),
+    TEST_CASE(test_resize_after_seal_shrink_and_grow),
+    TEST_CASE(test_seal_write_with_shared_mapping),
+    TEST_CASE(test_seals_on_regular_file),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -1,5 +1,7 @@
 #include <sys/stat.h>
 #include <sys/mount.h>
+#include <sys/statfs.h>
+#include <sys/statvfs.h>
 #include <errno.h>
 #include <fcntl.h>
 #include "test_fs.h"
@@ -213,6 +215,512 @@ static int test_umount_ramfs() {
     return test_mount_framework(__test_umount_fs, target, false);
 }
 
+// ============================================================================
+// Test cases for mount options of creation defaults
+// ============================================================================
+
+static int test_mount_ramfs_with_creation_defaults() {
+    const char *mnt_dir = "/mnt_ramfs_defaults";
+    const char *file_path = "/mnt_ramfs_defaults/test_creation_defaults.txt";
+    const char *dir_path = "/mnt_ramfs_defaults/test_creation_defaults_dir";
+    struct stat stat_buf;
+    int fd;
+
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create ramfs mnt dir");
+    }
+    if (mount("ramfs", mnt_dir, "ramfs", 0, "uid=abc") == 0 || errno != EINVAL) {
+        THROW_ERROR("failed to check invalid uid option");
+    }
+    if (mount("ramfs", mnt_dir, "ramfs", 0, "mode=0999") == 0 || errno != EINVAL) {
+        THROW_ERROR("failed to check invalid mode option");
+    }
+    if (mount("ramfs", mnt_dir, "ramfs", 0, "uid=1000,gid=1000,mode=0640,umask=027") < 0) {
+        THROW_ERROR("failed to mount ramfs with creation defaults");
+    }
+
+    // the mode of a file is the mode option, not the one masked by the process umask
+    fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 00777);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (fstat(fd, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    close(fd);
+    if (stat_buf.st_uid != 1000 || stat_buf.st_gid != 1000) {
+        THROW_ERROR("the ownership of the file does not match the mount options");
+    }
+    if ((stat_buf.st_mode & 07777) != 0640) {
+        THROW_ERROR("the mode of the file does not match the mount options");
+    }
+
+    // the mode of a dir is masked by the umask option
+    if (mkdir(dir_path, 00777) < 0) {
+        THROW_ERROR("failed to mkdir");
+    }
+    if (stat(dir_path, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the dir");
+    }
+    if (stat_buf.st_uid != 1000 || stat_buf.st_gid != 1000) {
+        THROW_ERROR("the ownership of the dir does not match the mount options");
+    }
+    if ((stat_buf.st_mode & 07777) != 0750) {
+        THROW_ERROR("the mode of the dir does not match the mount options");
+    }
+
+    if (umount(mnt_dir) < 0) {
+        THROW_ERROR("failed to umount fs on: %s", mnt_dir);
+    }
+    return 0;
+}
+
+static int test_mount_ramfs_with_size_limit() {
+    const char *mnt_dir = "/mnt_ramfs_size";
+    const char *file_path = "/mnt_ramfs_size/test_size_limit.txt";
+    const size_t size_limit = 64 * 1024;
+    char buf[4096] = {0};
+    struct statfs statfs_buf;
+    size_t total_len = 0;
+    ssize_t len;
+    int fd;
+
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create ramfs mnt dir");
+    }
+    if (mount("ramfs", mnt_dir, "ramfs", 0, "size=64x") == 0 || errno != EINVAL) {
+        THROW_ERROR("failed to check invalid size option");
+    }
+    if (mount("ramfs", mnt_dir, "ramfs", 0, "size=64k") < 0) {
+        THROW_ERROR("failed to mount ramfs with size limit");
+    }
+
+    if (statfs(mnt_dir, &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs the mount");
+    }
+    if (statfs_buf.f_blocks * statfs_buf.f_bsize != size_limit) {
+        THROW_ERROR("the capacity does not match the size option");
+    }
+
+    fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    while ((len = write(fd, buf, sizeof(buf))) > 0) {
+        total_len += len;
+        if (total_len > size_limit) {
+            THROW_ERROR("the size limit is exceeded");
+        }
+    }
+    if (len != -1 || errno != ENOSPC) {
+        THROW_ERROR("writing beyond the size limit should fail with ENOSPC");
+    }
+    if (total_len != size_limit) {
+        THROW_ERROR("failed to fill up the mount");
+    }
+    if (statfs(mnt_dir, &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs the mount");
+    }
+    if (statfs_buf.f_bfree != 0) {
+        THROW_ERROR("the mount should have no free blocks");
+    }
+
+    // the space is available again after the file is truncated
+    if (ftruncate(fd, 0) < 0) {
+        THROW_ERROR("failed to truncate the file");
+    }
+    if (pwrite(fd, buf, sizeof(buf), 0) != sizeof(buf)) {
+        THROW_ERROR("failed to write after truncating the file");
+    }
+
+    // the space of an unlinked file is kept until the file is closed
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    if (lseek(fd, sizeof(buf), SEEK_SET) < 0) {
+        THROW_ERROR("failed to seek the file");
+    }
+    total_len = sizeof(buf);
+    while ((len = write(fd, buf, sizeof(buf))) > 0) {
+        total_len += len;
+        if (total_len > size_limit) {
+            THROW_ERROR("the size limit is exceeded by an unlinked file");
+        }
+    }
+    if (len != -1 || errno != ENOSPC) {
+        THROW_ERROR("writing an unlinked file beyond the size limit should fail with ENOSPC");
+    }
+    close(fd);
+
+    fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (write(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        THROW_ERROR("the space of the unlinked file should be released after it is closed");
+    }
+    close(fd);
+
+    if (remove_file(file_path) < 0) {
+        return -1;
+    }
+    if (umount(mnt_dir) < 0) {
+        THROW_ERROR("failed to umount fs on: %s", mnt_dir);
+    }
+    return 0;
+}
+
+static int test_remount_ramfs_read_only_with_open_fd() {
+    const char *mnt_dir = "/mnt_ramfs_remount";
+    const char *file_path = "/mnt_ramfs_remount/test_remount.txt";
+    const char *write_str = "Hello World\n";
+    int fd;
+
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create ramfs mnt dir");
+    }
+    if (mount(NULL, mnt_dir, NULL, MS_REMOUNT | MS_RDONLY, NULL) == 0 || errno != EINVAL) {
+        THROW_ERROR("remounting a non-mountpoint should fail with EINVAL");
+    }
+    if (mount("ramfs", mnt_dir, "ramfs", 0, NULL) < 0) {
+        THROW_ERROR("failed to mount ramfs");
+    }
+    fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (write(fd, write_str, strlen(write_str)) != strlen(write_str)) {
+        THROW_ERROR("failed to write before remounting");
+    }
+
+    // the open fd is kept but cannot be written after remounting read-only
+    if (mount(NULL, mnt_dir, NULL, MS_REMOUNT | MS_RDONLY, NULL) < 0) {
+        THROW_ERROR("failed to remount ramfs read-only");
+    }
+    if (!(write(fd, write_str, strlen(write_str)) < 0 && errno == EROFS)) {
+        THROW_ERROR("writing the open fd on the read-only mount should fail");
+    }
+    if (!(ftruncate(fd, 0) < 0 && errno == EROFS)) {
+        THROW_ERROR("truncating the open fd on the read-only mount should fail");
+    }
+    if (fs_check_file_content(file_path, write_str) < 0) {
+        THROW_ERROR("failed to read the file on the read-only mount");
+    }
+
+    // the open fd can be written again after remounting writable
+    if (mount(NULL, mnt_dir, NULL, MS_REMOUNT, NULL) < 0) {
+        THROW_ERROR("failed to remount ramfs writable");
+    }
+    if (pwrite(fd, write_str, strlen(write_str), 0) != strlen(write_str)) {
+        THROW_ERROR("failed to write after remounting writable");
+    }
+    close(fd);
+
+    if (remove_file(file_path) < 0) {
+        return -1;
+    }
+    if (umount(mnt_dir) < 0) {
+        THROW_ERROR("failed to umount fs on: %s", mnt_dir);
+    }
+    return 0;
+}
+
+static int test_remount_config_mount_read_only() {
+    // The mount of /host is specified in Occlum.json, not made by the mount syscall
+    const char *mnt_dir = "/host";
+    const char *file_path = "/host/test_remount_config_mount.txt";
+
+    if (mount(NULL, mnt_dir, NULL, MS_REMOUNT | MS_RDONLY, NULL) < 0) {
+        THROW_ERROR("failed to remount the hostfs in Occlum.json read-only");
+    }
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 00666);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("creating a file on the read-only mount should fail with EROFS");
+    }
+    if (mount(NULL, mnt_dir, NULL, MS_REMOUNT, NULL) < 0) {
+        THROW_ERROR("failed to remount the hostfs in Occlum.json writable");
+    }
+    if (write_read_file(file_path) < 0) {
+        THROW_ERROR("failed to write the file after remounting writable");
+    }
+    if (remove_file(file_path) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+static int write_file_on_sefs(const char *sefs_dir, const char *file_name, const char *content) {
+    const char *mnt_dir = "/mnt_sefs_layer";
+    char file_path[PATH_MAX] = { 0 };
+    char options[PATH_MAX] = { 0 };
+    int fd;
+
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create sefs mnt dir");
+    }
+    snprintf(options, sizeof(options), "dir=%s", sefs_dir);
+    if (mount("sefs", mnt_dir, "sefs", 0, options) < 0) {
+        THROW_ERROR("failed to mount sefs");
+    }
+    snprintf(file_path, sizeof(file_path), "%s/%s", mnt_dir, file_name);
+    fd = open(file_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file on sefs");
+    }
+    if (write(fd, content, strlen(content)) != strlen(content)) {
+        THROW_ERROR("failed to write a file on sefs");
+    }
+    close(fd);
+    if (umount(mnt_dir) < 0) {
+        THROW_ERROR("failed to umount fs on: %s", mnt_dir);
+    }
+    return 0;
+}
+
+static int test_mount_unionfs_with_multiple_lower_layers() {
+    const char *mnt_dir = "/mnt_unionfs_layers";
+    const char *options = "lowerdir=./mnt_test/mnt_unionfs_layers/a:"
+                          "./mnt_test/mnt_unionfs_layers/b:"
+                          "./mnt_test/mnt_unionfs_layers/c,"
+                          "upperdir=./mnt_test/mnt_unionfs_layers/upper";
+
+    // The same file is in all the lower layers, with different contents
+    if (write_file_on_sefs("./mnt_test/mnt_unionfs_layers/a", "shadowed", "a") < 0 ||
+            write_file_on_sefs("./mnt_test/mnt_unionfs_layers/b", "shadowed", "b") < 0 ||
+            write_file_on_sefs("./mnt_test/mnt_unionfs_layers/c", "shadowed", "c") < 0 ||
+            write_file_on_sefs("./mnt_test/mnt_unionfs_layers/b", "only_in_b", "b") < 0 ||
+            write_file_on_sefs("./mnt_test/mnt_unionfs_layers/c", "only_in_c", "c") < 0) {
+        THROW_ERROR("failed to prepare the lower layers");
+    }
+
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create unionfs mnt dir");
+    }
+    if (mount("unionfs", mnt_dir, "unionfs", 0,
+              "lowerdir=./mnt_test/mnt_unionfs_layers/a::./mnt_test/mnt_unionfs_layers/b,"
+              "upperdir=./mnt_test/mnt_unionfs_layers/upper") == 0 || errno != EINVAL) {
+        THROW_ERROR("failed to check empty lowerdir");
+    }
+    if (mount("unionfs", mnt_dir, "unionfs", 0, options) < 0) {
+        THROW_ERROR("failed to mount unionfs with multiple lower layers");
+    }
+    // the left-most lower layer wins, and the files only in the others are seen
+    if (fs_check_file_content("/mnt_unionfs_layers/shadowed", "a") < 0 ||
+            fs_check_file_content("/mnt_unionfs_layers/only_in_b", "b") < 0 ||
+            fs_check_file_content("/mnt_unionfs_layers/only_in_c", "c") < 0) {
+        THROW_ERROR("failed to check the shadowing order of the layers");
+    }
+    // the lower file is hidden by the whiteout in the upper layer
+    if (remove_file("/mnt_unionfs_layers/only_in_c") < 0) {
+        return -1;
+    }
+    if (umount(mnt_dir) < 0) {
+        THROW_ERROR("failed to umount fs on: %s", mnt_dir);
+    }
+
+    if (mount("unionfs", mnt_dir, "unionfs", 0, options) < 0) {
+        THROW_ERROR("failed to remount unionfs with multiple lower layers");
+    }
+    if (check_file_no_exists("/mnt_unionfs_layers/only_in_c") < 0) {
+        THROW_ERROR("the whiteout does not hide the lower file");
+    }
+    if (fs_check_file_content("/mnt_unionfs_layers/shadowed", "a") < 0) {
+        THROW_ERROR("failed to check the shadowing order of the layers");
+    }
+    if (umount(mnt_dir) < 0) {
+        THROW_ERROR("failed to umount fs on: %s", mnt_dir);
+    }
+    return 0;
+}
+
+static int test_mount_ramfs_read_only() {
+    const char *mnt_dir = "/mnt_ramfs_ro";
+    const char *file_path = "/mnt_ramfs_ro/test_read_only.txt";
+    const char *new_file_path = "/mnt_ramfs_ro/test_read_only_new.txt";
+    struct statfs statfs_buf;
+    int fd;
+
+    if (create_dir(mnt_dir) < 0) {
+        THROW_ERROR("failed to create ramfs mnt dir");
+    }
+    if (mount("ramfs", mnt_dir, "ramfs", 0, NULL) < 0) {
+        THROW_ERROR("failed to mount ramfs");
+    }
+    if (write_read_file(file_path) < 0) {
+        THROW_ERROR("failed to write the file before remounting read-only");
+    }
+    if (mount(NULL, mnt_dir, NULL, MS_REMOUNT | MS_RDONLY, NULL) < 0) {
+        THROW_ERROR("failed to remount ramfs read-only");
+    }
+
+    if (statfs(mnt_dir, &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs the mount");
+    }
+    if ((statfs_buf.f_flags & ST_RDONLY) == 0) {
+        THROW_ERROR("the mount should be reported read-only");
+    }
+    // reads succeed but writes fail
+    if (fs_check_file_content(file_path, "Hello World\n") < 0) {
+        THROW_ERROR("failed to read the file on the read-only mount");
+    }
+    fd = open(file_path, O_WRONLY);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("opening the file to write on the read-only mount should fail");
+    }
+    fd = open(new_file_path, O_RDWR | O_CREAT, 00666);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("creating a file on the read-only mount should fail");
+    }
+    if (!(truncate(file_path, 0) < 0 && errno == EROFS)) {
+        THROW_ERROR("truncating the file on the read-only mount should fail");
+    }
+    fd = open(file_path, O_RDONLY | O_TRUNC);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("opening the file with O_TRUNC on the read-only mount should fail");
+    }
+    if (fs_check_file_content(file_path, "Hello World\n") < 0) {
+        THROW_ERROR("the file on the read-only mount should not be truncated");
+    }
+    if (!(unlink(file_path) < 0 && errno == EROFS)) {
+        THROW_ERROR("removing the file on the read-only mount should fail");
+    }
+
+    if (mount(NULL, mnt_dir, NULL, MS_REMOUNT, NULL) < 0) {
+        THROW_ERROR("failed to remount ramfs writable");
+    }
+    if (remove_file(file_path) < 0) {
+        return -1;
+    }
+    if (umount(mnt_dir) < 0) {
+        THROW_ERROR("failed to umount fs on: %s", mnt_dir);
+    }
+    return 0;
+}
+
+static int test_bind_mount() {
+    const char *src_dir = "/mnt_bind_src";
+    const char *dst_dir = "/mnt_bind_dst";
+    const char *src_file_path = "/mnt_bind_src/test_bind_mount.txt";
+    const char *dst_file_path = "/mnt_bind_dst/test_bind_mount.txt";
+
+    if (create_dir(src_dir) < 0 || create_dir(dst_dir) < 0) {
+        THROW_ERROR("failed to create the dirs to bind");
+    }
+    if (mount(src_dir, dst_dir, NULL, MS_BIND, NULL) < 0) {
+        THROW_ERROR("failed to bind mount");
+    }
+
+    // the file created via the target is seen at the source
+    if (write_read_file(dst_file_path) < 0) {
+        THROW_ERROR("failed to write the file via the bind target");
+    }
+    if (fs_check_file_content(src_file_path, "Hello World\n") < 0) {
+        THROW_ERROR("failed to see the file at the bind source");
+    }
+    if (remove_file(src_file_path) < 0) {
+        return -1;
+    }
+    if (check_file_no_exists(dst_file_path) < 0) {
+        THROW_ERROR("the removed file is still seen via the bind target");
+    }
+
+    if (umount(dst_dir) < 0) {
+        THROW_ERROR("failed to umount the bind mount on: %s", dst_dir);
+    }
+    return 0;
+}
+
+static int test_bind_mount_read_only() {
+    const char *src_dir = "/mnt_bind_src";
+    const char *dst_dir = "/mnt_bind_dst_ro";
+    const char *src_file_path = "/mnt_bind_src/test_bind_mount_ro.txt";
+    const char *dst_file_path = "/mnt_bind_dst_ro/test_bind_mount_ro.txt";
+    const char *dst_new_file_path = "/mnt_bind_dst_ro/test_bind_mount_ro_new.txt";
+    int fd;
+
+    if (create_dir(src_dir) < 0 || create_dir(dst_dir) < 0) {
+        THROW_ERROR("failed to create the dirs to bind");
+    }
+    if (write_read_file(src_file_path) < 0) {
+        THROW_ERROR("failed to write the file at the bind source");
+    }
+    if (mount(src_dir, dst_dir, NULL, MS_BIND | MS_RDONLY, NULL) < 0) {
+        THROW_ERROR("failed to bind mount read-only");
+    }
+
+    // the file can be read but not written via the target
+    if (fs_check_file_content(dst_file_path, "Hello World\n") < 0) {
+        THROW_ERROR("failed to read the file via the read-only bind target");
+    }
+    fd = open(dst_file_path, O_WRONLY);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("opening the file to write via the read-only bind target should fail");
+    }
+    fd = open(dst_new_file_path, O_RDWR | O_CREAT, 00666);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("creating a file via the read-only bind target should fail");
+    }
+    if (!(unlink(dst_file_path) < 0 && errno == EROFS)) {
+        THROW_ERROR("removing the file via the read-only bind target should fail");
+    }
+    // the source is still writable
+    if (write_read_file(src_file_path) < 0) {
+        THROW_ERROR("failed to write the file at the bind source");
+    }
+
+    if (umount(dst_dir) < 0) {
+        THROW_ERROR("failed to umount the bind mount on: %s", dst_dir);
+    }
+    if (remove_file(src_file_path) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+static int test_bind_mount_of_read_only_mount() {
+    const char *src_dir = "/mnt_ramfs_bind_ro_src";
+    const char *dst_dir = "/mnt_bind_dst_of_ro";
+    const char *src_file_path = "/mnt_ramfs_bind_ro_src/test_bind_mount_of_ro.txt";
+    const char *dst_file_path = "/mnt_bind_dst_of_ro/test_bind_mount_of_ro.txt";
+    int fd;
+
+    if (create_dir(src_dir) < 0 || create_dir(dst_dir) < 0) {
+        THROW_ERROR("failed to create the dirs to bind");
+    }
+    if (mount("ramfs", src_dir, "ramfs", 0, NULL) < 0) {
+        THROW_ERROR("failed to mount ramfs");
+    }
+    if (write_read_file(src_file_path) < 0) {
+        THROW_ERROR("failed to write the file at the bind source");
+    }
+    if (mount(NULL, src_dir, NULL, MS_REMOUNT | MS_RDONLY, NULL) < 0) {
+        THROW_ERROR("failed to remount ramfs read-only");
+    }
+    if (mount(src_dir, dst_dir, NULL, MS_BIND, NULL) < 0) {
+        THROW_ERROR("failed to bind mount");
+    }
+
+    // the bind of a read-only mount is read-only without MS_RDONLY
+    fd = open(dst_file_path, O_WRONLY);
+    if (!(fd < 0 && errno == EROFS)) {
+        THROW_ERROR("opening the file to write via the bind of a read-only mount should fail");
+    }
+
+    if (umount(dst_dir) < 0) {
+        THROW_ERROR("failed to umount the bind mount on: %s", dst_dir);
+    }
+    if (mount(NULL, src_dir, NULL, MS_REMOUNT, NULL) < 0) {
+        THROW_ERROR("failed to remount ramfs writable");
+    }
+    if (remove_file(src_file_path) < 0) {
+        return -1;
+    }
+    if (umount(src_dir) < 0) {
+        THROW_ERROR("failed to umount fs on: %s", src_dir);
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -227,6 +735,15 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_umount_hostfs),
     TEST_CASE(test_mount_ramfs),
     TEST_CASE(test_umount_ramfs),
+    TEST_CASE(test_mount_ramfs_with_creation_defaults),
+    TEST_CASE(test_mount_ramfs_with_size_limit),
+    TEST_CASE(test_mount_ramfs_read_only),
+    TEST_CASE(test_remount_ramfs_read_only_with_open_fd),
+    TEST_CASE(test_remount_config_mount_read_only),
+    TEST_CASE(test_mount_unionfs_with_multiple_lower_layers),
+    TEST_CASE(test_bind_mount),
+    TEST_CASE(test_bind_mount_read_only),
+    TEST_CASE(test_bind_mount_of_read_only_mount),
 };
 
 int main(int argc, const char *argv[]) {
@@ -1,8 +1,31 @@
 #include <sys/stat.h>
+#include <sys/syscall.h>
 #include <fcntl.h>
 #include <errno.h>
+#include <stdint.h>
 #include "test_fs.h"
 
+#ifndef SYS_openat2
+#define SYS_openat2 437
+#endif
+
+#define RESOLVE_NO_SYMLINKS 0x04
+#define RESOLVE_BENEATH     0x08
+
+#ifndef O_TMPFILE
+#define O_TMPFILE (020000000 | O_DIRECTORY)
+#endif
+
+#ifndef AT_EMPTY_PATH
+#define AT_EMPTY_PATH 0x1000
+#endif
+
+struct test_open_how {
+    uint64_t flags;
+    uint64_t mode;
+    uint64_t resolve;
+};
+
 // ============================================================================
 // Helper function
 // ============================================================================
@@ -112,6 +135,106 @@ static int __test_creat(const char *file_path, int flags, int mode) {
     return 0;
 }
 
+static int test_openat2(int dirfd, const char *path, int flags, uint64_t resolve) {
+    struct test_open_how how = { .flags = flags, .mode = 0, .resolve = resolve };
+    return syscall(SYS_openat2, dirfd, path, &how, sizeof(how));
+}
+
+static int __test_openat2_with_resolve_flags(const char *file_path, int flags, int mode) {
+    const char *link_path = "/root/test_filesystem_open_link";
+    char dir_buf[PATH_MAX] = { 0 };
+    char base_buf[PATH_MAX] = { 0 };
+    char escape_path[PATH_MAX] = { 0 };
+    char *dir_name, *file_name;
+    int dirfd, fd;
+
+    fd = open(file_path, flags, mode);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    close(fd);
+    if (symlink(file_path, link_path) < 0) {
+        THROW_ERROR("failed to create a symlink");
+    }
+
+    // Opening through a symlink is rejected with RESOLVE_NO_SYMLINKS
+    fd = test_openat2(AT_FDCWD, link_path, O_RDONLY, RESOLVE_NO_SYMLINKS);
+    if (fd >= 0 || errno != ELOOP) {
+        THROW_ERROR("failed to check openat2 with RESOLVE_NO_SYMLINKS");
+    }
+    // Without resolve flags, it behaves like openat
+    fd = test_openat2(AT_FDCWD, link_path, O_RDONLY, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to openat2 a symlink without resolve flags");
+    }
+    close(fd);
+
+    // Escaping the directory is rejected with RESOLVE_BENEATH
+    if (fs_split_path(file_path, dir_buf, &dir_name, base_buf, &file_name) < 0) {
+        THROW_ERROR("failed to split path");
+    }
+    dirfd = open(dir_name, O_RDONLY);
+    if (dirfd < 0) {
+        THROW_ERROR("failed to open dir");
+    }
+    fd = test_openat2(dirfd, file_name, O_RDONLY, RESOLVE_BENEATH);
+    if (fd < 0) {
+        THROW_ERROR("failed to openat2 a file beneath the directory");
+    }
+    close(fd);
+    // A missing file is created beneath the directory by the same walk
+    fd = test_openat2(dirfd, "test_filesystem_openat2_new.txt", O_RDWR | O_CREAT | O_EXCL,
+                      RESOLVE_BENEATH | RESOLVE_NO_SYMLINKS);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file beneath the directory with openat2");
+    }
+    close(fd);
+    if (unlinkat(dirfd, "test_filesystem_openat2_new.txt", 0) < 0) {
+        THROW_ERROR("failed to unlink the created file");
+    }
+    snprintf(escape_path, sizeof(escape_path), "..%s", file_path);
+    fd = test_openat2(dirfd, escape_path, O_RDONLY, RESOLVE_BENEATH);
+    if (fd >= 0 || errno != EXDEV) {
+        THROW_ERROR("failed to check openat2 with RESOLVE_BENEATH");
+    }
+    close(dirfd);
+
+    if (unlink(link_path) < 0) {
+        THROW_ERROR("failed to unlink the symlink");
+    }
+    return 0;
+}
+
+static int __test_open_tmpfile(const char *file_path, int flags, int mode) {
+    char dir_buf[PATH_MAX] = { 0 };
+    char base_buf[PATH_MAX] = { 0 };
+    char *dir_name, *file_name;
+    int fd;
+
+    if (fs_split_path(file_path, dir_buf, &dir_name, base_buf, &file_name) < 0) {
+        THROW_ERROR("failed to split path");
+    }
+    // O_TMPFILE requires write access
+    fd = open(dir_name, O_TMPFILE | O_RDONLY, mode);
+    if (fd >= 0 || errno != EINVAL) {
+        THROW_ERROR("open with O_TMPFILE and O_RDONLY should return EINVAL");
+    }
+    // O_TMPFILE without its O_DIRECTORY part is invalid
+    fd = open(dir_name, (O_TMPFILE & ~O_DIRECTORY) | O_RDWR, mode);
+    if (fd >= 0 || errno != EINVAL) {
+        THROW_ERROR("open with O_TMPFILE but no O_DIRECTORY should return EINVAL");
+    }
+    // No file system can create an unnamed file
+    fd = open(dir_name, O_TMPFILE | O_RDWR, mode);
+    if (fd >= 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("open with O_TMPFILE should return EOPNOTSUPP");
+    }
+    if (access(file_path, F_OK) == 0) {
+        THROW_ERROR("no file should be created by O_TMPFILE");
+    }
+    return 0;
+}
+
 typedef int(*test_open_func_t)(const char *, int, int);
 
 static int test_open_framework(test_open_func_t fn) {
@@ -152,6 +275,14 @@ static int test_creat() {
     return test_open_framework(__test_creat);
 }
 
+static int test_openat2_with_resolve_flags() {
+    return test_open_framework(__test_openat2_with_resolve_flags);
+}
+
+static int test_open_tmpfile() {
+    return test_open_framework(__test_open_tmpfile);
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -163,6 +294,8 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_openat_with_abs_path),
     TEST_CASE(test_openat_with_dirfd),
     TEST_CASE(test_creat),
+    TEST_CASE(test_openat2_with_resolve_flags),
+    TEST_CASE(test_open_tmpfile),
 };
 
 int main(int argc, const char *argv[]) {
@@ -7,6 +7,7 @@
 #include <sys/time.h>
 #include <sys/stat.h>
 #include <sys/ioctl.h>
+#include <sys/uio.h>
 #include <fcntl.h>
 #include <poll.h>
 #include <unistd.h>
@@ -385,6 +386,325 @@ int test_ioctl_fionread() {
     return 0;
 }
 
+int test_writev_partially() {
+    int pipe_fds[2];
+    if (pipe2(pipe_fds, O_NONBLOCK) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    int pipe_rd_fd = pipe_fds[0];
+    int pipe_wr_fd = pipe_fds[1];
+
+    // Fill up the pipe
+    char buf[4096] = {0};
+    size_t filled_len = 0;
+    ssize_t ret;
+    while ((ret = write(pipe_wr_fd, buf, sizeof(buf))) > 0) {
+        filled_len += ret;
+    }
+    if (errno != EAGAIN) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to fill up the pipe");
+    }
+
+    // Make room for 16 bytes, so that the writev stops in the middle of the second iovec
+    const size_t room_len = 16;
+    if (read(pipe_rd_fd, buf, room_len) != room_len) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to read the pipe");
+    }
+    char first[8], second[16];
+    memset(first, 'a', sizeof(first));
+    memset(second, 'b', sizeof(second));
+    struct iovec iov[2] = {
+        { .iov_base = first, .iov_len = sizeof(first) },
+        { .iov_base = second, .iov_len = sizeof(second) },
+    };
+    if (writev(pipe_wr_fd, iov, 2) != room_len) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("writev is expected to return the number of bytes written so far");
+    }
+    if (writev(pipe_wr_fd, iov, 2) != -1 || errno != EAGAIN) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("writev to a full pipe is expected to fail with EAGAIN");
+    }
+
+    // Drain the filling data and check the data written by writev
+    size_t drain_len = filled_len - room_len;
+    while (drain_len > 0) {
+        size_t len = drain_len < sizeof(buf) ? drain_len : sizeof(buf);
+        if ((ret = read(pipe_rd_fd, buf, len)) <= 0) {
+            free_pipe(pipe_fds);
+            THROW_ERROR("failed to drain the pipe");
+        }
+        drain_len -= ret;
+    }
+    if (read(pipe_rd_fd, buf, sizeof(buf)) != room_len ||
+            strncmp(buf, "aaaaaaaabbbbbbbb", room_len) != 0) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("the data written by writev is not as expected");
+    }
+
+    // Resume the writing from where it stopped
+    size_t written_in_second = room_len - sizeof(first);
+    iov[0].iov_base = second + written_in_second;
+    iov[0].iov_len = sizeof(second) - written_in_second;
+    if (writev(pipe_wr_fd, iov, 1) != iov[0].iov_len) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to resume the writev");
+    }
+    if (read(pipe_rd_fd, buf, sizeof(buf)) != iov[0].iov_len ||
+            strncmp(buf, "bbbbbbbb", iov[0].iov_len) != 0) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("the data of the resumed writev is not as expected");
+    }
+
+    free_pipe(pipe_fds);
+    return 0;
+}
+
+int test_readv_partially() {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    int pipe_rd_fd = pipe_fds[0];
+    int pipe_wr_fd = pipe_fds[1];
+
+    const char *msg = "0123456789";
+    size_t msg_len = strlen(msg);
+    if (write(pipe_wr_fd, msg, msg_len) != msg_len) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to write the pipe");
+    }
+    close(pipe_wr_fd);
+
+    // The data runs out in the middle of the second iovec
+    char first[6] = {0}, second[6] = {0};
+    struct iovec iov[2] = {
+        { .iov_base = first, .iov_len = sizeof(first) },
+        { .iov_base = second, .iov_len = sizeof(second) },
+    };
+    if (readv(pipe_rd_fd, iov, 2) != msg_len) {
+        close(pipe_rd_fd);
+        THROW_ERROR("readv is expected to return the number of bytes read so far");
+    }
+    if (strncmp(first, "012345", sizeof(first)) != 0 ||
+            strncmp(second, "6789", msg_len - sizeof(first)) != 0 ||
+            second[msg_len - sizeof(first)] != 0) {
+        close(pipe_rd_fd);
+        THROW_ERROR("the data read by readv is not as expected");
+    }
+
+    // Reach EOF
+    if (readv(pipe_rd_fd, iov, 2) != 0) {
+        close(pipe_rd_fd);
+        THROW_ERROR("readv is expected to return 0 at EOF");
+    }
+
+    close(pipe_rd_fd);
+    return 0;
+}
+
+int test_splice_file_to_pipe() {
+    const char *file_path = "/root/test_pipe_splice_file_to_pipe.txt";
+    const char *msg = "0123456789";
+    size_t msg_len = strlen(msg);
+    int file_fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    if (file_fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (write(file_fd, msg, msg_len) != msg_len) {
+        close(file_fd);
+        THROW_ERROR("failed to write the file");
+    }
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        close(file_fd);
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    // With an offset, which is updated but the file offset is not
+    loff_t offset = 2;
+    if (splice(file_fd, &offset, pipe_fds[1], NULL, 3, 0) != 3 || offset != 5) {
+        THROW_ERROR("failed to splice from the file with an offset");
+    }
+    if (lseek(file_fd, 0, SEEK_CUR) != msg_len) {
+        THROW_ERROR("the file offset should not be changed");
+    }
+    // With the file offset, which is updated
+    lseek(file_fd, 6, SEEK_SET);
+    if (splice(file_fd, NULL, pipe_fds[1], NULL, 100, 0) != 4) {
+        THROW_ERROR("failed to splice from the file with the file offset");
+    }
+    if (lseek(file_fd, 0, SEEK_CUR) != msg_len) {
+        THROW_ERROR("the file offset should be updated");
+    }
+    // At EOF
+    if (splice(file_fd, NULL, pipe_fds[1], NULL, 100, 0) != 0) {
+        THROW_ERROR("splice is expected to return 0 at EOF");
+    }
+
+    char buf[16] = {0};
+    if (read(pipe_fds[0], buf, sizeof(buf)) != 7 || strcmp(buf, "2346789") != 0) {
+        THROW_ERROR("the data spliced into the pipe is not as expected");
+    }
+
+    close(file_fd);
+    free_pipe(pipe_fds);
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+int test_splice_pipe_to_file() {
+    const char *file_path = "/root/test_pipe_splice_pipe_to_file.txt";
+    const char *msg = "0123456789";
+    size_t msg_len = strlen(msg);
+    int file_fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0644);
+    if (file_fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        close(file_fd);
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    // Without data in the pipe
+    if (splice(pipe_fds[0], NULL, file_fd, NULL, 100, SPLICE_F_NONBLOCK) != -1 ||
+            errno != EAGAIN) {
+        THROW_ERROR("splice is expected to fail with EAGAIN");
+    }
+    // An offset cannot be given to a pipe
+    loff_t offset = 0;
+    if (splice(pipe_fds[0], &offset, file_fd, NULL, 100, 0) != -1 || errno != ESPIPE) {
+        THROW_ERROR("splice is expected to fail with ESPIPE");
+    }
+
+    if (write(pipe_fds[1], msg, msg_len) != msg_len) {
+        THROW_ERROR("failed to write the pipe");
+    }
+    // With an offset, which is updated but the file offset is not
+    offset = 4;
+    if (splice(pipe_fds[0], NULL, file_fd, &offset, 6, 0) != 6 || offset != 10) {
+        THROW_ERROR("failed to splice to the file with an offset");
+    }
+    if (lseek(file_fd, 0, SEEK_CUR) != 0) {
+        THROW_ERROR("the file offset should not be changed");
+    }
+    // With the file offset, which is updated
+    if (splice(pipe_fds[0], NULL, file_fd, NULL, 100, 0) != 4) {
+        THROW_ERROR("failed to splice to the file with the file offset");
+    }
+    if (lseek(file_fd, 0, SEEK_CUR) != 4) {
+        THROW_ERROR("the file offset should be updated");
+    }
+    // At EOF
+    close(pipe_fds[1]);
+    if (splice(pipe_fds[0], NULL, file_fd, NULL, 100, 0) != 0) {
+        THROW_ERROR("splice is expected to return 0 at EOF");
+    }
+
+    char buf[16] = {0};
+    if (pread(file_fd, buf, sizeof(buf), 0) != msg_len || strcmp(buf, "6789012345") != 0) {
+        THROW_ERROR("the data spliced into the file is not as expected");
+    }
+
+    close(file_fd);
+    close(pipe_fds[0]);
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+int test_set_pipe_size() {
+    int pipe_fds[2];
+    if (pipe2(pipe_fds, O_NONBLOCK) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    int page_size = getpagesize();
+
+    // The size is rounded up to a power-of-two multiple of the page size
+    if (fcntl(pipe_fds[1], F_SETPIPE_SZ, 1) != page_size ||
+            fcntl(pipe_fds[0], F_GETPIPE_SZ) != page_size) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to shrink the pipe to one page");
+    }
+    char *buf = malloc(16 * page_size);
+    if (buf == NULL) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to malloc the buffer");
+    }
+    for (int i = 0; i < 16 * page_size; i++) {
+        buf[i] = (char)i;
+    }
+    if (write(pipe_fds[1], buf, 2 * page_size) != page_size) {
+        THROW_ERROR("the pipe is expected to be full after one page");
+    }
+
+    // The buffered data are kept after growing the pipe
+    int new_size = fcntl(pipe_fds[0], F_SETPIPE_SZ, 3 * page_size);
+    if (new_size != 4 * page_size || fcntl(pipe_fds[1], F_GETPIPE_SZ) != new_size) {
+        THROW_ERROR("failed to grow the pipe");
+    }
+    if (write(pipe_fds[1], buf + page_size, 3 * page_size) != 3 * page_size) {
+        THROW_ERROR("failed to write more than the old size of the pipe");
+    }
+    // Cannot shrink the pipe to be smaller than the buffered data
+    if (fcntl(pipe_fds[1], F_SETPIPE_SZ, page_size) != -1 || errno != EBUSY) {
+        THROW_ERROR("shrinking the pipe is expected to fail with EBUSY");
+    }
+
+    char *read_buf = calloc(16, page_size);
+    if (read_buf == NULL) {
+        THROW_ERROR("failed to malloc the buffer");
+    }
+    if (read(pipe_fds[0], read_buf, 16 * page_size) != 4 * page_size ||
+            memcmp(buf, read_buf, 4 * page_size) != 0) {
+        THROW_ERROR("the data read from the pipe is not as expected");
+    }
+
+    free(read_buf);
+    free(buf);
+    free_pipe(pipe_fds);
+    return 0;
+}
+
+#define DEFAULT_PIPE_SIZE (1024 * 1024)
+#define MAX_PIPE_SIZE (16 * DEFAULT_PIPE_SIZE)
+
+int test_set_pipe_size_limit() {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    // A pipe can grow beyond its default size, up to the max
+    if (fcntl(pipe_fds[1], F_SETPIPE_SZ, 2 * DEFAULT_PIPE_SIZE) != 2 * DEFAULT_PIPE_SIZE) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to grow the pipe beyond the default size");
+    }
+    if (fcntl(pipe_fds[1], F_SETPIPE_SZ, MAX_PIPE_SIZE) != MAX_PIPE_SIZE) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to grow the pipe to the max size");
+    }
+
+    // A size beyond the max is not permitted and leaves the pipe as it is
+    if (fcntl(pipe_fds[1], F_SETPIPE_SZ, MAX_PIPE_SIZE + 1) != -1 || errno != EPERM) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("growing the pipe beyond the max is expected to fail with EPERM");
+    }
+    if (fcntl(pipe_fds[0], F_GETPIPE_SZ) != MAX_PIPE_SIZE) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("the pipe size is changed by a failed F_SETPIPE_SZ");
+    }
+
+    free_pipe(pipe_fds);
+    return 0;
+}
+
 // ============================================================================
 // Test suite
 // ============================================================================
@@ -401,6 +721,12 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_epoll_no_timeout),
     TEST_CASE(test_select_read_write),
     TEST_CASE(test_ioctl_fionread),
+    TEST_CASE(test_writev_partially),
+    TEST_CASE(test_readv_partially),
+    TEST_CASE(test_splice_file_to_pipe),
+    TEST_CASE(test_splice_pipe_to_file),
+    TEST_CASE(test_set_pipe_size),
+    TEST_CASE(test_set_pipe_size_limit),
 };
 
 int main(int argc, const char *argv[]) {
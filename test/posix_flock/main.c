@@ -25,6 +25,13 @@ const char *child_argv[3] = {
     "child",
     NULL
 };
+const int unlocked_child_argc = 3;
+const char *unlocked_child_argv[4] = {
+    "posix_flock",
+    "child",
+    "unlocked",
+    NULL
+};
 
 static int open_or_create_file() {
     int flags = O_RDWR | O_CREAT;
@@ -122,6 +129,36 @@ static int test_spawn_child_and_unlock() {
     return 0;
 }
 
+static int test_close_and_unlock() {
+    int fd = open(g_file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file");
+    }
+    struct flock fl = { F_WRLCK, SEEK_SET, 0, 0, 0 };
+    if (fcntl(fd, F_SETLK, &fl) < 0) {
+        THROW_ERROR("failed to lock the whole file");
+    }
+    // Closing any fd of the file releases all the locks of the process on it
+    close(fd);
+
+    int status, child_pid;
+    int ret = posix_spawn(&child_pid,
+                          "/bin/posix_flock", NULL, NULL,
+                          (char *const *)unlocked_child_argv,
+                          NULL);
+    if (ret < 0) {
+        THROW_ERROR("spawn process error");
+    }
+    ret = wait4(child_pid, &status, 0, NULL);
+    if (ret < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!(WIFEXITED(status) && WEXITSTATUS(status) == 0)) {
+        THROW_ERROR("test cases in child faild");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Child Test cases
 // ============================================================================
@@ -163,6 +200,25 @@ static int test_child_setlkw() {
     return 0;
 }
 
+static int test_child_getlk_unlocked() {
+    struct flock fl = { F_WRLCK, SEEK_SET, 0, 0, 0 };
+    if (fcntl(g_fd, F_GETLK, &fl) < 0) {
+        THROW_ERROR("failed to call getlk");
+    }
+    if (fl.l_type != F_UNLCK) {
+        THROW_ERROR("the locks of the parent are expected to be released");
+    }
+    return 0;
+}
+
+static int test_child_setlk_unlocked() {
+    struct flock fl = { F_WRLCK, SEEK_SET, 0, 0, 0 };
+    if (fcntl(g_fd, F_SETLK, &fl) < 0) {
+        THROW_ERROR("failed to lock the whole file after the parent closed it");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -171,6 +227,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_getlk),
     TEST_CASE(test_setlk),
     TEST_CASE(test_spawn_child_and_unlock),
+    TEST_CASE(test_close_and_unlock),
 };
 
 static test_case_t child_test_cases[] = {
@@ -179,6 +236,11 @@ static test_case_t child_test_cases[] = {
     TEST_CASE(test_child_setlkw),
 };
 
+static test_case_t unlocked_child_test_cases[] = {
+    TEST_CASE(test_child_getlk_unlocked),
+    TEST_CASE(test_child_setlk_unlocked),
+};
+
 int main(int argc, const char *argv[]) {
     // Save argument for test cases
     g_argc = argc;
@@ -189,12 +251,17 @@ int main(int argc, const char *argv[]) {
     }
 
     // Test argc
-    if (argc == 2) {
+    if (argc == child_argc) {
         if (test_suite_run(child_test_cases, ARRAY_SIZE(child_test_cases)) < 0) {
             THROW_ERROR("failed run child test");
         }
         // Donot close file intentionally to unlock the lock on exit
         // close(g_fd);
+    } else if (argc == unlocked_child_argc) {
+        if (test_suite_run(unlocked_child_test_cases,
+                           ARRAY_SIZE(unlocked_child_test_cases)) < 0) {
+            THROW_ERROR("failed run child test");
+        }
     } else {
         if (test_suite_run(test_cases, ARRAY_SIZE(test_cases)) < 0) {
             THROW_ERROR("failed run test");
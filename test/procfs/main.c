@@ -1,10 +1,12 @@
 #define _GNU_SOURCE
 #include <sys/types.h>
 #include <sys/vfs.h>
+#include <sys/mman.h>
 #include <fcntl.h>
 #include <limits.h>
 #include <stdlib.h>
 #include <errno.h>
+#include <time.h>
 #include "test_fs.h"
 
 // ============================================================================
@@ -137,6 +139,60 @@ static int test_read_from_proc_self_cmdline() {
     return 0;
 }
 
+static int test_read_from_proc_pid_cmdline() {
+    char absolute_path[PATH_MAX] = { 0 };
+    char proc_cmdline[PATH_MAX] = { 0 };
+
+    if (snprintf(absolute_path, sizeof(absolute_path), "/bin/%s",
+                 program_invocation_short_name) < 0) {
+        THROW_ERROR("failed to call snprintf");
+    }
+    if (snprintf(proc_cmdline, sizeof(proc_cmdline), "/proc/%d/cmdline", getpid()) < 0) {
+        THROW_ERROR("failed to call snprintf");
+    }
+    char read_buf[PATH_MAX] = { 0 };
+    int fd = open(proc_cmdline, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open %s", proc_cmdline);
+    }
+    ssize_t len = read(fd, read_buf, sizeof(read_buf));
+    if (len != strlen(absolute_path) + 1 || strcmp(absolute_path, read_buf) != 0) {
+        THROW_ERROR("failed to check result in %s", proc_cmdline);
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_read_from_proc_self_status() {
+    const char *proc_self_status = "/proc/self/status";
+    char line[256];
+    char name[32] = { 0 };
+    int pid = -1, ppid = -1, threads = -1;
+
+    FILE *fp = fopen(proc_self_status, "r");
+    if (fp == NULL) {
+        THROW_ERROR("failed to fopen: %s", proc_self_status);
+    }
+    while (fgets(line, sizeof(line), fp) != NULL) {
+        sscanf(line, "Name: %31s", name);
+        sscanf(line, "Pid: %d", &pid);
+        sscanf(line, "PPid: %d", &ppid);
+        sscanf(line, "Threads: %d", &threads);
+    }
+    fclose(fp);
+
+    if (strncmp(name, program_invocation_short_name, 15) != 0) {
+        THROW_ERROR("failed to check the name in %s", proc_self_status);
+    }
+    if (pid != getpid() || ppid != getppid()) {
+        THROW_ERROR("failed to check the pids in %s", proc_self_status);
+    }
+    if (threads != 1) {
+        THROW_ERROR("failed to check the threads in %s", proc_self_status);
+    }
+    return 0;
+}
+
 static int test_read_from_proc_self_comm() {
     // The name can be up to 16 bytes long, including the terminating null byte.
     char comm_name[16] = { 0 };
@@ -188,6 +244,93 @@ static int test_read_from_proc_meminfo() {
     return 0;
 }
 
+static int read_proc_meminfo_available(unsigned long *available_kb) {
+    const char *proc_meminfo = "/proc/meminfo";
+    char line[256];
+    bool found = false;
+    FILE *fp = fopen(proc_meminfo, "r");
+    if (fp == NULL) {
+        THROW_ERROR("failed to fopen: %s", proc_meminfo);
+    }
+
+    while (fgets(line, sizeof(line), fp) != NULL) {
+        if (sscanf(line, "MemAvailable: %lu kB", available_kb) == 1) {
+            found = true;
+            break;
+        }
+    }
+    fclose(fp);
+
+    if (!found) {
+        THROW_ERROR("failed to parse %s", proc_meminfo);
+    }
+    return 0;
+}
+
+static int read_proc_self_statm(unsigned long *size, unsigned long *resident) {
+    const char *proc_statm = "/proc/self/statm";
+    FILE *fp = fopen(proc_statm, "r");
+    if (fp == NULL) {
+        THROW_ERROR("failed to fopen: %s", proc_statm);
+    }
+
+    int ret = fscanf(fp, "%lu %lu", size, resident);
+    fclose(fp);
+    if (ret != 2) {
+        THROW_ERROR("failed to parse %s", proc_statm);
+    }
+    return 0;
+}
+
+#define MAP_SIZE_KB     (16 * 1024)
+#define MAP_SIZE        (MAP_SIZE_KB * 1024UL)
+#define PAGE_SIZE_KB    4
+static int test_memory_usage_after_mmap() {
+    unsigned long available_before, available_after;
+    unsigned long size_before, resident_before, size_after, resident_after;
+
+    if (read_proc_meminfo_available(&available_before) < 0 ||
+            read_proc_self_statm(&size_before, &resident_before) < 0) {
+        THROW_ERROR("failed to read the memory usage");
+    }
+
+    char *buf = mmap(NULL, MAP_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1,
+                     0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("failed to mmap");
+    }
+    for (unsigned long offset = 0; offset < MAP_SIZE; offset += PAGE_SIZE_KB * 1024) {
+        buf[offset] = 1;
+    }
+
+    int ret = 0;
+    if (read_proc_meminfo_available(&available_after) < 0 ||
+            read_proc_self_statm(&size_after, &resident_after) < 0) {
+        ret = -1;
+        printf("failed to read the memory usage\n");
+        goto out;
+    }
+
+    // Other threads may map or unmap memory concurrently, so only check that
+    // the usage shifts by roughly the expected magnitude
+    unsigned long pages = MAP_SIZE_KB / PAGE_SIZE_KB;
+    if (size_after < size_before + pages * 9 / 10 ||
+            resident_after < resident_before + pages * 9 / 10) {
+        ret = -1;
+        printf("the size or resident of statm does not grow as expected\n");
+        goto out;
+    }
+    if (available_before < available_after + MAP_SIZE_KB * 9 / 10) {
+        ret = -1;
+        printf("the MemAvailable of meminfo does not drop as expected\n");
+        goto out;
+    }
+
+out:
+    munmap(buf, MAP_SIZE);
+    return ret;
+}
+
 static int test_read_from_proc_cpuinfo() {
     const char *proc_cpuinfo = "/proc/cpuinfo";
 
@@ -197,6 +340,77 @@ static int test_read_from_proc_cpuinfo() {
     return 0;
 }
 
+struct proc_stat {
+    unsigned long long user;
+    unsigned long long system;
+    unsigned long long idle;
+    unsigned long long ctxt;
+};
+
+static int read_proc_stat(struct proc_stat *stat) {
+    const char *proc_stat = "/proc/stat";
+    char line[256];
+    bool has_cpu = false, has_ctxt = false;
+    FILE *fp = fopen(proc_stat, "r");
+    if (fp == NULL) {
+        THROW_ERROR("failed to fopen: %s", proc_stat);
+    }
+
+    while (fgets(line, sizeof(line), fp) != NULL) {
+        unsigned long long nice;
+        if (sscanf(line, "cpu %llu %llu %llu %llu", &stat->user, &nice, &stat->system,
+                   &stat->idle) == 4) {
+            has_cpu = true;
+        } else if (sscanf(line, "ctxt %llu", &stat->ctxt) == 1) {
+            has_ctxt = true;
+        }
+    }
+    fclose(fp);
+
+    if (!has_cpu || !has_ctxt) {
+        THROW_ERROR("failed to parse %s", proc_stat);
+    }
+    return 0;
+}
+
+static void busy_loop_for_ms(long ms) {
+    struct timespec start, now;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    do {
+        clock_gettime(CLOCK_MONOTONIC, &now);
+    } while ((now.tv_sec - start.tv_sec) * 1000 + (now.tv_nsec - start.tv_nsec) / 1000000 < ms);
+}
+
+static int test_read_from_proc_stat() {
+    struct proc_stat before, after_busy, after_idle;
+
+    if (read_proc_stat(&before) < 0) {
+        THROW_ERROR("failed to read the stat");
+    }
+
+    // The user time should increase after running a CPU-bound period
+    busy_loop_for_ms(300);
+    if (read_proc_stat(&after_busy) < 0) {
+        THROW_ERROR("failed to read the stat");
+    }
+    if (after_busy.user <= before.user) {
+        THROW_ERROR("the user time is expected to increase after a busy loop");
+    }
+
+    // The idle time should increase after an idle period
+    usleep(300 * 1000);
+    if (read_proc_stat(&after_idle) < 0) {
+        THROW_ERROR("failed to read the stat");
+    }
+    if (after_idle.idle <= after_busy.idle) {
+        THROW_ERROR("the idle time is expected to increase after sleeping");
+    }
+    if (after_idle.ctxt <= before.ctxt) {
+        THROW_ERROR("the number of context switches is expected to increase");
+    }
+    return 0;
+}
+
 #define PROC_SUPER_MAGIC 0x9fa0
 static int test_statfs() {
     const char *file_path = "/proc/cpuinfo";
@@ -217,14 +431,15 @@ static int test_readdir_root() {
     const char *root = "/proc";
     char pid[NAME_MAX] = { 0 };
     snprintf(pid, sizeof(pid), "%d", getpid());
-    char expected_entries[4][NAME_MAX] = {
+    char expected_entries[5][NAME_MAX] = {
         "self",
         "meminfo",
         "cpuinfo",
+        "stat",
         { *pid },
     };
 
-    if (check_readdir_with_expected_entries(root, expected_entries, 4) < 0) {
+    if (check_readdir_with_expected_entries(root, expected_entries, 5) < 0) {
         THROW_ERROR("failed to test readdir %s", root);
     }
 
@@ -233,16 +448,19 @@ static int test_readdir_root() {
 
 static int test_readdir_self() {
     const char *self = "/proc/self";
-    char expected_entries[6][NAME_MAX] = {
+    char expected_entries[9][NAME_MAX] = {
         "exe",
         "cwd",
         "root",
         "fd",
         "comm",
         "cmdline",
+        "stat",
+        "statm",
+        "status",
     };
 
-    if (check_readdir_with_expected_entries(self, expected_entries, 6) < 0) {
+    if (check_readdir_with_expected_entries(self, expected_entries, 9) < 0) {
         THROW_ERROR("failed to test readdir %s", self);
     }
 
@@ -274,10 +492,14 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_readlink_from_proc_self_root),
     TEST_CASE(test_create_and_unlink_file_from_proc_self_root),
     TEST_CASE(test_read_from_proc_self_cmdline),
+    TEST_CASE(test_read_from_proc_pid_cmdline),
+    TEST_CASE(test_read_from_proc_self_status),
     TEST_CASE(test_read_from_proc_self_comm),
     TEST_CASE(test_read_from_proc_self_stat),
     TEST_CASE(test_read_from_proc_meminfo),
+    TEST_CASE(test_memory_usage_after_mmap),
     TEST_CASE(test_read_from_proc_cpuinfo),
+    TEST_CASE(test_read_from_proc_stat),
     TEST_CASE(test_statfs),
     TEST_CASE(test_readdir_root),
     TEST_CASE(test_readdir_self),
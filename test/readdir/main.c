@@ -2,6 +2,7 @@
 #include <sys/stat.h>
 #include <sys/syscall.h>
 #include <stdbool.h>
+#include <stdint.h>
 #include <errno.h>
 #include <fcntl.h>
 #include "test_fs.h"
@@ -104,6 +105,82 @@ static int test_getdents_via_explicit_syscall_with_too_small_buffer() {
     return getdents_with_too_small_buffer(use_explicit_syscall);
 }
 
+struct linux_dirent64 {
+    uint64_t d_ino;
+    int64_t d_off;
+    unsigned short d_reclen;
+    unsigned char d_type;
+    char d_name[];
+};
+
+static int test_getdents64_with_d_type() {
+    const char *dir_path = "/root/test_readdir_d_type";
+    const char *file_path = "/root/test_readdir_d_type/file";
+    const char *subdir_path = "/root/test_readdir_d_type/dir";
+    const char *link_path = "/root/test_readdir_d_type/link";
+    char buf[1024];
+    int fd, len;
+
+    if (mkdir(dir_path, 00775) < 0 || mkdir(subdir_path, 00775) < 0) {
+        THROW_ERROR("failed to create the directories");
+    }
+    fd = open(file_path, O_WRONLY | O_CREAT, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the file");
+    }
+    close(fd);
+    if (symlink(file_path, link_path) < 0) {
+        THROW_ERROR("failed to create the symlink");
+    }
+
+    fd = open(dir_path, O_RDONLY | O_DIRECTORY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open directory");
+    }
+    int found = 0;
+    while ((len = syscall(__NR_getdents64, fd, buf, sizeof(buf))) > 0) {
+        for (int pos = 0; pos < len;) {
+            struct linux_dirent64 *dirent = (struct linux_dirent64 *)(buf + pos);
+            if (dirent->d_reclen % 8 != 0 || pos + dirent->d_reclen > len) {
+                close(fd);
+                THROW_ERROR("the d_reclen is not correct");
+            }
+            unsigned char expected_type;
+            if (strcmp(dirent->d_name, "file") == 0) {
+                expected_type = DT_REG;
+            } else if (strcmp(dirent->d_name, "dir") == 0 ||
+                       strcmp(dirent->d_name, ".") == 0 ||
+                       strcmp(dirent->d_name, "..") == 0) {
+                expected_type = DT_DIR;
+            } else if (strcmp(dirent->d_name, "link") == 0) {
+                expected_type = DT_LNK;
+            } else {
+                close(fd);
+                THROW_ERROR("unexpected entry in the directory");
+            }
+            if (dirent->d_type != expected_type) {
+                close(fd);
+                THROW_ERROR("the d_type of %s is not correct", dirent->d_name);
+            }
+            found++;
+            pos += dirent->d_reclen;
+        }
+    }
+    close(fd);
+    if (len < 0) {
+        THROW_ERROR("failed to call getdents64");
+    }
+    if (found != 5) {
+        THROW_ERROR("failed to find all the entries");
+    }
+
+    if (unlink(link_path) < 0 || unlink(file_path) < 0 || rmdir(subdir_path) < 0 ||
+            rmdir(dir_path) < 0) {
+        THROW_ERROR("failed to remove the entries");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -118,6 +195,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_getdents_with_too_small_buffer),
 #endif
     TEST_CASE(test_getdents_via_explicit_syscall_with_too_small_buffer),
+    TEST_CASE(test_getdents64_with_d_type),
 };
 
 int main() {
@@ -1,4 +1,5 @@
 #include <sys/stat.h>
+#include <sys/syscall.h>
 #include <errno.h>
 #include <fcntl.h>
 #include <stdbool.h>
@@ -9,6 +10,14 @@
 // ============================================================================
 
 #define WRITE_MSG "Hello World"
+#define EXCHANGE_MSG "Goodbye World"
+
+#ifndef RENAME_NOREPLACE
+#define RENAME_NOREPLACE (1 << 0)
+#endif
+#ifndef RENAME_EXCHANGE
+#define RENAME_EXCHANGE (1 << 1)
+#endif
 
 static int create_file_with_content(const char *file_path, const char *msg) {
     int fd;
@@ -99,6 +108,52 @@ static int __test_renameat(const char *old_path, const char *new_path) {
     return 0;
 }
 
+static int __test_renameat2_with_noreplace(const char *old_path, const char *new_path) {
+    int ret = syscall(SYS_renameat2, AT_FDCWD, old_path, AT_FDCWD, new_path, RENAME_NOREPLACE);
+    if (!(ret < 0 && errno == EEXIST)) {
+        THROW_ERROR("renameat2 with RENAME_NOREPLACE should return EEXIST");
+    }
+    // Both files are untouched
+    if (fs_check_file_content(old_path, WRITE_MSG) < 0) {
+        THROW_ERROR("failed to check the content of the old file");
+    }
+    if (fs_check_file_content(new_path, "") < 0) {
+        THROW_ERROR("failed to check the content of the new file");
+    }
+
+    ret = syscall(SYS_renameat2, AT_FDCWD, old_path, AT_FDCWD, new_path,
+                  RENAME_NOREPLACE | RENAME_EXCHANGE);
+    if (!(ret < 0 && errno == EINVAL)) {
+        THROW_ERROR("renameat2 with conflicting flags should return EINVAL");
+    }
+
+    if (unlink(old_path) < 0 || unlink(new_path) < 0) {
+        THROW_ERROR("failed to remove the files");
+    }
+    return 0;
+}
+
+static int __test_renameat2_with_exchange(const char *old_path, const char *new_path) {
+    if (create_file_with_content(new_path, EXCHANGE_MSG) < 0) {
+        THROW_ERROR("failed to write the new file");
+    }
+
+    if (syscall(SYS_renameat2, AT_FDCWD, old_path, AT_FDCWD, new_path, RENAME_EXCHANGE) < 0) {
+        THROW_ERROR("failed to renameat2 with RENAME_EXCHANGE");
+    }
+    if (fs_check_file_content(old_path, EXCHANGE_MSG) < 0) {
+        THROW_ERROR("failed to check the content of the old path");
+    }
+    if (fs_check_file_content(new_path, WRITE_MSG) < 0) {
+        THROW_ERROR("failed to check the content of the new path");
+    }
+
+    if (unlink(old_path) < 0 || unlink(new_path) < 0) {
+        THROW_ERROR("failed to remove the files");
+    }
+    return 0;
+}
+
 typedef int(*test_rename_func_t)(const char *, const char *);
 
 static int test_rename_framework(test_rename_func_t fn, bool target_exist) {
@@ -131,6 +186,14 @@ static int test_renameat() {
     return test_rename_framework(__test_renameat, false);
 }
 
+static int test_renameat2_with_noreplace() {
+    return test_rename_framework(__test_renameat2_with_noreplace, true);
+}
+
+static int test_renameat2_with_exchange() {
+    return test_rename_framework(__test_renameat2_with_exchange, true);
+}
+
 static int test_rename_dir() {
     const char *old_dir = "/root/test_old_dir";
     const char *new_dir = "/root/test_new_dir";
@@ -212,6 +275,8 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_rename),
     TEST_CASE(test_rename_with_target_exist),
     TEST_CASE(test_renameat),
+    TEST_CASE(test_renameat2_with_noreplace),
+    TEST_CASE(test_renameat2_with_exchange),
     TEST_CASE(test_rename_dir),
     TEST_CASE(test_rename_dir_to_subdir),
 };
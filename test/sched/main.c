@@ -356,6 +356,42 @@ static int test_set_get_priority_user() {
     return 0;
 }
 
+static int test_set_priority_beyond_rlimit_nice() {
+    int which = PRIO_PROCESS;
+    id_t who = getpid();
+    struct rlimit old_rlim;
+    if (getrlimit(RLIMIT_NICE, &old_rlim) < 0) {
+        THROW_ERROR("failed to getrlimit");
+    }
+
+    // Limit the nice values to -10 or higher, i.e., out of the high priority band
+    struct rlimit new_rlim = { .rlim_cur = 30, .rlim_max = old_rlim.rlim_max };
+    if (setrlimit(RLIMIT_NICE, &new_rlim) < 0) {
+        THROW_ERROR("failed to setrlimit");
+    }
+    int prio = -11;
+    if (setpriority(which, who, prio) == 0 || errno != EACCES) {
+        THROW_ERROR("setpriority beyond RLIMIT_NICE should fail with EACCES");
+    }
+
+    // Lowering the priority is always allowed
+    if (test_set_get_priority(which, who, 5) < 0) {
+        THROW_ERROR("failed to lower prio");
+    }
+
+    // The default limit allows any nice value
+    if (setrlimit(RLIMIT_NICE, &old_rlim) < 0) {
+        THROW_ERROR("failed to recover rlimit");
+    }
+    if (test_set_get_priority(which, who, -20) < 0) {
+        THROW_ERROR("failed to setpriority within RLIMIT_NICE");
+    }
+    if (test_set_get_priority(which, who, 0) < 0) {
+        THROW_ERROR("failed to recover prio");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -377,6 +413,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_set_get_priority_process),
     TEST_CASE(test_set_get_priority_pgrp),
     TEST_CASE(test_set_get_priority_user),
+    TEST_CASE(test_set_priority_beyond_rlimit_nice),
 };
 
 int main() {
@@ -890,6 +890,109 @@ int test_epoll_wait() {
     return 0;
 }
 
+#define CLOSE_AFTER_WRITE_PORT  8808
+#define CLOSE_AFTER_WRITE_LEN   (1024 * 1024)
+
+// Run by the child process, which exits right after closing the socket
+static int write_and_close(int port) {
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (fd < 0) {
+        THROW_ERROR("create socket error");
+    }
+    struct sockaddr_in servaddr;
+    memset(&servaddr, 0, sizeof(servaddr));
+    servaddr.sin_family = AF_INET;
+    servaddr.sin_addr.s_addr = inet_addr("127.0.0.1");
+    servaddr.sin_port = htons(port);
+    if (connect(fd, (struct sockaddr *)&servaddr, sizeof(servaddr)) < 0) {
+        close(fd);
+        THROW_ERROR("connect error");
+    }
+
+    char buf[4096];
+    size_t written_len = 0;
+    while (written_len < CLOSE_AFTER_WRITE_LEN) {
+        for (int i = 0; i < sizeof(buf); i++) {
+            buf[i] = (char)((written_len + i) % 251);
+        }
+        ssize_t ret = write(fd, buf, sizeof(buf));
+        if (ret <= 0) {
+            close(fd);
+            THROW_ERROR("write error");
+        }
+        written_len += ret;
+    }
+
+    if (close(fd) < 0) {
+        THROW_ERROR("close error");
+    }
+    return 0;
+}
+
+int test_close_after_write() {
+    int listen_fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (listen_fd < 0) {
+        THROW_ERROR("create socket error");
+    }
+    int reuse = 1;
+    if (setsockopt(listen_fd, SOL_SOCKET, SO_REUSEADDR, &reuse, sizeof(reuse)) < 0) {
+        close(listen_fd);
+        THROW_ERROR("setsockopt port to reuse failed");
+    }
+    struct sockaddr_in servaddr;
+    memset(&servaddr, 0, sizeof(servaddr));
+    servaddr.sin_family = AF_INET;
+    servaddr.sin_addr.s_addr = htonl(INADDR_ANY);
+    servaddr.sin_port = htons(CLOSE_AFTER_WRITE_PORT);
+    if (bind(listen_fd, (struct sockaddr *)&servaddr, sizeof(servaddr)) < 0 ||
+            listen(listen_fd, 10) < 0) {
+        close(listen_fd);
+        THROW_ERROR("bind or listen socket error");
+    }
+
+    int child_pid;
+    char port_string[8];
+    sprintf(port_string, "%d", CLOSE_AFTER_WRITE_PORT);
+    char *child_argv[] = {"server", "write_and_close", port_string, NULL};
+    if (posix_spawn(&child_pid, "/bin/server", NULL, NULL, child_argv, NULL) < 0) {
+        close(listen_fd);
+        THROW_ERROR("spawn child process error");
+    }
+
+    int connected_fd = accept(listen_fd, (struct sockaddr *)NULL, NULL);
+    close(listen_fd);
+    if (connected_fd < 0) {
+        THROW_ERROR("accept socket error");
+    }
+
+    // The peer should receive all bytes even though the child exits right after closing
+    char buf[4096];
+    size_t read_len = 0;
+    ssize_t ret;
+    while ((ret = read(connected_fd, buf, sizeof(buf))) > 0) {
+        for (int i = 0; i < ret; i++) {
+            if (buf[i] != (char)((read_len + i) % 251)) {
+                close(connected_fd);
+                THROW_ERROR("the received data is corrupted at offset %lu", read_len + i);
+            }
+        }
+        read_len += ret;
+    }
+    close(connected_fd);
+    if (ret < 0) {
+        THROW_ERROR("read error");
+    }
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0 || !WIFEXITED(status) ||
+            WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process fails");
+    }
+    if (read_len != CLOSE_AFTER_WRITE_LEN) {
+        THROW_ERROR("only %lu of %d bytes are received", read_len, CLOSE_AFTER_WRITE_LEN);
+    }
+    return 0;
+}
+
 static test_case_t test_cases[] = {
     TEST_CASE(test_MSG_WAITALL),
     TEST_CASE(test_read_write),
@@ -908,8 +1011,12 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_getname_without_bind),
     TEST_CASE(test_shutdown),
     TEST_CASE(test_epoll_wait),
+    TEST_CASE(test_close_after_write),
 };
 
 int main(int argc, const char *argv[]) {
+    if (argc == 3 && strcmp(argv[1], "write_and_close") == 0) {
+        return write_and_close(atoi(argv[2])) < 0 ? -1 : 0;
+    }
     return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
 }
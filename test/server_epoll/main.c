@@ -2,6 +2,7 @@
 #include <errno.h>
 #include <fcntl.h>
 #include <netdb.h>
+#include <pthread.h>
 #include <stdio.h>
 #include <stdlib.h>
 #include <spawn.h>
@@ -20,8 +21,11 @@
 #define DEFAULT_MSG "Hello World!\n"
 // The recv buf length should be longer than that of DEFAULT_MSG
 #define RECV_BUF_LENGTH 32
+#define EXCLUSIVE_PORT 6668
+#define EXCLUSIVE_WORKER_NUM 4
+#define EXCLUSIVE_CONN_NUM 32
 
-static int create_and_bind() {
+static int create_and_bind_port(int port) {
     int listenfd = socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0);
     if (listenfd < 0) {
         printf("create socket error: %s(errno: %d)\n", strerror(errno), errno);
@@ -31,7 +35,7 @@ static int create_and_bind() {
     struct sockaddr_in servaddr = {0};
     servaddr.sin_family = AF_INET;
     servaddr.sin_addr.s_addr = htonl(INADDR_ANY);
-    servaddr.sin_port = htons(6667);
+    servaddr.sin_port = htons(port);
 
     int reuse = 1;
     if (setsockopt(listenfd, SOL_SOCKET, SO_REUSEADDR, &reuse, sizeof(reuse)) < 0) {
@@ -46,6 +50,10 @@ static int create_and_bind() {
     return listenfd;
 }
 
+static int create_and_bind() {
+    return create_and_bind_port(6667);
+}
+
 int test_ip_socket() {
     int ret = 0;
     int server_fd = create_and_bind();
@@ -172,8 +180,210 @@ int test_ip_socket() {
     return 0;
 }
 
+// ============================================================================
+// Test cases for EPOLLEXCLUSIVE
+// ============================================================================
+
+static int listen_fd_shared = -1;
+static int num_accepted = 0;
+
+struct exclusive_worker {
+    pthread_t thread;
+    int epfd;
+    int num_wakeups;
+    int num_spurious_wakeups;
+    int num_accepted;
+};
+
+static void *exclusive_worker_routine(void *arg) {
+    struct exclusive_worker *worker = (struct exclusive_worker *)arg;
+
+    while (__atomic_load_n(&num_accepted, __ATOMIC_SEQ_CST) < EXCLUSIVE_CONN_NUM) {
+        struct epoll_event event = {0};
+        // Stop if no connection arrives in a while
+        int nfds = epoll_wait(worker->epfd, &event, 1, 1000);
+        if (nfds <= 0) {
+            break;
+        }
+        worker->num_wakeups++;
+
+        int conn_fd = accept4(listen_fd_shared, NULL, NULL, SOCK_NONBLOCK);
+        if (conn_fd < 0) {
+            if (errno != EAGAIN && errno != EWOULDBLOCK) {
+                break;
+            }
+            worker->num_spurious_wakeups++;
+            continue;
+        }
+        worker->num_accepted++;
+        __atomic_add_fetch(&num_accepted, 1, __ATOMIC_SEQ_CST);
+        close(conn_fd);
+
+        // Pretend to be busy so that the other workers can take their turns
+        usleep(1000);
+    }
+    return NULL;
+}
+
+static int connect_to_port(int port) {
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (fd < 0) {
+        return -1;
+    }
+
+    struct sockaddr_in servaddr = {0};
+    servaddr.sin_family = AF_INET;
+    servaddr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    servaddr.sin_port = htons(port);
+    if (connect(fd, (struct sockaddr *)&servaddr, sizeof(servaddr)) < 0) {
+        close(fd);
+        return -1;
+    }
+    return fd;
+}
+
+int test_epoll_exclusive_invalid_usages() {
+    int ret = 0;
+    int server_fd = create_and_bind_port(EXCLUSIVE_PORT);
+    if (server_fd < 0) {
+        THROW_ERROR("failed to create the server socket");
+    }
+    int epfd = epoll_create1(0);
+    int inner_epfd = epoll_create1(0);
+    if (epfd < 0 || inner_epfd < 0) {
+        close(server_fd);
+        THROW_ERROR("epoll_create failed");
+    }
+
+    struct epoll_event event = {0};
+    event.data.fd = server_fd;
+    event.events = EPOLLIN | EPOLLEXCLUSIVE | EPOLLONESHOT;
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, server_fd, &event) == 0 || errno != EINVAL) {
+        ret = -1;
+        printf("EPOLLEXCLUSIVE with EPOLLONESHOT should be rejected\n");
+        goto out;
+    }
+
+    event.events = EPOLLIN | EPOLLEXCLUSIVE;
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, inner_epfd, &event) == 0 || errno != EINVAL) {
+        ret = -1;
+        printf("EPOLLEXCLUSIVE on an epoll fd should be rejected\n");
+        goto out;
+    }
+
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, server_fd, &event) < 0) {
+        ret = -1;
+        printf("failed to add the server socket exclusively\n");
+        goto out;
+    }
+    if (epoll_ctl(epfd, EPOLL_CTL_MOD, server_fd, &event) == 0 || errno != EINVAL) {
+        ret = -1;
+        printf("EPOLL_CTL_MOD with EPOLLEXCLUSIVE should be rejected\n");
+        goto out;
+    }
+    event.events = EPOLLIN;
+    if (epoll_ctl(epfd, EPOLL_CTL_MOD, server_fd, &event) == 0 || errno != EINVAL) {
+        ret = -1;
+        printf("EPOLL_CTL_MOD on an exclusive entry should be rejected\n");
+        goto out;
+    }
+
+out:
+    close_files(3, server_fd, epfd, inner_epfd);
+    if (ret < 0) {
+        THROW_ERROR("invalid usages of EPOLLEXCLUSIVE are not rejected");
+    }
+    return 0;
+}
+
+int test_epoll_exclusive_accept() {
+    int ret = 0;
+    struct exclusive_worker workers[EXCLUSIVE_WORKER_NUM] = {0};
+    int client_fds[EXCLUSIVE_CONN_NUM];
+    int num_clients = 0;
+
+    listen_fd_shared = create_and_bind_port(EXCLUSIVE_PORT);
+    if (listen_fd_shared < 0) {
+        THROW_ERROR("failed to create the server socket");
+    }
+    if (listen(listen_fd_shared, EXCLUSIVE_CONN_NUM) < 0) {
+        close(listen_fd_shared);
+        THROW_ERROR("failed to listen");
+    }
+
+    // Each worker waits for the shared listening socket on its own epoll fd
+    for (int i = 0; i < EXCLUSIVE_WORKER_NUM; i++) {
+        workers[i].epfd = epoll_create1(0);
+        if (workers[i].epfd < 0) {
+            THROW_ERROR("epoll_create failed");
+        }
+        struct epoll_event event = {0};
+        event.data.fd = listen_fd_shared;
+        event.events = EPOLLIN | EPOLLEXCLUSIVE;
+        if (epoll_ctl(workers[i].epfd, EPOLL_CTL_ADD, listen_fd_shared, &event) < 0) {
+            THROW_ERROR("epoll_ctl with EPOLLEXCLUSIVE failed");
+        }
+        if (pthread_create(&workers[i].thread, NULL, exclusive_worker_routine,
+                           &workers[i]) != 0) {
+            THROW_ERROR("failed to create a worker thread");
+        }
+    }
+
+    // Make sure that all the workers are waiting
+    usleep(100 * 1000);
+
+    // A burst of connections
+    for (; num_clients < EXCLUSIVE_CONN_NUM; num_clients++) {
+        client_fds[num_clients] = connect_to_port(EXCLUSIVE_PORT);
+        if (client_fds[num_clients] < 0) {
+            ret = -1;
+            printf("failed to connect to the server\n");
+            break;
+        }
+    }
+
+    int total_wakeups = 0;
+    int total_spurious_wakeups = 0;
+    int num_busy_workers = 0;
+    for (int i = 0; i < EXCLUSIVE_WORKER_NUM; i++) {
+        pthread_join(workers[i].thread, NULL);
+        close(workers[i].epfd);
+
+        total_wakeups += workers[i].num_wakeups;
+        total_spurious_wakeups += workers[i].num_spurious_wakeups;
+        if (workers[i].num_accepted > 0) {
+            num_busy_workers++;
+        }
+    }
+    for (int i = 0; i < num_clients; i++) {
+        close(client_fds[i]);
+    }
+    close(listen_fd_shared);
+
+    if (ret < 0) {
+        THROW_ERROR("failed to make the connections");
+    }
+    if (num_accepted != EXCLUSIVE_CONN_NUM) {
+        THROW_ERROR("only %d of %d connections are accepted", num_accepted,
+                    EXCLUSIVE_CONN_NUM);
+    }
+    // Without EPOLLEXCLUSIVE, every connection would wake up all the workers
+    if (total_spurious_wakeups >= EXCLUSIVE_CONN_NUM) {
+        THROW_ERROR("too many spurious wakeups: %d of %d", total_spurious_wakeups,
+                    total_wakeups);
+    }
+    // The connections should be distributed among the workers
+    if (num_busy_workers < 2) {
+        THROW_ERROR("the connections are accepted by only %d worker(s)",
+                    num_busy_workers);
+    }
+    return 0;
+}
+
 static test_case_t test_cases[] = {
     TEST_CASE(test_ip_socket),
+    TEST_CASE(test_epoll_exclusive_invalid_usages),
+    TEST_CASE(test_epoll_exclusive_accept),
 };
 
 int main(int argc, const char *argv[]) {
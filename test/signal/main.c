@@ -16,6 +16,7 @@
 #include <pthread.h>
 #include <errno.h>
 #include <time.h>
+#include <sys/syscall.h>
 #include "test.h"
 
 // ============================================================================
@@ -514,6 +515,236 @@ int test_sigtimedwait() {
     return 0;
 }
 
+// ============================================================================
+// Test tgkill, which sends a signal to a specific thread
+// ============================================================================
+
+#define NUM_TGKILL_THREADS 3
+
+static volatile pid_t tgkill_thread_tids[NUM_TGKILL_THREADS] = { 0 };
+static volatile int tgkill_thread_interrupted[NUM_TGKILL_THREADS] = { 0 };
+static volatile pid_t tgkill_handler_tid = 0;
+
+static void tgkill_handler(int signum) {
+    tgkill_handler_tid = syscall(SYS_gettid);
+}
+
+static void *tgkill_thread_func(void *arg) {
+    int idx = (int)(long)arg;
+    tgkill_thread_tids[idx] = syscall(SYS_gettid);
+
+    // Only a signal directed to this thread is expected to interrupt the sleep
+    unsigned int a_year_in_sec = 365 * 24 * 60 * 60;
+    sleep(a_year_in_sec);
+    tgkill_thread_interrupted[idx] = 1;
+    return NULL;
+}
+
+int test_tgkill() {
+    struct sigaction new_action, old_action;
+    pthread_t threads[NUM_TGKILL_THREADS];
+    const int target = 1;
+
+    memset(&new_action, 0, sizeof(struct sigaction));
+    new_action.sa_handler = tgkill_handler;
+    if (sigaction(SIGUSR1, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+
+    for (int i = 0; i < NUM_TGKILL_THREADS; i++) {
+        if (pthread_create(&threads[i], NULL, tgkill_thread_func, (void *)(long)i) < 0) {
+            THROW_ERROR("pthread_create failed unexpectedly");
+        }
+    }
+
+    // Wait until all child threads are running
+    for (int i = 0; i < NUM_TGKILL_THREADS; i++) {
+        while (tgkill_thread_tids[i] == 0) {
+            usleep(1000);
+        }
+    }
+    usleep(10 * 1000);
+
+    // The tid must belong to the tgid
+    int ret = syscall(SYS_tgkill, getpid() + 10000, tgkill_thread_tids[target], SIGUSR1);
+    if (ret == 0 || errno != ESRCH) {
+        THROW_ERROR("tgkill with a mismatched tgid must fail with ESRCH");
+    }
+
+    // Only the target thread should handle the signal and be interrupted
+    if (syscall(SYS_tgkill, getpid(), tgkill_thread_tids[target], SIGUSR1) < 0) {
+        THROW_ERROR("tgkill failed unexpectedly");
+    }
+    if (pthread_join(threads[target], NULL) != 0) {
+        THROW_ERROR("failed to join the thread");
+    }
+    if (tgkill_handler_tid != tgkill_thread_tids[target]) {
+        THROW_ERROR("the signal is handled by a thread other than the target");
+    }
+    for (int i = 0; i < NUM_TGKILL_THREADS; i++) {
+        if (i != target && tgkill_thread_interrupted[i]) {
+            THROW_ERROR("a non-target thread is interrupted");
+        }
+    }
+
+    // Wake up the remaining threads with tkill
+    for (int i = 0; i < NUM_TGKILL_THREADS; i++) {
+        if (i == target) {
+            continue;
+        }
+        if (syscall(SYS_tkill, tgkill_thread_tids[i], SIGUSR1) < 0) {
+            THROW_ERROR("tkill failed unexpectedly");
+        }
+        if (pthread_join(threads[i], NULL) != 0) {
+            THROW_ERROR("failed to join the thread");
+        }
+        if (tgkill_handler_tid != tgkill_thread_tids[i]) {
+            THROW_ERROR("the signal is handled by a thread other than the target");
+        }
+    }
+
+    if (sigaction(SIGUSR1, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test sigqueue, which sends real-time signals with payloads
+// ============================================================================
+
+#define NUM_QUEUED_SIGNALS 3
+
+static volatile int sigqueue_received_values[NUM_QUEUED_SIGNALS] = { 0 };
+static volatile int sigqueue_received_codes[NUM_QUEUED_SIGNALS] = { 0 };
+static volatile int sigqueue_received_count = 0;
+
+static void sigqueue_handler(int signum, siginfo_t *info, void *ucontext) {
+    int i = sigqueue_received_count;
+    if (i < NUM_QUEUED_SIGNALS) {
+        sigqueue_received_values[i] = info->si_value.sival_int;
+        sigqueue_received_codes[i] = info->si_code;
+    }
+    sigqueue_received_count++;
+}
+
+int test_sigqueue() {
+    struct sigaction new_action, old_action;
+    sigset_t new_mask, old_mask;
+    int sig = SIGRTMIN;
+
+    memset(&new_action, 0, sizeof(struct sigaction));
+    new_action.sa_sigaction = sigqueue_handler;
+    new_action.sa_flags = SA_SIGINFO;
+    if (sigaction(sig, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+
+    // Block the signal so that all the signals are queued before delivery
+    sigemptyset(&new_mask);
+    sigaddset(&new_mask, sig);
+    if (sigprocmask(SIG_BLOCK, &new_mask, &old_mask) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    for (int i = 0; i < NUM_QUEUED_SIGNALS; i++) {
+        union sigval value;
+        value.sival_int = 100 + i;
+        if (sigqueue(getpid(), sig, value) < 0) {
+            THROW_ERROR("sigqueue failed unexpectedly");
+        }
+    }
+    if (sigqueue_received_count != 0) {
+        THROW_ERROR("blocked signals must not be delivered");
+    }
+
+    // Real-time signals are queued, not merged. So all of them are expected
+    // to be delivered in order after being unblocked.
+    if (sigprocmask(SIG_SETMASK, &old_mask, NULL) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    if (sigqueue_received_count != NUM_QUEUED_SIGNALS) {
+        THROW_ERROR("all queued signals are expected to be delivered");
+    }
+    for (int i = 0; i < NUM_QUEUED_SIGNALS; i++) {
+        if (sigqueue_received_values[i] != 100 + i) {
+            THROW_ERROR("the payload of the signal is not received in order");
+        }
+        if (sigqueue_received_codes[i] != SI_QUEUE) {
+            THROW_ERROR("the si_code of the signal is expected to be SI_QUEUE");
+        }
+    }
+
+    if (sigaction(sig, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test sigsuspend
+// ============================================================================
+
+static volatile int sigsuspend_handled_count = 0;
+
+static void sigsuspend_handler(int signum) {
+    sigsuspend_handled_count++;
+}
+
+int test_sigsuspend() {
+    struct sigaction new_action, old_action;
+    sigset_t new_mask, old_mask, suspend_mask, curr_mask;
+    struct timespec delay;
+
+    memset(&new_action, 0, sizeof(struct sigaction));
+    new_action.sa_handler = sigsuspend_handler;
+    if (sigaction(SIGUSR2, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+
+    // Block SIGUSR2 so that it can only be delivered during sigsuspend
+    sigemptyset(&new_mask);
+    sigaddset(&new_mask, SIGUSR2);
+    if (sigprocmask(SIG_BLOCK, &new_mask, &old_mask) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+
+    delay.tv_sec = 0;
+    delay.tv_nsec = 10 * 1000 * 1000; // 10ms
+    pthread_t thread = raise_async(SIGUSR2, &delay);
+
+    // Wait with a temporary mask that unblocks SIGUSR2
+    if (sigprocmask(SIG_SETMASK, NULL, &suspend_mask) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    sigdelset(&suspend_mask, SIGUSR2);
+    int ret = sigsuspend(&suspend_mask);
+    if (ret != -1 || errno != EINTR) {
+        THROW_ERROR("sigsuspend must return with EINTR error");
+    }
+    if (sigsuspend_handled_count != 1) {
+        THROW_ERROR("the signal handler is expected to run exactly once");
+    }
+
+    // The original mask, which blocks SIGUSR2, must be restored
+    if (sigprocmask(SIG_SETMASK, NULL, &curr_mask) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    if (!sigismember(&curr_mask, SIGUSR2)) {
+        THROW_ERROR("the signal mask is not restored after sigsuspend");
+    }
+
+    if (pthread_join(thread, NULL) != 0) {
+        THROW_ERROR("failed to join the thread");
+    }
+    if (sigprocmask(SIG_SETMASK, &old_mask, NULL) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    if (sigaction(SIGUSR2, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -528,6 +759,9 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_sigaltstack),
     TEST_CASE(test_sigchld),
     TEST_CASE(test_sigtimedwait),
+    TEST_CASE(test_tgkill),
+    TEST_CASE(test_sigqueue),
+    TEST_CASE(test_sigsuspend),
 };
 
 int main(int argc, const char *argv[]) {
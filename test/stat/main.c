@@ -3,8 +3,58 @@
 #include <errno.h>
 #include <fcntl.h>
 #include <unistd.h>
+#include <stdint.h>
+#include <string.h>
+#include <sys/syscall.h>
 #include "test_fs.h"
 
+#ifndef __NR_statx
+#define __NR_statx 332
+#endif
+
+#define TEST_STATX_TYPE         0x0001U
+#define TEST_STATX_MODE         0x0002U
+#define TEST_STATX_INO          0x0100U
+#define TEST_STATX_SIZE         0x0200U
+#define TEST_STATX_BASIC_STATS  0x07ffU
+#define TEST_STATX_BTIME        0x0800U
+
+// Not all libc headers define struct statx, so define it here
+struct test_statx_timestamp {
+    int64_t tv_sec;
+    uint32_t tv_nsec;
+    int32_t __reserved;
+};
+
+struct test_statx {
+    uint32_t stx_mask;
+    uint32_t stx_blksize;
+    uint64_t stx_attributes;
+    uint32_t stx_nlink;
+    uint32_t stx_uid;
+    uint32_t stx_gid;
+    uint16_t stx_mode;
+    uint16_t __spare0;
+    uint64_t stx_ino;
+    uint64_t stx_size;
+    uint64_t stx_blocks;
+    uint64_t stx_attributes_mask;
+    struct test_statx_timestamp stx_atime;
+    struct test_statx_timestamp stx_btime;
+    struct test_statx_timestamp stx_ctime;
+    struct test_statx_timestamp stx_mtime;
+    uint32_t stx_rdev_major;
+    uint32_t stx_rdev_minor;
+    uint32_t stx_dev_major;
+    uint32_t stx_dev_minor;
+    uint64_t __spare2[14];
+};
+
+static int test_statx_syscall(int dirfd, const char *path, int flags, unsigned int mask,
+                              struct test_statx *buf) {
+    return syscall(__NR_statx, dirfd, path, flags, mask, buf);
+}
+
 // ============================================================================
 // Helper function
 // ============================================================================
@@ -134,6 +184,61 @@ static int __test_fstatat_with_dirfd(const char *file_path) {
     return 0;
 }
 
+static int __test_statx(const char *file_path) {
+    struct test_statx statx_buf;
+    struct stat stat_buf;
+    char data[100] = { 0 };
+    int fd;
+
+    fd = open(file_path, O_WRONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file");
+    }
+    if (write(fd, data, sizeof(data)) != sizeof(data)) {
+        close(fd);
+        THROW_ERROR("failed to write file");
+    }
+    if (stat(file_path, &stat_buf) < 0) {
+        close(fd);
+        THROW_ERROR("failed to stat file");
+    }
+
+    memset(&statx_buf, 0, sizeof(statx_buf));
+    if (test_statx_syscall(AT_FDCWD, file_path, 0, TEST_STATX_BASIC_STATS | TEST_STATX_BTIME,
+                           &statx_buf) < 0) {
+        close(fd);
+        THROW_ERROR("failed to statx file");
+    }
+    if ((statx_buf.stx_mask & TEST_STATX_BASIC_STATS) != TEST_STATX_BASIC_STATS ||
+            (statx_buf.stx_mask & TEST_STATX_BTIME) != 0) {
+        close(fd);
+        THROW_ERROR("unexpected statx mask");
+    }
+    if (statx_buf.stx_size != sizeof(data) || statx_buf.stx_mode != stat_buf.st_mode ||
+            statx_buf.stx_ino != stat_buf.st_ino) {
+        close(fd);
+        THROW_ERROR("statx returns wrong size, mode or ino");
+    }
+
+    // Only the requested fields are reported
+    memset(&statx_buf, 0, sizeof(statx_buf));
+    if (test_statx_syscall(fd, "", AT_EMPTY_PATH, TEST_STATX_SIZE, &statx_buf) < 0) {
+        close(fd);
+        THROW_ERROR("failed to statx empty path with AT_EMPTY_PATH flags");
+    }
+    close(fd);
+    if (statx_buf.stx_mask != TEST_STATX_SIZE || statx_buf.stx_size != sizeof(data) ||
+            statx_buf.stx_mode != 0 || statx_buf.stx_ino != 0) {
+        THROW_ERROR("statx reports the fields that are not requested");
+    }
+
+    if (!(test_statx_syscall(AT_FDCWD, "", 0, TEST_STATX_SIZE, &statx_buf) < 0 &&
+            errno == ENOENT)) {
+        THROW_ERROR("statx with empty path should return ENOENT");
+    }
+    return 0;
+}
+
 typedef int(*test_stat_func_t)(const char *);
 
 static int test_stat_framework(test_stat_func_t fn) {
@@ -175,6 +280,10 @@ static int test_fstatat_with_dirfd() {
     return test_stat_framework(__test_fstatat_with_dirfd);
 }
 
+static int test_statx() {
+    return test_stat_framework(__test_statx);
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -186,6 +295,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_fstatat_with_abs_path),
     TEST_CASE(test_fstatat_with_empty_path),
     TEST_CASE(test_fstatat_with_dirfd),
+    TEST_CASE(test_statx),
 };
 
 int main(int argc, const char *argv[]) {
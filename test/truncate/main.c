@@ -186,6 +186,136 @@ static int __test_truncate_then_write(const char *file_path) {
     return 0;
 }
 
+static int __test_truncate_bigger_reads_zeros(const char *file_path) {
+    size_t file_len = 4096 * 3 + 512;
+    off_t small_len = 100;
+    off_t big_len = 4096 * 8;
+    int fd;
+
+    fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file");
+    }
+
+    // shrink the file so that the freed blocks and the tail of the last block
+    // still hold stale data, then extend it across many blocks
+    if (fill_file_with_repeated_bytes(fd, file_len, 0xfa) < 0) {
+        THROW_ERROR("");
+    }
+    if (ftruncate(fd, small_len) < 0) {
+        THROW_ERROR("failed to call ftruncate to small length");
+    }
+    if (ftruncate(fd, big_len) < 0) {
+        THROW_ERROR("failed to call ftruncate to big length");
+    }
+    if (lseek(fd, small_len, SEEK_SET) < 0) {
+        THROW_ERROR("failed to call lseek");
+    }
+    if (check_file_with_repeated_bytes(fd, big_len - small_len, 0x00) < 0) {
+        THROW_ERROR("failed to check the extended region is zeroed");
+    }
+    close(fd);
+    return 0;
+}
+
+static int __test_truncate_path_shrink_then_grow(const char *file_path) {
+    size_t file_len = 4096 + 64;
+    off_t small_len = 32;
+    off_t big_len = 4096 * 2;
+    char read_buf[32] = { 0 };
+    int fd;
+
+    fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file");
+    }
+    if (fill_file_with_repeated_bytes(fd, file_len, 0xfa) < 0) {
+        THROW_ERROR("");
+    }
+
+    // the data beyond the new size is gone after shrinking
+    if (truncate(file_path, small_len) < 0) {
+        THROW_ERROR("failed to call truncate to small length");
+    }
+    if (pread(fd, read_buf, sizeof(read_buf), small_len) != 0) {
+        THROW_ERROR("failed to check no data beyond the small length");
+    }
+
+    // the re-exposed region reads as zeros, not the old data
+    if (truncate(file_path, big_len) < 0) {
+        THROW_ERROR("failed to call truncate to big length");
+    }
+    if (pread(fd, read_buf, small_len, 0) != small_len) {
+        THROW_ERROR("failed to read the data before the small length");
+    }
+    if (check_bytes_in_buf(read_buf, small_len, 0xfa) < 0) {
+        THROW_ERROR("failed to check the data before the small length");
+    }
+    if (lseek(fd, small_len, SEEK_SET) < 0) {
+        THROW_ERROR("failed to call lseek");
+    }
+    if (check_file_with_repeated_bytes(fd, big_len - small_len, 0x00) < 0) {
+        THROW_ERROR("failed to check the re-exposed region is zeroed");
+    }
+    close(fd);
+    return 0;
+}
+
+static int __test_fallocate_then_read(const char *file_path) {
+    off_t file_len = 64;
+    off_t len = 4096 * 2;
+    int fd;
+
+    fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file");
+    }
+    if (fill_file_with_repeated_bytes(fd, file_len, 0xfa) < 0) {
+        THROW_ERROR("");
+    }
+    if (posix_fallocate(fd, file_len, len) != 0) {
+        THROW_ERROR("failed to call posix_fallocate");
+    }
+    if (lseek(fd, file_len, SEEK_SET) < 0) {
+        THROW_ERROR("failed to call lseek");
+    }
+    if (check_file_with_repeated_bytes(fd, len, 0x00) < 0) {
+        THROW_ERROR("failed to check the fallocated region is zeroed");
+    }
+    close(fd);
+    return 0;
+}
+
+static int __test_sparse_hole_then_read(const char *file_path) {
+    off_t hole_offset = 16;
+    off_t write_offset = 4096 * 4;
+    char write_buf[16] = { 0 };
+    int fd;
+
+    fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file");
+    }
+    if (fill_file_with_repeated_bytes(fd, hole_offset, 0xfa) < 0) {
+        THROW_ERROR("");
+    }
+    memset(write_buf, 0xaa, sizeof(write_buf));
+    if (pwrite(fd, write_buf, sizeof(write_buf), write_offset) != sizeof(write_buf)) {
+        THROW_ERROR("failed to write beyond the end of file");
+    }
+    if (lseek(fd, hole_offset, SEEK_SET) < 0) {
+        THROW_ERROR("failed to call lseek");
+    }
+    if (check_file_with_repeated_bytes(fd, write_offset - hole_offset, 0x00) < 0) {
+        THROW_ERROR("failed to check the hole is zeroed");
+    }
+    if (check_file_with_repeated_bytes(fd, sizeof(write_buf), 0xaa) < 0) {
+        THROW_ERROR("failed to check the data after the hole");
+    }
+    close(fd);
+    return 0;
+}
+
 typedef int(*test_file_func_t)(const char *);
 
 static int test_file_framework(test_file_func_t fn) {
@@ -219,6 +349,68 @@ static int test_truncate_then_write() {
     return test_file_framework(__test_truncate_then_write);
 }
 
+static int test_truncate_bigger_reads_zeros() {
+    return test_file_framework(__test_truncate_bigger_reads_zeros);
+}
+
+static int test_truncate_path_shrink_then_grow() {
+    return test_file_framework(__test_truncate_path_shrink_then_grow);
+}
+
+static int test_fallocate_then_read() {
+    return test_file_framework(__test_fallocate_then_read);
+}
+
+static int test_sparse_hole_then_read() {
+    return test_file_framework(__test_sparse_hole_then_read);
+}
+
+static int test_freed_blocks_not_exposed() {
+    const char *old_file_path = "/root/test_filesystem_truncate_old.txt";
+    const char *new_file_path = "/root/test_filesystem_truncate_new.txt";
+    size_t len = 4096 * 16;
+    int fd;
+
+    // fill a file with non-zero data, then free its blocks
+    if (create_file(old_file_path) < 0) {
+        return -1;
+    }
+    fd = open(old_file_path, O_WRONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file");
+    }
+    if (fill_file_with_repeated_bytes(fd, len, 0xfa) < 0) {
+        THROW_ERROR("");
+    }
+    if (fsync(fd) < 0) {
+        THROW_ERROR("failed to call fsync");
+    }
+    close(fd);
+    if (remove_file(old_file_path) < 0) {
+        return -1;
+    }
+
+    // a new file that may reuse the freed blocks must not reveal the old data
+    if (create_file(new_file_path) < 0) {
+        return -1;
+    }
+    fd = open(new_file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file");
+    }
+    if (ftruncate(fd, len) < 0) {
+        THROW_ERROR("failed to call ftruncate");
+    }
+    if (check_file_with_repeated_bytes(fd, len, 0x00) < 0) {
+        THROW_ERROR("failed to check the reallocated blocks are zeroed");
+    }
+    close(fd);
+    if (remove_file(new_file_path) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -228,6 +420,11 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_open_truncate_existing_file),
     TEST_CASE(test_truncate_then_write),
     TEST_CASE(test_truncate_then_read),
+    TEST_CASE(test_truncate_bigger_reads_zeros),
+    TEST_CASE(test_truncate_path_shrink_then_grow),
+    TEST_CASE(test_fallocate_then_read),
+    TEST_CASE(test_sparse_hole_then_read),
+    TEST_CASE(test_freed_blocks_not_exposed),
 };
 
 int main(int argc, const char *argv[]) {
@@ -6,6 +6,7 @@
 #include <poll.h>
 #include <unistd.h>
 #include <stdlib.h>
+#include <stdint.h>
 #include <stdio.h>
 #include <spawn.h>
 #include <string.h>
@@ -375,6 +376,115 @@ int test_ioctl_fionread() {
     return 0;
 }
 
+int test_sendmsg_recvmsg_with_huge_iovs() {
+    int sockets[2];
+    char buf[16] = { 0 };
+    struct iovec iov[2];
+    struct msghdr msg = { 0 };
+
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, sockets) < 0) {
+        THROW_ERROR("failed to create a unix socket");
+    }
+
+    msg.msg_iov = iov;
+    msg.msg_iovlen = 2;
+
+    // The total length exceeds the max I/O size (2GB by default)
+    iov[0].iov_base = buf;
+    iov[0].iov_len = 3UL << 30;
+    iov[1].iov_base = buf;
+    iov[1].iov_len = 3UL << 30;
+    if (sendmsg(sockets[0], &msg, 0) >= 0 || errno != EMSGSIZE) {
+        THROW_ERROR("sendmsg with oversized iovecs should fail with EMSGSIZE");
+    }
+    if (recvmsg(sockets[1], &msg, MSG_DONTWAIT) >= 0 || errno != EMSGSIZE) {
+        THROW_ERROR("recvmsg with oversized iovecs should fail with EMSGSIZE");
+    }
+
+    // The total length overflows
+    iov[0].iov_len = SIZE_MAX;
+    iov[1].iov_len = 2;
+    if (sendmsg(sockets[0], &msg, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("sendmsg with overflowing iovecs should fail with EINVAL");
+    }
+    if (recvmsg(sockets[1], &msg, MSG_DONTWAIT) >= 0 || errno != EINVAL) {
+        THROW_ERROR("recvmsg with overflowing iovecs should fail with EINVAL");
+    }
+
+    close(sockets[0]);
+    close(sockets[1]);
+    return 0;
+}
+
+int test_scm_rights() {
+    int sockets[2];
+    int pipe_fds[2];
+    const char *msg_str = "Hello through the passed pipe";
+    char buf[64] = { 0 };
+    char data = 'x';
+    struct iovec iov = { .iov_base = &data, .iov_len = 1 };
+    union {
+        char buf[CMSG_SPACE(sizeof(int))];
+        struct cmsghdr align;
+    } control;
+    struct msghdr msg = { 0 };
+    struct cmsghdr *cmsg;
+
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, sockets) < 0) {
+        THROW_ERROR("failed to create a unix socket");
+    }
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    // Send the read end of the pipe along with one byte
+    msg.msg_iov = &iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.buf;
+    msg.msg_controllen = sizeof(control.buf);
+    cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(int));
+    memcpy(CMSG_DATA(cmsg), &pipe_fds[0], sizeof(int));
+    if (sendmsg(sockets[0], &msg, 0) != 1) {
+        THROW_ERROR("failed to send the read end of the pipe");
+    }
+    close(pipe_fds[0]);
+
+    // Receive the read end of the pipe as a new fd
+    memset(&control, 0, sizeof(control));
+    data = 0;
+    msg.msg_controllen = sizeof(control.buf);
+    if (recvmsg(sockets[1], &msg, 0) != 1 || data != 'x') {
+        THROW_ERROR("failed to receive the byte sent along with the read end");
+    }
+    if (msg.msg_flags & MSG_CTRUNC) {
+        THROW_ERROR("the control message should not be truncated");
+    }
+    cmsg = CMSG_FIRSTHDR(&msg);
+    if (cmsg == NULL || cmsg->cmsg_level != SOL_SOCKET || cmsg->cmsg_type != SCM_RIGHTS ||
+            cmsg->cmsg_len != CMSG_LEN(sizeof(int))) {
+        THROW_ERROR("failed to receive the read end of the pipe");
+    }
+    int recv_fd;
+    memcpy(&recv_fd, CMSG_DATA(cmsg), sizeof(int));
+
+    // Read through the received fd what is written to the pipe
+    if (write(pipe_fds[1], msg_str, strlen(msg_str)) != strlen(msg_str)) {
+        THROW_ERROR("failed to write to the pipe");
+    }
+    if (read(recv_fd, buf, sizeof(buf)) != strlen(msg_str) || strcmp(buf, msg_str) != 0) {
+        THROW_ERROR("failed to read from the received fd");
+    }
+
+    close(recv_fd);
+    close(pipe_fds[1]);
+    close(sockets[0]);
+    close(sockets[1]);
+    return 0;
+}
+
 static test_case_t test_cases[] = {
     TEST_CASE(test_unix_socket_inter_process),
     TEST_CASE(test_socketpair_inter_process),
@@ -382,7 +492,9 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_poll),
     TEST_CASE(test_getname),
     TEST_CASE(test_ioctl_fionread),
+    TEST_CASE(test_sendmsg_recvmsg_with_huge_iovs),
     TEST_CASE(test_unix_socket_rename),
+    TEST_CASE(test_scm_rights),
 };
 
 int main(int argc, const char *argv[]) {
@@ -208,12 +208,15 @@ fn main() {
         let user_occlum_json_config = InternalOcclumJson {
             resource_limits: InternalResourceLimits {
                 user_space_size: occlum_config.resource_limits.user_space_size.to_string(),
+                max_io_size: occlum_config.resource_limits.max_io_size.to_string(),
             },
             process: OcclumProcess {
                 default_stack_size: occlum_config.process.default_stack_size,
                 default_heap_size: occlum_config.process.default_heap_size,
                 default_mmap_size: occlum_config.process.default_mmap_size,
+                fault_policy: occlum_config.process.fault_policy,
             },
+            entropy_source: occlum_config.entropy_source,
             entry_points: occlum_config.entry_points,
             untrusted_unix_socks,
             env: occlum_config.env,
@@ -256,12 +259,15 @@ fn main() {
         let sys_occlum_json_config = InternalOcclumJson {
             resource_limits: InternalResourceLimits {
                 user_space_size: occlum_config.resource_limits.user_space_size.to_string(),
+                max_io_size: occlum_config.resource_limits.max_io_size.to_string(),
             },
             process: OcclumProcess {
                 default_stack_size: occlum_config.process.default_stack_size,
                 default_heap_size: occlum_config.process.default_heap_size,
                 default_mmap_size: occlum_config.process.default_mmap_size,
+                fault_policy: occlum_config.process.fault_policy,
             },
+            entropy_source: occlum_config.entropy_source,
             entry_points: json!(["/bin"]),
             env: occlum_config.env,
             untrusted_unix_socks,
@@ -414,6 +420,8 @@ fn gen_sys_mount_config(occlum_conf_init_fs_mac: String) -> serde_json::Value {
 struct OcclumConfiguration {
     resource_limits: OcclumResourceLimits,
     process: OcclumProcess,
+    #[serde(default = "OcclumConfiguration::default_entropy_source")]
+    entropy_source: String,
     entry_points: serde_json::Value,
     env: serde_json::Value,
     #[serde(default)]
@@ -422,6 +430,12 @@ struct OcclumConfiguration {
     mount: Vec<OcclumMount>,
 }
 
+impl OcclumConfiguration {
+    fn default_entropy_source() -> String {
+        "hardware".to_string()
+    }
+}
+
 #[derive(Debug, PartialEq, Deserialize)]
 struct OcclumResourceLimits {
     min_num_of_cpus: u32,
@@ -429,6 +443,14 @@ struct OcclumResourceLimits {
     kernel_space_heap_size: String,
     kernel_space_stack_size: String,
     user_space_size: String,
+    #[serde(default = "OcclumResourceLimits::default_max_io_size")]
+    max_io_size: String,
+}
+
+impl OcclumResourceLimits {
+    fn default_max_io_size() -> String {
+        "2GB".to_string()
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
@@ -436,6 +458,14 @@ struct OcclumProcess {
     default_stack_size: String,
     default_heap_size: String,
     default_mmap_size: String,
+    #[serde(default = "OcclumProcess::default_fault_policy")]
+    fault_policy: String,
+}
+
+impl OcclumProcess {
+    fn default_fault_policy() -> String {
+        "kill_process".to_string()
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
@@ -523,12 +553,14 @@ struct EnclaveConfiguration {
 #[derive(Debug, PartialEq, Clone, Serialize)]
 struct InternalResourceLimits {
     user_space_size: String,
+    max_io_size: String,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
 struct InternalOcclumJson {
     resource_limits: InternalResourceLimits,
     process: OcclumProcess,
+    entropy_source: String,
     entry_points: serde_json::Value,
     env: serde_json::Value,
     untrusted_unix_socks: Option<serde_json::Value>,